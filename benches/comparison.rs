@@ -0,0 +1,150 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! Compare the cost of counting with delegation against naive plurality, instant-runoff
+//! and Schulze counts of the same ballots, printing one JSON object per line.
+//! Run with: cargo bench --features strategies --bench comparison > report.jsonl
+//!
+//! The other methods need ranked ballots, so each ballot ranks the chain of candidates
+//! which its vote would be delegated through: the candidate it voted for, then the one
+//! they voted for, and so on until the chain ends or repeats. Plurality only looks at the
+//! first of them, which is the count without delegation.
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use electorium::strategies::{self, Seed};
+use electorium::{introspector::Introspector, Vote, VoteCounter, Weight};
+
+/// Each ballot as (weight, candidate indexes in order of preference)
+struct Ranked {
+    candidates: Vec<String>,
+    ballots: Vec<(Weight, Vec<usize>)>,
+}
+
+fn ranked(votes: &[Vote]) -> Ranked {
+    let candidates = votes.iter()
+        .filter(|v|v.willing_candidate)
+        .map(|v|v.voter_id.to_string())
+        .collect::<Vec<_>>();
+    let idx = candidates.iter().enumerate().map(|(i, c)|(c.as_str(), i)).collect::<HashMap<_, _>>();
+    let vote_for = votes.iter().map(|v|(&*v.voter_id, v.vote_for.as_deref())).collect::<HashMap<_, _>>();
+    let ballots = votes.iter().map(|v|{
+        let mut prefs = Vec::new();
+        let mut next = v.vote_for.as_deref();
+        while let Some(&c) = next.and_then(|n|idx.get(n)) {
+            if prefs.contains(&c) || next == Some(&*v.voter_id) {
+                break;
+            }
+            prefs.push(c);
+            next = next.and_then(|n|vote_for.get(n).copied().flatten());
+        }
+        (v.number_of_votes, prefs)
+    }).collect();
+    Ranked { candidates, ballots }
+}
+
+fn plurality(r: &Ranked) -> Option<usize> {
+    let mut totals = vec![0 as Weight; r.candidates.len()];
+    for (w, prefs) in &r.ballots {
+        if let Some(&c) = prefs.first() {
+            totals[c] += w;
+        }
+    }
+    (0..totals.len()).max_by_key(|&c|(totals[c], std::cmp::Reverse(c)))
+}
+
+fn irv(r: &Ranked) -> Option<usize> {
+    let mut eliminated = vec![false; r.candidates.len()];
+    loop {
+        let mut totals = vec![0 as Weight; r.candidates.len()];
+        for (w, prefs) in &r.ballots {
+            if let Some(&c) = prefs.iter().find(|&&c|!eliminated[c]) {
+                totals[c] += w;
+            }
+        }
+        let sum: Weight = totals.iter().sum();
+        let remaining = (0..totals.len()).filter(|&c|!eliminated[c]).collect::<Vec<_>>();
+        let leader = remaining.iter().copied().max_by_key(|&c|(totals[c], std::cmp::Reverse(c)))?;
+        if totals[leader] * 2 > sum || remaining.len() == 1 {
+            return Some(leader);
+        }
+        let last = remaining.iter().copied().min_by_key(|&c|(totals[c], std::cmp::Reverse(c)))?;
+        eliminated[last] = true;
+    }
+}
+
+fn schulze(r: &Ranked) -> Option<usize> {
+    let n = r.candidates.len();
+    // d[i][j] is the weight of the ballots which prefer i to j, an unranked candidate
+    // is below every ranked one
+    let mut d = vec![vec![0 as Weight; n]; n];
+    for (w, prefs) in &r.ballots {
+        let mut ranked = vec![false; n];
+        for &i in prefs {
+            ranked[i] = true;
+            for (j, row) in ranked.iter().enumerate() {
+                if !row {
+                    d[i][j] += w;
+                }
+            }
+        }
+    }
+    let mut p = vec![vec![0 as Weight; n]; n];
+    for i in 0..n {
+        for j in 0..n {
+            if i != j && d[i][j] > d[j][i] {
+                p[i][j] = d[i][j];
+            }
+        }
+    }
+    for k in 0..n {
+        for i in 0..n {
+            for j in 0..n {
+                if i != j && i != k && j != k {
+                    p[i][j] = p[i][j].max(p[i][k].min(p[k][j]));
+                }
+            }
+        }
+    }
+    (0..n).find(|&i|(0..n).all(|j|i == j || p[i][j] >= p[j][i]))
+}
+
+fn best_of(runs: usize, mut f: impl FnMut() -> Option<String>) -> (Duration, Option<String>) {
+    let mut best = Duration::MAX;
+    let mut winner = None;
+    for _ in 0..runs {
+        let start = Instant::now();
+        winner = f();
+        best = best.min(start.elapsed());
+    }
+    (best, winner)
+}
+
+fn report(method: &str, candidates: usize, ballots: usize, (time, winner): (Duration, Option<String>)) {
+    let winner = winner.map(|w|format!("\"{w}\"")).unwrap_or_else(||"null".into());
+    println!("{{\"method\":\"{method}\",\"candidates\":{candidates},\"ballots\":{ballots},\
+        \"nanos\":{},\"winner\":{winner}}}", time.as_nanos());
+}
+
+fn main() {
+    for (candidates, voters) in [(10, 1_000), (50, 10_000), (200, 100_000)] {
+        let votes = strategies::ballots(&mut Seed::new(1), candidates, voters, 5);
+        let ballots = votes.len();
+        report("electorium", candidates, ballots, best_of(5, ||{
+            let mut vc = VoteCounter::new(&votes, Introspector::default());
+            vc.find_winner().map(|w|w.voter_id.to_string())
+        }));
+        // Converting to ranked ballots is part of the cost of the other methods
+        let name = |r: &Ranked, c: Option<usize>| c.map(|c|r.candidates[c].clone());
+        report("plurality", candidates, ballots, best_of(5, ||{
+            let r = ranked(&votes);
+            name(&r, plurality(&r))
+        }));
+        report("irv", candidates, ballots, best_of(5, ||{
+            let r = ranked(&votes);
+            name(&r, irv(&r))
+        }));
+        report("schulze", candidates, ballots, best_of(5, ||{
+            let r = ranked(&votes);
+            name(&r, schulze(&r))
+        }));
+    }
+}
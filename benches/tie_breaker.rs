@@ -0,0 +1,20 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! Time the deterministic tie-breaker with many tied candidates.
+//! Run with: cargo bench --features strategies --bench tie_breaker
+use std::time::{Duration, Instant};
+
+use electorium::{worst_case, introspector::Introspector, VoteCounter};
+
+fn main() {
+    for n in [1_000, 10_000, 100_000] {
+        let votes = worst_case::all_tied(n);
+        let mut vc = VoteCounter::new(&votes, Introspector::default());
+        let mut best = Duration::MAX;
+        for _ in 0..10 {
+            let start = Instant::now();
+            vc.find_winner();
+            best = best.min(start.elapsed());
+        }
+        println!("all_tied n={n:<7} find_winner: {best:>12.3?}");
+    }
+}
@@ -0,0 +1,25 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! Time the tally of each pathological shape from electorium::worst_case.
+//! Run with: cargo bench --features strategies
+use std::time::Instant;
+
+use electorium::{worst_case, introspector::Introspector, Vote, VoteCounter};
+
+fn tally(name: &str, n: usize, gen: fn(usize) -> Vec<Vote<'static>>) {
+    let votes = gen(n);
+    let start = Instant::now();
+    let mut vc = VoteCounter::new(&votes, Introspector::default());
+    let counted = start.elapsed();
+    let winner = vc.find_winner().map(|w|w.voter_id.clone());
+    let total = start.elapsed();
+    println!("{name:>10} n={n:<7} count: {counted:>12.3?} total: {total:>12.3?} winner: {winner:?}");
+}
+
+fn main() {
+    for n in [100, 1_000, 5_000] {
+        tally("chain", n, worst_case::chain);
+        tally("giant_ring", n, worst_case::giant_ring);
+        tally("star", n, worst_case::star);
+        tally("all_tied", n, worst_case::all_tied);
+    }
+}
@@ -0,0 +1,88 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! Decoding the frames of electorium::compact_introspector and logging them with defmt,
+//! without std or allocation. See compact_introspector for the layout of the frames.
+#![no_std]
+
+/// The votes in a frame, as Weight in electorium.
+#[cfg(not(feature = "wide-weights"))]
+pub type Votes = u64;
+#[cfg(feature = "wide-weights")]
+pub type Votes = u128;
+
+/// Why a ballot was not counted, see InvalidVoteCause in electorium.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum Cause {
+    NoVote,
+    SelfVote,
+    UnrecognizedVote,
+    Duplicate,
+}
+
+/// One decoded frame, IDs are the bytes of at most the first 64 bytes of the ID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frame<'a> {
+    InvalidVote { cause: Cause, voter_id: &'a [u8] },
+    TieBreak { votes: Votes, tied: u64, winner_id: &'a [u8] },
+    Winner { votes: Votes, winner_id: &'a [u8] },
+    NoWinner,
+}
+
+fn take<'a>(b: &mut &'a [u8], n: usize) -> Option<&'a [u8]> {
+    if b.len() < n {
+        return None;
+    }
+    let (head, tail) = b.split_at(n);
+    *b = tail;
+    Some(head)
+}
+
+fn u64_of(b: &mut &[u8]) -> Option<u64> {
+    Some(u64::from_le_bytes(take(b, 8)?.try_into().ok()?))
+}
+
+fn votes_of(b: &mut &[u8]) -> Option<Votes> {
+    Some(Votes::from_le_bytes(take(b, core::mem::size_of::<Votes>())?.try_into().ok()?))
+}
+
+fn id_of<'a>(b: &mut &'a [u8]) -> Option<&'a [u8]> {
+    let len = u16::from_le_bytes(take(b, 2)?.try_into().ok()?);
+    take(b, usize::from(len))
+}
+
+/// Decode a frame, None if it is not a whole frame of a known kind.
+pub fn decode(frame: &[u8]) -> Option<Frame<'_>> {
+    let (&tag, mut b) = frame.split_first()?;
+    let b = &mut b;
+    let out = match tag {
+        b'I' => {
+            let cause = match take(b, 1)?[0] {
+                0 => Cause::NoVote,
+                1 => Cause::SelfVote,
+                2 => Cause::UnrecognizedVote,
+                3 => Cause::Duplicate,
+                _ => return None,
+            };
+            Frame::InvalidVote { cause, voter_id: id_of(b)? }
+        }
+        b'T' => Frame::TieBreak { votes: votes_of(b)?, tied: u64_of(b)?, winner_id: id_of(b)? },
+        b'W' => Frame::Winner { votes: votes_of(b)?, winner_id: id_of(b)? },
+        b'N' => Frame::NoWinner,
+        _ => return None,
+    };
+    b.is_empty().then_some(out)
+}
+
+/// Log a frame with defmt, at warn level for invalid votes and if the frame can not be
+/// decoded, and at info level otherwise.
+pub fn log(frame: &[u8]) {
+    match decode(frame) {
+        Some(Frame::InvalidVote { cause, voter_id }) =>
+            defmt::warn!("invalid vote {} from {=[u8]:a}", cause, voter_id),
+        Some(Frame::TieBreak { votes, tied, winner_id }) =>
+            defmt::info!("tie of {=u64} at {} votes won by {=[u8]:a}", tied, votes, winner_id),
+        Some(Frame::Winner { votes, winner_id }) =>
+            defmt::info!("winner {=[u8]:a} with {} votes", winner_id, votes),
+        Some(Frame::NoWinner) => defmt::info!("no winner"),
+        None => defmt::warn!("undecodable frame {=[u8]}", frame),
+    }
+}
@@ -4,12 +4,20 @@ use afl::fuzz;
 
 fn main() {
     let manual = std::env::args().any(|a|a == "--manual");
+    let json = std::env::args().any(|a|a == "--json");
+    let to_json = std::env::args().any(|a|a == "--to-json");
     let f = fuzzable::Fuzz::new(manual);
-    if manual {
+    if manual || json || to_json {
         let mut stdin = std::io::stdin().lock();
         let mut v = Vec::new();
         stdin.read_to_end(&mut v).unwrap();
-        f.run(&v);
+        if to_json {
+            println!("{}", f.to_json(&v));
+        } else if json {
+            f.run_json(&v).unwrap();
+        } else {
+            f.run(&v);
+        }
     } else {
         fuzz!(|data: &[u8]| {
             f.run(data);
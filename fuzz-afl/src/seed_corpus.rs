@@ -0,0 +1,22 @@
+use std::{fs, path::Path};
+
+use fuzzable::corpus;
+
+mod names;
+
+// Write the unit test scenarios as seeds for each of the fuzz input encodings:
+// <name>.case for compile-case, <name>.bin for fuzz-afl and fuzz-ordering and
+// <name>.incremental.bin for fuzz-incremental.
+fn main() {
+    let out = std::env::args().nth(1).unwrap_or_else(||"./inputs-seeds".to_owned());
+    let out = Path::new(&out);
+    fs::create_dir_all(out).unwrap();
+    let names = names::build();
+    for (name, case) in corpus::SCENARIOS {
+        println!("Writing seeds for {name}");
+        let bin = corpus::compile_case(case, &names);
+        fs::write(out.join(format!("{name}.case")), case).unwrap();
+        fs::write(out.join(format!("{name}.bin")), &bin).unwrap();
+        fs::write(out.join(format!("{name}.incremental.bin")), corpus::incremental(&bin)).unwrap();
+    }
+}
@@ -5,6 +5,8 @@
 
 #include "stdint.h"
 
+#define VOTE_WIDTH 4
+
 typedef struct Fuzz Fuzz;
 
 const Fuzz *electorium_fuzz_new(bool verbose);
@@ -13,4 +15,46 @@ void electorium_fuzz_destroy(const Fuzz *f);
 
 int16_t electorium_fuzz_run(const Fuzz *f, const uint8_t *buf, uintptr_t len);
 
+/**
+ * # Safety
+ * f must come from electorium_fuzz_new() and not be destroyed, buf must point to len
+ * readable bytes.
+ */
+void electorium_fuzz_run_incremental(const Fuzz *f, const uint8_t *buf, uintptr_t len);
+
+/**
+ * # Safety
+ * f must come from electorium_fuzz_new() and not be destroyed, buf must point to len
+ * readable bytes.
+ */
+void electorium_fuzz_run_ordering(const Fuzz *f, const uint8_t *buf, uintptr_t len);
+
+/**
+ * Run votes from JSON, returns the index of the winning vote, -1 if there is no winner
+ * or -2 if the JSON could not be parsed.
+ *
+ * # Safety
+ * f must come from electorium_fuzz_new() and not be destroyed, buf must point to len
+ * readable bytes.
+ */
+int32_t electorium_fuzz_run_json(const Fuzz *f, const uint8_t *buf, uintptr_t len);
+
+void *afl_custom_init(void *_afl, uint32_t seed);
+
+/**
+ * # Safety
+ * data must come from afl_custom_init(), buf must point to buf_size readable bytes,
+ * add_buf must be null or point to add_buf_size readable bytes and out_buf must be
+ * writable. The buffer written to out_buf is valid until the next call.
+ */
+uintptr_t afl_custom_fuzz(void *data,
+                          uint8_t *buf,
+                          uintptr_t buf_size,
+                          const uint8_t **out_buf,
+                          const uint8_t *add_buf,
+                          uintptr_t add_buf_size,
+                          uintptr_t max_size);
+
+void afl_custom_deinit(void *data);
+
 #endif /* electorium_fuzzable_H */
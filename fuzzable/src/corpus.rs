@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use electorium::scenario;
+
+use crate::VOTE_WIDTH;
+
+/// The scenarios of electorium's unit tests, the .case files in cases/ which the tests
+/// in src/tests.rs write from their ballots, see Votes::check_case() there.
+pub const SCENARIOS: &[(&str, &str)] = &[
+    ("test_alice_alone", include_str!("../../cases/test_alice_alone.case")),
+    ("test_alice_alone.2", include_str!("../../cases/test_alice_alone.2.case")),
+    ("test_alice_bob_charlie", include_str!("../../cases/test_alice_bob_charlie.case")),
+    ("charlie_is_patron", include_str!("../../cases/charlie_is_patron.case")),
+    ("ernist_is_patron", include_str!("../../cases/ernist_is_patron.case")),
+    ("tennassee_capital_election", include_str!("../../cases/tennassee_capital_election.case")),
+];
+
+/// Compile a .case file, a scenario in the format of electorium::scenario, into the
+/// binary vote format. Names which are not in the name table are assigned numbers
+/// counting down from 255, and a ballot with no vote votes for themselves. If any ballot
+/// has more than 255 votes, as in the Tennessee election, every ballot's votes are
+/// divided so that the most is 255, rounding up so that no ballot is left with none.
+pub fn compile_case(case: &str, names: &[&'static str]) -> Vec<u8> {
+    let mut next_num = 255_u8;
+    let mut number_by_name = HashMap::new();
+    let mut get_num = |name: &str| {
+        if let Some(num) = number_by_name.get(name) {
+            *num
+        } else {
+            let num = if let Some(i) = names.iter().position(|n|*n == name) {
+                i as u8
+            } else {
+                next_num -= 1;
+                next_num + 1
+            };
+            number_by_name.insert(name.to_string(), num);
+            num
+        }
+    };
+
+    let votes = scenario::parse(case).unwrap_or_else(|e|panic!("Invalid case: {e}"));
+    let most = votes.iter().map(|v|v.number_of_votes).max().unwrap_or(0);
+    let divisor = most.div_ceil(u8::MAX as u64).max(1);
+    let mut out = Vec::new();
+    for v in &votes {
+        let votes = u8::try_from(v.number_of_votes.div_ceil(divisor))
+            .unwrap_or_else(|_|panic!("Too many votes for {}", v.voter_id));
+        let who = get_num(&v.voter_id);
+        let vf = get_num(v.vote_for.as_deref().unwrap_or(&v.voter_id));
+        out.extend_from_slice(&[v.willing_candidate as u8, who, vf, votes]);
+    }
+    out
+}
+
+/// Convert a binary vote file into the input of the incremental fuzz target: add every
+/// vote which is not counted from the start, then update, revoke and remove each vote in
+/// turn, see Fuzz::run_incremental().
+pub fn incremental(votes: &[u8]) -> Vec<u8> {
+    let n = votes.len() / VOTE_WIDTH;
+    let mut mutations = Vec::new();
+    for _ in n / 2..n {
+        mutations.push([1, 0, 0]);
+    }
+    for i in 0..n {
+        // A is always 0, each removal takes the first vote which is counted away, and it
+        // is updated to vote for the one after it
+        mutations.push([2 | ((i % 64) as u8) << 2, 0, 1]);
+        mutations.push([0, 0, 0]);
+        mutations.push([3, 0, 0]);
+    }
+    mutations.truncate(u8::MAX as usize);
+    let mut out = Vec::with_capacity(1 + mutations.len() * 3 + votes.len());
+    out.push(mutations.len() as u8);
+    out.extend(mutations.iter().flatten());
+    out.extend_from_slice(votes);
+    out
+}
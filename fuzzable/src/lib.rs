@@ -2,12 +2,19 @@ use electorium::VoteCounter;
 use electorium::Vote;
 use electorium::introspector::Introspector;
 use electorium::logging_introspector;
+use electorium::limits::MAX_TOTAL_VOTES;
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
 
 mod names;
+pub mod corpus;
+pub mod mutator;
 
 // Data Shape:
 // [ Flags ][ VoterID ][ VoteForID ][ Votes ]
-const VOTE_WIDTH: usize = 4;
+pub const VOTE_WIDTH: usize = 4;
 
 #[inline]
 fn mk_id(input: u8, names: &[&'static str]) -> String {
@@ -15,21 +22,24 @@ fn mk_id(input: u8, names: &[&'static str]) -> String {
 }
 
 #[inline]
-fn parse_vote(data: &[u8], names: &[&'static str]) -> Vote {
+fn parse_vote(data: &[u8], names: &[&'static str]) -> Vote<'static> {
     let willing_candidate = data[0] & 1 == 1;
     let id = data[1];
     let vf = data[2];
     let number_of_votes = data[3] as u64;
     Vote {
-        voter_id: mk_id(id, names),
-        vote_for: mk_id(vf, names),
+        voter_id: mk_id(id, names).into(),
+        vote_for: Some(mk_id(vf, names).into()),
         number_of_votes,
         willing_candidate,
+        metadata: Default::default(),
+        fallbacks: Vec::new(),
+        abstain: false,
     }
 }
 
 #[inline]
-fn mk_votes(data: &[u8], names: &[&'static str]) -> Vec<Vote> {
+fn mk_votes(data: &[u8], names: &[&'static str]) -> Vec<Vote<'static>> {
     let ok_len = data.len() / VOTE_WIDTH * VOTE_WIDTH;
     let mut out = Vec::with_capacity(ok_len / VOTE_WIDTH);
     for i in (0..ok_len).step_by(VOTE_WIDTH) {
@@ -38,6 +48,40 @@ fn mk_votes(data: &[u8], names: &[&'static str]) -> Vec<Vote> {
     out
 }
 
+#[derive(Serialize, Deserialize)]
+struct JsonVote {
+    voter_id: String,
+    #[serde(default)]
+    vote_for: Option<String>,
+    weight: u64,
+    #[serde(default)]
+    willing: bool,
+}
+impl From<JsonVote> for Vote<'static> {
+    fn from(v: JsonVote) -> Self {
+        Vote {
+            voter_id: v.voter_id.into(),
+            // Older corpus files have "" for no vote
+            vote_for: v.vote_for.filter(|v|!v.is_empty()).map(Into::into),
+            number_of_votes: v.weight,
+            willing_candidate: v.willing,
+            metadata: Default::default(),
+            fallbacks: Vec::new(),
+            abstain: false,
+        }
+    }
+}
+impl From<Vote<'_>> for JsonVote {
+    fn from(v: Vote<'_>) -> Self {
+        JsonVote {
+            voter_id: v.voter_id.into_owned(),
+            vote_for: v.vote_for.map(|v|v.into_owned()),
+            weight: v.number_of_votes,
+            willing: v.willing_candidate,
+        }
+    }
+}
+
 pub struct Fuzz {
     verbose: bool,
     names: Vec<&'static str>,
@@ -50,8 +94,48 @@ impl Fuzz {
             names: names::build(),
         }
     }
+    /// The table which maps the VoterID and VoteForID bytes to names
+    pub fn names(&self) -> &[&'static str] {
+        &self.names
+    }
     pub fn run(&self, data: &[u8]) -> i16 {
         let votes = mk_votes(data, &self.names);
+        let win = match self.check(&votes) {
+            None => { return -1; },
+            Some(win) => win,
+        };
+        for (i, n) in self.names.iter().enumerate() {
+            if *n == win.voter_id {
+                return i as i16;
+            }
+        }
+        panic!("Name {} is not present in the list", win.voter_id);
+    }
+
+    /// Run the votes from a JSON array of objects with the fields voter_id, vote_for,
+    /// weight and willing. Returns the index of the winner's vote in the array, or None
+    /// if there is no winner.
+    pub fn run_json(&self, json: &[u8]) -> Result<Option<usize>, serde_json::Error> {
+        let votes = serde_json::from_slice::<Vec<JsonVote>>(json)?
+            .into_iter()
+            .map(Vote::from)
+            .collect::<Vec<_>>();
+        Ok(self.check(&votes).and_then(|win|votes.iter().position(|v|v == win)))
+    }
+
+    /// Convert votes in the binary format to JSON which can be edited and then passed
+    /// to run_json().
+    pub fn to_json(&self, data: &[u8]) -> String {
+        let votes = mk_votes(data, &self.names)
+            .into_iter()
+            .map(JsonVote::from)
+            .collect::<Vec<_>>();
+        serde_json::to_string_pretty(&votes).unwrap_or_default()
+    }
+
+    /// Tally the votes, then check that the winner has the best score when their own
+    /// vote is revoked. Returns the winner, if any.
+    fn check<'a>(&self, votes: &'a [Vote<'a>]) -> Option<&'a Vote<'a>> {
         let is = if self.verbose {
             logging_introspector::new()
         } else {
@@ -59,20 +143,21 @@ impl Fuzz {
         };
         if self.verbose {
             println!("Votes:");
-            for v in &votes {
-                println!("  - {} with {} votes --> {}", v.voter_id, v.number_of_votes, v.vote_for);
+            for v in votes {
+                println!("  - {} with {} votes --> {}", v.voter_id, v.number_of_votes, v.vote_for.as_deref().unwrap_or("nobody"));
             }
         }
-        let mut vc = VoteCounter::new(&votes, is);
+        let mut vc = VoteCounter::new(votes, is);
         if self.verbose {
             println!("Initial Scoring:");
             for (score, vote) in vc.iter() {
                 println!("  - {} max possible score: {}", vote.voter_id, score);
             }
         }
-        let win = match vc.find_winner() {
-            None => { return -1; },
-            Some(win) => win,
+        let win = match vc.try_find_winner() {
+            Ok(None) => { return None; },
+            Ok(Some(win)) => win,
+            Err(e) => panic!("Tally failed: {e}"),
         };
         vc.revoke_vote(win);
         if self.verbose {
@@ -91,15 +176,191 @@ impl Fuzz {
                 panic!("Projected winner does not have the best score");
             }
             if vote == win {
-                for (i, n) in self.names.iter().enumerate() {
-                    if *n == win.voter_id {
-                        return i as i16;
+                return Some(win);
+            }
+        }
+        None
+    }
+
+    /// Interleave incremental mutations of the counter with from-scratch tallies and
+    /// check that the incremental state always equals the from-scratch state.
+    ///
+    /// Data Shape:
+    /// [ Number of mutations ][ Mutation ]*[ Votes ]*
+    /// The first half of the votes are counted from the start, the rest are added by the
+    /// mutations. Each mutation is 3 bytes, [ Op ][ A ][ B ], where A and B are indexes
+    /// (modulo the number) of the votes which are being counted, and the low 2 bits of Op
+    /// choose the mutation:
+    /// 0. revoke_vote() of A
+    /// 1. add_vote() of the next vote which is not yet counted
+    /// 2. update_vote() of A to vote for B, or for nobody if B is 255, with the rest of
+    ///    Op as the number of votes, or if it is 63, 10 less than limits::MAX_TOTAL_VOTES
+    ///    so that the votes which are added after it go over the limit
+    /// 3. remove_vote() of A
+    pub fn run_incremental(&self, data: &[u8]) {
+        let (n_mutations, data) = match data.split_first() {
+            Some((&n, data)) => (n as usize, data),
+            None => return,
+        };
+        let n_mutations = (n_mutations * 3).min(data.len() / 3 * 3);
+        let (mutations, data) = data.split_at(n_mutations);
+        let votes = mk_votes(data, &self.names);
+        if votes.is_empty() {
+            return;
+        }
+        // The votes which are being counted, by index, in the order they were added
+        let mut present = (0..votes.len() / 2).collect::<Vec<_>>();
+        let mut next = present.len();
+        let mut revoked = vec![false; votes.len()];
+        let mut updated: HashMap<String, (Option<String>, u64)> = HashMap::new();
+        let mut vc = VoteCounter::new(&votes[..next], Introspector::default());
+        for m in mutations.chunks_exact(3) {
+            let (op, a, b) = (m[0], m[1] as usize, m[2]);
+            let chosen = |x: usize| present.get(x % present.len().max(1)).copied();
+            match op & 3 {
+                0 => if let Some(i) = chosen(a) {
+                    vc.revoke_vote(&votes[i]);
+                    revoked[i] = true;
+                    if self.verbose {
+                        println!("Revoked vote of {}", votes[i].voter_id);
+                    }
+                }
+                1 => if next < votes.len() {
+                    vc.add_vote(&votes[next]);
+                    present.push(next);
+                    if self.verbose {
+                        println!("Added vote of {}", votes[next].voter_id);
+                    }
+                    next += 1;
+                }
+                2 => if let Some(i) = chosen(a) {
+                    let id = votes[i].voter_id.to_string();
+                    let target = if b == 255 { None } else { chosen(b as usize).map(|t|&*votes[t].voter_id) };
+                    let weight = match op >> 2 {
+                        63 => MAX_TOTAL_VOTES - 10,
+                        w => w as u64,
+                    };
+                    if vc.update_vote(&id, target, weight).is_ok() {
+                        if self.verbose {
+                            println!("Updated vote of {id} to {target:?} with {weight} votes");
+                        }
+                        for &j in &present {
+                            if votes[j].voter_id == id {
+                                revoked[j] = false;
+                            }
+                        }
+                        updated.insert(id, (target.map(str::to_owned), weight));
+                    }
+                }
+                _ => if let Some(i) = chosen(a) {
+                    let id = votes[i].voter_id.to_string();
+                    vc.remove_vote(&id);
+                    present.retain(|&j|votes[j].voter_id != id);
+                    updated.remove(&id);
+                    if self.verbose {
+                        println!("Removed vote of {id}");
                     }
                 }
-                panic!("Name {} is not present in the list", win.voter_id);
+            }
+
+            let fresh_votes = present.iter().map(|&i|{
+                let mut v = parse_vote(&data[i * VOTE_WIDTH..], &self.names);
+                if let Some((target, weight)) = updated.get(&*v.voter_id) {
+                    v.vote_for = target.clone().map(Into::into);
+                    v.number_of_votes = *weight;
+                }
+                if revoked[i] {
+                    v.vote_for = None;
+                }
+                v
+            }).collect::<Vec<_>>();
+            let mut fresh = VoteCounter::new(&fresh_votes, Introspector::default());
+
+            let incremental = vc.iter().map(|(s, v)|(s, &v.voter_id)).collect::<Vec<_>>();
+            let scratch = fresh.iter().map(|(s, v)|(s, &v.voter_id)).collect::<Vec<_>>();
+            if incremental != scratch {
+                println!("Incremental: {:?}", incremental);
+                println!("From scratch: {:?}", scratch);
+                panic!("Incremental ranking differs from from-scratch ranking");
+            }
+            let win_i = vc.try_find_winner().map(|w|w.map(|w|&w.voter_id));
+            let win_s = fresh.try_find_winner().map(|w|w.map(|w|&w.voter_id));
+            if win_i != win_s {
+                println!("Incremental winner: {:?} From scratch winner: {:?}", win_i, win_s);
+                panic!("Incremental winner differs from from-scratch winner");
+            }
+        }
+    }
+
+    /// Check the contract of VoteCounter::iter(): scores are non-increasing, every willing
+    /// candidate appears exactly once, no unwilling voter appears, and the ranking does
+    /// not depend on the order in which the votes were supplied.
+    pub fn run_ordering(&self, data: &[u8]) {
+        let votes = mk_votes(data, &self.names);
+        let vc = VoteCounter::new(&votes, Introspector::default());
+        let ranking = vc.iter().collect::<Vec<_>>();
+        if self.verbose {
+            println!("Ranking:");
+            for (score, vote) in &ranking {
+                println!("  - {} max possible score: {}", vote.voter_id, score);
+            }
+        }
+
+        for w in ranking.windows(2) {
+            if w[0].0 < w[1].0 {
+                panic!("Score of {} is greater than score of {} which is ranked before",
+                    w[1].1.voter_id, w[0].1.voter_id);
+            }
+        }
+
+        // Only the first willing vote of a given voter_id is counted, later ones are
+        // duplicates, and unwilling voters with the same ID are also duplicates.
+        let mut expected = HashMap::new();
+        for v in &votes {
+            if v.willing_candidate {
+                expected.entry(&v.voter_id).or_insert(v);
             }
         }
-        return -1;
+        let mut seen = HashMap::new();
+        for (_, v) in &ranking {
+            if !v.willing_candidate {
+                panic!("Unwilling voter {} appears in the ranking", v.voter_id);
+            }
+            if seen.insert(&v.voter_id, ()).is_some() {
+                panic!("Candidate {} appears in the ranking more than once", v.voter_id);
+            }
+            if expected.get(&v.voter_id) != Some(v) {
+                panic!("Candidate {} appears in the ranking with the wrong ballot", v.voter_id);
+            }
+        }
+        if seen.len() != expected.len() {
+            panic!("Ranking has {} candidates but there are {} willing candidates",
+                seen.len(), expected.len());
+        }
+
+        // Which duplicate is kept depends on the order, so drop them before permuting.
+        let mut unique = HashMap::new();
+        let deduped = votes.iter()
+            .filter(|v|unique.insert(&v.voter_id, ()).is_none())
+            .map(|v|(v.voter_id.clone(), v.vote_for.clone(), v.number_of_votes, v.willing_candidate))
+            .collect::<Vec<_>>();
+        let to_vote = |(voter_id, vote_for, number_of_votes, willing_candidate)| Vote {
+            voter_id, vote_for, number_of_votes, willing_candidate, metadata: Default::default(), fallbacks: Vec::new(), abstain: false,
+        };
+        let forward = deduped.iter().cloned().map(to_vote).collect::<Vec<_>>();
+        let reverse = deduped.iter().rev().cloned().map(to_vote).collect::<Vec<_>>();
+        // Candidates with the same score may be ranked in either order
+        let score_groups = |votes: &[Vote]| {
+            let vc = VoteCounter::new(votes, Introspector::default());
+            let mut out = vc.iter()
+                .map(|(score, v)|(score, v.voter_id.to_string()))
+                .collect::<Vec<_>>();
+            out.sort();
+            out
+        };
+        if score_groups(&forward) != score_groups(&reverse) {
+            panic!("Ranking changes when the votes are supplied in reverse order");
+        }
     }
 }
 
@@ -124,4 +385,60 @@ pub extern "C" fn electorium_fuzz_run(f: *const Fuzz, buf: *const u8, len: usize
     let out = f.run(dat);
     Box::leak(f);
     out
+}
+
+/// # Safety
+/// f must come from electorium_fuzz_new() and not be destroyed, buf must point to len
+/// readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn electorium_fuzz_run_incremental(f: *const Fuzz, buf: *const u8, len: usize) {
+    let (f, dat) = unsafe {
+        (
+            Box::from_raw(f as *mut Fuzz),
+            std::slice::from_raw_parts(buf, len),
+        )
+    };
+    f.run_incremental(dat);
+    Box::leak(f);
+}
+
+/// # Safety
+/// f must come from electorium_fuzz_new() and not be destroyed, buf must point to len
+/// readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn electorium_fuzz_run_ordering(f: *const Fuzz, buf: *const u8, len: usize) {
+    let (f, dat) = unsafe {
+        (
+            Box::from_raw(f as *mut Fuzz),
+            std::slice::from_raw_parts(buf, len),
+        )
+    };
+    f.run_ordering(dat);
+    Box::leak(f);
+}
+
+/// Run votes from JSON, returns the index of the winning vote, -1 if there is no winner
+/// or -2 if the JSON could not be parsed.
+///
+/// # Safety
+/// f must come from electorium_fuzz_new() and not be destroyed, buf must point to len
+/// readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn electorium_fuzz_run_json(f: *const Fuzz, buf: *const u8, len: usize) -> i32 {
+    let (f, dat) = unsafe {
+        (
+            Box::from_raw(f as *mut Fuzz),
+            std::slice::from_raw_parts(buf, len),
+        )
+    };
+    let out = match f.run_json(dat) {
+        Ok(Some(idx)) => idx as i32,
+        Ok(None) => -1,
+        Err(e) => {
+            println!("Invalid JSON: {e}");
+            -2
+        }
+    };
+    Box::leak(f);
+    out
 }
\ No newline at end of file
@@ -0,0 +1,145 @@
+//! A structure-aware mutator for AFL++, loaded with AFL_CUSTOM_MUTATOR_LIBRARY.
+//! Random byte flips over VOTE_WIDTH-aligned votes rarely produce rings or patrons,
+//! so this mutates whole votes instead.
+use std::ffi::c_void;
+
+use electorium::strategies::Seed;
+
+use crate::VOTE_WIDTH;
+
+const FLAGS: usize = 0;
+const VOTER_ID: usize = 1;
+const VOTE_FOR: usize = 2;
+const VOTES: usize = 3;
+
+pub struct Mutator {
+    seed: Seed,
+    out: Vec<u8>,
+}
+
+impl Mutator {
+    pub fn new(seed: u64) -> Self {
+        Self { seed: Seed::new(seed), out: Vec::new() }
+    }
+
+    fn pick(&mut self, n: usize) -> usize {
+        self.seed.below(n as u64) as usize
+    }
+
+    /// Mutate the votes in data, using other as a source of votes to splice in.
+    /// The result is always a whole number of votes and never longer than max_size.
+    pub fn mutate(&mut self, data: &[u8], other: &[u8], max_size: usize) -> &[u8] {
+        self.out.clear();
+        self.out.extend_from_slice(&data[..data.len() / VOTE_WIDTH * VOTE_WIDTH]);
+        let n = self.out.len() / VOTE_WIDTH;
+        if n == 0 {
+            self.out.extend_from_slice(&[1, 0, 1, 1]);
+        } else {
+            match self.pick(6) {
+                0 => self.swap_targets(n),
+                1 => self.duplicate_voter(n),
+                2 => self.scale_weights(n),
+                3 => self.make_ring(n),
+                4 => self.splice(other),
+                _ => self.toggle_willing(n),
+            }
+        }
+        let max = max_size / VOTE_WIDTH * VOTE_WIDTH;
+        self.out.truncate(max);
+        &self.out
+    }
+
+    /// Two voters exchange the candidates they voted for
+    fn swap_targets(&mut self, n: usize) {
+        let (a, b) = (self.pick(n), self.pick(n));
+        self.out.swap(a * VOTE_WIDTH + VOTE_FOR, b * VOTE_WIDTH + VOTE_FOR);
+    }
+
+    /// A copy of a vote with a different target, as if they voted twice
+    fn duplicate_voter(&mut self, n: usize) {
+        let a = self.pick(n) * VOTE_WIDTH;
+        let mut vote = [0_u8; VOTE_WIDTH];
+        vote.copy_from_slice(&self.out[a..a + VOTE_WIDTH]);
+        let target = self.pick(n) * VOTE_WIDTH + VOTER_ID;
+        vote[VOTE_FOR] = self.out[target];
+        self.out.extend_from_slice(&vote);
+    }
+
+    /// Multiply or divide every weight by the same amount, or scale a single voter
+    fn scale_weights(&mut self, n: usize) {
+        let factor = 2 + self.pick(3) as u8;
+        let grow = self.pick(2) == 0;
+        let scale = |w: u8| if grow { w.saturating_mul(factor) } else { w / factor };
+        if self.pick(2) == 0 {
+            for i in 0..n {
+                self.out[i * VOTE_WIDTH + VOTES] = scale(self.out[i * VOTE_WIDTH + VOTES]);
+            }
+        } else {
+            let i = self.pick(n) * VOTE_WIDTH + VOTES;
+            self.out[i] = scale(self.out[i]);
+        }
+    }
+
+    /// Pick some voters and make each one vote for the next, closing a ring
+    fn make_ring(&mut self, n: usize) {
+        let len = 2 + self.pick(n.min(8));
+        let members = (0..len).map(|_|self.pick(n)).collect::<Vec<_>>();
+        for (i, &m) in members.iter().enumerate() {
+            let next = members[(i + 1) % len];
+            self.out[m * VOTE_WIDTH + VOTE_FOR] = self.out[next * VOTE_WIDTH + VOTER_ID];
+        }
+    }
+
+    /// Append a run of votes from another input
+    fn splice(&mut self, other: &[u8]) {
+        let n_other = other.len() / VOTE_WIDTH;
+        if n_other == 0 {
+            return;
+        }
+        let start = self.pick(n_other);
+        let len = 1 + self.pick(n_other - start);
+        self.out.extend_from_slice(&other[start * VOTE_WIDTH..(start + len) * VOTE_WIDTH]);
+    }
+
+    /// Make a candidate into a voter who is not willing, or vice versa
+    fn toggle_willing(&mut self, n: usize) {
+        let i = self.pick(n) * VOTE_WIDTH + FLAGS;
+        self.out[i] ^= 1;
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn afl_custom_init(_afl: *mut c_void, seed: u32) -> *mut c_void {
+    Box::into_raw(Box::new(Mutator::new(seed as u64))) as *mut c_void
+}
+
+/// # Safety
+/// data must come from afl_custom_init(), buf must point to buf_size readable bytes,
+/// add_buf must be null or point to add_buf_size readable bytes and out_buf must be
+/// writable. The buffer written to out_buf is valid until the next call.
+#[no_mangle]
+pub unsafe extern "C" fn afl_custom_fuzz(
+    data: *mut c_void,
+    buf: *mut u8,
+    buf_size: usize,
+    out_buf: *mut *const u8,
+    add_buf: *const u8,
+    add_buf_size: usize,
+    max_size: usize,
+) -> usize {
+    let (m, buf, add) = unsafe {
+        (
+            &mut *(data as *mut Mutator),
+            std::slice::from_raw_parts(buf, buf_size),
+            if add_buf.is_null() { &[][..] } else { std::slice::from_raw_parts(add_buf, add_buf_size) },
+        )
+    };
+    let out = m.mutate(buf, add, max_size);
+    unsafe { *out_buf = out.as_ptr() };
+    out.len()
+}
+
+#[no_mangle]
+pub extern "C" fn afl_custom_deinit(data: *mut c_void) {
+    drop(unsafe { Box::from_raw(data as *mut Mutator) });
+}
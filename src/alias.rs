@@ -0,0 +1,94 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! Tables of old IDs for the same members, applied to the ballots before they are
+//! counted.
+//!
+//! When organizations merge, ballots may name a delegate by a legacy member number or by
+//! email rather than by their member ID, and without an alias that vote is an
+//! UnrecognizedVote. Each AliasTable maps the old IDs of one kind to current IDs, and
+//! apply() rewrites every vote_for and fallback which is not itself the ID of a ballot
+//! but is in one of the tables, emitting an AliasApplied event for each one so that the
+//! record shows exactly which votes were redirected and by which table.
+use std::collections::{HashMap, HashSet};
+
+use crate::introspector::{AliasApplied, Introspector};
+use crate::types::Vote;
+
+/// Old IDs of one kind, with the ID each one stands for.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct AliasTable {
+    /// The name of the table for the record, e.g. "member numbers 2019"
+    pub name: String,
+    pub ids: HashMap<String, String>,
+}
+impl AliasTable {
+    pub fn new(name: &str) -> Self {
+        Self { name: name.to_owned(), ids: HashMap::new() }
+    }
+    /// alias stands for id
+    pub fn alias(mut self, alias: &str, id: &str) -> Self {
+        self.ids.insert(alias.to_owned(), id.to_owned());
+        self
+    }
+}
+
+/// Alias tables, in the order they are looked in.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Aliases {
+    pub tables: Vec<AliasTable>,
+}
+impl Aliases {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn table(mut self, table: AliasTable) -> Self {
+        self.tables.push(table);
+        self
+    }
+    /// The table which has alias, and the ID it stands for in that table. An alias is
+    /// only looked up once, the ID it stands for is not looked up again.
+    pub fn resolve(&self, alias: &str) -> Option<(&AliasTable, &str)> {
+        self.tables.iter().find_map(|t|t.ids.get(alias).map(|id|(t, &id[..])))
+    }
+}
+
+/// Apply aliases to every ballot, in order, returning the ballots to count. An ID which
+/// some ballot has as its voter_id is never treated as an alias. Each rewritten ID is
+/// emitted to is as an AliasApplied.
+pub fn apply<'s>(
+    votes: &[Vote<'s>],
+    aliases: &Aliases,
+    is: &mut Introspector<'_>,
+) -> Vec<Vote<'s>> {
+    let voters = votes.iter().map(|v|&*v.voter_id).collect::<HashSet<_>>();
+    let mut resolve = |voter_id: &str, target: &str| {
+        if voters.contains(target) {
+            return None;
+        }
+        let (table, id) = aliases.resolve(target)?;
+        is.event(||AliasApplied {
+            voter_id: voter_id.to_owned(),
+            alias: target.to_owned(),
+            resolved: id.to_owned(),
+            table: table.name.clone(),
+        });
+        Some(id.to_owned().into())
+    };
+    votes.iter().map(|v|{
+        let vote_for = match v.vote_for.as_deref().and_then(|t|resolve(&v.voter_id, t)) {
+            Some(id) => Some(id),
+            None => v.vote_for.clone(),
+        };
+        let fallbacks = v.fallbacks.iter()
+            .map(|f|resolve(&v.voter_id, f).unwrap_or_else(||f.clone()))
+            .collect();
+        Vote {
+            voter_id: v.voter_id.clone(),
+            vote_for,
+            number_of_votes: v.number_of_votes,
+            willing_candidate: v.willing_candidate,
+            metadata: v.metadata.clone(),
+            fallbacks,
+            abstain: v.abstain,
+        }
+    }).collect()
+}
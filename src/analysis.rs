@@ -0,0 +1,191 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! Exports for studying delegation flows, within one election and across a series.
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::archive::{self, ArchiveError};
+use crate::introspector::Introspector;
+use crate::options::ElectionOptions;
+use crate::types::{Weight, TallyError};
+use crate::VoteCounter;
+
+/// Who ultimately feeds whom: weights[i][j] is the number of candidate i's own votes
+/// which reach candidate j through delegation, counting every possible delegation as
+/// VoteCounter does. Rows and columns are the willing candidates, in ranking order, and
+/// the diagonal is each candidate's own votes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DelegationMatrix {
+    pub candidates: Vec<String>,
+    pub weights: Vec<Vec<Weight>>,
+}
+impl DelegationMatrix {
+    pub fn new(vc: &VoteCounter<'_>) -> Self {
+        let candidates = vc.iter().map(|(_, v)|v).collect::<Vec<_>>();
+        let idx = candidates.iter().enumerate()
+            .map(|(i, v)|(&*v.voter_id, i))
+            .collect::<HashMap<_, _>>();
+        let vote_for = vc.find(|_|true).into_iter()
+            .filter_map(|c|Some((&*c.vote.voter_id, &*c.vote_for?.voter_id)))
+            .collect::<HashMap<_, _>>();
+        let mut weights = vec![vec![0; candidates.len()]; candidates.len()];
+        let mut seen = HashSet::new();
+        for (i, c) in candidates.iter().enumerate() {
+            let votes = c.number_of_votes;
+            weights[i][i] += votes;
+            seen.clear();
+            let mut at = &*c.voter_id;
+            seen.insert(at);
+            // Through voters who are not candidates too, until the end of the chain or
+            // coming back around a ring
+            while let Some(&next) = vote_for.get(at) {
+                if !seen.insert(next) {
+                    break;
+                }
+                if let Some(&j) = idx.get(next) {
+                    weights[i][j] += votes;
+                }
+                at = next;
+            }
+        }
+        Self { candidates: candidates.iter().map(|v|v.voter_id.to_string()).collect(), weights }
+    }
+
+    /// As CSV, with a header row and a first column of candidate names
+    pub fn to_csv(&self) -> String {
+        let field = |s: &str| if s.contains([',', '"', '\n']) {
+            format!("\"{}\"", s.replace('"', "\"\""))
+        } else {
+            s.to_owned()
+        };
+        let mut out = String::from("from");
+        for c in &self.candidates {
+            out.push(',');
+            out.push_str(&field(c));
+        }
+        out.push('\n');
+        for (c, row) in self.candidates.iter().zip(&self.weights) {
+            out.push_str(&field(c));
+            for w in row {
+                let _ = write!(out, ",{}", w);
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// The turnout of one election in a series, see trends().
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Turnout {
+    pub label: String,
+    pub ballots: u64,
+    /// The sum of the votes on the ballots, before any rescaling under
+    /// OverflowPolicy::Rescale, Weight::MAX if they add up to more
+    pub votes: Weight,
+    pub winner: Option<String>,
+}
+
+/// How many voters changed who they voted for between one election and the next.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Churn {
+    /// The voters who have a ballot in both elections
+    pub voters_in_both: u64,
+    /// Those of them who voted for someone different
+    pub changed: u64,
+}
+
+/// A delegation ring and the elections it appeared in, with exactly these members.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RingHistory {
+    /// The voter_ids of the members, sorted
+    pub members: Vec<String>,
+    /// The indexes of the elections in the series
+    pub elections: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Trends {
+    pub turnout: Vec<Turnout>,
+    /// For each candidate, their total delegated votes in each election, None when they
+    /// were not a candidate
+    pub weight: BTreeMap<String, Vec<Option<Weight>>>,
+    /// Between each election and the next
+    pub churn: Vec<Churn>,
+    pub rings: Vec<RingHistory>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrendError {
+    /// The archive with this label could not be read
+    Archive(String, ArchiveError),
+    /// The election with this label could not be counted
+    Tally(String, TallyError),
+}
+impl std::fmt::Display for TrendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TrendError::Archive(l, e) => write!(f, "Archive {l}: {e}"),
+            TrendError::Tally(l, e) => write!(f, "Election {l}: {e}"),
+        }
+    }
+}
+impl std::error::Error for TrendError {}
+
+/// Count each archived election of a series, given in order with a label such as the
+/// year, under options, and compute the trends across them.
+pub fn trends<'x>(
+    archives: impl IntoIterator<Item = (&'x str, &'x [u8])>,
+    options: &ElectionOptions,
+) -> Result<Trends, TrendError> {
+    let mut out = Trends { turnout: Vec::new(), weight: BTreeMap::new(), churn: Vec::new(), rings: Vec::new() };
+    let mut rings: BTreeMap<Vec<String>, Vec<usize>> = BTreeMap::new();
+    let mut last_votes: Option<HashMap<String, Option<String>>> = None;
+    for (n, (label, data)) in archives.into_iter().enumerate() {
+        let votes = archive::decompress(data).map_err(|e|TrendError::Archive(label.to_owned(), e))?;
+        let mut vc = VoteCounter::with_options(&votes, Introspector::default(), options.clone());
+        let result = vc.tally().map_err(|e|TrendError::Tally(label.to_owned(), e))?;
+        out.turnout.push(Turnout {
+            label: label.to_owned(),
+            ballots: votes.len() as u64,
+            votes: votes.iter().map(|v|v.number_of_votes).fold(0, Weight::saturating_add),
+            winner: result.winner,
+        });
+        for (total, v) in vc.iter() {
+            let w = out.weight.entry(v.voter_id.to_string()).or_default();
+            w.resize(n, None);
+            w.push(Some(total));
+        }
+        for ring in vc.rings() {
+            let mut members = ring.iter().map(|v|v.voter_id.to_string()).collect::<Vec<_>>();
+            members.sort();
+            rings.entry(members).or_default().push(n);
+        }
+        let now = votes.iter()
+            .map(|v|(v.voter_id.to_string(), v.vote_for.as_deref().map(str::to_owned)))
+            .collect::<HashMap<_, _>>();
+        if let Some(last) = last_votes {
+            let mut churn = Churn { voters_in_both: 0, changed: 0 };
+            for (id, vote_for) in &now {
+                if let Some(before) = last.get(id) {
+                    churn.voters_in_both += 1;
+                    churn.changed += (before != vote_for) as u64;
+                }
+            }
+            out.churn.push(churn);
+        }
+        last_votes = Some(now);
+    }
+    for w in out.weight.values_mut() {
+        w.resize(out.turnout.len(), None);
+    }
+    out.rings = rings.into_iter().map(|(members, elections)|RingHistory { members, elections }).collect();
+    Ok(out)
+}
@@ -0,0 +1,278 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! A compact binary format for archiving ballots.
+//!
+//! Every ID (voter or vote target) is stored once in a sorted table, front coded so
+//! that each ID only stores what differs from the previous one. Ballots are then sorted
+//! and stored as varint indexes into the table, with the voter index delta encoded,
+//! followed by a varint weight and a flags byte. The same ballots always compress to the
+//! same bytes, whatever order they were given in. The flags are 1 for a willing candidate
+//! and 2 if a varint count of Vote::fallbacks and their indexes follow, and 4 for an
+//! abstention, so a ballot with neither is stored as it was before they existed.
+//! Vote::metadata is not archived, it does not affect the count. A weight which does not
+//! fit in a u64 is stored the same way but can only be read back with the wide-weights
+//! feature, see Weight.
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+
+use crate::types::{Vote, Weight, narrow};
+
+const MAGIC: &[u8; 4] = b"ELA1";
+/// The tag of a ballot record in a live tally log
+const ADD: u8 = b'A';
+const WILLING: u8 = 1;
+const FALLBACKS: u8 = 2;
+const ABSTAIN: u8 = 4;
+
+fn flags(v: &Vote) -> u8 {
+    (if v.willing_candidate { WILLING } else { 0 })
+        | (if v.fallbacks.is_empty() { 0 } else { FALLBACKS })
+        | (if v.abstain { ABSTAIN } else { 0 })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArchiveError {
+    /// The data does not start with the archive magic
+    BadMagic,
+    /// The data ended in the middle of a value
+    Truncated,
+    /// A varint was longer than 64 bits, or a weight longer than a Weight
+    Overflow,
+    /// An ID is not valid UTF-8
+    InvalidUtf8,
+    /// A ballot refers to an ID which is not in the table
+    BadIndex(u64),
+    /// There is data after the last ballot
+    TrailingData,
+}
+impl std::fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArchiveError::BadMagic => write!(f, "Not a ballot archive"),
+            ArchiveError::Truncated => write!(f, "Archive is truncated"),
+            ArchiveError::Overflow => write!(f, "Number in archive is too large"),
+            ArchiveError::InvalidUtf8 => write!(f, "ID in archive is not valid UTF-8"),
+            ArchiveError::BadIndex(i) => write!(f, "Ballot refers to ID number {} which does not exist", i),
+            ArchiveError::TrailingData => write!(f, "Unexpected data after the last ballot"),
+        }
+    }
+}
+impl std::error::Error for ArchiveError {}
+
+fn put_varint(out: &mut Vec<u8>, n: impl Into<u128>) {
+    let mut n = n.into();
+    while n >= 0x80 {
+        out.push((n as u8) | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+impl<'a> Reader<'a> {
+    fn varint(&mut self) -> Result<u64, ArchiveError> {
+        u64::try_from(self.varint_of(u64::BITS)?).map_err(|_|ArchiveError::Overflow)
+    }
+    fn weight(&mut self) -> Result<Weight, ArchiveError> {
+        Ok(narrow(self.varint_of(Weight::BITS)?))
+    }
+    /// A varint of no more than bits bits
+    fn varint_of(&mut self, bits: u32) -> Result<u128, ArchiveError> {
+        let mut n = 0_u128;
+        for shift in (0..bits).step_by(7) {
+            let (&b, rest) = self.data.split_first().ok_or(ArchiveError::Truncated)?;
+            self.data = rest;
+            if shift + 7 > bits && u32::from(b) >> (bits - shift) != 0 {
+                return Err(ArchiveError::Overflow);
+            }
+            n |= u128::from(b & 0x7f) << shift;
+            if b & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+        Err(ArchiveError::Overflow)
+    }
+    fn bytes(&mut self, len: u64) -> Result<&'a [u8], ArchiveError> {
+        let len = usize::try_from(len).map_err(|_|ArchiveError::Truncated)?;
+        if len > self.data.len() {
+            return Err(ArchiveError::Truncated);
+        }
+        let (b, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(b)
+    }
+}
+
+/// Compress ballots into the archive format. A vote_for of None is stored as index 0, the
+/// same as an empty ID, and an empty vote_for is read back as None.
+pub fn compress(votes: &[Vote]) -> Vec<u8> {
+    let ids = votes.iter()
+        .flat_map(|v|[Some(&*v.voter_id), v.vote_for.as_deref()].into_iter().flatten()
+            .chain(v.fallbacks.iter().map(|f|&**f)))
+        .filter(|id|!id.is_empty())
+        .collect::<BTreeSet<_>>();
+    let mut out = Vec::from(&MAGIC[..]);
+    put_varint(&mut out, ids.len() as u64);
+    let mut prev: &[u8] = &[];
+    for id in &ids {
+        let id = id.as_bytes();
+        let shared = prev.iter().zip(id).take_while(|(a, b)|a == b).count();
+        put_varint(&mut out, shared as u64);
+        put_varint(&mut out, (id.len() - shared) as u64);
+        out.extend_from_slice(&id[shared..]);
+        prev = id;
+    }
+    let index = ids.iter().enumerate().map(|(i, id)|(*id, i as u64)).collect::<HashMap<_, _>>();
+    let idx = |id: &str| if id.is_empty() { 0 } else { index.get(id).map(|i|i + 1).unwrap_or(0) };
+    let mut ballots = votes.iter()
+        .map(|v| {
+            let vote_for = v.vote_for.as_deref().unwrap_or_default();
+            let fallbacks = v.fallbacks.iter().map(|f|idx(f)).collect::<Vec<_>>();
+            (idx(&v.voter_id), idx(vote_for), v.number_of_votes, flags(v), fallbacks)
+        })
+        .collect::<Vec<_>>();
+    ballots.sort_unstable();
+    put_varint(&mut out, ballots.len() as u64);
+    let mut prev_voter = 0;
+    for (voter, vote_for, weight, flags, fallbacks) in ballots {
+        put_varint(&mut out, voter - prev_voter);
+        put_varint(&mut out, vote_for);
+        put_varint(&mut out, weight);
+        out.push(flags);
+        if !fallbacks.is_empty() {
+            put_varint(&mut out, fallbacks.len() as u64);
+            for f in fallbacks {
+                put_varint(&mut out, f);
+            }
+        }
+        prev_voter = voter;
+    }
+    out
+}
+
+/// Decompress an archive, the ballots are returned in archive order, sorted by voter_id.
+pub fn decompress(data: &[u8]) -> Result<Vec<Vote<'static>>, ArchiveError> {
+    let mut r = Reader { data };
+    if r.bytes(MAGIC.len() as u64).map_err(|_|ArchiveError::BadMagic)? != MAGIC {
+        return Err(ArchiveError::BadMagic);
+    }
+    let id_count = r.varint()?;
+    let mut ids: Vec<String> = Vec::new();
+    let mut prev: Vec<u8> = Vec::new();
+    for _ in 0..id_count {
+        let shared = r.varint()?;
+        let suffix_len = r.varint()?;
+        let shared = usize::try_from(shared).map_err(|_|ArchiveError::Truncated)?;
+        if shared > prev.len() {
+            return Err(ArchiveError::Truncated);
+        }
+        prev.truncate(shared);
+        prev.extend_from_slice(r.bytes(suffix_len)?);
+        ids.push(String::from_utf8(prev.clone()).map_err(|_|ArchiveError::InvalidUtf8)?);
+    }
+    let id = |i: u64| -> Result<String, ArchiveError> {
+        if i == 0 {
+            return Ok(String::new());
+        }
+        usize::try_from(i - 1).ok()
+            .and_then(|i|ids.get(i))
+            .cloned()
+            .ok_or(ArchiveError::BadIndex(i))
+    };
+    let ballot_count = r.varint()?;
+    let mut votes = Vec::new();
+    let mut voter = 0_u64;
+    for _ in 0..ballot_count {
+        voter = voter.checked_add(r.varint()?).ok_or(ArchiveError::Overflow)?;
+        let vote_for = r.varint()?;
+        let number_of_votes = r.weight()?;
+        let flags = r.bytes(1)?[0];
+        let mut fallbacks = Vec::new();
+        if flags & FALLBACKS != 0 {
+            for _ in 0..r.varint()? {
+                fallbacks.push(id(r.varint()?)?.into());
+            }
+        }
+        votes.push(Vote {
+            voter_id: id(voter)?.into(),
+            vote_for: Some(id(vote_for)?).filter(|v|!v.is_empty()).map(Into::into),
+            number_of_votes,
+            willing_candidate: flags & WILLING != 0,
+            metadata: Default::default(),
+            fallbacks,
+            abstain: flags & ABSTAIN != 0,
+        });
+    }
+    if !r.data.is_empty() {
+        return Err(ArchiveError::TrailingData);
+    }
+    Ok(votes)
+}
+
+/// Append one ballot on its own, as a record of a live tally log (see live::LiveTally).
+/// Each record is a tag byte, the two IDs as varint length and bytes, a varint weight and
+/// a flags byte, followed by the fallbacks in the same way as the archive. A ballot which
+/// does not vote for anyone has an empty vote_for.
+pub(crate) fn put_ballot(out: &mut Vec<u8>, v: &Vote) {
+    out.push(ADD);
+    for id in [&*v.voter_id, v.vote_for.as_deref().unwrap_or_default()] {
+        put_varint(out, id.len() as u64);
+        out.extend_from_slice(id.as_bytes());
+    }
+    put_varint(out, v.number_of_votes);
+    out.push(flags(v));
+    if !v.fallbacks.is_empty() {
+        put_varint(out, v.fallbacks.len() as u64);
+        for f in &v.fallbacks {
+            put_varint(out, f.len() as u64);
+            out.extend_from_slice(f.as_bytes());
+        }
+    }
+}
+
+fn read_id(r: &mut Reader<'_>) -> Result<String, ArchiveError> {
+    let len = r.varint()?;
+    String::from_utf8(r.bytes(len)?.to_vec()).map_err(|_|ArchiveError::InvalidUtf8)
+}
+
+fn read_ballot(r: &mut Reader<'_>) -> Result<Vote<'static>, ArchiveError> {
+    if r.bytes(1)?[0] != ADD {
+        return Err(ArchiveError::BadMagic);
+    }
+    let voter_id = read_id(r)?;
+    let vote_for = read_id(r)?;
+    let number_of_votes = r.weight()?;
+    let flags = r.bytes(1)?[0];
+    let mut fallbacks = Vec::new();
+    if flags & FALLBACKS != 0 {
+        for _ in 0..r.varint()? {
+            fallbacks.push(read_id(r)?.into());
+        }
+    }
+    Ok(Vote {
+        voter_id: voter_id.into(),
+        vote_for: Some(vote_for).filter(|v|!v.is_empty()).map(Into::into),
+        number_of_votes,
+        willing_candidate: flags & WILLING != 0,
+        metadata: Default::default(),
+        fallbacks,
+        abstain: flags & ABSTAIN != 0,
+    })
+}
+
+/// Read the records written by put_ballot. A record which is cut off at the end of the
+/// data is not an error, it is left out and the returned length is where it starts.
+pub(crate) fn read_ballots(data: &[u8]) -> Result<(Vec<Vote<'static>>, usize), ArchiveError> {
+    let mut r = Reader { data };
+    let mut votes = Vec::new();
+    while !r.data.is_empty() {
+        let before = r.data;
+        match read_ballot(&mut r) {
+            Ok(v) => votes.push(v),
+            Err(ArchiveError::Truncated) => return Ok((votes, data.len() - before.len())),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok((votes, data.len()))
+}
@@ -0,0 +1,73 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! k-of-n certification of a result by independent tally operators.
+//! Each operator counts the ballots and signs their result (see signing::sign_result),
+//! the result is certified when at least k of the known operators signed the same
+//! result over the same ballots.
+use std::collections::BTreeMap;
+
+use crate::result::ElectionResult;
+use crate::signing::{SignedResult, SignatureVerifier, signed_message, verify_signed_result};
+
+/// A result which enough operators agree on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Agreement {
+    pub result: ElectionResult,
+    pub fingerprint: Vec<u8>,
+    /// The public keys of the operators who signed it
+    pub operators: Vec<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AttestationError {
+    /// No result was signed by enough operators, best is the most that agreed on one result
+    NotEnough { best: usize, threshold: usize },
+    /// More than one result reached the threshold, the threshold is too low for the
+    /// number of operators
+    Conflicting(Vec<Agreement>),
+}
+impl std::fmt::Display for AttestationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AttestationError::NotEnough { best, threshold } =>
+                write!(f, "Only {} operators agree on a result, {} are needed", best, threshold),
+            AttestationError::Conflicting(a) =>
+                write!(f, "{} different results each have enough operators", a.len()),
+        }
+    }
+}
+impl std::error::Error for AttestationError {}
+
+/// Find the result which at least threshold of the operators signed.
+/// Attestations with a bad signature or from a key which is not in operators are
+/// ignored, and each operator is only counted once per result.
+pub fn verify_threshold(
+    attestations: &[SignedResult],
+    operators: &[Vec<u8>],
+    threshold: usize,
+    verifier: &dyn SignatureVerifier,
+) -> Result<Agreement, AttestationError> {
+    let mut by_result: BTreeMap<Vec<u8>, Agreement> = BTreeMap::new();
+    for a in attestations {
+        if !operators.contains(&a.public_key) || !verify_signed_result(a, &a.public_key, verifier) {
+            continue;
+        }
+        let agreement = by_result.entry(signed_message(&a.result, &a.fingerprint))
+            .or_insert_with(|| Agreement {
+                result: a.result.clone(),
+                fingerprint: a.fingerprint.clone(),
+                operators: Vec::new(),
+            });
+        if !agreement.operators.contains(&a.public_key) {
+            agreement.operators.push(a.public_key.clone());
+        }
+    }
+    let best = by_result.values().map(|a|a.operators.len()).max().unwrap_or(0);
+    let mut certified = by_result.into_values()
+        .filter(|a|a.operators.len() >= threshold.max(1))
+        .collect::<Vec<_>>();
+    match certified.len() {
+        0 => Err(AttestationError::NotEnough { best, threshold }),
+        1 => Ok(certified.remove(0)),
+        _ => Err(AttestationError::Conflicting(certified)),
+    }
+}
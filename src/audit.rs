@@ -0,0 +1,152 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! An audit log which is streamed to disk while the tally runs, one JSON EventRecord per
+//! line after a first line of `{"rules":RuleSet}` with the rules of the count. The log is
+//! flushed and synced every `sync_every` records so a crash leaves a usable partial log,
+//! and finalize() appends a line with the blake2b hash of every line before it, the rules
+//! included, so the log cannot be read under other rules without failing verification.
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::rc::Rc;
+
+use blake2::{Blake2b512, Digest};
+use serde::{Deserialize, Serialize};
+
+use crate::introspector::Introspector;
+use crate::record::{EventRecord, subscribe_all};
+use crate::redact::{RedactionPolicy, Role};
+use crate::result::RuleSet;
+
+/// The first line of the log
+#[derive(Serialize, Deserialize)]
+struct Header {
+    rules: RuleSet,
+}
+
+struct Inner {
+    out: BufWriter<File>,
+    hasher: Blake2b512,
+    records: u64,
+    sync_every: u64,
+    /// The first write error, events cannot return errors so it is reported by finalize()
+    error: Option<io::Error>,
+}
+impl Inner {
+    fn write_line(&mut self, value: &impl Serialize) -> io::Result<()> {
+        let mut line = serde_json::to_vec(value)?;
+        line.push(b'\n');
+        self.hasher.update(&line);
+        self.out.write_all(&line)
+    }
+    fn write(&mut self, r: &EventRecord) -> io::Result<()> {
+        self.write_line(r)?;
+        self.records += 1;
+        if self.sync_every > 0 && self.records.is_multiple_of(self.sync_every) {
+            self.out.flush()?;
+            self.out.get_ref().sync_data()?;
+        }
+        Ok(())
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b|format!("{:02x}", b)).collect()
+}
+
+fn integrity_line(hash: &str, records: u64) -> String {
+    format!("{{\"integrity\":\"{}\",\"records\":{}}}", hash, records)
+}
+
+pub struct AuditLog {
+    inner: Rc<RefCell<Inner>>,
+}
+impl AuditLog {
+    /// Create (or truncate) the log file at path for a count under rules, which are the
+    /// first line, syncing to disk every sync_every records, 0 means only when finalized.
+    pub fn create(path: impl AsRef<Path>, rules: &RuleSet, sync_every: u64) -> io::Result<Self> {
+        let mut inner = Inner {
+            out: BufWriter::new(File::create(path)?),
+            hasher: Blake2b512::new(),
+            records: 0,
+            sync_every,
+            error: None,
+        };
+        inner.write_line(&Header { rules: rules.clone() })?;
+        Ok(Self { inner: Rc::new(RefCell::new(inner)) })
+    }
+
+    fn sink(&self) -> impl FnMut(EventRecord) + 'static {
+        let inner = self.inner.clone();
+        move |r| {
+            let mut inner = inner.borrow_mut();
+            if inner.error.is_none() {
+                if let Err(e) = inner.write(&r) {
+                    inner.error = Some(e);
+                }
+            }
+        }
+    }
+
+    /// Write every event emitted by is to this log
+    pub fn subscribe(&self, is: &mut Introspector<'_>) {
+        subscribe_all(is, self.sink());
+    }
+
+    /// Write the events emitted by is as role may see them under policy, for a log
+    /// which is published
+    pub fn subscribe_redacted(&self, is: &mut Introspector<'_>, policy: &RedactionPolicy, role: Role) {
+        policy.subscribe(is, role, self.sink());
+    }
+
+    /// Append a record which did not come from an introspector
+    pub fn write(&self, r: &EventRecord) -> io::Result<()> {
+        self.inner.borrow_mut().write(r)
+    }
+
+    /// Write the integrity line and sync the file, returning the hash as hex.
+    /// Any error which happened while writing events is returned here.
+    pub fn finalize(self) -> io::Result<String> {
+        let mut inner = self.inner.borrow_mut();
+        if let Some(e) = inner.error.take() {
+            return Err(e);
+        }
+        let hash = hex(&inner.hasher.clone().finalize());
+        let line = integrity_line(&hash, inner.records);
+        writeln!(inner.out, "{}", line)?;
+        inner.out.flush()?;
+        inner.out.get_ref().sync_all()?;
+        Ok(hash)
+    }
+}
+
+/// Check that a finalized audit log has not been altered or truncated, including the
+/// rules which it was counted under.
+pub fn verify_audit_log(path: impl AsRef<Path>) -> io::Result<bool> {
+    let mut hasher = Blake2b512::new();
+    let mut lines = 0_u64;
+    let mut last = None;
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        if lines == 0 && serde_json::from_str::<Header>(&line).is_err() {
+            return Ok(false);
+        }
+        if let Some(prev) = last.replace(line) {
+            hasher.update(prev.as_bytes());
+            hasher.update(b"\n");
+        }
+        lines += 1;
+    }
+    let hash = hex(&hasher.finalize());
+    // Neither the rules nor the integrity line are records
+    let records = lines.saturating_sub(2);
+    Ok(lines >= 2 && last.is_some_and(|l| l == integrity_line(&hash, records)))
+}
+
+/// The rules which an audit log was counted under, from its first line, this does not
+/// verify the log.
+pub fn audit_log_rules(path: impl AsRef<Path>) -> io::Result<RuleSet> {
+    let mut line = String::new();
+    BufReader::new(File::open(path)?).read_line(&mut line)?;
+    Ok(serde_json::from_str::<Header>(&line)?.rules)
+}
@@ -0,0 +1,79 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! Command line tools for tally operators.
+//!
+//! `electorium selftest [--rounds N] [--scenario] <file>` counts the ballots in file
+//! several times, shuffled and with different thread counts, prints a reproducibility
+//! report and exits with status 1 if any run differed. The file is a ballot archive, a
+//! scenario in the format of electorium::scenario with --scenario, or CSV (or with the
+//! json feature NDJSON, if the name ends in .ndjson) in the format of electorium::bulk.
+use std::io::BufReader;
+use std::ops::ControlFlow;
+use std::process::ExitCode;
+
+use electorium::bulk::{self, Format};
+use electorium::live::LiveTally;
+use electorium::{archive, scenario, selftest, ElectionOptions, Vote};
+
+const USAGE: &str = "usage: electorium selftest [--rounds N] [--scenario] <file>";
+
+fn read_ballots(path: &str, is_scenario: bool) -> Result<Vec<Vote<'static>>, String> {
+    let data = std::fs::read(path).map_err(|e|format!("{path}: {e}"))?;
+    if is_scenario {
+        let text = String::from_utf8(data).map_err(|e|format!("{path}: {e}"))?;
+        return scenario::parse(&text).map_err(|e|format!("{path}: {e}"));
+    }
+    if data.starts_with(b"ELA1") {
+        return archive::decompress(&data).map_err(|e|format!("{path}: {e}"));
+    }
+    #[cfg(feature = "json")]
+    let format = if path.ends_with(".ndjson") { Format::NdJson } else { Format::Csv };
+    #[cfg(not(feature = "json"))]
+    let format = Format::Csv;
+    let mut live = LiveTally::new(ElectionOptions::default());
+    let report = bulk::ingest(BufReader::new(&data[..]), format, &mut live, 4096, |_|ControlFlow::Continue(()))
+        .map_err(|e|format!("{path}: {e}"))?;
+    if let Some(r) = report.rejected.first() {
+        return Err(format!("{path}:{}: {:?}", r.line, r.reason));
+    }
+    Ok(live.ballots().iter().map(Vote::to_owned_vote).collect())
+}
+
+fn selftest(args: &[String]) -> Result<bool, String> {
+    let mut rounds = 10;
+    let mut is_scenario = false;
+    let mut args = args;
+    let path = loop {
+        args = match args {
+            [flag, n, rest @ ..] if flag == "--rounds" => {
+                rounds = n.parse().map_err(|_|USAGE.to_owned())?;
+                rest
+            }
+            [flag, rest @ ..] if flag == "--scenario" => {
+                is_scenario = true;
+                rest
+            }
+            [path] => break path,
+            _ => return Err(USAGE.to_owned()),
+        };
+    };
+    let votes = read_ballots(path, is_scenario)?;
+    let report = selftest::run(&votes, &ElectionOptions::default(), rounds);
+    println!("{report}");
+    Ok(report.is_reproducible())
+}
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let res = match args.split_first() {
+        Some((cmd, rest)) if cmd == "selftest" => selftest(rest),
+        _ => Err(USAGE.to_owned()),
+    };
+    match res {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::from(2)
+        }
+    }
+}
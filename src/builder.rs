@@ -0,0 +1,73 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! Building the ballots of an election without writing out each Vote.
+//!
+//! ```
+//! use electorium::Election;
+//! let election = Election::builder()
+//!     .candidate("Alice")
+//!     .candidate_voting_for("Bob", "Alice")
+//!     .voter("v1", "Alice", 3)
+//!     .build();
+//! assert_eq!(election.tally().unwrap().winner.as_deref(), Some("Alice"));
+//! ```
+use crate::options::ElectionOptions;
+use crate::owned::OwnedVoteCounter;
+use crate::types::{Vote, Weight};
+
+#[derive(Debug, Default)]
+pub struct ElectionBuilder {
+    votes: Vec<Vote<'static>>,
+    options: ElectionOptions,
+}
+impl ElectionBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn ballot(mut self, id: &str, vote_for: Option<&str>, number_of_votes: Weight, willing_candidate: bool) -> Self {
+        self.votes.push(Vote {
+            voter_id: id.to_owned().into(),
+            vote_for: vote_for.map(|v|v.to_owned().into()),
+            number_of_votes,
+            willing_candidate,
+            metadata: Default::default(),
+            fallbacks: Vec::new(),
+            abstain: false,
+        });
+        self
+    }
+
+    /// A willing candidate with one vote who does not vote for anyone
+    pub fn candidate(self, id: &str) -> Self {
+        self.ballot(id, None, 1, true)
+    }
+
+    /// A willing candidate with one vote, who votes for vote_for
+    pub fn candidate_voting_for(self, id: &str, vote_for: &str) -> Self {
+        self.ballot(id, Some(vote_for), 1, true)
+    }
+
+    /// A willing candidate with number_of_votes, who votes for vote_for if it is Some
+    pub fn weighted_candidate(self, id: &str, vote_for: Option<&str>, number_of_votes: Weight) -> Self {
+        self.ballot(id, vote_for, number_of_votes, true)
+    }
+
+    /// A voter who is not a candidate, with number_of_votes for vote_for
+    pub fn voter(self, id: &str, vote_for: &str, number_of_votes: Weight) -> Self {
+        self.ballot(id, Some(vote_for), number_of_votes, false)
+    }
+
+    pub fn options(mut self, options: ElectionOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// The ballots, to count with VoteCounter
+    pub fn into_votes(self) -> Vec<Vote<'static>> {
+        self.votes
+    }
+
+    pub fn build(self) -> OwnedVoteCounter {
+        OwnedVoteCounter::new(self.votes, self.options)
+    }
+}
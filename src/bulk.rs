@@ -0,0 +1,247 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! Bulk ingestion of ballots into a LiveTally from a stream, CSV or (with the json
+//! feature) newline-delimited JSON, one ballot per line.
+//!
+//! Lines are read and validated a chunk at a time. A line which is not a valid ballot is
+//! rejected and reported with its line number, the rest of the upload is still accepted.
+//! After each chunk the caller is told what happened and may stop the upload, and since
+//! the stream is only read as fast as ballots are accepted, a slow tally slows the upload
+//! rather than buffering it.
+//!
+//! CSV lines are `voter_id,vote_for,number_of_votes,willing_candidate` where
+//! willing_candidate is true, false, 1 or 0, and a field may be in double quotes, with
+//! `""` for a quote inside it. An empty vote_for means they did not vote for anyone.
+//! Empty lines are skipped. NDJSON lines are objects with the same four fields, where
+//! vote_for may also be null or left out, an optional metadata object of strings and an
+//! optional fallbacks array of later preferences and an optional abstain flag. CSV has no
+//! fallbacks or abstentions.
+use std::collections::HashSet;
+use std::io::{self, BufRead};
+use std::ops::ControlFlow;
+
+use crate::limits;
+use crate::live::LiveTally;
+use crate::options::DuplicatePolicy;
+use crate::types::Vote;
+
+/// Why a line was rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rejection {
+    /// The line could not be parsed, with a description of the problem
+    Malformed(String),
+    EmptyVoterId,
+    /// An ID is longer than the ElectionOptions::max_id_len of the LiveTally
+    IdTooLong,
+    /// There is already a ballot with this voter_id, under DuplicatePolicy::FirstWins or
+    /// Error. Under the other policies the ballot is added and the count applies them.
+    Duplicate,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RejectedRow {
+    /// The line number, starting from 1
+    pub line: u64,
+    pub reason: Rejection,
+    /// The line as it was read, see quarantine::Quarantine::rejected()
+    pub text: String,
+}
+
+/// What happened to one chunk of lines.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ChunkReport {
+    /// The line number of the first and one past the last line of the chunk
+    pub lines: (u64, u64),
+    pub accepted: u64,
+    pub rejected: Vec<RejectedRow>,
+}
+
+/// What happened to the whole upload.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct BulkReport {
+    pub accepted: u64,
+    pub rejected: Vec<RejectedRow>,
+    /// True if the caller stopped the upload before the end of the stream
+    pub stopped: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    #[cfg(feature = "json")]
+    NdJson,
+}
+
+fn csv_fields(line: &str) -> Result<Vec<String>, Rejection> {
+    let mut fields = Vec::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        let mut field = String::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('"') if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        field.push('"');
+                    }
+                    Some('"') => break,
+                    Some(c) => field.push(c),
+                    None => return Err(Rejection::Malformed("unterminated quote".into())),
+                }
+            }
+            if !matches!(chars.peek(), None | Some(',')) {
+                return Err(Rejection::Malformed("text after closing quote".into()));
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                if c == ',' {
+                    break;
+                }
+                field.push(c);
+                chars.next();
+            }
+        }
+        fields.push(field);
+        if chars.next().is_none() {
+            return Ok(fields);
+        }
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"']) || field.trim() != field {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+/// The ballot as a CSV line which ingest() reads back as the same ballot, except for
+/// its metadata, fallbacks and abstain flag which CSV does not have.
+pub fn csv_line(v: &Vote<'_>) -> String {
+    format!("{},{},{},{}",
+        csv_field(&v.voter_id),
+        csv_field(v.vote_for.as_deref().unwrap_or("")),
+        v.number_of_votes,
+        v.willing_candidate)
+}
+
+fn parse_csv(line: &str) -> Result<Vote<'static>, Rejection> {
+    let fields = csv_fields(line)?;
+    let [voter_id, vote_for, number_of_votes, willing] = <[String; 4]>::try_from(fields)
+        .map_err(|f|Rejection::Malformed(format!("{} fields, expected 4", f.len())))?;
+    let number_of_votes = number_of_votes.trim().parse()
+        .map_err(|_|Rejection::Malformed(format!("bad number of votes {:?}", number_of_votes)))?;
+    let willing_candidate = match willing.trim() {
+        "true" | "1" => true,
+        "false" | "0" => false,
+        w => return Err(Rejection::Malformed(format!("bad willing_candidate {:?}", w))),
+    };
+    Ok(Vote {
+        voter_id: voter_id.into(),
+        vote_for: Some(vote_for).filter(|v|!v.is_empty()).map(Into::into),
+        number_of_votes,
+        willing_candidate,
+        metadata: Default::default(),
+        fallbacks: Vec::new(),
+        abstain: false,
+    })
+}
+
+#[cfg(feature = "json")]
+fn parse_json(line: &str) -> Result<Vote<'static>, Rejection> {
+    #[derive(serde::Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct Row {
+        voter_id: String,
+        #[serde(default)]
+        vote_for: Option<String>,
+        number_of_votes: crate::types::Weight,
+        willing_candidate: bool,
+        #[serde(default)]
+        metadata: crate::types::Metadata,
+        #[serde(default)]
+        fallbacks: Vec<String>,
+        #[serde(default)]
+        abstain: bool,
+    }
+    let r: Row = serde_json::from_str(line).map_err(|e|Rejection::Malformed(e.to_string()))?;
+    Ok(Vote {
+        voter_id: r.voter_id.into(),
+        vote_for: r.vote_for.filter(|v|!v.is_empty()).map(Into::into),
+        number_of_votes: r.number_of_votes,
+        willing_candidate: r.willing_candidate,
+        metadata: std::borrow::Cow::Owned(r.metadata),
+        fallbacks: r.fallbacks.into_iter().map(Into::into).collect(),
+        abstain: r.abstain,
+    })
+}
+
+/// Read ballots from input into live, chunk_lines lines at a time, calling on_chunk after
+/// each chunk. Return ControlFlow::Break from on_chunk to stop. An error reading input or
+/// writing the LiveTally's log stops the upload, the ballots before it remain added.
+pub fn ingest(
+    input: impl BufRead,
+    format: Format,
+    live: &mut LiveTally,
+    chunk_lines: usize,
+    mut on_chunk: impl FnMut(&ChunkReport) -> ControlFlow<()>,
+) -> io::Result<BulkReport> {
+    let max_id_len = live.options().max_id_len;
+    // A later ballot from the same voter replaces or adds to the first one under the other
+    // policies, so it must reach the count
+    let reject_duplicates = matches!(live.options().duplicate_voter, DuplicatePolicy::FirstWins | DuplicatePolicy::Error);
+    let mut seen = live.ballots().iter().map(|v|v.voter_id.to_string()).collect::<HashSet<_>>();
+    let mut report = BulkReport::default();
+    let mut lines = input.lines();
+    let mut line_no = 0_u64;
+    loop {
+        let mut chunk = ChunkReport { lines: (line_no + 1, line_no + 1), ..Default::default() };
+        let mut done = true;
+        for line in lines.by_ref() {
+            let line = line?;
+            line_no += 1;
+            if !line.trim().is_empty() {
+                let parsed = match format {
+                    Format::Csv => parse_csv(&line),
+                    #[cfg(feature = "json")]
+                    Format::NdJson => parse_json(&line),
+                };
+                let checked = parsed.and_then(|v| {
+                    if v.voter_id.is_empty() {
+                        Err(Rejection::EmptyVoterId)
+                    } else if max_id_len.is_some_and(|max|limits::longest_id(&v) > max) {
+                        Err(Rejection::IdTooLong)
+                    } else if reject_duplicates && seen.contains(&*v.voter_id) {
+                        Err(Rejection::Duplicate)
+                    } else {
+                        Ok(v)
+                    }
+                });
+                match checked {
+                    Ok(v) => {
+                        seen.insert(v.voter_id.to_string());
+                        live.add(v)?;
+                        chunk.accepted += 1;
+                    }
+                    Err(reason) => chunk.rejected.push(RejectedRow { line: line_no, reason, text: line }),
+                }
+            }
+            if (line_no + 1 - chunk.lines.0) as usize >= chunk_lines.max(1) {
+                done = false;
+                break;
+            }
+        }
+        chunk.lines.1 = line_no + 1;
+        report.accepted += chunk.accepted;
+        let flow = on_chunk(&chunk);
+        report.rejected.append(&mut chunk.rejected);
+        if flow.is_break() {
+            report.stopped = !done;
+            return Ok(report);
+        }
+        if done {
+            return Ok(report);
+        }
+    }
+}
@@ -0,0 +1,79 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! A minimal introspector for embedded deployments which logs only the key events, as
+//! small binary frames rather than formatted text, so that they can be forwarded as
+//! they are to a compact logger such as defmt (`defmt::info!("{=[u8]}", frame)`) and
+//! decoded on the host.
+//!
+//! Each frame starts with a tag byte, numbers are little endian u64, or u128 for votes
+//! with the wide-weights feature, and IDs are a little endian u16 length followed by at
+//! most 64 bytes of the ID:
+//!
+//! * `I` invalid vote: cause (0 no vote, 1 self vote, 2 unrecognized, 3 duplicate), voter ID
+//! * `T` tie-break: votes, number of tied candidates, winner ID
+//! * `W` winner: votes, winner ID
+//! * `N` no winner
+//!
+//! The crate itself still depends on std, this only avoids formatting in the log path.
+//! The electorium-defmt crate in defmt/ is no_std and decodes these frames without
+//! allocating, to log them with defmt on the device or on a processor they are sent to.
+use crate::introspector::{
+    Introspector,
+    InvalidVote,
+    InvalidVoteCause,
+    DeterministicTieBreaker,
+    Winner,
+};
+use crate::types::Weight;
+
+/// The longest ID prefix which is logged
+pub const MAX_ID: usize = 64;
+
+fn put_u64(out: &mut Vec<u8>, n: u64) {
+    out.extend_from_slice(&n.to_le_bytes());
+}
+
+fn put_weight(out: &mut Vec<u8>, w: Weight) {
+    out.extend_from_slice(&w.to_le_bytes());
+}
+
+fn put_id(out: &mut Vec<u8>, id: &str) {
+    let id = &id.as_bytes()[..id.len().min(MAX_ID)];
+    out.extend_from_slice(&(id.len() as u16).to_le_bytes());
+    out.extend_from_slice(id);
+}
+
+/// Create an introspector which passes a frame for each key event to sink.
+pub fn new<'a>(sink: impl FnMut(&[u8]) + 'static) -> Introspector<'a> {
+    let sink = std::rc::Rc::new(std::cell::RefCell::new(sink));
+    let mut is = Introspector::default();
+    is.subscribe(sink.clone(), |s, e: &InvalidVote<'a>| {
+        let mut f = vec![b'I', match e.cause {
+            InvalidVoteCause::NoVote => 0,
+            InvalidVoteCause::SelfVote => 1,
+            InvalidVoteCause::UnrecognizedVote => 2,
+            InvalidVoteCause::Duplicate => 3,
+        }];
+        put_id(&mut f, &e.vote.voter_id);
+        (s.borrow_mut())(&f);
+    });
+    is.subscribe(sink.clone(), |s, e: &DeterministicTieBreaker<'a>| {
+        let mut f = vec![b'T'];
+        put_weight(&mut f, e.votes);
+        put_u64(&mut f, e.tied_candidates.len() as u64);
+        put_id(&mut f, e.tied_candidates.first().map_or("", |(v, _)|&*v.voter_id));
+        (s.borrow_mut())(&f);
+    });
+    is.subscribe(sink, |s, e: &Option<Winner<'a>>| {
+        let mut f = Vec::new();
+        match e {
+            Some(w) => {
+                f.push(b'W');
+                put_weight(&mut f, w.votes);
+                put_id(&mut f, &w.candidate.voter_id);
+            }
+            None => f.push(b'N'),
+        }
+        (s.borrow_mut())(&f);
+    });
+    is
+}
@@ -0,0 +1,111 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! Count the same ballots under two sets of options and report what changed, in the
+//! ranking, in the decisions made along the way, and in the winner.
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::introspector::Introspector;
+use crate::record::{EventRecord, subscribe_all};
+use crate::options::ElectionOptions;
+use crate::result::ElectionResult;
+use crate::types::{Vote, Weight, TallyError};
+use crate::VoteCounter;
+
+/// A candidate whose position or score in the ranking differs between the two counts.
+/// Positions start from 0, None means the candidate is not ranked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RankChange {
+    pub voter_id: String,
+    pub a: Option<(usize, Weight)>,
+    pub b: Option<(usize, Weight)>,
+}
+
+/// The difference between two counts of the same ballots.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comparison {
+    pub a: ElectionResult,
+    pub b: ElectionResult,
+    /// Every candidate whose rank or score changed
+    pub ranking_changes: Vec<RankChange>,
+    /// The decisions made under the first options: best ring, within-ring tie-break,
+    /// patron selection, tie-breaker and winner
+    pub decisions_a: Vec<EventRecord>,
+    /// The decisions made under the second options
+    pub decisions_b: Vec<EventRecord>,
+}
+impl Comparison {
+    pub fn winner_changed(&self) -> bool {
+        self.a.winner != self.b.winner
+    }
+    /// The index of the first decision which differs, if any
+    pub fn first_divergence(&self) -> Option<usize> {
+        let (a, b) = (&self.decisions_a, &self.decisions_b);
+        (0..a.len().max(b.len())).find(|&i|a.get(i) != b.get(i))
+    }
+}
+
+/// Whether an event is one of the decisions made on the way to finding the winner,
+/// rather than part of computing the delegated votes.
+fn is_decision(r: &EventRecord) -> bool {
+    matches!(r,
+        EventRecord::BestRing { .. } |
+        EventRecord::BestOfRing { .. } |
+        EventRecord::PatronSelection { .. } |
+        EventRecord::TieBreaker { .. } |
+        EventRecord::Winner { .. })
+}
+
+struct Count {
+    result: ElectionResult,
+    ranking: Vec<(String, Weight)>,
+    decisions: Vec<EventRecord>,
+}
+
+fn count(votes: &[Vote], options: ElectionOptions) -> Result<Count, TallyError> {
+    let decisions = Rc::new(RefCell::new(Vec::new()));
+    let mut is = Introspector::default();
+    let out = decisions.clone();
+    subscribe_all(&mut is, move |r| {
+        if is_decision(&r) {
+            out.borrow_mut().push(r);
+        }
+    });
+    let mut vc = VoteCounter::with_options(votes, is, options);
+    let ranking = vc.iter().map(|(score, v)|(v.voter_id.to_string(), score)).collect();
+    let result = vc.tally()?;
+    let decisions = decisions.take();
+    Ok(Count { result, ranking, decisions })
+}
+
+/// Count the ballots under options a and under options b, and report the differences.
+pub fn compare(
+    votes: &[Vote],
+    a: ElectionOptions,
+    b: ElectionOptions,
+) -> Result<Comparison, TallyError> {
+    let Count { result: result_a, ranking: ranking_a, decisions: decisions_a } = count(votes, a)?;
+    let Count { result: result_b, ranking: ranking_b, decisions: decisions_b } = count(votes, b)?;
+    let positions = |ranking: &[(String, Weight)]| {
+        ranking.iter()
+            .enumerate()
+            .map(|(pos, (id, score))|(id.clone(), (pos, *score)))
+            .collect::<HashMap<_, _>>()
+    };
+    let (pos_a, pos_b) = (positions(&ranking_a), positions(&ranking_b));
+    let mut ranking_changes = Vec::new();
+    let b_only = ranking_b.iter().filter(|(id, _)|!pos_a.contains_key(id));
+    for (id, _) in ranking_a.iter().chain(b_only) {
+        let (in_a, in_b) = (pos_a.get(id).copied(), pos_b.get(id).copied());
+        if in_a != in_b {
+            ranking_changes.push(RankChange { voter_id: id.clone(), a: in_a, b: in_b });
+        }
+    }
+    Ok(Comparison {
+        a: result_a,
+        b: result_b,
+        ranking_changes,
+        decisions_a,
+        decisions_b,
+    })
+}
@@ -0,0 +1,84 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! Differentially private noise for the published totals of small elections.
+//!
+//! The count and the winner are always exact, noise is only added to the totals which
+//! are published. Each total gets independent noise from the two-sided geometric
+//! distribution (the discrete Laplace mechanism), with P(noise = k) proportional to
+//! exp(-epsilon * |k| / sensitivity). The noise is drawn from a seeded generator so a
+//! publication can be reproduced, and it is computed in integer arithmetic only.
+use crate::types::{Vote, Weight, weight};
+
+/// exp(-0.001) in 32 bit fixed point
+const EXP_MINUS_MILLI: u128 = 4_290_674_475;
+const ONE: u128 = 1 << 32;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoiseOptions {
+    /// The privacy parameter epsilon in thousandths, smaller is more private and noisier
+    pub epsilon_milli: u64,
+    /// The most that one voter can change a total, for ballots of one vote each this is 1,
+    /// with weighted ballots it is the largest weight
+    pub sensitivity: u64,
+    pub seed: u64,
+}
+
+/// splitmix64, small and well distributed, this is not a cryptographic generator and
+/// the seed must be kept secret for the noise to protect anyone
+struct Rng(u64);
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// exp(-milli / 1000) in 32 bit fixed point
+fn exp_minus(mut milli: u64) -> u128 {
+    let mut out = ONE;
+    let mut base = EXP_MINUS_MILLI;
+    while milli > 0 {
+        if milli & 1 == 1 {
+            out = (out * base) >> 32;
+        }
+        base = (base * base) >> 32;
+        milli >>= 1;
+    }
+    out
+}
+
+/// The number of successes before the first failure, each with probability alpha
+fn geometric(rng: &mut Rng, alpha: u128) -> u64 {
+    let mut k = 0;
+    while ((rng.next() >> 32) as u128) < alpha {
+        k += 1;
+    }
+    k
+}
+
+/// Add noise to each total, totals which would be negative are published as 0.
+/// An epsilon / sensitivity smaller than 0.001 is treated as 0.001.
+pub fn noisy_totals(totals: &[(String, Weight)], options: &NoiseOptions) -> Vec<(String, Weight)> {
+    let milli = (options.epsilon_milli / options.sensitivity.max(1)).max(1);
+    let alpha = exp_minus(milli);
+    let mut rng = Rng(options.seed);
+    totals.iter()
+        .map(|(id, t)| {
+            let up = geometric(&mut rng, alpha);
+            let down = geometric(&mut rng, alpha);
+            (id.clone(), t.saturating_add(weight(up)).saturating_sub(weight(down)))
+        })
+        .collect()
+}
+
+/// The ranking of a count, as VoteCounter::iter(), with noisy totals. The order is the
+/// exact ranking, not the order of the noisy totals.
+pub fn noisy_ranking<'a>(
+    ranking: impl Iterator<Item = (Weight, &'a Vote<'a>)>,
+    options: &NoiseOptions,
+) -> Vec<(String, Weight)> {
+    let totals = ranking.map(|(t, v)|(v.voter_id.to_string(), t)).collect::<Vec<_>>();
+    noisy_totals(&totals, options)
+}
@@ -0,0 +1,238 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! Many named elections in one process, each with its own options, ballots and audit
+//! log, for services which host elections for many organizations. A service maps its
+//! endpoints onto the lifecycle here: open, add ballots, close, tally, archive.
+//!
+//! Downstream systems can be told when the leader of an election changes and when it is
+//! finalized, with a Notifier, rather than polling.
+use std::collections::BTreeMap;
+#[cfg(feature = "json")]
+use std::path::PathBuf;
+
+use crate::archive;
+use crate::introspector::Introspector;
+use crate::live::{LiveTally, LiveReader};
+use crate::metrics::Metrics;
+use crate::options::ElectionOptions;
+use crate::result::ElectionResult;
+use crate::signing::{self, ResultSigner, SignedResult};
+use crate::types::{Vote, Weight, TallyError};
+use crate::VoteCounter;
+
+/// Where an election is in its lifecycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Accepting ballots
+    Open,
+    /// No more ballots are accepted, waiting to be tallied
+    Closed,
+    /// The final result has been counted
+    Tallied,
+    /// The ballots have been archived and released, only the result is kept
+    Archived,
+}
+
+#[derive(Debug)]
+pub enum ElectionError {
+    NoSuchElection(String),
+    AlreadyExists(String),
+    /// Names are used in file names, so only ASCII letters, digits, '-' and '_' are allowed
+    BadName(String),
+    /// The operation is not possible at this stage, for example adding a ballot after close
+    WrongStage { name: String, stage: Stage },
+    Tally(TallyError),
+    Io(std::io::Error),
+}
+impl std::fmt::Display for ElectionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ElectionError::NoSuchElection(n) => write!(f, "There is no election named {n}"),
+            ElectionError::AlreadyExists(n) => write!(f, "There is already an election named {n}"),
+            ElectionError::BadName(n) => write!(f, "{n:?} is not a valid election name"),
+            ElectionError::WrongStage { name, stage } =>
+                write!(f, "Election {name} is {stage:?}, this is not possible"),
+            ElectionError::Tally(e) => write!(f, "Tally failed: {e}"),
+            ElectionError::Io(e) => write!(f, "I/O error: {e}"),
+        }
+    }
+}
+impl std::error::Error for ElectionError {}
+impl From<TallyError> for ElectionError {
+    fn from(e: TallyError) -> Self {
+        ElectionError::Tally(e)
+    }
+}
+impl From<std::io::Error> for ElectionError {
+    fn from(e: std::io::Error) -> Self {
+        ElectionError::Io(e)
+    }
+}
+
+/// Receives notice of changes to the elections, for example to deliver webhooks.
+/// Notifications are delivered synchronously, a Notifier which does I/O should queue them.
+pub trait Notifier {
+    /// The leader changed when the standings were published, previous is the old leader
+    fn leader_changed(&mut self, election: &str, previous: Option<&str>, leader: Option<&str>, votes: Weight);
+    /// The election was tallied, the result is signed by the Elections' signer
+    fn finalized(&mut self, election: &str, result: &SignedResult);
+}
+
+struct Election {
+    stage: Stage,
+    live: LiveTally,
+    result: Option<ElectionResult>,
+}
+
+#[derive(Default)]
+pub struct Elections {
+    elections: BTreeMap<String, Election>,
+    metrics: Option<Metrics>,
+    notify: Option<(Box<dyn Notifier + Send>, Box<dyn ResultSigner + Send>)>,
+    #[cfg(feature = "json")]
+    audit_dir: Option<PathBuf>,
+}
+impl Elections {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Write the audit log of each tally to `<dir>/<name>.audit.jsonl`
+    #[cfg(feature = "json")]
+    pub fn with_audit_dir(dir: impl Into<PathBuf>) -> Self {
+        Self { audit_dir: Some(dir.into()), ..Self::default() }
+    }
+
+    /// Count the ballots and tallies of every election opened from now on in metrics
+    pub fn set_metrics(&mut self, metrics: Metrics) {
+        self.metrics = Some(metrics);
+    }
+
+    /// Notify notifier of leader changes and finalized results, which are signed by signer
+    pub fn set_notifier(
+        &mut self,
+        notifier: impl Notifier + Send + 'static,
+        signer: impl ResultSigner + Send + 'static,
+    ) {
+        self.notify = Some((Box::new(notifier), Box::new(signer)));
+    }
+
+    fn get(&mut self, name: &str) -> Result<&mut Election, ElectionError> {
+        self.elections.get_mut(name).ok_or_else(||ElectionError::NoSuchElection(name.to_owned()))
+    }
+
+    fn at_stage(&mut self, name: &str, stage: Stage) -> Result<&mut Election, ElectionError> {
+        let e = self.get(name)?;
+        if e.stage != stage {
+            return Err(ElectionError::WrongStage { name: name.to_owned(), stage: e.stage });
+        }
+        Ok(e)
+    }
+
+    /// The names of the elections, in order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.elections.keys().map(|n|n.as_str())
+    }
+
+    pub fn stage(&self, name: &str) -> Option<Stage> {
+        self.elections.get(name).map(|e|e.stage)
+    }
+
+    /// Start a new election which accepts ballots.
+    pub fn open(&mut self, name: &str, options: ElectionOptions) -> Result<(), ElectionError> {
+        let valid = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+        if name.is_empty() || !name.chars().all(valid) {
+            return Err(ElectionError::BadName(name.to_owned()));
+        }
+        if self.elections.contains_key(name) {
+            return Err(ElectionError::AlreadyExists(name.to_owned()));
+        }
+        let mut live = LiveTally::new(options);
+        if let Some(m) = &self.metrics {
+            live.set_metrics(m.clone());
+        }
+        self.elections.insert(name.to_owned(), Election {
+            stage: Stage::Open,
+            live,
+            result: None,
+        });
+        Ok(())
+    }
+
+    pub fn add(&mut self, name: &str, vote: Vote) -> Result<(), ElectionError> {
+        Ok(self.at_stage(name, Stage::Open)?.live.add(vote)?)
+    }
+
+    /// A reader of the live standings of the election, see LiveTally::reader()
+    pub fn reader(&mut self, name: &str) -> Result<LiveReader, ElectionError> {
+        Ok(self.get(name)?.live.reader())
+    }
+
+    /// Count the ballots so far and update the live standings, while the election is open
+    pub fn publish(&mut self, name: &str) -> Result<(), ElectionError> {
+        let live = &mut self.at_stage(name, Stage::Open)?.live;
+        let previous = live.reader().snapshot();
+        let now = live.publish()?;
+        if let Some((n, _)) = &mut self.notify {
+            if now.leader != previous.leader {
+                n.leader_changed(name, previous.leader.as_deref(), now.leader.as_deref(), now.votes);
+            }
+        }
+        Ok(())
+    }
+
+    /// Stop accepting ballots
+    pub fn close(&mut self, name: &str) -> Result<(), ElectionError> {
+        self.at_stage(name, Stage::Open)?.stage = Stage::Closed;
+        Ok(())
+    }
+
+    /// Count the final result of a closed election
+    pub fn tally(&mut self, name: &str) -> Result<ElectionResult, ElectionError> {
+        #[cfg(feature = "json")]
+        let audit_dir = self.audit_dir.clone();
+        let metrics = self.metrics.clone();
+        let e = self.at_stage(name, Stage::Closed)?;
+        let mut is = Introspector::default();
+        if let Some(m) = metrics {
+            m.subscribe(&mut is);
+        }
+        let options = e.live.options().clone();
+        #[cfg(feature = "json")]
+        let audit = match audit_dir {
+            Some(dir) => {
+                let path = dir.join(format!("{name}.audit.jsonl"));
+                let log = crate::audit::AuditLog::create(path, &crate::RuleSet::new(options.clone()), 0)?;
+                log.subscribe(&mut is);
+                Some(log)
+            }
+            None => None,
+        };
+        let result = VoteCounter::with_options(e.live.ballots(), is, options).tally()?;
+        #[cfg(feature = "json")]
+        if let Some(audit) = audit {
+            audit.finalize()?;
+        }
+        e.result = Some(result.clone());
+        e.stage = Stage::Tallied;
+        if let Some((n, signer)) = &mut self.notify {
+            let e = self.elections.get(name).ok_or_else(||ElectionError::NoSuchElection(name.to_owned()))?;
+            n.finalized(name, &signing::sign_result(result.clone(), e.live.ballots(), signer.as_ref()));
+        }
+        Ok(result)
+    }
+
+    /// The final result, once tallied
+    pub fn result(&self, name: &str) -> Option<&ElectionResult> {
+        self.elections.get(name).and_then(|e|e.result.as_ref())
+    }
+
+    /// Release the ballots of a tallied election and return them in the archive format,
+    /// see archive::compress()
+    pub fn archive(&mut self, name: &str) -> Result<Vec<u8>, ElectionError> {
+        let e = self.at_stage(name, Stage::Tallied)?;
+        let data = archive::compress(e.live.ballots());
+        e.live = LiveTally::new(e.live.options().clone());
+        e.stage = Stage::Archived;
+        Ok(data)
+    }
+}
@@ -0,0 +1,133 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! Checking the events of a count in tests, without matching the text of the logging
+//! introspector.
+//!
+//! ExpectEvents records every event, then check() looks for each Expect in order. Other
+//! events may come between the expected ones, so a test only names the steps it is
+//! about and keeps passing when unrelated events are added.
+//!
+//! ```
+//! use electorium::{Vote, VoteCounter};
+//! use electorium::expect::{Expect, ExpectEvents};
+//! use electorium::introspector::{Introspector, InvalidVoteCause};
+//! let votes = vec![
+//!     Vote::borrowed("alice", Some("alice"), 2, true),
+//!     Vote::borrowed("bob", Some("alice"), 1, true),
+//! ];
+//! let mut is = Introspector::default();
+//! let events = ExpectEvents::subscribe(&mut is);
+//! VoteCounter::new(&votes, is).find_winner();
+//! events.check(&[
+//!     Expect::invalid_vote(InvalidVoteCause::SelfVote),
+//!     Expect::winner(Some("alice")),
+//! ]).unwrap();
+//! ```
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::introspector::{Introspector, InvalidVoteCause, FallbackCause};
+use crate::record::{self, EventRecord};
+
+/// One expected event, see the constructors.
+pub struct Expect {
+    description: String,
+    matches: Box<dyn Fn(&EventRecord) -> bool>,
+}
+impl fmt::Debug for Expect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.description)
+    }
+}
+impl Expect {
+    /// Any event for which f returns true, described as description in errors
+    pub fn matching(description: &str, f: impl Fn(&EventRecord) -> bool + 'static) -> Self {
+        Expect { description: description.to_owned(), matches: Box::new(f) }
+    }
+    pub fn invalid_vote(cause: InvalidVoteCause) -> Self {
+        Self::matching(&format!("InvalidVote({cause:?})"), move |r|
+            matches!(r, EventRecord::InvalidVote { cause: c, .. } if *c == cause))
+    }
+    pub fn fallback(cause: FallbackCause) -> Self {
+        Self::matching(&format!("Fallback({cause:?})"), move |r|
+            matches!(r, EventRecord::Fallback { cause: c, .. } if *c == cause))
+    }
+    /// A delegation of votes from one voter to another
+    pub fn delegation(from: &str, to: &str) -> Self {
+        let (f, t) = (from.to_owned(), to.to_owned());
+        Self::matching(&format!("VoteDelegation {from} -> {to}"), move |r|
+            matches!(r, EventRecord::VoteDelegation { from, to, .. } if *from == f && *to == t))
+    }
+    /// A BestRing with a ring of this many members
+    pub fn best_ring(members: usize) -> Self {
+        Self::matching(&format!("BestRing with {members} members"), move |r|
+            matches!(r, EventRecord::BestRing { rings, .. } if rings.iter().any(|r|r.len() == members)))
+    }
+    /// A tie-break between this many candidates
+    pub fn tie_breaker(tied: usize) -> Self {
+        Self::matching(&format!("TieBreaker between {tied}"), move |r|
+            matches!(r, EventRecord::TieBreaker { tied: t, .. } if t.len() == tied))
+    }
+    /// The Winner, None for no winner
+    pub fn winner(candidate: Option<&str>) -> Self {
+        let c = candidate.map(str::to_owned);
+        Self::matching(&format!("Winner={}", candidate.unwrap_or("(none)")), move |r|
+            matches!(r, EventRecord::Winner { candidate, .. } if *candidate == c))
+    }
+    pub fn seat(seat: usize, candidate: &str) -> Self {
+        let c = candidate.to_owned();
+        Self::matching(&format!("Seat {seat}={candidate}"), move |r|
+            matches!(r, EventRecord::Seat { seat: s, candidate, .. } if *s == seat && *candidate == c))
+    }
+}
+
+/// An expected event which was not found, see ExpectEvents::check().
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectError {
+    /// The description of the first Expect which was not found
+    pub missing: String,
+    /// The number of Expects before it which were found
+    pub found: usize,
+    /// Every event which was recorded
+    pub events: Vec<EventRecord>,
+}
+impl fmt::Display for ExpectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Expected {} after {} expected events, the events were:", self.missing, self.found)?;
+        for e in &self.events {
+            writeln!(f, "    {e:?}")?;
+        }
+        Ok(())
+    }
+}
+impl std::error::Error for ExpectError {}
+
+/// Records every event of a count, see the module documentation.
+#[derive(Debug, Clone, Default)]
+pub struct ExpectEvents {
+    records: Rc<RefCell<Vec<EventRecord>>>,
+}
+impl ExpectEvents {
+    /// Record the events of is, as record::subscribe_all()
+    pub fn subscribe(is: &mut Introspector<'_>) -> Self {
+        let out = Self::default();
+        let sink = out.records.clone();
+        record::subscribe_all(is, move |r| sink.borrow_mut().push(r));
+        out
+    }
+    /// The events recorded so far
+    pub fn events(&self) -> Vec<EventRecord> {
+        self.records.borrow().clone()
+    }
+    /// Check that the events include each of expected, in order
+    pub fn check(&self, expected: &[Expect]) -> Result<(), ExpectError> {
+        let records = self.records.borrow();
+        let mut rest = records.iter();
+        for (found, e) in expected.iter().enumerate() {
+            if !rest.any(|r|(e.matches)(r)) {
+                return Err(ExpectError { missing: e.description.clone(), found, events: records.clone() });
+            }
+        }
+        Ok(())
+    }
+}
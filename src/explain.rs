@@ -0,0 +1,233 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! A short account of how a count was decided, for announcements to the members.
+//!
+//! explain() reads the events of a count, as recorded by record::subscribe_all(), and
+//! picks out the decisions which a member would ask about: who won, which ring was the
+//! strongest, who was eliminated within it and why no patron was chosen. Each Sentence
+//! names the candidates it refers to so they can be linked or translated, and Display
+//! writes it in English. Delegations of individual ballots are left out, they are in the
+//! trail for anyone who wants them.
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::introspector::InvalidVoteCause;
+use crate::record::{EventRecord, PatronOutcome};
+use crate::types::Weight;
+
+/// One statement about the count, see explain().
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(tag = "sentence"))]
+pub enum Sentence {
+    Won { candidate: String, votes: Weight },
+    NoWinner,
+    NoQuorum { ballots: u64, votes: Weight },
+    Seat { seat: usize, candidate: String, votes: Weight },
+    /// Ballots which were not counted for anyone else, with why
+    Invalid { cause: InvalidVoteCause, ballots: u64 },
+    Abstained { ballots: u64, votes: Weight },
+    /// A ring of candidates delegating to one another had the most votes
+    Ring { members: Vec<String>, votes: Weight },
+    /// A member of the ring had fewer votes than the best of it
+    OutscoredInRing { candidate: String, votes: Weight, by: Vec<String> },
+    /// The candidate had as many votes as winner but lost the deterministic tie-break
+    LostTieBreak { candidate: String, winner: String, votes: Weight },
+    /// The candidate could not take the win from the ring they delegated to
+    NotPatron { candidate: String, votes: Weight, outcome: PatronOutcome },
+    Patron { candidate: String, votes: Weight },
+}
+impl Sentence {
+    /// The voter_id of each candidate this refers to, in the order they are written
+    pub fn candidates(&self) -> Vec<&str> {
+        match self {
+            Sentence::NoWinner |
+            Sentence::NoQuorum { .. } |
+            Sentence::Invalid { .. } |
+            Sentence::Abstained { .. } => Vec::new(),
+            Sentence::Won { candidate, .. } |
+            Sentence::Seat { candidate, .. } |
+            Sentence::Patron { candidate, .. } => vec![candidate],
+            Sentence::Ring { members, .. } => members.iter().map(|m|&m[..]).collect(),
+            Sentence::OutscoredInRing { candidate, by, .. } =>
+                [candidate].into_iter().chain(by).map(|c|&c[..]).collect(),
+            Sentence::LostTieBreak { candidate, winner, .. } => vec![candidate, winner],
+            Sentence::NotPatron { candidate, outcome, .. } => match outcome {
+                PatronOutcome::NotBeatingSecondBest(_, other) |
+                PatronOutcome::NotBeatingSecondBestOutright(_, other) => vec![candidate, other],
+                _ => vec![candidate],
+            },
+        }
+    }
+}
+
+/// a, b and c
+fn list(names: &[String]) -> String {
+    match names {
+        [] => String::new(),
+        [one] => one.clone(),
+        [rest @ .., last] => format!("{} and {last}", rest.join(", ")),
+    }
+}
+
+/// 1 ballot, 2 ballots
+fn plural(n: impl Into<u128>, noun: &str) -> String {
+    let n = n.into();
+    if n == 1 {
+        format!("1 {noun}")
+    } else {
+        format!("{n} {noun}s")
+    }
+}
+
+impl fmt::Display for Sentence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Sentence::Won { candidate, votes } =>
+                write!(f, "{candidate} won with {votes} delegated votes."),
+            Sentence::NoWinner => write!(f, "There was no winner."),
+            Sentence::NoQuorum { ballots, votes } =>
+                write!(f, "No winner was chosen because the quorum was not reached, \
+                    with {ballots} ballots carrying {votes} votes."),
+            Sentence::Seat { seat, candidate, votes } =>
+                write!(f, "{candidate} took seat {seat} with {votes} delegated votes."),
+            Sentence::Invalid { cause, ballots } => {
+                let why = match cause {
+                    InvalidVoteCause::NoVote => "did not vote for anyone",
+                    InvalidVoteCause::SelfVote => "voted for themselves",
+                    InvalidVoteCause::UnrecognizedVote => "voted for someone who has no ballot",
+                    InvalidVoteCause::Duplicate => "duplicated another ballot",
+                };
+                write!(f, "{} {why}, so their votes were not delegated.", plural(*ballots, "ballot"))
+            }
+            Sentence::Abstained { ballots, votes } =>
+                write!(f, "{} carrying {} abstained.", plural(*ballots, "ballot"), plural(*votes, "vote")),
+            Sentence::Ring { members, votes } =>
+                write!(f, "{} delegated to one another, forming the strongest ring with {votes} votes.",
+                    list(members)),
+            Sentence::OutscoredInRing { candidate, votes, by } =>
+                write!(f, "{candidate} was eliminated within the ring with {votes} votes, fewer than {}.",
+                    list(by)),
+            Sentence::LostTieBreak { candidate, winner, votes } =>
+                write!(f, "{candidate} was tied with {winner} at {votes} votes and was eliminated \
+                    by the deterministic tie-break."),
+            Sentence::NotPatron { candidate, votes, outcome } => {
+                write!(f, "{candidate} could not act as patron because ")?;
+                match outcome {
+                    PatronOutcome::LoopCandidate => write!(f, "they were a member of the ring."),
+                    PatronOutcome::NotWillingCandidate => write!(f, "they are not a candidate."),
+                    PatronOutcome::NotProvidingMajority(mark) =>
+                        write!(f, "their {votes} votes were not more than {mark}, \
+                            half of the ring's votes."),
+                    PatronOutcome::NotBeatingSecondBest(runner_up, other) =>
+                        write!(f, "their {votes} votes did not beat the {runner_up} votes of {other}."),
+                    PatronOutcome::NotBeatingSecondBestOutright(needed, other) =>
+                        write!(f, "their {votes} votes were not more than the {needed} which \
+                            {other} could have had."),
+                    PatronOutcome::PatronFound => write!(f, "of an inconsistent count."),
+                }
+            }
+            Sentence::Patron { candidate, votes } =>
+                write!(f, "{candidate} was chosen as the patron of the ring with {votes} votes."),
+        }
+    }
+}
+
+/// The sentences of one count, see explain().
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Explanation {
+    pub sentences: Vec<Sentence>,
+}
+impl Explanation {
+    /// Every candidate referred to, each once, in the order they first appear
+    pub fn candidates(&self) -> Vec<&str> {
+        let mut seen = HashSet::new();
+        self.sentences.iter()
+            .flat_map(Sentence::candidates)
+            .filter(|c|seen.insert(*c))
+            .collect()
+    }
+}
+impl fmt::Display for Explanation {
+    /// The sentences as one paragraph for each decision
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, s) in self.sentences.iter().enumerate() {
+            let starts_paragraph = matches!(s,
+                Sentence::Won { .. } | Sentence::NoWinner | Sentence::NoQuorum { .. });
+            if i > 0 {
+                f.write_str(if starts_paragraph { "\n\n" } else { " " })?;
+            }
+            write!(f, "{s}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Explain the count whose events are trail. The ballots which were set aside or
+/// abstained come first, then for each decision, the winner followed by how they were
+/// chosen.
+pub fn explain(trail: &[EventRecord]) -> Explanation {
+    let mut out = Explanation::default();
+    // Each election of find_winners() ingests the ballots again
+    let mut seen_invalid = HashSet::new();
+    let mut invalid = Vec::<(InvalidVoteCause, u64)>::new();
+    let mut abstained = (HashSet::new(), 0);
+    // The reasons for the decision which is being made
+    let mut reasons = Vec::new();
+    for r in trail {
+        match r {
+            EventRecord::InvalidVote { cause, voter_id, .. } if seen_invalid.insert(voter_id) => {
+                match invalid.iter_mut().find(|(c, _)|c == cause) {
+                    Some((_, n)) => *n += 1,
+                    None => invalid.push((cause.clone(), 1)),
+                }
+            }
+            EventRecord::Abstention { voter_id, votes } if abstained.0.insert(voter_id) => {
+                abstained.1 += votes;
+            }
+            EventRecord::BestRing { rings, votes } => {
+                reasons.extend(rings.iter().map(|members|Sentence::Ring { members: members.clone(), votes: *votes }));
+            }
+            EventRecord::BestOfRing { scores, winners } => {
+                reasons.extend(scores.iter().filter(|(c, _)|!winners.contains(c)).map(|(c, votes)|{
+                    Sentence::OutscoredInRing { candidate: c.clone(), votes: *votes, by: winners.clone() }
+                }));
+            }
+            EventRecord::TieBreaker { votes, tied } => {
+                if let Some(((winner, _), rest)) = tied.split_first() {
+                    reasons.extend(rest.iter().map(|(c, _)|Sentence::LostTieBreak {
+                        candidate: c.clone(), winner: winner.clone(), votes: *votes,
+                    }));
+                }
+            }
+            EventRecord::PatronSelection { candidate, votes, outcome, .. } => {
+                reasons.push(match outcome {
+                    PatronOutcome::PatronFound => Sentence::Patron { candidate: candidate.clone(), votes: *votes },
+                    _ => Sentence::NotPatron { candidate: candidate.clone(), votes: *votes, outcome: outcome.clone() },
+                });
+            }
+            EventRecord::Winner { candidate, votes, .. } => {
+                out.sentences.push(match candidate {
+                    Some(c) => Sentence::Won { candidate: c.clone(), votes: *votes },
+                    None => Sentence::NoWinner,
+                });
+                out.sentences.append(&mut reasons);
+            }
+            EventRecord::NoQuorum { ballots, votes, .. } => {
+                out.sentences.push(Sentence::NoQuorum { ballots: *ballots, votes: *votes });
+                reasons.clear();
+            }
+            EventRecord::Seat { seat, candidate, votes } => {
+                out.sentences.push(Sentence::Seat { seat: *seat, candidate: candidate.clone(), votes: *votes });
+            }
+            _ => {}
+        }
+    }
+    let mut first = invalid.into_iter()
+        .map(|(cause, ballots)|Sentence::Invalid { cause, ballots })
+        .collect::<Vec<_>>();
+    if !abstained.0.is_empty() {
+        first.push(Sentence::Abstained { ballots: abstained.0.len() as u64, votes: abstained.1 });
+    }
+    out.sentences.splice(0..0, first);
+    out
+}
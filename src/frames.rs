@@ -0,0 +1,92 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! The recorded events of a count as frames for stepping through the algorithm in a
+//! user interface. Replay::initial is every voter with their own votes, and each Frame
+//! is one event with the delegated totals which it changed, so a front-end can show the
+//! totals growing one delegation at a time and then the decisions which pick the winner.
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use std::collections::BTreeMap;
+
+use crate::record::EventRecord;
+use crate::types::{Vote, Weight};
+
+/// Which part of the algorithm a frame belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Stage {
+    /// Ballots which are not counted, and policy decisions on the ballots
+    Ingestion,
+    /// Votes passed along chains of delegation
+    Delegation,
+    /// Choosing the winner from the delegated totals
+    Decision,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Frame {
+    pub stage: Stage,
+    pub event: EventRecord,
+    /// The new delegated total of each voter whose total this event changed
+    pub changes: Vec<(String, Weight)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Replay {
+    /// Each voter with their own votes, in the order of the ballots
+    pub initial: Vec<(String, Weight)>,
+    pub frames: Vec<Frame>,
+}
+
+/// Build the frames from the ballots and the records of their count, as produced by
+/// record::subscribe_all(). Progress records are left out.
+pub fn replay(votes: &[Vote], records: &[EventRecord]) -> Replay {
+    // The totals in the records are of the rescaled votes, if they were rescaled
+    let shift = records.iter().find_map(|r|match r {
+        EventRecord::WeightsRescaled { shift } => Some(*shift),
+        _ => None,
+    }).unwrap_or(0);
+    let mut totals = BTreeMap::new();
+    let mut initial = Vec::with_capacity(votes.len());
+    for v in votes {
+        if !totals.contains_key(&*v.voter_id) {
+            let own = v.number_of_votes.checked_shr(shift).unwrap_or(0);
+            totals.insert(v.voter_id.to_string(), own);
+            initial.push((v.voter_id.to_string(), own));
+        }
+    }
+    let mut frames = Vec::with_capacity(records.len());
+    for r in records {
+        let mut add = |to: &str, votes: Weight| {
+            let t = totals.entry(to.to_owned()).or_insert(0);
+            *t = t.saturating_add(votes);
+            vec![(to.to_owned(), *t)]
+        };
+        let (stage, changes) = match r {
+            EventRecord::Progress { .. } => continue,
+            EventRecord::InvalidVote { .. } |
+            EventRecord::Fallback { .. } |
+            EventRecord::Abstention { .. } |
+            EventRecord::PolicyDecision { .. } |
+            EventRecord::AliasApplied { .. } |
+            EventRecord::WeightsRescaled { .. } => (Stage::Ingestion, Vec::new()),
+            EventRecord::VoteDelegation { to, votes, .. } |
+            EventRecord::ImplicitDelegation { to, votes, .. } => (Stage::Delegation, add(to, *votes)),
+            EventRecord::VoteDelegationRing { .. } |
+            EventRecord::DeepDelegation { .. } => (Stage::Delegation, Vec::new()),
+            EventRecord::BestRing { .. } |
+            EventRecord::BestOfRing { .. } |
+            EventRecord::PatronSelection { .. } |
+            EventRecord::TieBreakerHash { .. } |
+            EventRecord::TieBreaker { .. } |
+            EventRecord::TieBreakSalt { .. } |
+            EventRecord::Winner { .. } |
+            EventRecord::Seat { .. } |
+            EventRecord::NoQuorum { .. } => (Stage::Decision, Vec::new()),
+        };
+        frames.push(Frame { stage, event: r.clone(), changes });
+    }
+    Replay { initial, frames }
+}
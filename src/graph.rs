@@ -0,0 +1,82 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! Export of the delegation graph for publication, in Graphviz DOT.
+//!
+//! Willing candidates are shown by name. Voters who are not candidates can be shown by
+//! name (for administrators) or, with export_anonymized(), only as groups of at least k
+//! voters who delegated to the same person, so that no exported node identifies one
+//! voter. Groups of fewer than k are left out and reported, the totals of the candidates
+//! always include their votes.
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::types::Weight;
+use crate::VoteCounter;
+
+/// A group of voters which was left out of an anonymized export.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suppressed {
+    /// The voter_id of the candidate they delegated to
+    pub target: String,
+    pub voters: usize,
+    pub votes: Weight,
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn header(out: &mut String, vc: &VoteCounter<'_>) {
+    out.push_str("digraph delegation {\n");
+    for c in vc.find(|c|c.vote.willing_candidate) {
+        let _ = writeln!(out, "  {} [label={}];", quote(&c.vote.voter_id),
+            quote(&format!("{}\n{}", c.vote.voter_id, c.total_votes)));
+        if let Some(to) = c.vote_for {
+            let _ = writeln!(out, "  {} -> {};", quote(&c.vote.voter_id), quote(&to.voter_id));
+        }
+    }
+}
+
+/// The whole graph, with every voter by name.
+pub fn export(vc: &VoteCounter<'_>) -> String {
+    let mut out = String::new();
+    header(&mut out, vc);
+    for c in vc.find(|c|!c.vote.willing_candidate) {
+        let _ = writeln!(out, "  {} [shape=box];", quote(&c.vote.voter_id));
+        if let Some(to) = c.vote_for {
+            let _ = writeln!(out, "  {} -> {} [label={}];", quote(&c.vote.voter_id),
+                quote(&to.voter_id), c.total_votes);
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// The graph with the voters who are not candidates grouped by who they delegated to,
+/// groups of fewer than k voters, and voters who delegated to a voter who is not a
+/// candidate, are left out and returned.
+pub fn export_anonymized(vc: &VoteCounter<'_>, k: usize) -> (String, Vec<Suppressed>) {
+    let mut out = String::new();
+    header(&mut out, vc);
+    let mut groups: BTreeMap<&str, (usize, Weight)> = BTreeMap::new();
+    for c in vc.find(|c|!c.vote.willing_candidate) {
+        if let Some(to) = c.vote_for {
+            let g = groups.entry(&*to.voter_id).or_default();
+            g.0 += 1;
+            g.1 += c.total_votes;
+        }
+    }
+    let mut suppressed = Vec::new();
+    for (target, (voters, votes)) in groups {
+        // A group which delegated to another voter would name that voter
+        let named = vc.find(|c|c.vote.voter_id == target).iter().all(|c|c.vote.willing_candidate);
+        if voters < k || !named {
+            suppressed.push(Suppressed { target: target.to_owned(), voters, votes });
+            continue;
+        }
+        let name = format!("{} voters of {}", voters, target);
+        let _ = writeln!(out, "  {} [shape=box,label={}];", quote(&name), quote(&format!("{} voters", voters)));
+        let _ = writeln!(out, "  {} -> {} [label={}];", quote(&name), quote(target), votes);
+    }
+    out.push_str("}\n");
+    (out, suppressed)
+}
@@ -0,0 +1,124 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! Results for each subgroup of the voters, such as the chapters of a federation,
+//! alongside the overall result, from one set of ballots.
+//!
+//! Each group is counted as an election of its own members only: a vote for someone
+//! outside of the group is a vote for someone who has no ballot, and is handled
+//! according to the UnrecognizedVotePolicy in the options.
+use std::collections::BTreeMap;
+
+use crate::introspector::Introspector;
+use crate::options::ElectionOptions;
+use crate::result::ElectionResult;
+use crate::types::{Vote, Weight, TallyError, widen, narrow};
+use crate::VoteCounter;
+
+/// The result of one group.
+#[derive(Debug, Clone)]
+pub struct GroupResult<'a> {
+    pub group: String,
+    /// The number of ballots in the group
+    pub ballots: u64,
+    /// The sum of the votes on those ballots, before any rescaling under
+    /// OverflowPolicy::Rescale, Weight::MAX if they add up to more
+    pub votes: Weight,
+    pub result: ElectionResult,
+    /// The ranking of the group's candidates, as VoteCounter::iter()
+    pub ranking: Vec<(Weight, &'a Vote<'a>)>,
+}
+
+/// The overall result and the result of each group, ordered by group.
+#[derive(Debug, Clone)]
+pub struct GroupedResult<'a> {
+    pub overall: ElectionResult,
+    pub groups: Vec<GroupResult<'a>>,
+}
+
+/// Count the ballots overall and within each group, as given by group_of. Ballots for
+/// which group_of returns None only count toward the overall result.
+pub fn tally_by_group<'a, 'g>(
+    votes: &'a [Vote],
+    options: ElectionOptions,
+    group_of: impl Fn(&'a Vote<'a>) -> Option<&'g str>,
+) -> Result<GroupedResult<'a>, TallyError> {
+    let mut members: BTreeMap<&str, Vec<&'a Vote<'a>>> = BTreeMap::new();
+    for v in votes {
+        if let Some(g) = group_of(v) {
+            members.entry(g).or_default().push(v);
+        }
+    }
+    let overall = VoteCounter::with_options(votes, Introspector::default(), options.clone()).tally()?;
+    let mut groups = Vec::with_capacity(members.len());
+    for (group, members) in members {
+        let ballots = members.len() as u64;
+        let votes = members.iter().map(|v|v.number_of_votes).fold(0, Weight::saturating_add);
+        let mut vc = VoteCounter::with_selected(members, Introspector::default(), options.clone());
+        let ranking = vc.iter().collect();
+        let result = vc.tally()?;
+        groups.push(GroupResult { group: group.to_owned(), ballots, votes, result, ranking });
+    }
+    Ok(GroupedResult { overall, groups })
+}
+
+/// A group which did not reach quorum.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuorumShortfall {
+    pub group: String,
+    /// The votes on the group's ballots
+    pub participating: Weight,
+    /// The votes which the group's members could have cast
+    pub eligible: Weight,
+    /// The least number of participating votes which would have reached quorum
+    pub required: Weight,
+}
+
+/// The groups which did not reach quorum, ordered by group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuorumFailure {
+    pub shortfalls: Vec<QuorumShortfall>,
+}
+impl std::fmt::Display for QuorumFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Quorum not reached in:")?;
+        for s in &self.shortfalls {
+            write!(f, " {} ({} of {} needed)", s.group, s.participating, s.required)?;
+        }
+        Ok(())
+    }
+}
+impl std::error::Error for QuorumFailure {}
+
+/// Check that in every group of eligible, the votes cast are at least percent of the
+/// eligible votes. Participation is weighted, so with one vote per member it is the share
+/// of members who cast a ballot. Groups which are not in eligible are not checked, and a
+/// group in eligible which has no ballots at all has no participation.
+pub fn check_quorum(
+    grouped: &GroupedResult<'_>,
+    eligible: &BTreeMap<String, Weight>,
+    percent: u64,
+) -> Result<(), QuorumFailure> {
+    let participating = grouped.groups.iter()
+        .map(|g|(g.group.as_str(), g.votes))
+        .collect::<BTreeMap<_, _>>();
+    let mut shortfalls = Vec::new();
+    for (group, &eligible) in eligible {
+        let participating = participating.get(group.as_str()).copied().unwrap_or(0);
+        // ceil(eligible * percent / 100) without overflow, even for wide weights
+        let (hundreds, rest) = (widen(eligible) / 100, widen(eligible) % 100);
+        let required = hundreds.saturating_mul(u128::from(percent))
+            .saturating_add((rest * u128::from(percent)).div_ceil(100));
+        if widen(participating) < required {
+            shortfalls.push(QuorumShortfall {
+                group: group.clone(),
+                participating,
+                eligible,
+                required: narrow(required),
+            });
+        }
+    }
+    if shortfalls.is_empty() {
+        Ok(())
+    } else {
+        Err(QuorumFailure { shortfalls })
+    }
+}
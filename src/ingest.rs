@@ -0,0 +1,160 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! Helpers for preparing ballots before they are counted.
+use std::collections::HashSet;
+
+use crate::types::{Vote, widen, narrow};
+
+/// A ballot for every ID which is voted for but has no ballot of its own, with no votes,
+/// not willing and not voting for anyone.
+///
+/// Appending these to the ballots makes each of those IDs a node in the delegation graph,
+/// so the votes delegated to them appear in every event and in rings() like any other
+/// voter. This is the same as counting with UnrecognizedVotePolicy::ImplicitVoter, except
+/// that the nodes are real ballots which the caller can later replace with the ballot
+/// that the delegate files, at which point their delegation continues onward.
+pub fn implicit_ballots(votes: &[Vote]) -> Vec<Vote<'static>> {
+    let known = votes.iter().map(|v|&*v.voter_id).collect::<HashSet<_>>();
+    let mut added = HashSet::new();
+    let mut out = Vec::new();
+    for v in votes {
+        let Some(target) = v.vote_for.as_deref() else {
+            continue;
+        };
+        if known.contains(target) || !added.insert(target) {
+            continue;
+        }
+        out.push(Vote {
+            voter_id: target.to_owned().into(),
+            vote_for: None,
+            number_of_votes: 0,
+            willing_candidate: false,
+            metadata: Default::default(),
+            fallbacks: Vec::new(),
+            abstain: false,
+        });
+    }
+    out
+}
+
+/// Why split_ballot() could not split a ballot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SplitError {
+    /// There are no shares, or their parts add up to zero
+    NoShares,
+    /// The parts add up to more than u64::MAX
+    TooManyParts,
+    /// A share is for the voter themselves
+    SelfShare,
+    /// The voter is a willing candidate, whose own total would only count the first share
+    Candidate,
+    /// The voter_id of a ballot which votes for the voter, or names them as a fallback,
+    /// those votes could only pass on with one of the shares
+    Delegated(String),
+    /// The ID of a share ballot is already a voter or voted for
+    IdTaken(String),
+}
+impl std::fmt::Display for SplitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SplitError::NoShares => write!(f, "The ballot is not split between anyone"),
+            SplitError::TooManyParts => write!(f, "The parts of the shares add up to more than {}", u64::MAX),
+            SplitError::SelfShare => write!(f, "A share of the ballot is for the voter themselves"),
+            SplitError::Candidate => write!(f, "The ballot of a willing candidate can not be split"),
+            SplitError::Delegated(by) => write!(f, "The ballot can not be split, {} votes for it", by),
+            SplitError::IdTaken(id) => write!(f, "The share ID {} is already in use", id),
+        }
+    }
+}
+impl std::error::Error for SplitError {}
+
+/// Split a ballot's votes between several candidates, given as (vote_for, parts), for
+/// instance 60 parts to Alice and 40 to Bob, where votes are the other ballots of the
+/// election. The count is over whole votes and each ballot votes for one candidate, so
+/// the result is ballots which the count already understands, to use in place of vote:
+/// the voter's own ballot, which carries the first share, and an unwilling ballot with
+/// voter_id `<voter_id>#<n>` for the nth other share (from 1), which appears in the
+/// events like any other voter. Every share keeps the ballot's fallbacks, so a share for
+/// someone who can not be voted for moves on like the ballot would have.
+///
+/// Each share gets its proportion of number_of_votes rounded down, then the votes which
+/// are left go one each to the shares with the largest remainders, the earlier share on
+/// a tie, so the shares always add up to number_of_votes. Other shares which round to
+/// nothing get no ballot. The parts may add up to at most u64::MAX.
+///
+/// Only a voter who nobody delegates to and who is not a candidate can be split. Votes
+/// delegated to them could only pass on with one share, and a candidate's total would
+/// only count the first. Because of that the voter and their share ballots are never in
+/// a ring and never a patron, so the ring and patron scoring are the same as if each
+/// share were the ballot of a separate voter. A share ID which is already used by votes,
+/// as a voter_id, vote_for or fallback, is refused rather than merged with it.
+pub fn split_ballot(
+    votes: &[Vote<'_>],
+    vote: &Vote<'_>,
+    shares: &[(&str, u64)],
+) -> Result<Vec<Vote<'static>>, SplitError> {
+    let parts = shares.iter().map(|&(_, p)|u128::from(p)).sum::<u128>();
+    if parts == 0 {
+        return Err(SplitError::NoShares);
+    }
+    if parts > u128::from(u64::MAX) {
+        return Err(SplitError::TooManyParts);
+    }
+    if shares.iter().any(|&(to, _)|to == vote.voter_id) {
+        return Err(SplitError::SelfShare);
+    }
+    if vote.willing_candidate {
+        return Err(SplitError::Candidate);
+    }
+    let delegates_to = |v: &Vote<'_>, id: &str| {
+        v.vote_for.as_deref() == Some(id) || v.fallbacks.iter().any(|f|f == id)
+    };
+    if let Some(by) = votes.iter().find(|v|v.voter_id != vote.voter_id && delegates_to(v, &vote.voter_id)) {
+        return Err(SplitError::Delegated(by.voter_id.to_string()));
+    }
+    // total * p / parts and its remainder, as q * p + r * p / parts so that a wide weight
+    // does not overflow, r is less than parts so r * p fits while parts fits in a u64
+    let total = widen(vote.number_of_votes);
+    let (q, r) = (total / parts, total % parts);
+    let share = |p: u64| {
+        let rp = r * u128::from(p);
+        (q * u128::from(p) + rp / parts, rp % parts)
+    };
+    let mut weights = shares.iter().map(|&(_, p)|share(p).0).collect::<Vec<_>>();
+    let mut by_remainder = (0..shares.len()).collect::<Vec<_>>();
+    by_remainder.sort_by_key(|&i|std::cmp::Reverse(share(shares[i].1).1));
+    let left = total.saturating_sub(weights.iter().sum::<u128>());
+    for &i in by_remainder.iter().take(usize::try_from(left).unwrap_or(usize::MAX)) {
+        weights[i] += 1;
+    }
+    let fallbacks = vote.fallbacks.iter()
+        .filter(|f|**f != *vote.voter_id)
+        .map(|f|f.to_string().into())
+        .collect::<Vec<_>>();
+    let mut out = Vec::with_capacity(shares.len());
+    for (n, (&(to, _), w)) in shares.iter().zip(weights).enumerate() {
+        // Every weight is at most number_of_votes
+        let w = narrow(w);
+        if n == 0 {
+            let mut own = vote.to_owned_vote();
+            own.vote_for = Some(to.to_owned().into());
+            own.number_of_votes = w;
+            own.fallbacks = fallbacks.clone();
+            out.push(own);
+        } else if w > 0 {
+            let id = format!("{}#{}", vote.voter_id, n);
+            if votes.iter().any(|v|v.voter_id == id || delegates_to(v, &id)) {
+                return Err(SplitError::IdTaken(id));
+            }
+            out.push(Vote {
+                voter_id: id.into(),
+                vote_for: Some(to.to_owned().into()),
+                number_of_votes: w,
+                willing_candidate: false,
+                metadata: Default::default(),
+                fallbacks: fallbacks.clone(),
+                abstain: false,
+            });
+        }
+    }
+    Ok(out)
+}
@@ -0,0 +1,153 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! Storing ballots with each ID kept once, for holding millions of ballots between
+//! ingestion and the count. Each distinct ID gets a Handle, and an IdBallot refers to
+//! the voter and the person they voted for by handle, so an ID which is voted for by
+//! many ballots is stored and hashed only once.
+//!
+//! VoteCounter::with_interned() counts them by handle: the candidates are found by the
+//! handle of their voter_id and the delegations by the handle of whom they voted for, so
+//! no ID is hashed after it was interned, and the delegation works on the indexes of the
+//! candidates which those lead to. Events still refer to Votes, because every subscriber
+//! of the Introspector receives Votes, so InternedBallots::to_votes() makes a Vote of
+//! each ballot for counting whose IDs are borrowed from the Interner rather than copied.
+//! A VoteCounter which is changed with add_vote() or remove_vote() finds people by ID.
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::types::{Vote, Metadata, Weight};
+
+/// A compact reference to an ID in an Interner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Handle(u32);
+impl Handle {
+    pub(crate) fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Interner {
+    ids: Vec<Arc<str>>,
+    handles: HashMap<Arc<str>, Handle>,
+}
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// The handle of id, adding it if it is new. None if there are already u32::MAX IDs.
+    pub fn intern(&mut self, id: &str) -> Option<Handle> {
+        if let Some(&h) = self.handles.get(id) {
+            return Some(h);
+        }
+        let h = Handle(u32::try_from(self.ids.len()).ok().filter(|&n|n < u32::MAX)?);
+        let id: Arc<str> = id.into();
+        self.ids.push(id.clone());
+        self.handles.insert(id, h);
+        Some(h)
+    }
+    /// The handle of id, if it was interned
+    pub fn get(&self, id: &str) -> Option<Handle> {
+        self.handles.get(id).copied()
+    }
+    /// The ID of a handle, None if it came from another Interner
+    pub fn resolve(&self, h: Handle) -> Option<&str> {
+        self.ids.get(h.0 as usize).map(|id|&**id)
+    }
+    /// The number of distinct IDs
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+}
+
+/// A ballot whose IDs are handles, see Vote for the fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdBallot {
+    pub voter: Handle,
+    /// None if they did not vote
+    pub vote_for: Option<Handle>,
+    pub number_of_votes: Weight,
+    pub willing_candidate: bool,
+    pub abstain: bool,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct InternedBallots {
+    pub ids: Interner,
+    pub ballots: Vec<IdBallot>,
+    /// The Vote::fallbacks of the ballots which have any, by index in ballots
+    pub fallbacks: HashMap<usize, Vec<Handle>>,
+    /// The Vote::metadata of the ballots which have any, by index in ballots
+    pub metadata: HashMap<usize, Metadata>,
+}
+impl InternedBallots {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Intern every ballot, None if there are too many distinct IDs
+    pub fn from_votes(votes: &[Vote]) -> Option<Self> {
+        let mut out = Self::new();
+        out.ballots.reserve(votes.len());
+        for v in votes {
+            out.push(v)?;
+        }
+        Some(out)
+    }
+    /// Add a ballot, None if there are too many distinct IDs
+    pub fn push(&mut self, v: &Vote) -> Option<()> {
+        let voter = self.ids.intern(&v.voter_id)?;
+        let vote_for = match v.vote_for.as_deref() {
+            Some(vote_for) => Some(self.ids.intern(vote_for)?),
+            None => None,
+        };
+        if !v.fallbacks.is_empty() {
+            let fallbacks = v.fallbacks.iter().map(|f|self.ids.intern(f)).collect::<Option<Vec<_>>>()?;
+            self.fallbacks.insert(self.ballots.len(), fallbacks);
+        }
+        if !v.metadata.is_empty() {
+            self.metadata.insert(self.ballots.len(), v.metadata.clone().into_owned());
+        }
+        self.ballots.push(IdBallot {
+            voter,
+            vote_for,
+            number_of_votes: v.number_of_votes,
+            willing_candidate: v.willing_candidate,
+            abstain: v.abstain,
+        });
+        Some(())
+    }
+    /// Whether votes are these ballots, in the same order, as made by to_votes(). The IDs
+    /// are compared with those of the handles, without hashing them.
+    pub fn describes(&self, votes: &[Vote]) -> bool {
+        let is = |h: &Handle, id: &str| self.ids.resolve(*h) == Some(id);
+        votes.len() == self.ballots.len() && self.ballots.iter().zip(votes).enumerate().all(|(i, (b, v))|{
+            let fallbacks = self.fallbacks.get(&i).map_or(&[][..], |f|&f[..]);
+            is(&b.voter, &v.voter_id)
+                && b.vote_for.as_ref().map(|h|self.ids.resolve(*h)) == v.vote_for.as_deref().map(Some)
+                && fallbacks.len() == v.fallbacks.len()
+                && fallbacks.iter().zip(&v.fallbacks).all(|(h, f)|is(h, f))
+        })
+    }
+    /// The ballots as Votes which borrow their IDs from the Interner and their metadata
+    /// from this, for counting
+    pub fn to_votes(&self) -> Vec<Vote<'_>> {
+        self.ballots.iter().enumerate().map(|(i, b)|Vote {
+            voter_id: self.ids.resolve(b.voter).unwrap_or_default().into(),
+            vote_for: b.vote_for.and_then(|h|self.ids.resolve(h)).map(Into::into),
+            number_of_votes: b.number_of_votes,
+            willing_candidate: b.willing_candidate,
+            metadata: match self.metadata.get(&i) {
+                Some(m) => Cow::Borrowed(m),
+                None => Default::default(),
+            },
+            fallbacks: self.fallbacks.get(&i).into_iter().flatten()
+                .filter_map(|&h|self.ids.resolve(h))
+                .map(Into::into)
+                .collect(),
+            abstain: b.abstain,
+        }).collect()
+    }
+}
@@ -2,30 +2,74 @@
 #![allow(non_camel_case_types)] // better_any derive needs this
 
 use std::{collections::HashMap, marker::PhantomData};
-use std::any::TypeId;
+use std::any::{Any, TypeId};
+use std::time::{Duration, Instant};
 
 use better_any::{Tid, TidAble, TidExt};
 
-use crate::types::Vote;
+use crate::types::{Vote, Weight};
+use crate::TallyState;
 
 /// A marker trait for each struct that can be used as an introspector event.
 pub trait Event<'a>: Tid<'a> {}
 
 #[derive(Tid)]
 pub struct VoteDelegation<'a> {
-    pub from: &'a Vote,
-    pub to: &'a Vote,
-    pub because_of: &'a Vote,
+    pub from: &'a Vote<'a>,
+    pub to: &'a Vote<'a>,
+    pub because_of: &'a Vote<'a>,
+    /// The votes which were delegated, from's own votes
+    pub votes: Weight,
 }
 impl<'a> Event<'a> for VoteDelegation<'a> {}
 
+/// A chain of delegations reached someone who was already in it, so it stops there.
 #[derive(Tid)]
 pub struct VoteDelegationRing<'a> {
-    pub chain: Vec<&'a Vote>,
-    pub next: &'a Vote,
+    /// The chain from the voter whose votes are being delegated, chain[0], to the last
+    /// person who received them
+    pub chain: Vec<&'a Vote<'a>>,
+    /// The person in the chain who the last one voted for
+    pub next: &'a Vote<'a>,
+    /// The voter whose votes were delegated along the chain
+    pub from: &'a Vote<'a>,
+    /// The number of votes which were delegated
+    pub votes: Weight,
+    /// The last person in the chain, where the votes stopped. Every member of the chain
+    /// received the votes but they go no further than this.
+    pub stopped_at: &'a Vote<'a>,
 }
 impl<'a> Event<'a> for VoteDelegationRing<'a> {}
 
+/// A chain of delegations was longer than the deep delegation depth (see
+/// Introspector::set_deep_delegation_depth). Long chains are allowed, but they can be a
+/// sign of a ladder of fake voters, so they are worth looking at.
+#[derive(Tid)]
+pub struct DeepDelegation<'a> {
+    /// The voter at the start of the chain
+    pub from: &'a Vote<'a>,
+    /// The number of delegations in the chain
+    pub depth: usize,
+    /// The chain from the voter to the last person who received their votes
+    pub chain: Vec<&'a Vote<'a>>,
+}
+impl<'a> Event<'a> for DeepDelegation<'a> {}
+
+/// Votes delegated to someone who has no ballot, under UnrecognizedVotePolicy::ImplicitVoter.
+/// The delegation stops there because they did not vote.
+#[derive(Tid)]
+pub struct ImplicitDelegation<'a> {
+    pub from: &'a Vote<'a>,
+    /// The voter_id of the implicit voter
+    pub to: &'a str,
+    pub because_of: &'a Vote<'a>,
+    /// The votes which were delegated, from's own votes
+    pub votes: Weight,
+}
+impl<'a> Event<'a> for ImplicitDelegation<'a> {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum InvalidVoteCause {
     NoVote,
     SelfVote,
@@ -36,21 +80,53 @@ pub enum InvalidVoteCause {
 #[derive(Tid)]
 pub struct InvalidVote<'a> {
     pub cause: InvalidVoteCause,
-    pub vote: &'a Vote,
+    pub vote: &'a Vote<'a>,
 }
 impl<'a> Event<'a> for InvalidVote<'a> {}
 
+/// Why a choice on a ballot with fallbacks was passed over, see Vote::fallbacks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FallbackCause {
+    SelfVote,
+    /// There is no ballot with this ID
+    Unrecognized,
+    /// They are not a willing candidate, or have been eliminated, for instance by
+    /// already taking a seat in VoteCounter::find_winners()
+    Unwilling,
+}
+
+/// A ballot's vote fell through from one of its choices to the next
+#[derive(Tid)]
+pub struct Fallback<'a> {
+    pub vote: &'a Vote<'a>,
+    /// The choice which was passed over
+    pub skipped: &'a str,
+    pub cause: FallbackCause,
+    /// The choice which is tried next, None if there are no more, in which case the
+    /// ballot is counted for its first choice as if it had no fallbacks
+    pub next: Option<&'a str>,
+}
+impl<'a> Event<'a> for Fallback<'a> {}
+
+/// A ballot was cast as an abstention, see Vote::abstain
+#[derive(Tid)]
+pub struct Abstention<'a> {
+    pub vote: &'a Vote<'a>,
+}
+impl<'a> Event<'a> for Abstention<'a> {}
+
 #[derive(Tid)]
 pub struct BestRing<'a> {
-    pub best_total_delegated_votes: u64,
-    pub best_rings_members: Vec<Vec<&'a Vote>>,
+    pub best_total_delegated_votes: Weight,
+    pub best_rings_members: Vec<Vec<&'a Vote<'a>>>,
 }
 impl<'a> Event<'a> for BestRing<'a> {}
 
 #[derive(Tid)]
 pub struct BestOfRing<'a> {
-    pub rings_member_scores: Vec<(&'a Vote, u64)>,
-    pub winners: Vec<&'a Vote>,
+    pub rings_member_scores: Vec<(&'a Vote<'a>, Weight)>,
+    pub winners: Vec<&'a Vote<'a>>,
 }
 impl<'a> Event<'a> for BestOfRing<'a> {}
 
@@ -62,37 +138,57 @@ pub enum PatronSelectionReason<'a> {
     NotWillingCandidate,
 
     /// The potential patron is not providing a majority of the votes to the candidate
-    NotProvidingMajority(u64),
+    NotProvidingMajority(Weight),
 
     /// The potential patron would not have enough votes to beat the second best ring,
     /// so since they can't beat second best, they lose and thus delegate their votes.
     /// The arguments are: number of votes in the 2nd best ring, and node in the 2nd best
     /// ring with that number of votes.
-    NotBeatingSecondBest(u64, &'a Vote),
+    NotBeatingSecondBest(Weight, &'a Vote<'a>),
+
+    /// Under PatronRule::Strict, the potential patron would not beat the second best if
+    /// all of the tenative winner's other votes went to the second best.
+    /// The arguments are: the number of votes which the potential patron needed to
+    /// exceed, and node in the 2nd best ring.
+    NotBeatingSecondBestOutright(Weight, &'a Vote<'a>),
 
     /// The patron was selected
     PatronFound,
 }
 
+/// The numbers which a patron is checked against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PatronMath {
+    /// The total delegated votes of the tenative winner
+    pub tenative_winner_votes: Weight,
+    /// Half of the tenative winner's votes, a patron must have more than this
+    pub mark_to_beat: Weight,
+    /// The total delegated votes of the runner-up which the patron is compared to, if any
+    pub runner_up_votes: Option<Weight>,
+}
+
 #[derive(Tid)]
 pub struct PatronSelection<'a> {
     /// The potential patron whom we are considering
-    pub potential_patron: &'a Vote,
+    pub potential_patron: &'a Vote<'a>,
     /// The total number of delegated votes of the potential patron
-    pub potential_patron_votes: u64,
+    pub potential_patron_votes: Weight,
     /// The selection, whether the potential patron IS the patron, or if not, why not.
     pub selection: PatronSelectionReason<'a>,
+    /// The numbers which the potential patron was checked against
+    pub math: PatronMath,
 }
 impl<'a> Event<'a> for PatronSelection<'a> {}
 
 #[derive(Tid)]
 pub struct DeterministicTieBreaker<'a> {
     /// The number of total delegated votes which each of the winners received.
-    pub votes: u64,
+    pub votes: Weight,
     /// The candidates who are tied with this number of votes, along with their hash
-    /// of name + number of votes. These are ordered by the hash, so the first one is the
-    /// final winner.
-    pub tied_candidates: Vec<(&'a Vote, [u8;64])>,
+    /// of name + number of votes + ElectionOptions::tie_break_salt. These are ordered by
+    /// the hash, so the first one is the final winner.
+    pub tied_candidates: Vec<(&'a Vote<'a>, [u8;64])>,
 }
 impl<'a> Event<'a> for DeterministicTieBreaker<'a> {}
 
@@ -100,24 +196,142 @@ impl<'a> Event<'a> for DeterministicTieBreaker<'a> {}
 pub struct DeterministicTieBreakerHash {
     /// The candidate's ID
     pub candidate: String,
-    /// The bytes which are hashed for the candidate
+    /// The bytes which are hashed for the candidate, their ID, votes and any
+    /// ElectionOptions::tie_break_salt
     pub bytes: Vec<u8>,
     /// Total number of possible indirect votes
-    pub total_indirect_votes: u64,
+    pub total_indirect_votes: Weight,
 }
 impl<'a> Event<'a> for DeterministicTieBreakerHash {}
 
+/// Where a candidate's total delegated votes came from. The parts always add up to the
+/// total: own + every supporter + every ring member.
+#[derive(Debug, Clone)]
+pub struct Contributions<'a> {
+    /// The candidate's own votes
+    pub own: Weight,
+    /// Each candidate who voted directly for them (outside of their ring), with the
+    /// total delegated votes which that candidate passed on, largest first
+    pub supporters: Vec<(&'a Vote<'a>, Weight)>,
+    /// Each other member of their delegation ring, with the votes which arrived through
+    /// that member: the member's own votes and those of the member's supporters from
+    /// outside the ring, largest first
+    pub through_ring: Vec<(&'a Vote<'a>, Weight)>,
+}
+
 #[derive(Tid)]
 pub struct Winner<'a> {
     /// The candidate who finally won
-    pub candidate: &'a Vote,
+    pub candidate: &'a Vote<'a>,
     /// The number of votes which they received
-    pub votes: u64,
+    pub votes: Weight,
+    /// Where those votes came from
+    pub contributions: Contributions<'a>,
 }
 impl<'a> Event<'a> for Option<Winner<'a>> {}
 
+/// Fewer ballots or votes were counted than the ElectionOptions require, so no winner is
+/// declared and the count fails with TallyError::NoQuorum. This comes instead of the
+/// Winner event.
+#[derive(Tid)]
+pub struct NoQuorum {
+    /// The ballots which were counted
+    pub ballots: u64,
+    /// The votes on those ballots, after any rescaling
+    pub votes: Weight,
+    pub min_ballots: Option<u64>,
+    pub min_votes: Option<Weight>,
+}
+impl<'a> Event<'a> for NoQuorum {}
+
+/// A seat of a multi-winner election was filled, see VoteCounter::find_winners().
+#[derive(Tid)]
+pub struct Seat<'a> {
+    /// Which seat, starting from 1
+    pub seat: usize,
+    pub candidate: &'a Vote<'a>,
+    /// Their delegated votes in the count which filled this seat
+    pub votes: Weight,
+}
+impl<'a> Event<'a> for Seat<'a> {}
+
+/// The stage of the tally which a Progress event refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Phase {
+    /// Reading the ballots
+    Ingestion,
+    /// Delegating each ballot's votes along its chain of delegations
+    Delegation,
+    /// Ordering the candidates by their delegated votes
+    Ranking,
+}
+
+/// Emitted periodically during long phases, at most once per progress interval
+/// (see Introspector::set_progress_interval) plus once at the start and end of each phase.
+#[derive(Tid)]
+pub struct Progress {
+    pub phase: Phase,
+    /// The number of items done in this phase
+    pub processed: u64,
+    /// The number of items in this phase
+    pub total: u64,
+    /// Extrapolated from the time taken so far in this phase, None at the start
+    pub estimated_remaining: Option<Duration>,
+}
+impl<'a> Event<'a> for Progress {}
+
+/// A BallotPolicy changed or vetoed a ballot before the count, see policy::apply().
+#[derive(Tid)]
+pub struct PolicyDecision {
+    /// The ballot which the policy acted on
+    pub voter_id: String,
+    pub action: crate::policy::PolicyAction,
+}
+impl<'a> Event<'a> for PolicyDecision {}
+
+/// A vote_for or fallback was an alias and was replaced by the ID it stands for before
+/// the count, see alias::apply().
+#[derive(Tid)]
+pub struct AliasApplied {
+    /// The ballot which named the alias
+    pub voter_id: String,
+    pub alias: String,
+    pub resolved: String,
+    /// The name of the AliasTable which has the alias
+    pub table: String,
+}
+impl<'a> Event<'a> for AliasApplied {}
+
+/// Every ballot's votes were shifted right by shift bits before counting, under
+/// OverflowPolicy::Rescale. Emitted before any other event of the ingestion.
+#[derive(Tid)]
+pub struct WeightsRescaled {
+    pub shift: u32,
+    /// The total votes of the ballots before they were shifted
+    pub total: u128,
+}
+impl<'a> Event<'a> for WeightsRescaled {}
+
+/// Progress is only checked every this many items so the clock is not read for each one
+const PROGRESS_CHECK_EVERY: u64 = 1024;
+
+struct ProgressState {
+    interval: Duration,
+    phase_start: Instant,
+    last: Instant,
+}
+impl Default for ProgressState {
+    fn default() -> Self {
+        let now = Instant::now();
+        Self { interval: Duration::from_millis(250), phase_start: now, last: now }
+    }
+}
+
 trait Callable<'a> {
-    fn call(&mut self, t: &dyn Event<'a>);
+    fn call(&mut self, t: &dyn Event<'a>, state: Option<&TallyState<'_, 'a>>);
+    fn context_mut(&mut self) -> &mut dyn Any;
+    fn into_context(self: Box<Self>) -> Box<dyn Any>;
 }
 struct FnCallable<'a, C: 'static, R: Event<'a>> {
     f: fn(&mut C, &R),
@@ -125,21 +339,67 @@ struct FnCallable<'a, C: 'static, R: Event<'a>> {
     _a: PhantomData<&'a R>,
 }
 impl<'a, C: 'static, R: Event<'a>> Callable<'a> for FnCallable<'a, C, R> {
-    fn call(&mut self, t: &dyn Event<'a>) {
+    fn call(&mut self, t: &dyn Event<'a>, _: Option<&TallyState<'_, 'a>>) {
         if let Some(t) = t.downcast_ref() {
             (self.f)(&mut self.c, t);
         } else {
             println!("Warning: Unable to downcast");
         }
     }
+    fn context_mut(&mut self) -> &mut dyn Any {
+        &mut self.c
+    }
+    fn into_context(self: Box<Self>) -> Box<dyn Any> {
+        Box::new(self.c)
+    }
+}
+type StateFn<'a, C, R> = fn(&mut C, &R, Option<&TallyState<'_, 'a>>);
+struct StateFnCallable<'a, C: 'static, R: Event<'a>> {
+    f: StateFn<'a, C, R>,
+    c: C,
+    _a: PhantomData<&'a R>,
+}
+impl<'a, C: 'static, R: Event<'a>> Callable<'a> for StateFnCallable<'a, C, R> {
+    fn call(&mut self, t: &dyn Event<'a>, state: Option<&TallyState<'_, 'a>>) {
+        if let Some(t) = t.downcast_ref() {
+            (self.f)(&mut self.c, t, state);
+        } else {
+            println!("Warning: Unable to downcast");
+        }
+    }
+    fn context_mut(&mut self) -> &mut dyn Any {
+        &mut self.c
+    }
+    fn into_context(self: Box<Self>) -> Box<dyn Any> {
+        Box::new(self.c)
+    }
+}
+/// A subscriber, with the order in which it was subscribed
+struct Handler<'a> {
+    seq: usize,
+    callable: Box<dyn Callable<'a> + 'a>,
 }
 #[derive(Default)]
 pub struct Introspector<'a> {
-    handlers: HashMap<TypeId, Vec<Box<dyn Callable<'a> + 'a>>>,
+    handlers: HashMap<TypeId, Vec<Handler<'a>>>,
+    next_seq: usize,
+    progress: ProgressState,
+    deep_delegation_depth: Option<usize>,
 }
 impl<'a> Introspector<'a> {
     pub fn subscribe<C: 'static, R: Event<'a>>(&mut self, c: C, f: fn(c: &mut C, &R)) {
-        let fnc = Box::new(FnCallable{ c, f, _a: PhantomData::default() });
+        self.add_handler::<R>(Box::new(FnCallable{ c, f, _a: PhantomData }));
+    }
+    /// As subscribe(), and f is also given a read-only view of the count as it stands
+    /// when the event is emitted, for looking up other candidates without keeping a copy
+    /// of the count in the context. The state is None for events which are not emitted
+    /// by a count in progress, such as the ingestion events and PolicyDecision.
+    pub fn subscribe_with_state<C: 'static, R: Event<'a>>(&mut self, c: C, f: StateFn<'a, C, R>) {
+        self.add_handler::<R>(Box::new(StateFnCallable{ c, f, _a: PhantomData }));
+    }
+    fn add_handler<R: Event<'a>>(&mut self, callable: Box<dyn Callable<'a> + 'a>) {
+        let fnc = Handler{ seq: self.next_seq, callable };
+        self.next_seq += 1;
         let id = R::id();
         if let Some(h) = self.handlers.get_mut(&id) {
             h.push(fnc);
@@ -147,12 +407,95 @@ impl<'a> Introspector<'a> {
             self.handlers.insert(id, vec![ fnc ]);
         }
     }
-    pub fn event<R: Event<'a>>(&mut self, f: impl Fn() -> R) {
+    /// The contexts of every subscriber whose context is a C, in the order that they
+    /// subscribed.
+    pub fn contexts_mut<C: 'static>(&mut self) -> Vec<&mut C> {
+        let mut out = self.handlers.values_mut()
+            .flatten()
+            .filter_map(|h|Some((h.seq, h.callable.context_mut().downcast_mut::<C>()?)))
+            .collect::<Vec<_>>();
+        out.sort_by_key(|(seq, _)|*seq);
+        out.into_iter().map(|(_, c)|c).collect()
+    }
+    /// Take back the contexts of every subscriber whose context is a C, in the order that
+    /// they subscribed, for example to read an accumulator after the tally.
+    pub fn into_contexts<C: 'static>(self) -> Vec<C> {
+        let mut out = self.handlers.into_values()
+            .flatten()
+            .filter_map(|h|Some((h.seq, *h.callable.into_context().downcast::<C>().ok()?)))
+            .collect::<Vec<_>>();
+        out.sort_by_key(|(seq, _)|*seq);
+        out.into_iter().map(|(_, c)|c).collect()
+    }
+    /// Move every subscriber of other into this Introspector, after the ones already here.
+    pub fn merge(&mut self, other: Introspector<'a>) {
+        let base = self.next_seq;
+        self.next_seq += other.next_seq;
+        for (id, handlers) in other.handlers {
+            self.handlers.entry(id).or_default().extend(handlers.into_iter().map(|mut h|{
+                h.seq += base;
+                h
+            }));
+        }
+    }
+    /// Whether there is any subscriber for events of type R, so that work which is only
+    /// needed to produce an event can be skipped.
+    pub fn is_subscribed<R: Event<'a>>(&self) -> bool {
+        self.handlers.contains_key(&R::id())
+    }
+    /// Emit an event, f is only called if there is a subscriber for the event.
+    pub fn event<R: Event<'a>>(&mut self, f: impl FnOnce() -> R) {
+        self.emit(None, f);
+    }
+    /// Emit an event during the count, with the state for subscribe_with_state()
+    pub(crate) fn event_in<R: Event<'a>>(&mut self, state: TallyState<'_, 'a>, f: impl FnOnce() -> R) {
+        self.emit(Some(&state), f);
+    }
+    fn emit<R: Event<'a>>(&mut self, state: Option<&TallyState<'_, 'a>>, f: impl FnOnce() -> R) {
         if let Some(handlers) = self.handlers.get_mut(&R::id()) {
             let r = f();
             for h in handlers {
-                h.call(&r);
+                h.callable.call(&r, state);
             }
         }
     }
+
+    /// Emit DeepDelegation for chains with more than depth delegations, the default is 20.
+    pub fn set_deep_delegation_depth(&mut self, depth: usize) {
+        self.deep_delegation_depth = Some(depth);
+    }
+    pub(crate) fn deep_delegation_depth(&self) -> usize {
+        self.deep_delegation_depth.unwrap_or(20)
+    }
+
+    /// Set the minimum time between two Progress events in the same phase, the default
+    /// is 250 milliseconds.
+    pub fn set_progress_interval(&mut self, interval: Duration) {
+        self.progress.interval = interval;
+    }
+
+    /// Report that processed out of total items of phase are done, this is cheap unless
+    /// there is a Progress subscriber and the progress interval has elapsed.
+    pub(crate) fn progress(&mut self, phase: Phase, processed: u64, total: u64) {
+        let edge = processed == 0 || processed == total;
+        if !edge && !processed.is_multiple_of(PROGRESS_CHECK_EVERY) {
+            return;
+        }
+        if !self.is_subscribed::<Progress>() {
+            return;
+        }
+        let now = Instant::now();
+        if processed == 0 {
+            self.progress.phase_start = now;
+        } else if !edge && now.duration_since(self.progress.last) < self.progress.interval {
+            return;
+        }
+        self.progress.last = now;
+        let elapsed = now.duration_since(self.progress.phase_start);
+        let estimated_remaining = (processed > 0).then(|| {
+            let nanos = elapsed.as_nanos() * total.saturating_sub(processed) as u128 / processed as u128;
+            Duration::from_nanos(u64::try_from(nanos).unwrap_or(u64::MAX))
+        });
+        self.event(||Progress { phase, processed, total, estimated_remaining });
+    }
 }
@@ -1,42 +1,143 @@
 // SPDX-License-Identifier: MIT OR ISC
+// The tally must never abort the process which embeds it, internal inconsistencies are
+// reported as a TallyError instead.
+#![cfg_attr(not(test), deny(
+    clippy::panic,
+    clippy::unreachable,
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::todo,
+    clippy::unimplemented,
+))]
+// Some jurisdictions forbid inexact arithmetic in official counts, with this feature
+// any floating point arithmetic in the crate fails clippy.
+#![cfg_attr(feature = "integer-only", deny(
+    clippy::float_arithmetic,
+    clippy::float_cmp,
+    clippy::cast_precision_loss,
+    clippy::lossy_float_literal,
+))]
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::collections::BTreeMap;
+use std::borrow::Cow;
+
+use intern::InternedBallots;
 
 mod types;
+mod scc;
+pub mod prelude;
+pub mod options;
+pub mod result;
+pub mod compare;
+pub mod archive;
+pub mod ingest;
+pub mod intern;
+pub mod vote_like;
+pub mod groups;
+pub mod graph;
+pub mod analysis;
+pub mod limits;
+pub mod live;
+pub mod owned;
+pub mod builder;
+pub mod elections;
+pub mod metrics;
+pub mod bulk;
+pub mod quarantine;
+pub mod scenario;
+pub mod percent;
+pub mod dp;
+pub mod signing;
+pub mod policy;
+pub mod alias;
+pub mod preview;
+pub mod salt;
+pub mod attestation;
+pub mod record;
+pub mod frames;
+pub mod expect;
+pub mod explain;
+pub mod parallel;
+pub mod selftest;
+pub mod view;
+pub mod redact;
+#[cfg(feature = "json")]
+pub mod audit;
+#[cfg(feature = "json")]
+pub mod schema;
+#[cfg(feature = "json")]
+pub mod plugin;
 pub mod introspector;
 pub mod logging_introspector;
+pub mod teaching_introspector;
+pub mod compact_introspector;
+#[cfg(any(test, feature = "strategies"))]
+pub mod strategies;
+#[cfg(any(test, feature = "strategies"))]
+pub mod worst_case;
 #[cfg(test)]
 mod tests;
 
-pub use types::Vote;
+pub use types::{Vote, Metadata, Weight, TallyError, BallotError};
+use types::widen;
+pub use owned::Election;
+pub use options::{
+    ElectionOptions, PatronRule, AlgorithmVersion, UnrecognizedVotePolicy, DuplicatePolicy, OverflowPolicy,
+    Preset, OptionsError,
+};
+pub use result::{ElectionResult, RuleSet, SourceTotals, Engagement};
+use parallel::Parallelism;
 use introspector::{
     Introspector,
     VoteDelegation,
     VoteDelegationRing,
+    DeepDelegation,
+    ImplicitDelegation,
     InvalidVote,
     InvalidVoteCause,
+    Fallback, FallbackCause,
+    Abstention,
     BestRing, BestOfRing,
-    PatronSelection, PatronSelectionReason,
+    PatronSelection, PatronSelectionReason, PatronMath,
     DeterministicTieBreaker,
     DeterministicTieBreakerHash,
+    WeightsRescaled,
     Winner,
+    Seat,
+    NoQuorum,
+    Contributions,
+    Phase,
 };
 
 #[derive(Debug)]
 struct Candidate<'a> {
     /// A reference to the Vote object which corrisponds to this candidate
-    vote: &'a Vote,
+    vote: &'a Vote<'a>,
+    /// The position of that Vote in the ballots of the VoteCounter
+    ballot: usize,
     /// The index of the Candidate who they voted for, if any
     vote_for: Option<usize>,
+    /// The index of the implicit voter who they voted for, if they voted for someone
+    /// without a ballot under UnrecognizedVotePolicy::ImplicitVoter
+    implicit_vote_for: Option<usize>,
     /// The index of another Candidate who voted for the same person, if any
     voting_for_same: Option<usize>,
     /// The number of indirect votes which would be received if every candidate
     /// delegated their votes.
-    total_indirect_votes: u64,
+    total_indirect_votes: Weight,
+    /// The strongly connected component of the delegation graph which this candidate
+    /// is in, candidates in the same delegation ring have the same component.
+    component: usize,
+    /// The sum of total_indirect_votes of every candidate who voted directly for this
+    /// candidate, used to score ring members without walking voted_for_me.
+    votes_from_voters: Weight,
     /// The first candidate who voted for voted for this candidate.
     /// This and voting_for_same are used to create a linked list.
     voted_for_me: Option<usize>,
+    /// Their own votes, those of their ballot unless more than one ballot was merged
+    /// under DuplicatePolicy::MergeWeights.
+    own_votes: Weight,
     /// True if this is someone who is willing to potentially win the election.
     is_willing_candidate: bool,
     /// Forms a linked list of candidates ordered by total indirect votes, descending
@@ -49,126 +150,480 @@ impl<'a> PartialEq for Candidate<'a> {
     }
 }
 
-fn mk_candidates<'a, 'b: 'a>(
-    votes: &'b[Vote],
+/// Someone who was voted for but has no ballot, see UnrecognizedVotePolicy::ImplicitVoter
+#[derive(Debug)]
+struct ImplicitVoter<'a> {
+    voter_id: &'a str,
+    total_indirect_votes: Weight,
+}
+
+/// The result of reading the ballots
+struct Ingested<'a> {
+    total_willing: usize,
+    /// The invalid votes, with the position of each one in the ballots
+    invalid: Vec<(InvalidVoteCause, &'a Vote<'a>, usize)>,
+    implicit: Vec<ImplicitVoter<'a>>,
+    /// A ballot which the options do not allow
+    error: Option<TallyError>,
+}
+
+fn invalid_vote<'a>(
+    invalid: &mut Vec<(InvalidVoteCause, &'a Vote<'a>)>,
+    is: &mut Introspector<'a>,
+    cause: InvalidVoteCause,
+    vote: &'a Vote<'a>,
+) {
+    is.event(||InvalidVote{ cause: cause.clone(), vote });
+    invalid.push((cause, vote));
+}
+
+/// As invalid_vote(), for mk_candidates() which also keeps the position of the ballot
+fn invalid_ballot<'a>(
+    invalid: &mut Vec<(InvalidVoteCause, &'a Vote<'a>, usize)>,
+    is: &mut Introspector<'a>,
+    cause: InvalidVoteCause,
+    vote: &'a Vote<'a>,
+    ballot: usize,
+) {
+    is.event(||InvalidVote{ cause: cause.clone(), vote });
+    invalid.push((cause, vote, ballot));
+}
+
+/// The ballots which a VoteCounter counts, all of the caller's ballots or a selection
+/// of them.
+enum Ballots<'a> {
+    All(&'a [Vote<'a>]),
+    Selected(Vec<&'a Vote<'a>>),
+}
+impl<'a> Ballots<'a> {
+    fn len(&self) -> usize {
+        match self {
+            Ballots::All(v) => v.len(),
+            Ballots::Selected(v) => v.len(),
+        }
+    }
+    fn iter(&self) -> impl Iterator<Item = &'a Vote<'a>> + Clone + '_ {
+        let (all, selected) = match self {
+            Ballots::All(v) => (v.iter(), [].iter()),
+            Ballots::Selected(v) => ([].iter(), v.iter()),
+        };
+        all.chain(selected.copied())
+    }
+    fn push(&mut self, vote: &'a Vote<'a>) {
+        match self {
+            Ballots::All(all) => {
+                let all: &'a [Vote<'a>] = all;
+                *self = Ballots::Selected(all.iter().chain([vote]).collect());
+            }
+            Ballots::Selected(v) => v.push(vote),
+        }
+    }
+    fn retain(&mut self, keep: impl Fn(&Vote<'_>) -> bool) {
+        match self {
+            Ballots::All(all) => {
+                let all: &'a [Vote<'a>] = all;
+                *self = Ballots::Selected(all.iter().filter(|v|keep(v)).collect());
+            }
+            Ballots::Selected(v) => v.retain(|v|keep(v)),
+        }
+    }
+}
+
+/// The IDs of the ballots as numbers, so that mk_candidates() finds people in vectors
+/// rather than maps: the handles of InternedBallots when those are the ballots which are
+/// counted, otherwise a number which is given to each ID the first time it is seen.
+enum IdKeys<'a> {
+    Handles(&'a InternedBallots),
+    Strings(HashMap<&'a str, usize>),
+}
+impl<'a> IdKeys<'a> {
+    /// Handles are only used for all of the ballots, in the order of the InternedBallots
+    fn new(votes: &Ballots<'a>, interned: Option<&'a InternedBallots>) -> Self {
+        match (votes, interned) {
+            (Ballots::All(_), Some(interned)) => IdKeys::Handles(interned),
+            _ => IdKeys::Strings(HashMap::with_capacity(votes.len())),
+        }
+    }
+    fn key(keys: &mut HashMap<&'a str, usize>, id: &'a str) -> usize {
+        let n = keys.len();
+        *keys.entry(id).or_insert(n)
+    }
+    /// The key of the voter of votes[ballot], which is v
+    fn voter(&mut self, ballot: usize, v: &'a Vote<'a>) -> usize {
+        match self {
+            IdKeys::Handles(b) => b.ballots[ballot].voter.index(),
+            IdKeys::Strings(keys) => Self::key(keys, &v.voter_id),
+        }
+    }
+    /// The keys of whom votes[ballot], which is v, voted for, followed by their fallbacks
+    fn choices(&mut self, ballot: usize, v: &'a Vote<'a>) -> Vec<usize> {
+        match self {
+            IdKeys::Handles(b) => b.ballots[ballot].vote_for.iter()
+                .chain(b.fallbacks.get(&ballot).into_iter().flatten())
+                .map(|h|h.index())
+                .collect(),
+            IdKeys::Strings(keys) => v.vote_for.iter().chain(&v.fallbacks)
+                .map(|c|Self::key(keys, c))
+                .collect(),
+        }
+    }
+}
+
+/// A map whose keys are those of IdKeys
+struct KeyMap<T>(Vec<Option<T>>);
+impl<T: Copy> KeyMap<T> {
+    fn new() -> Self {
+        KeyMap(Vec::new())
+    }
+    fn get(&self, key: usize) -> Option<T> {
+        self.0.get(key).copied().flatten()
+    }
+    fn insert(&mut self, key: usize, t: T) {
+        if key >= self.0.len() {
+            self.0.resize(key + 1, None);
+        }
+        self.0[key] = Some(t);
+    }
+}
+
+/// The first choice of a ballot with fallbacks who is a willing candidate, other than the
+/// voter themselves, or if there is none, the first choice. Each choice is given with its
+/// key, see IdKeys::choices().
+fn choose_fallback<'a>(
+    vote: &'a Vote<'a>,
+    voter_key: usize,
+    choice_keys: &[usize],
+    cands: &[Candidate<'a>],
+    candidate_by_key: &KeyMap<usize>,
+    is: &mut Introspector<'a>,
+) -> Option<(&'a str, usize)> {
+    let choices = vote.vote_for.iter().chain(&vote.fallbacks).map(|c|&**c).zip(choice_keys.iter().copied())
+        .collect::<Vec<_>>();
+    for (i, &(choice, key)) in choices.iter().enumerate() {
+        let cause = if key == voter_key {
+            FallbackCause::SelfVote
+        } else {
+            match candidate_by_key.get(key).and_then(|idx|cands.get(idx)) {
+                Some(c) if c.is_willing_candidate => return Some((choice, key)),
+                Some(_) => FallbackCause::Unwilling,
+                None => FallbackCause::Unrecognized,
+            }
+        };
+        is.event(||Fallback { vote, skipped: choice, cause, next: choices.get(i + 1).map(|c|c.0) });
+    }
+    choices.first().copied()
+}
+
+/// Read the ballots into cands, with every ballot's votes shifted right by weight_shift,
+/// see limits::weight_shift(). The caller emits WeightsRescaled first if it is not 0.
+/// When interned is given, the ballots are interned.to_votes(), see IdKeys.
+fn mk_candidates<'a>(
+    votes: &Ballots<'a>,
     cands: &mut Vec<Candidate<'a>>,
+    options: &ElectionOptions,
+    weight_shift: u32,
+    interned: Option<&'a InternedBallots>,
     is: &mut Introspector<'a>,
-) -> usize {
-    let mut candidate_idx_by_name = HashMap::with_capacity(votes.len());
+) -> Ingested<'a> {
+    let weight = |v: &Vote<'_>| v.number_of_votes.checked_shr(weight_shift).unwrap_or(0);
+    let mut keys = IdKeys::new(votes, interned);
+    let voter_keys = votes.iter().enumerate().map(|(i, v)|keys.voter(i, v)).collect::<Vec<_>>();
+    let mut candidate_by_key = KeyMap::new();
+    // The position in votes of each candidate's ballot
+    let mut ballot_of = Vec::with_capacity(votes.len());
     let mut total_willing = 0;
-    for &willing in [true,false].iter() {
-        for v in votes.iter() {
-            if v.willing_candidate != willing {
+    let mut invalid = Vec::new();
+    let mut error = None;
+    // Under LastWins, the position of each voter's last ballot
+    let last_ballot = (options.duplicate_voter == DuplicatePolicy::LastWins).then(||{
+        let mut last = KeyMap::new();
+        for (i, &key) in voter_keys.iter().enumerate() {
+            last.insert(key, i);
+        }
+        last
+    });
+    // Each ballot is looked at twice, once to find willing candidates and once for voters
+    let total = votes.len() as u64 * 2;
+    for (pass, &willing) in [true,false].iter().enumerate() {
+        for ((i, v), &key) in votes.iter().enumerate().zip(&voter_keys) {
+            is.progress(Phase::Ingestion, (pass * votes.len() + i) as u64, total);
+            if (v.willing_candidate && !v.abstain) != willing {
                 // Pile up all of the willing candidates at the beginning
                 // to reduce memory fragmentation, we also rely on this in
                 // order_by_total_indirect
                 continue;
             }
-			if candidate_idx_by_name.contains_key(&v.voter_id) {
-				is.event(||InvalidVote{ cause: InvalidVoteCause::Duplicate, vote: v });
-				continue;
-			}
+            let superseded = last_ballot.as_ref().is_some_and(|l|l.get(key) != Some(i));
+            if superseded {
+                invalid_ballot(&mut invalid, is, InvalidVoteCause::Duplicate, v, i);
+                continue;
+            }
+            if let Some(idx) = candidate_by_key.get(key) {
+                invalid_ballot(&mut invalid, is, InvalidVoteCause::Duplicate, v, i);
+                match options.duplicate_voter {
+                    DuplicatePolicy::MergeWeights => {
+                        let c: &mut Candidate = &mut cands[idx];
+                        c.own_votes += weight(v);
+                        c.total_indirect_votes += weight(v);
+                    }
+                    DuplicatePolicy::Error => {
+                        error.get_or_insert_with(||TallyError::DuplicateVoter(v.voter_id.to_string()));
+                    }
+                    DuplicatePolicy::FirstWins | DuplicatePolicy::LastWins => {}
+                }
+                continue;
+            }
             total_willing += if willing { 1 } else { 0 };
             let cand = Candidate{
                 vote: v,
+                ballot: i,
                 vote_for: None,
+                implicit_vote_for: None,
                 voting_for_same: None,
                 // Everyone implicitly votes for themselves
-                total_indirect_votes: v.number_of_votes,
+                total_indirect_votes: weight(v),
+                own_votes: weight(v),
+                votes_from_voters: 0,
+                component: 0,
                 voted_for_me: None,
-                is_willing_candidate: v.willing_candidate,
+                is_willing_candidate: willing,
                 next_by_total_indirect_votes: None,
             };
-            candidate_idx_by_name.insert(&v.voter_id, cands.len());
+            candidate_by_key.insert(key, cands.len());
+            ballot_of.push(i);
             cands.push(cand);
         }
     }
-    for c in cands.iter_mut() {
-        let vote = c.vote;
-        if vote.vote_for == "" {
+    let mut implicit = Vec::new();
+    let mut implicit_by_key = KeyMap::new();
+    for ci in 0..cands.len() {
+        let vote = cands[ci].vote;
+        if vote.abstain {
+            // Counted, but not for anyone
+            is.event(||Abstention { vote });
+            continue;
+        }
+        let ballot = ballot_of[ci];
+        let choices = keys.choices(ballot, vote);
+        let vote_for = if vote.fallbacks.is_empty() {
+            vote.vote_for.as_deref().zip(choices.first().copied())
+        } else {
+            choose_fallback(vote, voter_keys[ballot], &choices, cands, &candidate_by_key, is)
+        };
+        let c = &mut cands[ci];
+        let Some((vote_for, key)) = vote_for else {
             // They didn't vote
-            is.event(||InvalidVote{ cause: InvalidVoteCause::NoVote, vote });
-        } else if vote.vote_for == vote.voter_id {
+            invalid_ballot(&mut invalid, is, InvalidVoteCause::NoVote, vote, ballot);
+            continue;
+        };
+        if key == voter_keys[ballot] {
             // Voted for themselves
-            is.event(||InvalidVote{ cause: InvalidVoteCause::SelfVote, vote });
-        } else if let Some(&idx) = candidate_idx_by_name.get(&vote.vote_for) {
+            invalid_ballot(&mut invalid, is, InvalidVoteCause::SelfVote, vote, ballot);
+        } else if let Some(idx) = candidate_by_key.get(key) {
             c.vote_for = Some(idx);
         } else {
-            // Voted for someone that is unrecognized 
-            is.event(||InvalidVote{ cause: InvalidVoteCause::UnrecognizedVote, vote });
+            // Voted for someone that is unrecognized
+            match options.unrecognized_vote {
+                UnrecognizedVotePolicy::Discard => {
+                    invalid_ballot(&mut invalid, is, InvalidVoteCause::UnrecognizedVote, vote, ballot);
+                }
+                UnrecognizedVotePolicy::ImplicitVoter => {
+                    let idx = implicit_by_key.get(key).unwrap_or_else(||{
+                        implicit.push(ImplicitVoter{
+                            voter_id: vote_for,
+                            total_indirect_votes: 0,
+                        });
+                        implicit_by_key.insert(key, implicit.len() - 1);
+                        implicit.len() - 1
+                    });
+                    c.implicit_vote_for = Some(idx);
+                }
+                UnrecognizedVotePolicy::Error => {
+                    invalid_ballot(&mut invalid, is, InvalidVoteCause::UnrecognizedVote, vote, ballot);
+                    error.get_or_insert_with(||TallyError::UnrecognizedVote(
+                        vote.voter_id.to_string(), vote_for.to_owned()));
+                }
+            }
         }
     }
-    total_willing
+    is.progress(Phase::Ingestion, total, total);
+    Ingested { total_willing, invalid, implicit, error }
 }
 
-fn compute_delegated_votes<'a>(cand: &mut Vec<Candidate<'a>>, is: &mut Introspector<'a>) {
-    let mut delegation_path = Vec::new();
-    for node_id in 0..cand.len() {
-        let (mut vote_for, orig_vote) = {
-            let c = &cand[node_id];
-            (c.vote_for, c.vote)
-        };
-        let votes = orig_vote.number_of_votes;
-        // Insert ourselves into the voted_for_me linked list
-        if let Some(vote_for) = vote_for {
-            cand[node_id].voting_for_same = cand[vote_for].voted_for_me;
-            cand[vote_for].voted_for_me = Some(node_id);
+/// Which of the delegation events have subscribers, checked once per count because there
+/// is an event for every step of every chain.
+struct DelegationReports {
+    delegation: bool,
+    ring: bool,
+    implicit: bool,
+    deep_depth: Option<usize>,
+}
+impl DelegationReports {
+    fn new(is: &Introspector<'_>) -> Self {
+        DelegationReports {
+            delegation: is.is_subscribed::<VoteDelegation>(),
+            ring: is.is_subscribed::<VoteDelegationRing>(),
+            implicit: is.is_subscribed::<ImplicitDelegation>(),
+            deep_depth: is.is_subscribed::<DeepDelegation>().then(||is.deep_delegation_depth()),
         }
-        delegation_path.clear();
-        delegation_path.push(node_id);
-        let mut last_vote = orig_vote;
-        loop {
-            vote_for = if let Some(vote_for) = vote_for {
-                let c_vf = &mut cand[vote_for];
-                if delegation_path.contains(&vote_for) {
-                    is.event(||VoteDelegationRing {
+    }
+}
+
+/// Add the own votes of node_id to everyone down the chain of who they voted for, until
+/// the end of the chain or a ring. The chain is left in delegation_path, starting with
+/// node_id.
+fn delegate<'a>(
+    node_id: usize,
+    cand: &mut [Candidate<'a>],
+    implicit: &mut [ImplicitVoter<'a>],
+    is: &mut Introspector<'a>,
+    report: &DelegationReports,
+    delegation_path: &mut Vec<usize>,
+) {
+    let (mut vote_for, orig_vote) = {
+        let c = &cand[node_id];
+        (c.vote_for, c.vote)
+    };
+    let votes = cand[node_id].own_votes;
+    delegation_path.clear();
+    delegation_path.push(node_id);
+    let mut last_vote = orig_vote;
+    loop {
+        vote_for = if let Some(vote_for) = vote_for {
+            if delegation_path.contains(&vote_for) {
+                if report.ring {
+                    is.event_in(TallyState::new(cand), ||VoteDelegationRing {
                         chain: delegation_path.iter().map(|&id|cand[id].vote).collect(),
                         next: cand[vote_for].vote,
+                        from: orig_vote,
+                        votes,
+                        stopped_at: last_vote,
                     });
-                    // It's a ring, we already delegated to them, abort.
-                    break;
                 }
-                is.event(||VoteDelegation {
-                    from: &orig_vote,
-                    to: &c_vf.vote,
+                // It's a ring, we already delegated to them, abort.
+                break;
+            }
+            if report.delegation {
+                is.event_in(TallyState::new(cand), ||VoteDelegation {
+                    from: orig_vote,
+                    to: cand[vote_for].vote,
                     because_of: last_vote,
+                    votes,
                 });
+            }
 
-                delegation_path.push(vote_for);
+            delegation_path.push(vote_for);
+            let c_vf = &mut cand[vote_for];
 
-                // Add the votes
-                c_vf.total_indirect_votes += votes;
+            // Add the votes
+            c_vf.total_indirect_votes += votes;
 
-                // Next round
-                last_vote = c_vf.vote;
-                c_vf.vote_for
-            } else {
-                // nobody left to delegate to, end of the line
-                break;
-            };
+            // Next round
+            last_vote = c_vf.vote;
+            c_vf.vote_for
+        } else {
+            // nobody left to delegate to, end of the line, unless the last one in the
+            // chain voted for an implicit voter
+            let last = delegation_path.last().and_then(|&id|cand[id].implicit_vote_for);
+            if let Some(iv) = last.and_then(|iv|implicit.get_mut(iv)) {
+                iv.total_indirect_votes += votes;
+                if report.implicit {
+                    let to = iv.voter_id;
+                    is.event_in(TallyState::new(cand), ||ImplicitDelegation {
+                        from: orig_vote, to, because_of: last_vote, votes,
+                    });
+                }
+            }
+            break;
+        };
+    }
+    if let Some(deep_depth) = report.deep_depth {
+        let depth = delegation_path.len() - 1;
+        if depth > deep_depth {
+            is.event_in(TallyState::new(cand), ||DeepDelegation {
+                from: orig_vote,
+                depth,
+                chain: delegation_path.iter().map(|&id|cand[id].vote).collect(),
+            });
+        }
+    }
+}
+
+/// Undo delegate(), taking the own votes of node_id back from everyone down their chain.
+fn withdraw<'a>(
+    node_id: usize,
+    cand: &mut [Candidate<'a>],
+    implicit: &mut [ImplicitVoter<'a>],
+    delegation_path: &mut Vec<usize>,
+) {
+    let votes = cand[node_id].own_votes;
+    delegation_path.clear();
+    delegation_path.push(node_id);
+    let mut vote_for = cand[node_id].vote_for;
+    while let Some(vf) = vote_for {
+        if delegation_path.contains(&vf) {
+            return;
+        }
+        delegation_path.push(vf);
+        cand[vf].total_indirect_votes -= votes;
+        vote_for = cand[vf].vote_for;
+    }
+    let last = delegation_path.last().and_then(|&id|cand[id].implicit_vote_for);
+    if let Some(iv) = last.and_then(|iv|implicit.get_mut(iv)) {
+        iv.total_indirect_votes -= votes;
+    }
+}
+
+fn compute_delegated_votes<'a>(
+    cand: &mut [Candidate<'a>],
+    implicit: &mut [ImplicitVoter<'a>],
+    is: &mut Introspector<'a>,
+) {
+    let mut delegation_path = Vec::new();
+    let total = cand.len() as u64;
+    let report = DelegationReports::new(is);
+    for node_id in 0..cand.len() {
+        is.progress(Phase::Delegation, node_id as u64, total);
+        // Insert ourselves into the voted_for_me linked list
+        if let Some(vote_for) = cand[node_id].vote_for {
+            cand[node_id].voting_for_same = cand[vote_for].voted_for_me;
+            cand[vote_for].voted_for_me = Some(node_id);
+        }
+        delegate(node_id, cand, implicit, is, &report, &mut delegation_path);
+    }
+    for node_id in 0..cand.len() {
+        if let Some(vote_for) = cand[node_id].vote_for {
+            cand[vote_for].votes_from_voters += cand[node_id].total_indirect_votes;
         }
     }
+    is.progress(Phase::Delegation, total, total);
 }
 
 /// Link-list the Candidates by # of votes, return the index of the candidate w/ max votes (first)
+/// The indexes of the candidates, from the most votes to the least, are left in ranking.
 fn order_by_total_indirect<'b,'a:'b>(
     cand: &'b mut Vec<Candidate<'a>>,
     total_willing_candidates: usize,
-) -> Option<usize> {
+    ranking: &mut Vec<usize>,
+) -> Result<Option<usize>, TallyError> {
+    ranking.clear();
     struct Sortable {
         idx: usize,
-        score: u64,
+        score: Weight,
     }
     let mut sortable = Vec::with_capacity(total_willing_candidates);
     for (idx, c) in (0..total_willing_candidates).zip(cand.iter()) {
         // they should have been put in order from before
-        assert!(c.is_willing_candidate);
+        if !c.is_willing_candidate {
+            return Err(TallyError::UnwillingInRanking(c.vote.voter_id.to_string()));
+        }
         sortable.push(Sortable{
             idx,
             score: c.total_indirect_votes,
         });
     }
     sortable.sort_by_key(|c|c.score);
+    ranking.extend(sortable.iter().rev().map(|s|s.idx));
     let mut si = sortable.iter();
     if let Some(first) = si.next() {
         let mut last = first;
@@ -176,74 +631,69 @@ fn order_by_total_indirect<'b,'a:'b>(
             cand[s.idx].next_by_total_indirect_votes = Some(last.idx);
             last = s;
         }
-        return Some(last.idx);
+        return Ok(Some(last.idx));
     }
-    None
+    Ok(None)
 }
 
-fn push_vfm<'b, 'a: 'b>(
-    cand: &'b Vec<Candidate<'a>>,
-    me: &'b Candidate<'a>,
-    unorganized: &mut BTreeMap<usize, &'b Candidate<'a>>,
-    ring: &mut Vec<&'b Candidate<'a>>,
-) {
-    let mut maybe_vfm = me.voted_for_me;
-    while let Some(vfm) = maybe_vfm {
-        if let Some(vfm_c) = unorganized.remove(&vfm) {
-            // println!("Pushing candidate {} to ring", vfm_c.vote.voter_id);
-            ring.push(vfm_c);
-            push_vfm(cand, vfm_c, unorganized, ring);
-        }
-        maybe_vfm = cand[vfm].voting_for_same;
-    }
-}
-
-/// A "ring" is potentially more than one ring, this breaks it down into the component rings.
+/// The best candidates are potentially more than one ring, this breaks them down into
+/// the component rings. Best candidates who voted for one another are in the same ring,
+/// and with by_component (AlgorithmVersion::V3) so are candidates in the same strongly
+/// connected component of the delegation graph, who may only reach one another through
+/// unwilling voters.
 fn compute_ring_members<'b, 'a: 'b>(
-    cand: &'b Vec<Candidate<'a>>,
+    cand: &'b [Candidate<'a>],
     ring: &BTreeMap<usize, &'b Candidate<'a>>,
-) -> Vec<Vec<&'a Vote>> {
-    let mut out: Vec<Vec<&Vote>> = Vec::new();
-    let mut unorganized: BTreeMap<usize, &'b Candidate<'a>> = BTreeMap::new();
-    for (&k, &v) in ring {
-        unorganized.insert(k, v);
+    by_component: bool,
+) -> Vec<Vec<&'a Vote<'a>>> {
+    // Union-find over the positions of the candidates in the ring
+    fn find(parent: &mut [usize], mut x: usize) -> usize {
+        while parent[x] != x {
+            parent[x] = parent[parent[x]];
+            x = parent[x];
+        }
+        x
     }
-    loop {
-        let mut ring = Vec::new();
-        let (idx, _) = match unorganized.iter().next() {
-            Some(x) => x,
-            None => break,
+    let keys = ring.keys().copied().collect::<Vec<_>>();
+    let mut parent = (0..keys.len()).collect::<Vec<_>>();
+    let mut first_in_component = HashMap::new();
+    for (i, &idx) in keys.iter().enumerate() {
+        let c = &cand[idx];
+        let mut join = |j: usize| {
+            let (ri, rj) = (find(&mut parent, i), find(&mut parent, j));
+            parent[ri.max(rj)] = ri.min(rj);
         };
-        let mut maybe_idx = Some(*idx);
-        while let Some(idx) = maybe_idx {
-            match unorganized.remove(&idx) {
-                Some(c) => {
-                    // println!("Pushing candidate {}/{} to ring {}",
-                    //     cand[idx].vote.voter_id, idx, out.len());
-                    ring.push(c);
-                    push_vfm(cand, c, &mut unorganized, &mut ring);
-                    maybe_idx = c.vote_for;
-                }
-                None => {
-                    if !ring.contains(&&cand[idx]) && cand[idx].is_willing_candidate {
-                        panic!("Candidate: {}/{} is not in unorganized nor in ring",
-                            cand[idx].vote.voter_id, idx);
-                    }
-                    break;
-                }
+        if by_component {
+            if let Some(&j) = first_in_component.get(&c.component) {
+                join(j);
+            } else {
+                first_in_component.insert(c.component, i);
             }
         }
-        out.push(ring.iter().map(|c|c.vote).collect());
+        if let Some(j) = c.vote_for.and_then(|vf|keys.binary_search(&vf).ok()) {
+            join(j);
+        }
+    }
+    let mut out: Vec<Vec<&Vote>> = Vec::new();
+    let mut out_idx_by_root = HashMap::new();
+    for (i, &idx) in keys.iter().enumerate() {
+        let root = find(&mut parent, i);
+        let out_idx = *out_idx_by_root.entry(root).or_insert_with(||{
+            out.push(Vec::new());
+            out.len() - 1
+        });
+        out[out_idx].push(cand[idx].vote);
     }
     out
 }
 
 /// Returns the candidates with the best score
 fn get_best_candidates<'b, 'a: 'b>(
-    cand: &'b Vec<Candidate<'a>>,
+    cand: &'b [Candidate<'a>],
     best: usize,
+    version: AlgorithmVersion,
     is: &mut Introspector<'a>,
-) -> (BTreeMap<usize, &'b Candidate<'a>>, usize) {
+) -> Result<(BTreeMap<usize, &'b Candidate<'a>>, usize), TallyError> {
     let mut best_ring = BTreeMap::new();
     let mut c_idx = best;
     let score = cand[c_idx].total_indirect_votes;
@@ -259,39 +709,40 @@ fn get_best_candidates<'b, 'a: 'b>(
             break;
         }
     };
-    println!("Best ring contains:");
-    for (&k, &v) in &best_ring {
-        println!("  - {}/{}", v.vote.voter_id, k);
-    }
-    let best_rings_members = compute_ring_members(cand, &best_ring);
+    let best_rings_members = compute_ring_members(cand, &best_ring, version == AlgorithmVersion::V3);
     let ring_count = best_rings_members.len();
-    is.event(|| {
+    is.event_in(TallyState::new(cand), || {
         BestRing{
-            best_rings_members: compute_ring_members(cand, &best_ring),
+            best_rings_members,
             best_total_delegated_votes: score,
         }
     });
-    (best_ring, ring_count)
+    Ok((best_ring, ring_count))
 }
 
 /// Get the best candidate(s) out of the ring, i.e. the one(s) who would have the most
 /// votes if the ring did not exist. Returns multiple in case of a tie.
+///
+/// Each member's score is their own votes plus votes_from_voters, less the votes from
+/// the members of the ring who voted for them, so the cost is linear in the size of
+/// the ring rather than in the number of voters for each member.
 fn best_of_ring<'b, 'a: 'b>(
-    cand: &'b Vec<Candidate<'a>>,
+    cand: &[Candidate<'a>],
     ring: &BTreeMap<usize, &'b Candidate<'a>>,
     is: &mut Introspector<'a>,
 ) -> Vec<&'b Candidate<'a>> {
-    let mut scores = Vec::new();
-    for (_, &c) in ring {
-        let mut score = c.vote.number_of_votes;
-        let mut maybe_vfm = c.voted_for_me;
-        while let Some(vfm) = maybe_vfm {
-            let c_vfm = &cand[vfm];
-            if !ring.contains_key(&vfm) {
-                score += c_vfm.total_indirect_votes;
+    let mut from_ring: BTreeMap<usize, Weight> = BTreeMap::new();
+    for &c in ring.values() {
+        if let Some(vote_for) = c.vote_for {
+            if ring.contains_key(&vote_for) {
+                *from_ring.entry(vote_for).or_default() += c.total_indirect_votes;
             }
-            maybe_vfm = c_vfm.voting_for_same;
         }
+    }
+    let mut scores = Vec::new();
+    for (idx, &c) in ring {
+        let from_ring = from_ring.get(idx).copied().unwrap_or(0);
+        let score = c.own_votes + c.votes_from_voters - from_ring;
         scores.push((c, score));
     }
     let mut winning_count = 0;
@@ -306,7 +757,7 @@ fn best_of_ring<'b, 'a: 'b>(
             out.push(*c);
         }
     }
-    is.event(||BestOfRing{
+    is.event_in(TallyState::new(cand), ||BestOfRing{
         rings_member_scores: 
             scores.iter().map(|(c, score)|(c.vote, *score)).collect(),
         winners:
@@ -318,17 +769,19 @@ fn best_of_ring<'b, 'a: 'b>(
 fn mk_patron_selection<'a>(
     p: &Candidate<'a>,
     selection: PatronSelectionReason<'a>,
+    math: PatronMath,
 ) -> PatronSelection<'a> {
     PatronSelection{
         potential_patron: p.vote,
         potential_patron_votes: p.total_indirect_votes,
         selection,
+        math,
     }
 }
 
 /// Get the first candidate who is not part of the 
 fn get_runner_up<'b, 'a: 'b>(
-    cand: &'b Vec<Candidate<'a>>,
+    cand: &'b [Candidate<'a>],
     tenative_winner: &'b Candidate<'a>,
     exclude_ring: &BTreeMap<usize, &'b Candidate<'a>>,
 ) -> Option<&'b Candidate<'a>> {
@@ -351,11 +804,12 @@ fn get_runner_up<'b, 'a: 'b>(
 /// It is impossible to have more than 1 patron because being a patron implies
 /// supplying more than 50% of the votes to the candidate you voted for.
 fn get_patron<'b, 'a: 'b>(
-    cand: &'b Vec<Candidate<'a>>,
+    cand: &'b [Candidate<'a>],
     tenative_winner: &'b Candidate<'a>,
     exclude_ring: &BTreeMap<usize, &'b Candidate<'a>>,
+    rule: PatronRule,
     is: &mut Introspector<'a>,
-) -> Option<&'b Candidate<'a>> {
+) -> Result<Option<&'b Candidate<'a>>, TallyError> {
 
     let mut runner_up = get_runner_up(cand, tenative_winner, exclude_ring);
 
@@ -376,27 +830,44 @@ fn get_patron<'b, 'a: 'b>(
         best_cand
     };
 
-    // Return true if the potential patron is a valid patron.
+    // Return true if the potential patron is a valid patron, along with the numbers
+    // which they were checked against.
     // Does not check that they're not part of the excluded ring, but does all other checks.
     let mut is_valid_patron =
         |patron: &'b Candidate<'a>, runner_up: Option<&'b Candidate<'a>>|
     {
         let mark_to_beat = tenative_winner.total_indirect_votes / 2;
-        if !patron.is_willing_candidate {
-            is.event(||mk_patron_selection(
-                patron, PatronSelectionReason::NotWillingCandidate));
+        let math = PatronMath {
+            tenative_winner_votes: tenative_winner.total_indirect_votes,
+            mark_to_beat,
+            runner_up_votes: runner_up.map(|ru|ru.total_indirect_votes),
+        };
+        let valid = if !patron.is_willing_candidate {
+            is.event_in(TallyState::new(cand), ||mk_patron_selection(
+                patron, PatronSelectionReason::NotWillingCandidate, math));
             false
         } else if patron.total_indirect_votes <= mark_to_beat {
-            is.event(||mk_patron_selection(
-                patron, PatronSelectionReason::NotProvidingMajority(mark_to_beat)));
+            is.event_in(TallyState::new(cand), ||mk_patron_selection(
+                patron, PatronSelectionReason::NotProvidingMajority(mark_to_beat), math));
             false
-        } else {
-            if let Some(ru) = runner_up {
-                if patron.total_indirect_votes <= ru.total_indirect_votes {
-                    assert_ne!(patron, ru);
-                    is.event(||mk_patron_selection(
-                        patron, PatronSelectionReason::NotBeatingSecondBest(
-                            ru.total_indirect_votes, ru.vote)));
+        } else if let Some(ru) = runner_up {
+            if patron.total_indirect_votes <= ru.total_indirect_votes {
+                if patron == ru {
+                    return Err(TallyError::PatronIsRunnerUp(patron.vote.voter_id.to_string()));
+                }
+                is.event_in(TallyState::new(cand), ||mk_patron_selection(
+                    patron, PatronSelectionReason::NotBeatingSecondBest(
+                        ru.total_indirect_votes, ru.vote), math));
+                false
+            } else if rule == PatronRule::Strict {
+                // The votes which the tenative winner did not get from the patron might
+                // all have gone to the runner-up.
+                let needed = ru.total_indirect_votes +
+                    (tenative_winner.total_indirect_votes - patron.total_indirect_votes);
+                if patron.total_indirect_votes <= needed {
+                    is.event_in(TallyState::new(cand), ||mk_patron_selection(
+                        patron, PatronSelectionReason::NotBeatingSecondBestOutright(
+                            needed, ru.vote), math));
                     false
                 } else {
                     true
@@ -404,12 +875,15 @@ fn get_patron<'b, 'a: 'b>(
             } else {
                 true
             }
-        }
+        } else {
+            true
+        };
+        Ok((valid, math))
     };
 
     // Get the potential patron of the best candidate of the ring
     let mut potential_patron = match get_potential_patron(tenative_winner) {
-        None => return None,
+        None => return Ok(None),
         Some(pp) => pp,
     };
 
@@ -420,35 +894,36 @@ fn get_patron<'b, 'a: 'b>(
         // otherwise we might end up comparing the patron to himself.
         if runner_up == Some(potential_patron) {
             runner_up = runner_up
-                .map(|ru|ru.next_by_total_indirect_votes)
-                .flatten()
+                .and_then(|ru|ru.next_by_total_indirect_votes)
                 .map(|ru|&cand[ru]);
         }
         // If they're not valid, break out and keep what we've got
-        if !is_valid_patron(potential_patron, runner_up) {
+        let (valid, math) = is_valid_patron(potential_patron, runner_up)?;
+        if !valid {
             break;
         }
         // The current candidate IS a patron, store them and see if a
         // node who voted for them is a patron.
-        patron = Some(potential_patron);
+        patron = Some((potential_patron, math));
         potential_patron = match get_potential_patron(potential_patron) {
             None => break,
             Some(pp) => pp,
         };
     }
 
-    if let Some(p) = patron {
-        is.event(||mk_patron_selection(p, PatronSelectionReason::PatronFound));
+    if let Some((p, math)) = patron {
+        is.event_in(TallyState::new(cand), ||mk_patron_selection(p, PatronSelectionReason::PatronFound, math));
     }
-    patron
+    Ok(patron.map(|(p, _)|p))
 }
 
 fn solve_winner<'b, 'a: 'b>(
-    cand: &'b Vec<Candidate<'a>>,
+    cand: &'b [Candidate<'a>],
     tenative_winner: Vec<&'b Candidate<'a>>,
     best_ring: &BTreeMap<usize, &'b Candidate<'a>>,
+    rule: PatronRule,
     is: &mut Introspector<'a>,
-) -> Vec<&'b Candidate<'a>> {
+) -> Result<Vec<&'b Candidate<'a>>, TallyError> {
 
     // tenative_winner becomes THE winner, unless they got more than half of their
     // votes from one candidate (their "patron"), and that candidate alone has enough
@@ -458,152 +933,1258 @@ fn solve_winner<'b, 'a: 'b>(
     // because no one of the patrons can possibly win by "revoking" their vote for
     // their tenative winner.
 
-    let tenative_winner = if tenative_winner.len() != 1 {
-        return tenative_winner;
-    } else if let Some(&tenative_winner) = tenative_winner.get(0) {
-        tenative_winner
-    } else {
-        unreachable!();
+    let tenative_winner = match tenative_winner[..] {
+        [tenative_winner] if rule != PatronRule::Disabled => tenative_winner,
+        _ => return Ok(tenative_winner),
     };
 
-    vec![
+    Ok(vec![
         get_patron(
             cand,
             tenative_winner,
             best_ring,
+            rule,
             is,
-        ).unwrap_or(tenative_winner)
-    ]
+        )?.unwrap_or(tenative_winner)
+    ])
 }
 
-fn tie_breaker_hash<'a>(c: &Candidate, name: &str, is: &mut Introspector<'a>) -> [u8; 64] {
-    use blake2::{Blake2b512, Digest};
-    let mut hasher = Blake2b512::new();
-    hasher.update(name.as_bytes());
-    hasher.update(c.total_indirect_votes.to_le_bytes());
-    let hash = hasher.finalize().into();
-    is.event(||{
-        let nab = name.as_bytes();
-        let mut buf = vec![0_u8; nab.len() + 8];
-        buf[0..nab.len()].copy_from_slice(nab);
-        buf[nab.len()..].copy_from_slice(&c.total_indirect_votes.to_le_bytes()[..]);
-        DeterministicTieBreakerHash{
-            candidate: name.to_string(),
-            total_indirect_votes: c.total_indirect_votes,
-            bytes: buf,
+/// The candidates tied for the win, found from the best candidate by steps 3 to 5 of
+/// the algorithm, before the deterministic tie-breaker.
+fn tied_winners<'b, 'a: 'b>(
+    cand: &'b [Candidate<'a>],
+    best: usize,
+    version: AlgorithmVersion,
+    rule: PatronRule,
+    is: &mut Introspector<'a>,
+) -> Result<Vec<&'b Candidate<'a>>, TallyError> {
+    let (best_ring, ring_count) = get_best_candidates(cand, best, version, is)?;
+
+    // 4. Get the best candidate out of the best ring
+    let tenative_winner = best_of_ring(cand, &best_ring, is);
+
+    if ring_count < 2 {
+        // Under V3 the unwilling voters in the ring are not looked at as patrons either
+        let mut exclude = best_ring.clone();
+        if version == AlgorithmVersion::V3 {
+            let components = best_ring.values().map(|c|c.component).collect::<Vec<_>>();
+            exclude.extend(cand.iter().enumerate().filter(|(_, c)|components.contains(&c.component)));
         }
-    });
-    hash
+        return solve_winner(cand, tenative_winner, &exclude, rule, is);
+    }
+    Ok(tenative_winner)
+}
+
+/// The little endian bytes of votes in a tie-breaker hash, only the first 8 when they fit
+/// in a u64 so that the hash is the same with or without wide-weights.
+fn votes_bytes(votes: Weight) -> ([u8; 16], usize) {
+    let len = if widen(votes) >> 64 == 0 { 8 } else { 16 };
+    (widen(votes).to_le_bytes(), len)
 }
 
+/// The hash of name, votes and ElectionOptions::tie_break_salt, an empty salt for none.
+/// The salt is the same for every candidate so it can follow the name and votes without
+/// making two keys hash the same bytes.
+fn tie_breaker_hash(name: &str, total_indirect_votes: Weight, salt: &[u8], hasher: &mut blake2::Blake2b512) -> [u8; 64] {
+    use blake2::Digest;
+    hasher.update(name.as_bytes());
+    let (votes, len) = votes_bytes(total_indirect_votes);
+    hasher.update(&votes[..len]);
+    hasher.update(salt);
+    hasher.finalize_reset().into()
+}
+
+/// Below this many tied candidates the hashes are computed on the calling thread, starting
+/// threads costs more than hashing.
+const PARALLEL_TIE_BREAK_MIN: usize = 4096;
+
+/// The tie-breaker hash of every (name, total_indirect_votes), in order, split across up
+/// to threads threads. Each hash only depends on its own key so the output is the same
+/// whatever the number of threads.
+pub(crate) fn tie_breaker_hashes(keys: &[(&str, Weight)], salt: &[u8], threads: usize) -> Vec<[u8; 64]> {
+    use blake2::Digest;
+    let serial = |keys: &[(&str, Weight)]| {
+        let mut hasher = blake2::Blake2b512::new();
+        keys.iter().map(|&(name, votes)|tie_breaker_hash(name, votes, salt, &mut hasher)).collect::<Vec<_>>()
+    };
+    if threads <= 1 || keys.len() < 2 {
+        return serial(keys);
+    }
+    let chunk = keys.len().div_ceil(threads);
+    std::thread::scope(|s| {
+        let handles = keys.chunks(chunk)
+            .map(|keys|(keys, s.spawn(move ||serial(keys))))
+            .collect::<Vec<_>>();
+        // If a thread could not finish, its share is hashed again here
+        handles.into_iter()
+            .flat_map(|(keys, h)|h.join().unwrap_or_else(|_|serial(keys)))
+            .collect()
+    })
+}
+
+fn tie_breaker_hash_event(c: &Candidate, name: &str, salt: &[u8]) -> DeterministicTieBreakerHash {
+    let (votes, len) = votes_bytes(c.total_indirect_votes);
+    let mut buf = Vec::with_capacity(name.len() + len + salt.len());
+    buf.extend_from_slice(name.as_bytes());
+    buf.extend_from_slice(&votes[..len]);
+    buf.extend_from_slice(salt);
+    DeterministicTieBreakerHash{
+        candidate: name.to_string(),
+        total_indirect_votes: c.total_indirect_votes,
+        bytes: buf,
+    }
+}
+
+/// The winners in the order of the deterministic tie-breaker, the first one wins.
 fn tie_breaker<'b, 'a: 'b>(
-    winners: &Vec<&'b Candidate<'a>>,
+    cand: &[Candidate<'a>],
+    winners: &[&'b Candidate<'a>],
+    salt: &[u8],
+    parallelism: Parallelism,
     is: &mut Introspector<'a>,
-) -> Option<&'b Candidate<'a>> {
-    match winners.len() {
-        0 => None,
-        1 => Some(winners[0]),
+) -> Result<Vec<&'b Candidate<'a>>, TallyError> {
+    Ok(match winners.len() {
+        0 | 1 => winners.to_vec(),
         _ => {
-            let mut wh = winners.iter()
-                .map(|&w|{
-                    let hash = tie_breaker_hash(w, &w.vote.voter_id, is);
-                    (hash, w)
-                })
+            if is.is_subscribed::<DeterministicTieBreakerHash>() {
+                for &w in winners {
+                    is.event_in(TallyState::new(cand), ||tie_breaker_hash_event(w, &w.vote.voter_id, salt));
+                }
+            }
+            let keys = winners.iter()
+                .map(|w|(&*w.vote.voter_id, w.total_indirect_votes))
                 .collect::<Vec<_>>();
-            wh.sort_by_key(|(k,_)|k.clone());
-            is.event(||DeterministicTieBreaker{
-                votes: wh[0].1.total_indirect_votes,
-                tied_candidates: wh.iter().map(|c|(c.1.vote, c.0)).collect(),
+            let hashes = tie_breaker_hashes(&keys, salt, parallelism.threads(keys.len(), PARALLEL_TIE_BREAK_MIN));
+            if parallelism == Parallelism::Validate && hashes != tie_breaker_hashes(&keys, salt, 1) {
+                return Err(TallyError::ParallelMismatch("tie-breaker hashes"));
+            }
+            // Sort the positions rather than the hashes, so that the 64 byte hashes are
+            // compared in place rather than copied for every comparison
+            let mut order = (0..winners.len()).collect::<Vec<_>>();
+            order.sort_unstable_by(|&a, &b|hashes[a].cmp(&hashes[b]).then(a.cmp(&b)));
+            is.event_in(TallyState::new(cand), ||DeterministicTieBreaker{
+                votes: winners[order[0]].total_indirect_votes,
+                tied_candidates: order.iter().map(|&i|(winners[i].vote, hashes[i])).collect(),
             });
-            wh.iter().map(|(_,c)|*c).next()
+            order.iter().map(|&i|winners[i]).collect()
         }
-    }
+    })
+}
+
+/// Break down where a candidate's total delegated votes came from.
+fn contributions<'a>(cand: &[Candidate<'a>], c: &Candidate<'a>) -> Contributions<'a> {
+    // The votes which arrive at a node from outside of its ring
+    let from_outside = |node: &Candidate<'a>| {
+        let mut supporters = Vec::new();
+        let mut next = node.voted_for_me;
+        while let Some(s) = next {
+            let s = &cand[s];
+            if s.component != node.component {
+                supporters.push((s.vote, s.total_indirect_votes));
+            }
+            next = s.voting_for_same;
+        }
+        supporters
+    };
+    let by_votes = |v: &mut Vec<(&'a Vote<'a>, Weight)>| {
+        v.sort_by(|a, b|b.1.cmp(&a.1).then_with(||a.0.voter_id.cmp(&b.0.voter_id)));
+    };
+    let mut supporters = from_outside(c);
+    by_votes(&mut supporters);
+    let mut through_ring = cand.iter()
+        .filter(|m|m.component == c.component && *m != c)
+        .map(|m|(m.vote, m.own_votes + from_outside(m).iter().map(|s|s.1).sum::<Weight>()))
+        .collect::<Vec<_>>();
+    by_votes(&mut through_ring);
+    Contributions { own: c.own_votes, supporters, through_ring }
+}
+
+/// The winner of a count, see VoteCounter::solve()
+struct Solution<'a> {
+    winner: &'a Vote<'a>,
+    votes: Weight,
+    contributions: Contributions<'a>,
+    /// The positions in the ballots of the winner and of the candidates who lost the
+    /// tie-break to them, in the order of the tie-break
+    ballot: usize,
+    tied_with: Vec<usize>,
+}
+
+/// A ballot as changed by VoteCounter::update_vote()
+#[derive(Clone, Copy)]
+struct Update<'a> {
+    vote_for: Option<&'a str>,
+    number_of_votes: Weight,
+}
+
+/// Who a ballot as changed by VoteCounter::update_vote() votes for
+enum Target {
+    /// A candidate, by index in cand
+    Candidate(usize),
+    /// Someone who has no ballot, by index in implicit
+    Implicit(usize),
+    /// Nobody, and why
+    Invalid(InvalidVoteCause),
 }
 
 pub struct VoteCounter<'a> {
+    votes: Ballots<'a>,
     cand: Vec<Candidate<'a>>,
+    implicit: Vec<ImplicitVoter<'a>>,
+    invalid: Vec<(InvalidVoteCause, &'a Vote<'a>)>,
     is: Introspector<'a>,
+    options: ElectionOptions,
+    parallelism: Parallelism,
+    /// See limits::weight_shift()
+    weight_shift: u32,
     total_willing_candidates: usize,
-    best: Option<usize>
+    /// A ballot which the options do not allow, the tally fails with this error
+    ingestion_error: Option<TallyError>,
+    best: Result<Option<usize>, TallyError>,
+    /// The same order as the next_by_total_indirect_votes list, for random access
+    ranking: Vec<usize>,
+    /// The ballots whose votes were revoked, they stay revoked if the ballots are read again
+    revoked: Vec<&'a Vote<'a>>,
+    /// The changes made with update_vote(), by voter_id, they are made again if the
+    /// ballots are read again
+    updated: HashMap<String, Update<'a>>,
+    /// The ballots as interned, if they were counted with with_interned()
+    interned: Option<&'a InternedBallots>,
 }
 impl<'a> VoteCounter<'a> {
     /// Create a new VoteCounter and compute the delegated votes.
     /// After this has been called, you may call iter() to
     /// walk the ranking of the candidates, or you may call find_winner to attempt to
     /// compute a winning candidate.
-    pub fn new(votes: &'a [Vote], is: Introspector<'a>) -> Self {
+    /// VoteCounter borrows the ballots, to count ballots which are streamed from an
+    /// iterator collect them into an Election, which owns them, see Election::from_ballots().
+    pub fn new(votes: &'a [Vote<'a>], is: Introspector<'a>) -> Self {
+        Self::with_options(votes, is, ElectionOptions::default())
+    }
+
+    /// Create a new VoteCounter which will count the election according to options.
+    pub fn with_options(votes: &'a [Vote<'a>], is: Introspector<'a>, options: ElectionOptions) -> Self {
+        Self::with_ballots(Ballots::All(votes), is, options, None)
+    }
+
+    /// Create a new VoteCounter for interned ballots, votes must be ballots.to_votes().
+    /// People are found by the handles of their IDs rather than by hashing the IDs, so
+    /// the IDs are hashed only when they are interned. If votes are not ballots.to_votes()
+    /// then this is the same as with_options().
+    pub fn with_interned(
+        ballots: &'a InternedBallots,
+        votes: &'a [Vote<'a>],
+        is: Introspector<'a>,
+        options: ElectionOptions,
+    ) -> Self {
+        let interned = ballots.describes(votes).then_some(ballots);
+        Self::with_ballots(Ballots::All(votes), is, options, interned)
+    }
+
+    /// Check the ballots without counting them, returning every ballot which would not
+    /// be counted as a vote for anyone under options, in the order of the ballots. These
+    /// are the same as invalid_votes() after counting.
+    ///
+    /// Fails with the error which the count would fail with before reading any ballot,
+    /// because the options are invalid or the ballots are over the limits in them, see
+    /// limits::check().
+    pub fn validate(votes: &'a [Vote<'a>], options: &ElectionOptions) -> Result<Vec<BallotError>, TallyError> {
+        let ballots = Ballots::All(votes);
+        let shift = limits::check(&ballots, options)?;
+        let mut cand = Vec::with_capacity(votes.len());
+        let mut is = Introspector::default();
+        let ingested = mk_candidates(&ballots, &mut cand, options, shift, None, &mut is);
+        let mut errors = ingested.invalid.iter()
+            .map(|(cause, v, ballot)|BallotError::new(cause, *ballot, v))
+            .collect::<Vec<_>>();
+        errors.sort_by_key(BallotError::index);
+        Ok(errors)
+    }
+
+    /// Count only some of the ballots, the rest are treated as if they did not exist.
+    pub(crate) fn with_selected(
+        votes: Vec<&'a Vote<'a>>,
+        is: Introspector<'a>,
+        options: ElectionOptions,
+    ) -> Self {
+        Self::with_ballots(Ballots::Selected(votes), is, options, None)
+    }
+
+    fn with_ballots(
+        votes: Ballots<'a>,
+        is: Introspector<'a>,
+        options: ElectionOptions,
+        interned: Option<&'a InternedBallots>,
+    ) -> Self {
         let mut out = VoteCounter{
-            cand: Vec::with_capacity(votes.len()),
+            cand: Vec::new(),
+            implicit: Vec::new(),
+            invalid: Vec::new(),
             is,
+            options,
+            parallelism: Parallelism::default(),
+            weight_shift: 0,
             total_willing_candidates: 0,
-            best: None,
+            ingestion_error: None,
+            best: Ok(None),
+            ranking: Vec::new(),
+            revoked: Vec::new(),
+            updated: HashMap::new(),
+            interned,
+            votes,
         };
-        out.total_willing_candidates = mk_candidates(votes, &mut out.cand, &mut out.is);
-        out.compute_delegated_votes();
+        out.read_ballots();
         out
     }
 
+    /// Read every ballot and count them from the start
+    fn read_ballots(&mut self) {
+        self.cand.clear();
+        self.implicit.clear();
+        self.invalid.clear();
+        self.total_willing_candidates = 0;
+        self.ingestion_error = None;
+        self.best = Ok(None);
+        match self.check_limits() {
+            Ok((shift, total)) => {
+                self.weight_shift = shift;
+                if shift > 0 {
+                    self.is.event(||WeightsRescaled { shift, total });
+                }
+            }
+            Err(e) => {
+                // Nothing is read, the count fails with the error, this includes invalid options
+                self.ingestion_error = Some(e);
+                self.compute_delegated_votes();
+                return;
+            }
+        }
+        self.cand.reserve_exact(self.votes.len());
+        let ingested = mk_candidates(
+            &self.votes, &mut self.cand, &self.options, self.weight_shift, self.interned, &mut self.is);
+        self.total_willing_candidates = ingested.total_willing;
+        self.implicit = ingested.implicit;
+        self.invalid = ingested.invalid.into_iter().map(|(cause, v, _)|(cause, v)).collect();
+        self.ingestion_error = ingested.error;
+        if !self.updated.is_empty() {
+            let idx_by_name = self.cand.iter().enumerate()
+                .map(|(i, c)|{
+                    let vote: &'a Vote<'a> = c.vote;
+                    (&*vote.voter_id, i)
+                })
+                .collect::<HashMap<_, _>>();
+            let mut updates = self.updated.iter()
+                .filter_map(|(id, &u)|Some((*idx_by_name.get(&id[..])?, u)))
+                .collect::<Vec<_>>();
+            updates.sort_by_key(|u|u.0);
+            for (idx, u) in updates {
+                let voter_id = self.cand[idx].vote.voter_id.as_ref();
+                let target = match u.vote_for {
+                    None => Target::Invalid(InvalidVoteCause::NoVote),
+                    Some(t) if t == voter_id => Target::Invalid(InvalidVoteCause::SelfVote),
+                    Some(t) => match idx_by_name.get(t) {
+                        Some(&to) => Target::Candidate(to),
+                        None => self.unrecognized(t),
+                    },
+                };
+                let votes = u.number_of_votes.checked_shr(self.weight_shift).unwrap_or(0);
+                self.amend(idx, target, votes);
+            }
+            // The count fails on the first vote for someone who has no ballot as the ballots
+            // are now, mk_candidates() found those of the ballots before they were updated
+            let unrecognized_error = matches!(self.ingestion_error, None | Some(TallyError::UnrecognizedVote(..)));
+            if self.options.unrecognized_vote == UnrecognizedVotePolicy::Error && unrecognized_error {
+                let unrecognized = self.invalid.iter()
+                    .filter(|(cause, _)|*cause == InvalidVoteCause::UnrecognizedVote)
+                    .map(|(_, v)|std::ptr::from_ref(*v))
+                    .collect::<HashSet<_>>();
+                self.ingestion_error = self.cand.iter()
+                    .find(|c|unrecognized.contains(&std::ptr::from_ref(c.vote)))
+                    .map(|c|{
+                        let voter_id = &*c.vote.voter_id;
+                        let vote_for = match self.updated.get(voter_id) {
+                            Some(u) => u.vote_for,
+                            None => c.vote.vote_for.iter().chain(&c.vote.fallbacks).next().map(|f|&**f),
+                        };
+                        TallyError::UnrecognizedVote(voter_id.to_owned(), vote_for.unwrap_or_default().to_owned())
+                    });
+            }
+        }
+        // After the updates, a vote which was revoked after it was updated stays revoked
+        for c in &mut self.cand {
+            if self.revoked.contains(&c.vote) {
+                c.vote_for = None;
+                c.implicit_vote_for = None;
+            }
+        }
+        if !self.updated.is_empty() {
+            self.renumber_implicit();
+        }
+        self.compute_delegated_votes();
+    }
+
+    /// limits::check() of the ballots as changed by update_vote(), which are what is
+    /// counted, and the total votes before they are shifted if the shift is not 0.
+    fn check_limits(&self) -> Result<(u32, u128), TallyError> {
+        let amended;
+        let ballots = if self.updated.is_empty() {
+            &self.votes
+        } else {
+            amended = self.votes.iter().map(|v|self.amended(v)).collect::<Vec<_>>();
+            &Ballots::All(&amended)
+        };
+        let shift = limits::check(ballots, &self.options)?;
+        let total = if shift > 0 { limits::weight_shift(ballots.iter()).1 } else { 0 };
+        Ok((shift, total))
+    }
+
+    /// Add a ballot which arrived after the VoteCounter was created, so that one
+    /// VoteCounter can follow an election while the ballots come in. The ballot is
+    /// borrowed for as long as the VoteCounter, like the others, see live::LiveTally for
+    /// a tally which owns the ballots.
+    ///
+    /// When nobody voted for the new voter, they have no fallbacks and the weights do not
+    /// need to be rescaled, only the votes of the new ballot are delegated and the
+    /// candidates ranked again. Otherwise every ballot is read again, emitting the events
+    /// of new() once more. Either way the count is the same as that of a new VoteCounter
+    /// with every ballot, except that votes which were revoked stay revoked.
+    pub fn add_vote(&mut self, vote: &'a Vote<'a>) {
+        let id = &*vote.voter_id;
+        // The count of another ballot depends on whether this voter has a ballot
+        let named = self.votes.iter().any(|v|{
+            v.voter_id == id || v.vote_for.as_deref() == Some(id) || v.fallbacks.iter().any(|f|f == id)
+        }) || self.updated.values().any(|u|u.vote_for == Some(id));
+        self.votes.push(vote);
+        if named ||
+            vote.abstain ||
+            !vote.fallbacks.is_empty() ||
+            self.ingestion_error.is_some() ||
+            self.check_limits().ok().map(|l|l.0) != Some(self.weight_shift) ||
+            !self.add_candidate(vote)
+        {
+            self.read_ballots();
+        }
+    }
+
+    /// Count a ballot without reading the others again, returns false if it votes for
+    /// someone who has no ballot, which is left to read_ballots().
+    fn add_candidate(&mut self, vote: &'a Vote<'a>) -> bool {
+        let target = match vote.vote_for.as_deref() {
+            Some(t) if t != vote.voter_id => match self.cand.iter().position(|c|c.vote.voter_id == t) {
+                Some(idx) => Some(idx),
+                None => return false,
+            },
+            _ => None,
+        };
+        // Willing candidates are kept before the others, and in the order of the ballots,
+        // as mk_candidates() would have put them
+        let willing = vote.willing_candidate;
+        let at = if willing { self.total_willing_candidates } else { self.cand.len() };
+        let shifted = |i: usize| if i >= at { i + 1 } else { i };
+        for c in &mut self.cand {
+            c.vote_for = c.vote_for.map(shifted);
+            c.voted_for_me = None;
+            c.voting_for_same = None;
+            c.next_by_total_indirect_votes = None;
+        }
+        let weight = vote.number_of_votes.checked_shr(self.weight_shift).unwrap_or(0);
+        self.cand.insert(at, Candidate{
+            vote,
+            // add_vote() pushed it after the others
+            ballot: self.votes.len() - 1,
+            vote_for: target.map(shifted),
+            implicit_vote_for: None,
+            voting_for_same: None,
+            total_indirect_votes: weight,
+            own_votes: weight,
+            votes_from_voters: 0,
+            component: 0,
+            voted_for_me: None,
+            is_willing_candidate: willing,
+            next_by_total_indirect_votes: None,
+        });
+        if willing {
+            self.total_willing_candidates += 1;
+        }
+        match vote.vote_for {
+            None => invalid_vote(&mut self.invalid, &mut self.is, InvalidVoteCause::NoVote, vote),
+            Some(_) if target.is_none() =>
+                invalid_vote(&mut self.invalid, &mut self.is, InvalidVoteCause::SelfVote, vote),
+            Some(_) => {}
+        }
+        let report = DelegationReports::new(&self.is);
+        delegate(at, &mut self.cand, &mut self.implicit, &mut self.is, &report, &mut Vec::new());
+        self.relink();
+        true
+    }
+
+    /// Rebuild what follows from the delegations after some of them changed: the
+    /// voted_for_me lists, in the order of the candidates as in a new count, the votes
+    /// from voters and the ranking.
+    fn relink(&mut self) {
+        for c in &mut self.cand {
+            c.voted_for_me = None;
+            c.voting_for_same = None;
+            c.votes_from_voters = 0;
+            c.next_by_total_indirect_votes = None;
+        }
+        for node_id in 0..self.cand.len() {
+            if let Some(vote_for) = self.cand[node_id].vote_for {
+                self.cand[node_id].voting_for_same = self.cand[vote_for].voted_for_me;
+                self.cand[vote_for].voted_for_me = Some(node_id);
+                self.cand[vote_for].votes_from_voters += self.cand[node_id].total_indirect_votes;
+            }
+        }
+        self.renumber_implicit();
+        self.rank();
+    }
+
+    /// Number the implicit voters in the order that the candidates vote for them, as
+    /// mk_candidates() does, and drop those who nobody votes for any more.
+    fn renumber_implicit(&mut self) {
+        let mut new_idx = vec![None; self.implicit.len()];
+        let mut order = Vec::with_capacity(self.implicit.len());
+        for c in &mut self.cand {
+            if let Some(iv) = c.implicit_vote_for {
+                let n = *new_idx[iv].get_or_insert_with(||{
+                    order.push(iv);
+                    order.len() - 1
+                });
+                c.implicit_vote_for = Some(n);
+            }
+        }
+        if order.iter().copied().eq(0..self.implicit.len()) {
+            return;
+        }
+        let mut old = std::mem::take(&mut self.implicit).into_iter().map(Some).collect::<Vec<_>>();
+        self.implicit = order.into_iter().filter_map(|iv|old[iv].take()).collect();
+    }
+
+    /// Who a ballot as changed by update_vote() votes for when they vote for someone who
+    /// has no ballot, as mk_candidates() would count it. Under UnrecognizedVotePolicy::Error
+    /// the vote is not counted, the caller decides whether the count fails.
+    fn unrecognized(&mut self, vote_for: &'a str) -> Target {
+        if self.options.unrecognized_vote != UnrecognizedVotePolicy::ImplicitVoter {
+            return Target::Invalid(InvalidVoteCause::UnrecognizedVote);
+        }
+        let iv = self.implicit.iter().position(|iv|iv.voter_id == vote_for).unwrap_or_else(||{
+            self.implicit.push(ImplicitVoter { voter_id: vote_for, total_indirect_votes: 0 });
+            self.implicit.len() - 1
+        });
+        Target::Implicit(iv)
+    }
+
+    /// Change who cand[idx] votes for, or why they vote for nobody, and their own votes,
+    /// without delegating them.
+    fn amend(&mut self, idx: usize, target: Target, votes: Weight) {
+        let c = &mut self.cand[idx];
+        c.total_indirect_votes = c.total_indirect_votes - c.own_votes + votes;
+        c.own_votes = votes;
+        c.vote_for = None;
+        c.implicit_vote_for = None;
+        let vote = c.vote;
+        self.invalid.retain(|(cause, v)|*cause == InvalidVoteCause::Duplicate || *v != vote);
+        match target {
+            Target::Candidate(to) => self.cand[idx].vote_for = Some(to),
+            Target::Implicit(iv) => self.cand[idx].implicit_vote_for = Some(iv),
+            Target::Invalid(cause) => invalid_vote(&mut self.invalid, &mut self.is, cause, vote),
+        }
+    }
+
+    /// Change the ballot of cand[idx], see amend(), and delegate again the votes of
+    /// everyone whose chain passes through them. Nobody else's totals change.
+    fn retarget(&mut self, idx: usize, target: Target, votes: Weight) {
+        let mut affected = vec![idx];
+        let mut seen = vec![false; self.cand.len()];
+        seen[idx] = true;
+        let mut i = 0;
+        while let Some(&id) = affected.get(i) {
+            let mut next = self.cand[id].voted_for_me;
+            while let Some(n) = next {
+                if !seen[n] {
+                    seen[n] = true;
+                    affected.push(n);
+                }
+                next = self.cand[n].voting_for_same;
+            }
+            i += 1;
+        }
+        let mut path = Vec::new();
+        for &id in &affected {
+            withdraw(id, &mut self.cand, &mut self.implicit, &mut path);
+        }
+        self.amend(idx, target, votes);
+        let report = DelegationReports::new(&self.is);
+        for &id in &affected {
+            delegate(id, &mut self.cand, &mut self.implicit, &mut self.is, &report, &mut path);
+        }
+        self.relink();
+    }
+
+    /// Change who a voter delegates to and how many votes they have, for a platform
+    /// where voters may change their delegate at any time. new_weight is a number of
+    /// votes as in Vote::number_of_votes, the ballot's fallbacks are no longer used.
+    ///
+    /// Only the chains which pass through the voter are delegated again, so the cost
+    /// depends on how many voters delegate to them rather than on the number of ballots,
+    /// unless under OverflowPolicy::Rescale the votes must be shifted by a different
+    /// number of bits, then every ballot is read again. The ballot itself is borrowed and
+    /// is not changed, but the count, find_winners() and view() are as if it had been,
+    /// including if the ballots are read again by add_vote() or remove_vote(). A vote for
+    /// someone who has no ballot is counted under ElectionOptions::unrecognized_vote, so
+    /// new_target is borrowed for as long as the VoteCounter, like the ballots.
+    ///
+    /// Fails with TallyError::NoBallot if no ballot from voter_id is counted, with
+    /// UnrecognizedVote if nobody by the name of new_target has a ballot under
+    /// UnrecognizedVotePolicy::Error, with the error of limits::check() of the ballots as
+    /// updated, such as WeightOverflow under OverflowPolicy::Error, or with the error of
+    /// the count if it failed, in which case nothing is changed.
+    pub fn update_vote(
+        &mut self,
+        voter_id: &str,
+        new_target: Option<&'a str>,
+        new_weight: Weight,
+    ) -> Result<(), TallyError> {
+        if let Some(e) = &self.ingestion_error {
+            return Err(e.clone());
+        }
+        let Some(idx) = self.cand.iter().position(|c|c.vote.voter_id == voter_id) else {
+            return Err(TallyError::NoBallot(voter_id.to_owned()));
+        };
+        let target = match new_target {
+            None => Target::Invalid(InvalidVoteCause::NoVote),
+            Some(t) if t == voter_id => Target::Invalid(InvalidVoteCause::SelfVote),
+            Some(t) => match self.cand.iter().position(|c|c.vote.voter_id == t) {
+                Some(to) => Target::Candidate(to),
+                None if self.options.unrecognized_vote == UnrecognizedVotePolicy::Error =>
+                    return Err(TallyError::UnrecognizedVote(voter_id.to_owned(), t.to_owned())),
+                // Resolved once the update is known to be within the limits
+                None => Target::Invalid(InvalidVoteCause::UnrecognizedVote),
+            },
+        };
+        let previous = self.updated.insert(voter_id.to_owned(), Update {
+            vote_for: new_target,
+            number_of_votes: new_weight,
+        });
+        let shift = match self.check_limits() {
+            Ok((shift, _)) => shift,
+            Err(e) => {
+                match previous {
+                    Some(u) => self.updated.insert(voter_id.to_owned(), u),
+                    None => self.updated.remove(voter_id),
+                };
+                return Err(e);
+            }
+        };
+        self.revoked.retain(|v|v.voter_id != voter_id);
+        if shift != self.weight_shift {
+            // Everyone's votes are shifted differently
+            self.read_ballots();
+        } else {
+            let target = match (target, new_target) {
+                (Target::Invalid(InvalidVoteCause::UnrecognizedVote), Some(t)) => self.unrecognized(t),
+                (target, _) => target,
+            };
+            self.retarget(idx, target, new_weight.checked_shr(shift).unwrap_or(0));
+        }
+        Ok(())
+    }
+
+    /// Stop counting the ballots of voter_id, returning false if there are none. When
+    /// nobody delegates to them, only their own votes are taken back from their chain,
+    /// otherwise every ballot is read again, emitting the events of new() once more, and
+    /// those who voted for them are voting for someone who has no ballot. A vote for them
+    /// made with update_vote() is no longer delegated.
+    pub fn remove_vote(&mut self, voter_id: &str) -> bool {
+        // The positions of the ballots which are removed
+        let gone = self.votes.iter().enumerate()
+            .filter_map(|(i, v)|(v.voter_id == voter_id).then_some(i))
+            .collect::<Vec<_>>();
+        if gone.is_empty() {
+            return false;
+        }
+        self.votes.retain(|v|v.voter_id != voter_id);
+        self.updated.remove(voter_id);
+        self.revoked.retain(|v|v.voter_id != voter_id);
+        let idx = self.cand.iter().position(|c|c.vote.voter_id == voter_id);
+        match idx {
+            Some(idx) if self.ingestion_error.is_none() &&
+                self.cand[idx].voted_for_me.is_none() &&
+                self.check_limits().ok().map(|l|l.0) == Some(self.weight_shift) =>
+            {
+                withdraw(idx, &mut self.cand, &mut self.implicit, &mut Vec::new());
+                let removed = self.cand.remove(idx);
+                if removed.is_willing_candidate {
+                    self.total_willing_candidates -= 1;
+                }
+                for c in &mut self.cand {
+                    c.vote_for = c.vote_for.map(|i|if i > idx { i - 1 } else { i });
+                    c.ballot -= gone.partition_point(|&r|r < c.ballot);
+                }
+                self.invalid.retain(|(_, v)|v.voter_id != voter_id);
+                self.relink();
+            }
+            _ => self.read_ballots(),
+        }
+        true
+    }
+
+    /// A borrowed copy of a ballot, as changed by update_vote()
+    fn amended(&self, v: &'a Vote<'a>) -> Vote<'a> {
+        let Some(u) = self.updated.get(&*v.voter_id) else {
+            return Vote {
+                voter_id: Cow::Borrowed(&*v.voter_id),
+                vote_for: v.vote_for.as_deref().map(Cow::Borrowed),
+                number_of_votes: v.number_of_votes,
+                willing_candidate: v.willing_candidate,
+                metadata: Cow::Borrowed(&*v.metadata),
+                fallbacks: v.fallbacks.iter().map(|f|Cow::Borrowed(&**f)).collect(),
+                abstain: v.abstain,
+            };
+        };
+        Vote {
+            voter_id: Cow::Borrowed(&*v.voter_id),
+            vote_for: u.vote_for.map(Cow::Borrowed),
+            number_of_votes: u.number_of_votes,
+            // An abstainer who delegates is still not a candidate
+            willing_candidate: v.willing_candidate && !v.abstain,
+            metadata: Cow::Borrowed(&*v.metadata),
+            fallbacks: Vec::new(),
+            abstain: false,
+        }
+    }
+
+    /// The Introspector which receives events, subscribers which are added here receive
+    /// the events from the search for the winner but not those which were emitted when
+    /// the ballots were read, use replay() for those.
+    pub fn introspector_mut(&mut self) -> &mut Introspector<'a> {
+        &mut self.is
+    }
+
+    /// Choose how many threads the search for the winner may use, the result is the same
+    /// whichever is chosen, see parallel::validate().
+    pub fn set_parallelism(&mut self, parallelism: Parallelism) {
+        self.parallelism = parallelism;
+    }
+
+    /// Take back the Introspector, for example to read the contexts of its subscribers
+    /// with Introspector::into_contexts().
+    pub fn into_introspector(self) -> Introspector<'a> {
+        self.is
+    }
+
+    /// Send the events from reading the ballots and delegating the votes to the
+    /// subscribers of is, as they were emitted by new(), then add those subscribers to
+    /// this VoteCounter so that they receive every later event.
+    ///
+    /// The events are reproduced by reading the ballots again rather than kept in memory,
+    /// so this costs as much as new(), and votes revoked with revoke_vote() or changed with
+    /// update_vote() are not taken into account.
+    pub fn replay(&mut self, mut is: Introspector<'a>) {
+        if limits::check(&self.votes, &self.options).is_err() {
+            // Nothing was read so there is nothing to replay
+            self.is.merge(is);
+            return;
+        }
+        if self.weight_shift > 0 {
+            let total = limits::weight_shift(self.votes.iter()).1;
+            is.event(||WeightsRescaled { shift: self.weight_shift, total });
+        }
+        let mut cand = Vec::with_capacity(self.votes.len());
+        let mut ingested =
+            mk_candidates(&self.votes, &mut cand, &self.options, self.weight_shift, self.interned, &mut is);
+        compute_delegated_votes(&mut cand, &mut ingested.implicit, &mut is);
+        self.is.merge(is);
+    }
+
     fn compute_delegated_votes(&mut self) {
-        compute_delegated_votes(&mut self.cand, &mut self.is);
-        self.best = order_by_total_indirect(&mut self.cand, self.total_willing_candidates);
+        compute_delegated_votes(&mut self.cand, &mut self.implicit, &mut self.is);
+        self.rank();
+    }
+
+    /// Find the delegation rings and rank the willing candidates
+    fn rank(&mut self) {
+        let next = self.cand.iter().map(|c|c.vote_for).collect::<Vec<_>>();
+        for (c, component) in self.cand.iter_mut().zip(scc::tarjan(&next)) {
+            c.component = component;
+        }
+        self.is.progress(Phase::Ranking, 0, 1);
+        self.best = match &self.ingestion_error {
+            Some(e) => Err(e.clone()),
+            None => order_by_total_indirect(
+                &mut self.cand, self.total_willing_candidates, &mut self.ranking),
+        };
+        self.is.progress(Phase::Ranking, 1, 1);
     }
  
-    /// Attempt to find a winning candidate using the search algorithm
-    pub fn find_winner(&mut self) -> Option<&'a Vote> {
-        let best = match self.best {
-            Some(best) => best,
-            None => {
-                self.is.event(||None);
-                return None
-            }
+    /// Attempt to find a winning candidate using the search algorithm.
+    /// Returns None if there is no winner, or if the tally failed with a TallyError,
+    /// use try_find_winner() to tell the difference.
+    pub fn find_winner(&mut self) -> Option<&'a Vote<'a>> {
+        self.try_find_winner().unwrap_or(None)
+    }
+
+    /// Attempt to find a winning candidate using the search algorithm, reporting any
+    /// internal inconsistency as an error rather than aborting the process.
+    pub fn try_find_winner(&mut self) -> Result<Option<&'a Vote<'a>>, TallyError> {
+        Ok(self.solve()?.map(|s|s.winner))
+    }
+
+    /// Every candidate tied for the win, before the deterministic tie-breaker picks one
+    /// of them, ordered by voter_id. One candidate if there is no tie, none if there is
+    /// no winner or the tally failed, use try_find_winner_all() to tell the difference.
+    pub fn find_winner_all(&mut self) -> Vec<&'a Vote<'a>> {
+        self.try_find_winner_all().unwrap_or_default()
+    }
+
+    /// As find_winner_all(), reporting any internal inconsistency as an error. The
+    /// events are those of try_find_winner() up to the tie-breaker, there is no Winner
+    /// event since no winner is chosen.
+    pub fn try_find_winner_all(&mut self) -> Result<Vec<&'a Vote<'a>>, TallyError> {
+        self.best.clone()?;
+        self.check_quorum()?;
+        let Some(best) = self.best.clone()? else {
+            return Ok(Vec::new());
+        };
+        let tied = match self.options.algorithm {
+            v @ (AlgorithmVersion::V2 | AlgorithmVersion::V3) =>
+                tied_winners(&self.cand, best, v, self.options.patron_rule, &mut self.is)?,
         };
-        let (best_ring, ring_count) = get_best_candidates(&self.cand, best, &mut self.is);
+        let mut tied = tied.into_iter().map(|c|c.vote).collect::<Vec<_>>();
+        tied.sort_by(|a, b|a.voter_id.cmp(&b.voter_id));
+        Ok(tied)
+    }
+
+    /// Fill seats seats of a committee, returning the winners in the order they were
+    /// elected, fewer if the candidates run out, see try_find_winners().
+    pub fn find_winners(&mut self, seats: usize) -> Vec<&'a Vote<'a>> {
+        self.try_find_winners(seats).unwrap_or_default()
+    }
+
+    /// Fill seats seats of a committee. The first seat goes to the winner, then each
+    /// winner stops being a candidate and the ballots are counted again for the next
+    /// seat. A winner's ballot still counts, so the votes delegated to them pass on to
+    /// whoever they voted for. A Seat event is emitted for each seat.
+    ///
+    /// The events of the first count are emitted as for try_find_winner(), the counts
+    /// for later seats only emit their Seat. Like replay(), the later counts read the
+    /// ballots again so they do not take votes revoked with revoke_vote() into account.
+    pub fn try_find_winners(&mut self, seats: usize) -> Result<Vec<&'a Vote<'a>>, TallyError> {
+        let mut winners: Vec<&'a Vote<'a>> = Vec::new();
+        let first = if seats > 0 { self.solve()? } else { None };
+        let Some(Solution { winner: first, votes, .. }) = first else {
+            return Ok(winners);
+        };
+        self.is.event_in(TallyState::new(&self.cand), ||Seat { seat: 1, candidate: first, votes });
+        winners.push(first);
+        while winners.len() < seats {
+            let Some(s) = self.elect(&winners)? else {
+                break;
+            };
+            self.is.event_in(TallyState::new(&self.cand), ||Seat { seat: s.position, candidate: s.candidate, votes: s.votes });
+            winners.push(s.candidate);
+        }
+        Ok(winners)
+    }
+
+    /// Count the same ballots again with the excluded candidates no longer willing, the
+    /// winner takes the position after them. The count has its own introspector so it
+    /// emits no events.
+    fn elect(&self, excluded: &[&'a Vote<'a>]) -> Result<Option<Standing<'a>>, TallyError> {
+        // The same ballots, borrowed, with the excluded candidates no longer willing
+        let votes = self.votes.iter().collect::<Vec<_>>();
+        let ballots = votes.iter().map(|&v|Vote {
+            willing_candidate: v.willing_candidate && !excluded.contains(&v),
+            ..self.amended(v)
+        }).collect::<Vec<_>>();
+        let mut vc = VoteCounter::with_options(&ballots, Introspector::default(), self.options.clone());
+        vc.parallelism = self.parallelism;
+        let Some(s) = vc.solve()? else {
+            return Ok(None);
+        };
+        // The copies are in the same order as the ballots
+        let original = |ballot: usize| votes.get(ballot).copied().ok_or(TallyError::BallotOutOfRange(ballot));
+        Ok(Some(Standing {
+            position: excluded.len() + 1,
+            candidate: original(s.ballot)?,
+            votes: s.votes,
+            tied_with: s.tied_with.into_iter().map(original).collect::<Result<_, _>>()?,
+        }))
+    }
+
+    /// Get the final finishing order: the winner, then the candidate who would win if the
+    /// winner were not standing, and so on until nobody else can win. Unlike iter(), which
+    /// gives the votes each candidate could receive with all possible delegations, each
+    /// position is the result of a full count so rings are resolved and patrons applied.
+    ///
+    /// This costs one count per position, for a quick preview use iter().
+    pub fn standings(&self) -> Result<Vec<Standing<'a>>, TallyError> {
+        let mut out: Vec<Standing<'a>> = Vec::new();
+        let mut placed: Vec<&'a Vote<'a>> = Vec::new();
+        while let Some(s) = self.elect(&placed)? {
+            placed.push(s.candidate);
+            out.push(s);
+        }
+        Ok(out)
+    }
+
+    /// Get a TallyView of this count, which owns everything it refers to and can outlive
+    /// the ballots, see view::TallyView. The ballots are counted again to record the
+    /// events, so this costs as much as new() and tally(), and like replay(), votes
+    /// revoked with revoke_vote() are not taken into account.
+    pub fn view(&self) -> view::TallyView {
+        let ballots = self.votes.iter().map(|v|self.amended(v)).collect::<Vec<_>>();
+        let trail = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let mut is = Introspector::default();
+        let sink = trail.clone();
+        record::subscribe_all(&mut is, move |r| sink.borrow_mut().push(r));
+        let mut vc = VoteCounter::with_options(&ballots, is, self.options.clone());
+        vc.parallelism = self.parallelism;
+        let ranking = vc.iter().map(|(votes, v)|(v.voter_id.to_string(), votes)).collect();
+        let candidates = vc.find(|_|true).iter().map(Into::into).collect();
+        let result = vc.tally();
+        drop(vc);
+        view::TallyView::new(result, ranking, candidates, trail.take())
+    }
 
-        // 4. Get the best candidate out of the best ring
-        let mut tenative_winner = best_of_ring(&self.cand, &best_ring, &mut self.is);
+    /// Find the winner and produce an owned ElectionResult which records the options
+    /// that the election was counted under.
+    pub fn tally(&mut self) -> Result<ElectionResult, TallyError> {
+        let winner = self.solve()?;
+        let votes = winner.as_ref().map(|s|s.votes).unwrap_or(0);
+        Ok(ElectionResult {
+            winner: winner.as_ref().map(|s|s.winner.voter_id.to_string()),
+            votes,
+            contributions: winner.as_ref().map(|s|(&s.contributions).into()).unwrap_or_default(),
+            winner_metadata: winner.as_ref().map(|s|(*s.winner.metadata).clone()).unwrap_or_default(),
+            weight_shift: self.weight_shift,
+            sources: Vec::new(),
+            engagement: self.engagement(votes),
+            rules: RuleSet::new(self.options.clone()),
+        })
+    }
 
-        if ring_count < 2 {
-            tenative_winner = solve_winner(&self.cand, tenative_winner, &best_ring, &mut self.is);
+    /// Fail with TallyError::NoQuorum if fewer ballots or votes were counted than the
+    /// options require. min_votes is rescaled in the same way as the ballots.
+    fn check_quorum(&mut self) -> Result<(), TallyError> {
+        let (min_ballots, min_votes) = (self.options.min_ballots, self.options.min_votes);
+        if min_ballots.is_none() && min_votes.is_none() {
+            return Ok(());
         }
+        let e = self.engagement(0);
+        let min_counted_votes = min_votes.map(|m|m.checked_shr(self.weight_shift).unwrap_or(0));
+        if min_ballots.is_some_and(|m|e.valid_ballots < m) || min_counted_votes.is_some_and(|m|e.valid_votes < m) {
+            let (ballots, votes) = (e.valid_ballots, e.valid_votes);
+            self.is.event_in(TallyState::new(&self.cand), ||NoQuorum { ballots, votes, min_ballots, min_votes });
+            return Err(TallyError::NoQuorum { ballots, votes });
+        }
+        Ok(())
+    }
+
+    fn engagement(&self, winner_votes: Weight) -> Engagement {
+        let valid_votes = self.cand.iter().map(|c|c.own_votes).sum();
+        let valid_ballots = self.cand.len() as u64;
+        let (abstentions, abstaining_votes) = self.cand.iter()
+            .filter(|c|c.vote.abstain)
+            .fold((0, 0), |(n, w), c|(n + 1, w + c.own_votes));
+        let delegating_ballots = self.cand.iter()
+            .filter(|c|c.vote_for.is_some() || c.implicit_vote_for.is_some())
+            .count() as u64;
+        Engagement {
+            valid_ballots,
+            valid_votes,
+            delegating_ballots,
+            abstentions,
+            abstaining_votes,
+            candidates: self.total_willing_candidates as u64,
+            turnout_bp: self.options.registry_weight.map(|w|Engagement::bp(valid_votes, w)),
+            delegation_bp: Engagement::bp(delegating_ballots, valid_ballots),
+            utilization_bp: Engagement::bp(winner_votes, valid_votes),
+        }
+    }
+
+    /// As tally(), and also total the votes by the source of each ballot, as given by
+    /// source_of, for elections which take ballots in more than one way.
+    pub fn tally_by_source<'s>(
+        &mut self,
+        source_of: impl Fn(&'a Vote<'a>) -> &'s str,
+    ) -> Result<ElectionResult, TallyError> {
+        let mut result = self.tally()?;
+        // Everyone whose votes reach the winner, found by walking the delegations backward
+        let mut reaches_winner = vec![false; self.cand.len()];
+        let winner = result.winner.as_ref()
+            .and_then(|w|self.cand.iter().position(|c|&c.vote.voter_id == w));
+        let mut stack = winner.into_iter().collect::<Vec<_>>();
+        while let Some(idx) = stack.pop() {
+            if std::mem::replace(&mut reaches_winner[idx], true) {
+                continue;
+            }
+            let mut next = self.cand[idx].voted_for_me;
+            while let Some(n) = next {
+                stack.push(n);
+                next = self.cand[n].voting_for_same;
+            }
+        }
+        let mut sources: BTreeMap<&str, SourceTotals> = BTreeMap::new();
+        for (c, reaches) in self.cand.iter().zip(reaches_winner) {
+            let source = source_of(c.vote);
+            let t = sources.entry(source).or_insert_with(||SourceTotals {
+                source: source.to_owned(),
+                ballots: 0,
+                votes: 0,
+                to_winner: 0,
+            });
+            t.ballots += 1;
+            t.votes += c.own_votes;
+            if reaches {
+                t.to_winner += c.own_votes;
+            }
+        }
+        result.sources = sources.into_values().collect();
+        Ok(result)
+    }
+
+    /// Run the search algorithm selected in the options, returning the winner, their
+    /// total delegated votes and where those votes came from.
+    fn solve(&mut self) -> Result<Option<Solution<'a>>, TallyError> {
+        self.best.clone()?;
+        self.check_quorum()?;
+        match self.options.algorithm {
+            AlgorithmVersion::V2 | AlgorithmVersion::V3 => self.solve_v2(),
+        }
+    }
+
+    fn solve_v2(&mut self) -> Result<Option<Solution<'a>>, TallyError> {
+        let best = match self.best.clone()? {
+            Some(best) => best,
+            None => {
+                self.is.event_in(TallyState::new(&self.cand), ||None);
+                return Ok(None)
+            }
+        };
+        let tenative_winner = tied_winners(&self.cand, best, self.options.algorithm, self.options.patron_rule, &mut self.is)?;
     
         // 6. In case of a tie, resolve 
-        let winner = tie_breaker(&tenative_winner, &mut self.is);
+        let salt = self.options.tie_break_salt.as_deref().unwrap_or_default();
+        let order = tie_breaker(&self.cand, &tenative_winner, salt, self.parallelism, &mut self.is)?;
     
-        self.is.event(||winner.map(|w|Winner{ candidate: w.vote, votes: w.total_indirect_votes }));
+        let winner = order.split_first().map(|(w, tied_with)|Solution {
+            winner: w.vote,
+            votes: w.total_indirect_votes,
+            contributions: contributions(&self.cand, w),
+            ballot: w.ballot,
+            tied_with: tied_with.iter().map(|c|c.ballot).collect(),
+        });
+
+        self.is.event_in(TallyState::new(&self.cand), ||winner.as_ref().map(|s|Winner{
+            candidate: s.winner,
+            votes: s.votes,
+            contributions: s.contributions.clone(),
+        }));
     
-        winner.map(|w|w.vote)
+        Ok(winner)
     }
 
     /// Revoke a vote and re-compute, this can be used when a winning candidate has been
     /// identified to demonstrate conclusively that they are the winner - if they do not
-    /// delegate their vote.
-    pub fn revoke_vote(&mut self, projected_winner: &Vote) {
+    /// delegate their vote. projected_winner must be one of the ballots which were
+    /// counted, not a copy, see revoke_vote_by_id().
+    pub fn revoke_vote(&mut self, projected_winner: &Vote<'_>) {
+        self.revoke_where(|v|v == projected_winner);
+    }
+
+    /// Same as revoke_vote() but the ballot is identified by its voter_id, so a copy of
+    /// the ballots can be used to find it.
+    pub fn revoke_vote_by_id(&mut self, voter_id: &str) {
+        self.revoke_where(|v|v.voter_id == voter_id);
+    }
+
+    fn revoke_where(&mut self, revoked: impl Fn(&Vote<'_>) -> bool) {
         for c in &mut self.cand {
             c.next_by_total_indirect_votes = None;
-            c.total_indirect_votes = c.vote.number_of_votes;
+            c.total_indirect_votes = c.own_votes;
+            c.votes_from_voters = 0;
             c.voted_for_me = None;
             c.voting_for_same = None;
-            if c.vote == projected_winner {
+            if revoked(c.vote) {
+                self.revoked.push(c.vote);
                 c.vote_for = None;
+                c.implicit_vote_for = None;
             }
         }
-        self.best = None;
+        for iv in &mut self.implicit {
+            iv.total_indirect_votes = 0;
+        }
+        self.best = Ok(None);
         self.compute_delegated_votes();
     }
 
+    /// Get the ballots which were not counted as a vote for anyone, and why, in the order
+    /// that they were found. These are the same as the InvalidVote events.
+    pub fn invalid_votes(&self) -> &[(InvalidVoteCause, &'a Vote<'a>)] {
+        &self.invalid
+    }
+
+    /// Get the people who were voted for but have no ballot, with the number of votes
+    /// delegated to them, under UnrecognizedVotePolicy::ImplicitVoter.
+    pub fn implicit_voters(&self) -> Vec<(&'a str, Weight)> {
+        self.implicit.iter().map(|iv|(iv.voter_id, iv.total_indirect_votes)).collect()
+    }
+
+    /// Get the delegation rings, i.e. the groups of two or more voters who, directly or
+    /// indirectly, all voted for one another. Unwilling voters are included.
+    pub fn rings(&self) -> Vec<Vec<&'a Vote<'a>>> {
+        let mut by_component: BTreeMap<usize, Vec<&'a Vote<'a>>> = BTreeMap::new();
+        for c in &self.cand {
+            by_component.entry(c.component).or_default().push(c.vote);
+        }
+        by_component.into_values().filter(|r|r.len() > 1).collect()
+    }
+
+    /// Get up to limit candidates of the ranking, starting from position offset (0 is the
+    /// best), without walking the ranking before them. This is the same order as iter().
+    pub fn ranking_page(&self, offset: usize, limit: usize) -> RankingPage<'a> {
+        let total = if self.best.is_ok() { self.ranking.len() } else { 0 };
+        let entries = self.ranking[..total].iter()
+            .skip(offset)
+            .take(limit)
+            .map(|&i|(self.cand[i].total_indirect_votes, self.cand[i].vote))
+            .collect();
+        RankingPage { total, entries }
+    }
+
+    /// Get the information about every ballot for which f returns true, ordered by total
+    /// delegated votes, most first, then by voter_id.
+    pub fn find(&self, mut f: impl FnMut(&CandidateInfo<'a>) -> bool) -> Vec<CandidateInfo<'a>> {
+        let mut rank = vec![None; self.cand.len()];
+        if self.best.is_ok() {
+            for (pos, &i) in self.ranking.iter().enumerate() {
+                rank[i] = Some(pos);
+            }
+        }
+        let mut component_size: HashMap<usize, usize> = HashMap::new();
+        for c in &self.cand {
+            *component_size.entry(c.component).or_default() += 1;
+        }
+        let mut out = self.cand.iter()
+            .zip(rank)
+            .map(|(c, rank)|CandidateInfo {
+                vote: c.vote,
+                total_votes: c.total_indirect_votes,
+                rank,
+                vote_for: c.vote_for.map(|vf|self.cand[vf].vote),
+                in_ring: component_size.get(&c.component).is_some_and(|&n|n > 1),
+            })
+            .filter(|info|f(info))
+            .collect::<Vec<_>>();
+        out.sort_by(|a, b|b.total_votes.cmp(&a.total_votes)
+            .then_with(||a.vote.voter_id.cmp(&b.vote.voter_id)));
+        out
+    }
+
+    /// Get the information about every ballot whose voter_id starts with prefix, in the
+    /// same order as find().
+    pub fn search_prefix(&self, prefix: &str) -> Vec<CandidateInfo<'a>> {
+        self.find(|info|info.vote.voter_id.starts_with(prefix))
+    }
+
+    /// Get each candidate of the ranking, in the order of iter(), with their share of the
+    /// sum of the ranked candidates' totals, see percent::largest_remainder().
+    pub fn percentages(&self, decimals: u32) -> Vec<(percent::Percent, &'a Vote<'a>)> {
+        let ranking = self.iter().collect::<Vec<_>>();
+        let totals = ranking.iter().map(|(t, _)|*t).collect::<Vec<_>>();
+        percent::largest_remainder(&totals, decimals).into_iter()
+            .zip(ranking.into_iter().map(|(_, v)|v))
+            .collect()
+    }
+
     /// Get an iterator which yields the candidates in order by number of votes they would
     /// receive with all possible delegations.
-    pub fn iter<'b>(&'b self) -> impl Iterator<Item = (u64, &'a Vote)> + 'b {
-        WinnersIter{ vc: self, next: self.best }
+    pub fn iter<'b>(&'b self) -> impl Iterator<Item = (Weight, &'a Vote<'a>)> + 'b {
+        WinnersIter{ vc: self, next: self.best.clone().unwrap_or(None) }
     }
 }
 
+/// What the count found about one ballot, see VoteCounter::find().
+#[derive(Debug, Clone)]
+pub struct CandidateInfo<'a> {
+    pub vote: &'a Vote<'a>,
+    /// The number of votes they would receive with all possible delegations
+    pub total_votes: Weight,
+    /// Their position in the ranking, 0 is the best, None if they are not a willing
+    /// candidate
+    pub rank: Option<usize>,
+    /// The ballot of the person they voted for, if that vote was counted
+    pub vote_for: Option<&'a Vote<'a>>,
+    /// Whether they are part of a delegation ring, see VoteCounter::rings()
+    pub in_ring: bool,
+}
+
+/// A read-only view of a count in progress, given to the handlers of
+/// Introspector::subscribe_with_state() with each event. During the delegation the
+/// totals only include the delegations which have been made so far.
+#[derive(Clone, Copy)]
+pub struct TallyState<'s, 'a> {
+    cand: &'s [Candidate<'a>],
+}
+impl<'s, 'a> TallyState<'s, 'a> {
+    fn new(cand: &'s [Candidate<'a>]) -> Self {
+        Self { cand }
+    }
+    fn get(&self, voter_id: &str) -> Option<&'s Candidate<'a>> {
+        self.cand.iter().find(|c|c.vote.voter_id == voter_id)
+    }
+    /// Every ballot which is counted, with its total delegated votes so far, in the
+    /// order the ballots were ingested
+    pub fn ballots(&self) -> impl Iterator<Item = (&'a Vote<'a>, Weight)> + 's {
+        self.cand.iter().map(|c|(c.vote, c.total_indirect_votes))
+    }
+    /// The total delegated votes of voter_id so far, None if they have no ballot
+    pub fn total_votes(&self, voter_id: &str) -> Option<Weight> {
+        self.get(voter_id).map(|c|c.total_indirect_votes)
+    }
+    /// The votes of voter_id's own ballot, as counted
+    pub fn own_votes(&self, voter_id: &str) -> Option<Weight> {
+        self.get(voter_id).map(|c|c.own_votes)
+    }
+    /// The ballot of the person voter_id voted for, if that vote is counted
+    pub fn vote_for(&self, voter_id: &str) -> Option<&'a Vote<'a>> {
+        self.get(voter_id)?.vote_for.map(|vf|self.cand[vf].vote)
+    }
+    /// Whether voter_id is a willing candidate who can win
+    pub fn is_willing_candidate(&self, voter_id: &str) -> bool {
+        self.get(voter_id).is_some_and(|c|c.is_willing_candidate)
+    }
+}
+
+/// One place in the finishing order, see VoteCounter::standings().
+#[derive(Debug, Clone)]
+pub struct Standing<'a> {
+    /// 1 for the winner
+    pub position: usize,
+    pub candidate: &'a Vote<'a>,
+    /// The total delegated votes which won them this position
+    pub votes: Weight,
+    /// The candidates who had the same number of votes for this position and lost the
+    /// deterministic tie-break, empty if there was no tie
+    pub tied_with: Vec<&'a Vote<'a>>,
+}
+
+/// Part of the ranking, see VoteCounter::ranking_page().
+#[derive(Debug, Clone)]
+pub struct RankingPage<'a> {
+    /// The number of candidates in the whole ranking
+    pub total: usize,
+    /// The total delegated votes and ballot of each candidate on the page, in order
+    pub entries: Vec<(Weight, &'a Vote<'a>)>,
+}
+
 struct WinnersIter<'a, 'b> {
     vc: &'b VoteCounter<'a>,
     next: Option<usize>,
 }
 impl<'a, 'b> Iterator for WinnersIter<'a, 'b> {
-    type Item = (u64, &'a Vote);
+    type Item = (Weight, &'a Vote<'a>);
     fn next(&mut self) -> Option<Self::Item> {
         if let Some(next) = self.next {
             let cand = &self.vc.cand[next];
@@ -614,20 +2195,3 @@ impl<'a, 'b> Iterator for WinnersIter<'a, 'b> {
         }
     }
 }
-
-struct VotersIter<'a, 'b> {
-    vc: &'b VoteCounter<'a>,
-    idx: usize,
-}
-impl<'a, 'b> Iterator for VotersIter<'a, 'b> {
-    type Item = (u64, &'a Vote);
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.idx < self.vc.cand.len() {
-            let cand = &self.vc.cand[self.idx];
-            self.idx += 1;
-            Some((cand.total_indirect_votes, cand.vote))
-        } else {
-            None
-        }
-    }
-}
\ No newline at end of file
@@ -0,0 +1,105 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! Limits on the ballots which will be counted, so that a service which counts untrusted
+//! uploads has predictable memory use and refuses oversized inputs early.
+use std::borrow::Cow;
+use std::mem::size_of;
+
+use crate::options::{ElectionOptions, OverflowPolicy};
+use crate::types::{Vote, Weight, TallyError, widen};
+
+/// An upper bound on the bytes used by a VoteCounter for n_votes ballots whose IDs are
+/// avg_id_len bytes long on average, including the ballots themselves. The bound only
+/// depends on its arguments and the target's pointer width.
+pub fn estimate_memory(n_votes: usize, avg_id_len: usize) -> usize {
+    let ptr = size_of::<usize>();
+    // Each ballot: the Vote, its two IDs and allocator rounding of each of them
+    let ballot = size_of::<Vote<'static>>() + 2 * (avg_id_len + ptr);
+    // Each candidate: the Candidate, the name index (a hashbrown table at most half full,
+    // with one control byte per slot), the ranking and the sort buffer, the strongly
+    // connected component tables, the delegation path, and an entry in the list of
+    // invalid votes or implicit voters
+    let counter = size_of::<crate::Candidate<'static>>()
+        + 2 * (2 * ptr + 1)
+        + ptr + 2 * size_of::<Weight>()
+        + 5 * ptr
+        + ptr
+        + 3 * ptr;
+    n_votes.saturating_mul(ballot.saturating_add(counter))
+}
+
+/// estimate_memory() of the ballots, with their fallbacks, each of which is an ID and its
+/// allocator rounding, as well as an entry in the ballot's list of them.
+fn estimate_for<'a>(votes: impl Iterator<Item = &'a Vote<'a>>) -> usize {
+    let (mut n, mut id_bytes, mut fallback_bytes) = (0_usize, 0_usize, 0_usize);
+    for v in votes {
+        n += 1;
+        let vote_for = v.vote_for.as_ref().map_or(0, |v|v.len());
+        id_bytes = id_bytes.saturating_add(v.voter_id.len()).saturating_add(vote_for);
+        for f in &v.fallbacks {
+            let bytes = f.len().saturating_add(size_of::<Cow<'static, str>>() + size_of::<usize>());
+            fallback_bytes = fallback_bytes.saturating_add(bytes);
+        }
+    }
+    estimate_memory(n, id_bytes.div_ceil((2 * n).max(1))).saturating_add(fallback_bytes)
+}
+
+/// The length of the longest ID on a ballot, of the voter, who they vote for or any of
+/// their fallbacks, which ElectionOptions::max_id_len limits.
+pub(crate) fn longest_id(v: &Vote<'_>) -> usize {
+    let vote_for = v.vote_for.as_ref().map_or(0, |v|v.len());
+    v.fallbacks.iter().map(|f|f.len()).fold(v.voter_id.len().max(vote_for), usize::max)
+}
+
+/// The most votes which the ballots may carry in total. A delegated total never exceeds
+/// the total votes, but the score of a ring member and the runner-up's total under
+/// PatronRule::Strict can add up to about three times it, so two bits are kept free.
+pub const MAX_TOTAL_VOTES: Weight = Weight::MAX / 4;
+
+/// The fewest bits by which every ballot's votes must be shifted right for their total
+/// to be no more than MAX_TOTAL_VOTES, and the total before shifting, which stops at
+/// u128::MAX if it is more than that.
+pub fn weight_shift<'a>(votes: impl Iterator<Item = &'a Vote<'a>> + Clone) -> (u32, u128) {
+    let total_shifted = |shift: u32| votes.clone()
+        .map(|v|widen(v.number_of_votes.checked_shr(shift).unwrap_or(0)))
+        .fold(0, u128::saturating_add);
+    let total = total_shifted(0);
+    let mut shift = 0;
+    if total > widen(MAX_TOTAL_VOTES) {
+        // Each bit halves the total, give or take the bits which are dropped
+        shift = (total / widen(MAX_TOTAL_VOTES)).ilog2();
+        while total_shifted(shift) > widen(MAX_TOTAL_VOTES) {
+            shift += 1;
+        }
+    }
+    (shift, total)
+}
+
+/// Check the options, and the ballots against the limits in them, before anything is
+/// allocated for them. Returns the weight_shift() which the ballots are counted with.
+pub(crate) fn check(votes: &crate::Ballots<'_>, options: &ElectionOptions) -> Result<u32, TallyError> {
+    options.validate().map_err(TallyError::InvalidOptions)?;
+    if let Some(limit) = options.max_ballots {
+        if votes.len() > limit {
+            return Err(TallyError::TooManyBallots { count: votes.len(), limit });
+        }
+    }
+    if let Some(limit) = options.max_id_len {
+        for (ballot, v) in votes.iter().enumerate() {
+            let len = longest_id(v);
+            if len > limit {
+                return Err(TallyError::IdTooLong { ballot, len, limit });
+            }
+        }
+    }
+    if let Some(limit) = options.memory_limit {
+        let estimate = estimate_for(votes.iter());
+        if estimate > limit {
+            return Err(TallyError::MemoryLimit { estimate, limit });
+        }
+    }
+    let (shift, total) = weight_shift(votes.iter());
+    if shift > 0 && options.overflow == OverflowPolicy::Error {
+        return Err(TallyError::WeightOverflow { total, limit: MAX_TOTAL_VOTES });
+    }
+    Ok(shift)
+}
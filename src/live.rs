@@ -0,0 +1,136 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! Counting while ballots are still arriving, such as on election night.
+//!
+//! LiveTally owns the ballots received so far. Writers add ballots and publish() counts
+//! them, then replaces the current Standings. Readers hold a LiveReader and take the
+//! current Standings as an Arc, which stays valid however many times it is replaced, so
+//! readers never wait for a count and a count never waits for readers. The lock is only
+//! held to clone or swap the Arc.
+//!
+//! With log_to(), every ballot is written to an append-only log before it is added, and
+//! recover() rebuilds the LiveTally from the log after a crash, so the ballots do not
+//! need to be requested again.
+use std::io::{self, Write};
+use std::sync::{Arc, RwLock};
+
+use crate::archive::{self, ArchiveError};
+use crate::introspector::Introspector;
+use crate::metrics::Metrics;
+use crate::options::ElectionOptions;
+use crate::types::{Vote, Weight, TallyError};
+use crate::VoteCounter;
+
+/// The result of one count of a LiveTally.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Standings {
+    /// The number of times the LiveTally has been published, 0 before the first time
+    pub generation: u64,
+    /// The number of ballots which were counted
+    pub ballots: usize,
+    /// The voter_id of the leader, if there is one
+    pub leader: Option<String>,
+    /// The total delegated votes of the leader
+    pub votes: Weight,
+    /// Each candidate with their total delegated votes, as VoteCounter::iter()
+    pub ranking: Vec<(String, Weight)>,
+}
+
+type Current = Arc<RwLock<Arc<Standings>>>;
+
+/// Reads the latest Standings of a LiveTally, this can be cloned and sent to other threads.
+#[derive(Debug, Clone)]
+pub struct LiveReader {
+    current: Current,
+}
+impl LiveReader {
+    /// The Standings of the latest publish()
+    pub fn snapshot(&self) -> Arc<Standings> {
+        // A panic while holding the lock cannot leave a half-written Arc behind
+        self.current.read().unwrap_or_else(|e|e.into_inner()).clone()
+    }
+}
+
+pub struct LiveTally {
+    votes: Vec<Vote<'static>>,
+    options: ElectionOptions,
+    generation: u64,
+    current: Current,
+    log: Option<Box<dyn Write + Send>>,
+    metrics: Option<Metrics>,
+}
+impl LiveTally {
+    pub fn new(options: ElectionOptions) -> Self {
+        Self { votes: Vec::new(), options, generation: 0, current: Default::default(), log: None, metrics: None }
+    }
+
+    /// Rebuild a LiveTally from a log written by log_to(). A ballot which was cut off by a
+    /// crash is left out, the returned length is the end of the last complete ballot and
+    /// the log must be truncated to it before more ballots are appended.
+    pub fn recover(options: ElectionOptions, log: &[u8]) -> Result<(Self, usize), ArchiveError> {
+        let (votes, len) = archive::read_ballots(log)?;
+        Ok((Self { votes, ..Self::new(options) }, len))
+    }
+
+    /// Append every ballot which is added from now on to log, which is flushed after each
+    /// one. Ballots which were already added are not written.
+    pub fn log_to(&mut self, log: impl Write + Send + 'static) {
+        self.log = Some(Box::new(log));
+    }
+
+    /// Count the ballots added and the events and phase durations of each publish() in
+    /// metrics
+    pub fn set_metrics(&mut self, metrics: Metrics) {
+        self.metrics = Some(metrics);
+    }
+
+    /// Add a ballot, it is counted at the next publish(). If it cannot be written to the
+    /// log then it is not added.
+    pub fn add(&mut self, vote: Vote<'_>) -> io::Result<()> {
+        if let Some(log) = &mut self.log {
+            let mut rec = Vec::new();
+            archive::put_ballot(&mut rec, &vote);
+            log.write_all(&rec)?;
+            log.flush()?;
+        }
+        self.votes.push(vote.into_owned());
+        if let Some(m) = &self.metrics {
+            m.ballots_added(1);
+        }
+        Ok(())
+    }
+
+    pub fn options(&self) -> &ElectionOptions {
+        &self.options
+    }
+
+    /// The ballots which have been added
+    pub fn ballots(&self) -> &[Vote<'static>] {
+        &self.votes
+    }
+
+    /// A reader of the Standings
+    pub fn reader(&self) -> LiveReader {
+        LiveReader { current: self.current.clone() }
+    }
+
+    /// Count every ballot which has been added and make the result the current Standings.
+    /// If the count fails, the current Standings are left as they were.
+    pub fn publish(&mut self) -> Result<Arc<Standings>, TallyError> {
+        let mut is = Introspector::default();
+        if let Some(m) = &self.metrics {
+            m.subscribe(&mut is);
+        }
+        let mut vc = VoteCounter::with_options(&self.votes, is, self.options.clone());
+        let result = vc.tally()?;
+        self.generation += 1;
+        let standings = Arc::new(Standings {
+            generation: self.generation,
+            ballots: self.votes.len(),
+            leader: result.winner,
+            votes: result.votes,
+            ranking: vc.iter().map(|(votes, v)|(v.voter_id.to_string(), votes)).collect(),
+        });
+        *self.current.write().unwrap_or_else(|e|e.into_inner()) = standings.clone();
+        Ok(standings)
+    }
+}
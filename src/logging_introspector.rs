@@ -1,20 +1,29 @@
 // SPDX-License-Identifier: MIT OR ISC
-use crate::types::Vote;
+use crate::types::{Vote, Weight};
 use crate::introspector::{
     Introspector,
     VoteDelegation,
     VoteDelegationRing,
+    DeepDelegation,
+    ImplicitDelegation,
     InvalidVote,
     InvalidVoteCause,
+    Fallback,
+    Abstention,
     BestRing, BestOfRing,
     PatronSelection, PatronSelectionReason,
     DeterministicTieBreaker,
     DeterministicTieBreakerHash,
     Winner,
+    Seat,
+    NoQuorum,
+    PolicyDecision,
+    AliasApplied,
+    WeightsRescaled,
 };
 
-fn print_ring(ring_members: &Vec<Vec<&Vote>>, delegated_votes: u64) {
-    if ring_members.len() == 0 {
+fn print_ring(ring_members: &[Vec<&Vote>], delegated_votes: Weight) {
+    if ring_members.is_empty() {
         println!("    No candidates found");
         return;
     }
@@ -35,7 +44,7 @@ fn print_ring(ring_members: &Vec<Vec<&Vote>>, delegated_votes: u64) {
 pub fn new<'a>() -> Introspector<'a> {
     let mut is = Introspector::default();
     is.subscribe((), |(),e:&VoteDelegation<'a>|{
-        println!("Possible delegation of {} vote(s)", e.from.number_of_votes);
+        println!("Possible delegation of {} vote(s)", e.votes);
         println!("    From       : {}", e.from.voter_id);
         println!("    To         : {}", e.to.voter_id);
         if e.because_of.voter_id != e.from.voter_id {
@@ -45,9 +54,22 @@ pub fn new<'a>() -> Introspector<'a> {
     is.subscribe((), |(),e:&VoteDelegationRing|{
         println!("Vote delegation encountered a ring:");
         for v in &e.chain {
-            println!("    - {} -> {}", v.voter_id, v.vote_for);
+            println!("    - {} -> {}", v.voter_id, v.vote_for.as_deref().unwrap_or_default());
         }
         println!("    Stop at: {}", e.next.voter_id);
+        println!("    {} vote(s) from {} stay with {}",
+            e.votes, e.from.voter_id, e.stopped_at.voter_id);
+    });
+    is.subscribe((), |(),e:&DeepDelegation|{
+        println!("Warning: votes of {} were delegated {} times", e.from.voter_id, e.depth);
+    });
+    is.subscribe((), |(),e:&ImplicitDelegation|{
+        println!("Delegation of {} vote(s) to {} who has no ballot",
+            e.votes, e.to);
+        println!("    From       : {}", e.from.voter_id);
+        if e.because_of.voter_id != e.from.voter_id {
+            println!("    Because {} voted for {}", e.because_of.voter_id, e.to);
+        }
     });
     is.subscribe((), |(),e:&InvalidVote|{
         println!("Discarding vote from {}/{} because: {:?}",
@@ -55,7 +77,8 @@ pub fn new<'a>() -> Introspector<'a> {
             InvalidVoteCause::NoVote => "They didn't vote for anyone".into(),
             InvalidVoteCause::SelfVote => "They voted for themselves".into(),
             InvalidVoteCause::UnrecognizedVote =>
-                format!("They voted for [{}] which is not a voter or candidate", e.vote.vote_for),
+                format!("They voted for [{}] which is not a voter or candidate",
+                    e.vote.vote_for.as_deref().unwrap_or_default()),
             InvalidVoteCause::Duplicate => "Duplicate voter".into(),
         });
     });
@@ -88,9 +111,18 @@ pub fn new<'a>() -> Introspector<'a> {
                 PatronSelectionReason::NotBeatingSecondBest(score, cand) => {
                     format!("NO - Can't defeat 2nd best ({} with {} possible votes)", cand.voter_id, score)
                 }
+                PatronSelectionReason::NotBeatingSecondBestOutright(needed, cand) => {
+                    format!("NO - Can't defeat 2nd best ({}) outright, would need more than {}",
+                        cand.voter_id, needed)
+                }
                 PatronSelectionReason::PatronFound => "YES - Patron found".into()
             }
         );
+        println!("    Tenative winner has {} votes, patron needs more than {}, runner-up has {}",
+            e.math.tenative_winner_votes,
+            e.math.mark_to_beat,
+            e.math.runner_up_votes.map(|v|v.to_string()).unwrap_or_else(||"none".into()),
+        );
     });
     is.subscribe((), |(), e:&DeterministicTieBreakerHash|{
         print!("Deterministic Tie Breaker Hash: {} w/ {} -> ",
@@ -98,7 +130,7 @@ pub fn new<'a>() -> Introspector<'a> {
         for b in &e.bytes {
             print!("{:02x}", b);
         }
-        println!("");
+        println!();
     });
     is.subscribe((), |(),e:&DeterministicTieBreaker|{
         println!("Deterministic Tie Breaker:");
@@ -116,9 +148,38 @@ pub fn new<'a>() -> Introspector<'a> {
         if let Some(e) = e.as_ref() {
             println!("The winner is: {} with a total of {} delegated votes",
                 e.candidate.voter_id, e.votes);
+            println!("    {} of their own votes", e.contributions.own);
+            for (v, votes) in &e.contributions.supporters {
+                println!("    {} votes from {}", votes, v.voter_id);
+            }
+            for (v, votes) in &e.contributions.through_ring {
+                println!("    {} votes through ring member {}", votes, v.voter_id);
+            }
         } else {
             println!("No winner could be found");
         }
     });
+    is.subscribe((), |(), e:&PolicyDecision|{
+        println!("Ballot policy decision for {}: {:?}", e.voter_id, e.action);
+    });
+    is.subscribe((), |(), e:&AliasApplied|{
+        println!("Vote of {} for {} goes to {} by alias table {}", e.voter_id, e.alias, e.resolved, e.table);
+    });
+    is.subscribe((), |(), e:&Fallback|{
+        println!("Vote of {} passes over {} ({:?}) to {}",
+            e.vote.voter_id, e.skipped, e.cause, e.next.unwrap_or("their first choice"));
+    });
+    is.subscribe((), |(), e:&Abstention|{
+        println!("{} abstains with {} votes", e.vote.voter_id, e.vote.number_of_votes);
+    });
+    is.subscribe((), |(), e:&Seat|{
+        println!("Seat {} goes to {} with {} delegated votes", e.seat, e.candidate.voter_id, e.votes);
+    });
+    is.subscribe((), |(), e:&NoQuorum|{
+        println!("No winner, quorum was not reached with {} ballots carrying {} votes", e.ballots, e.votes);
+    });
+    is.subscribe((), |(), e:&WeightsRescaled|{
+        println!("The ballots carry {} votes, every weight is divided by 2^{}", e.total, e.shift);
+    });
     is
 }
\ No newline at end of file
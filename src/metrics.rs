@@ -0,0 +1,185 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! Counters and histograms about tallies, in the Prometheus text format, so that
+//! operations can alert on anomalies such as a spike of invalid ballots.
+//!
+//! A Metrics is shared by cloning it, it is subscribed to the Introspector of each tally
+//! (LiveTally does this itself, see LiveTally::set_metrics()) and render() is served to
+//! the Prometheus scraper.
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::introspector::{
+    Event,
+    Introspector,
+    VoteDelegation,
+    VoteDelegationRing,
+    DeepDelegation,
+    ImplicitDelegation,
+    InvalidVote,
+    InvalidVoteCause,
+    BestRing, BestOfRing,
+    PatronSelection,
+    DeterministicTieBreaker,
+    DeterministicTieBreakerHash,
+    Winner,
+    Phase,
+    Progress,
+};
+
+/// The upper bounds of the phase duration buckets, in nanoseconds and as printed
+const BUCKETS: [(u128, &str); 5] = [
+    (1_000_000, "0.001"),
+    (10_000_000, "0.01"),
+    (100_000_000, "0.1"),
+    (1_000_000_000, "1"),
+    (10_000_000_000, "10"),
+];
+const PHASES: [Phase; 3] = [Phase::Ingestion, Phase::Delegation, Phase::Ranking];
+const CAUSES: [InvalidVoteCause; 4] = [
+    InvalidVoteCause::NoVote,
+    InvalidVoteCause::SelfVote,
+    InvalidVoteCause::UnrecognizedVote,
+    InvalidVoteCause::Duplicate,
+];
+
+#[derive(Default)]
+struct Histogram {
+    /// Count of observations in each bucket of BUCKETS, not cumulative, then above them
+    buckets: [u64; BUCKETS.len() + 1],
+    sum_nanos: u128,
+    count: u64,
+}
+
+#[derive(Default)]
+struct Inner {
+    ballots: u64,
+    tallies: u64,
+    invalid: [u64; CAUSES.len()],
+    events: BTreeMap<&'static str, u64>,
+    phase_start: [Option<Instant>; PHASES.len()],
+    phases: [Histogram; PHASES.len()],
+}
+
+fn phase_idx(p: Phase) -> usize {
+    match p {
+        Phase::Ingestion => 0,
+        Phase::Delegation => 1,
+        Phase::Ranking => 2,
+    }
+}
+
+fn cause_idx(c: &InvalidVoteCause) -> usize {
+    match c {
+        InvalidVoteCause::NoVote => 0,
+        InvalidVoteCause::SelfVote => 1,
+        InvalidVoteCause::UnrecognizedVote => 2,
+        InvalidVoteCause::Duplicate => 3,
+    }
+}
+
+type Shared = Arc<Mutex<Inner>>;
+
+fn lock(s: &Shared) -> std::sync::MutexGuard<'_, Inner> {
+    // Every update leaves the counters consistent, so a poisoned lock is still usable
+    s.lock().unwrap_or_else(|e|e.into_inner())
+}
+
+fn seconds(nanos: u128) -> String {
+    format!("{}.{:09}", nanos / 1_000_000_000, nanos % 1_000_000_000)
+}
+
+#[derive(Clone, Default)]
+pub struct Metrics {
+    inner: Shared,
+}
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count n ballots as ingested
+    pub fn ballots_added(&self, n: u64) {
+        lock(&self.inner).ballots += n;
+    }
+
+    fn count<'a, E: Event<'a>>(&self, is: &mut Introspector<'a>, name: &'static str) {
+        is.subscribe((self.inner.clone(), name), |(s, name), _: &E| {
+            *lock(s).events.entry(name).or_default() += 1;
+        });
+    }
+
+    /// Count the events of the tally which is uses, and time its phases
+    pub fn subscribe(&self, is: &mut Introspector<'_>) {
+        lock(&self.inner).tallies += 1;
+        self.count::<VoteDelegation>(is, "VoteDelegation");
+        self.count::<VoteDelegationRing>(is, "VoteDelegationRing");
+        self.count::<DeepDelegation>(is, "DeepDelegation");
+        self.count::<ImplicitDelegation>(is, "ImplicitDelegation");
+        self.count::<InvalidVote>(is, "InvalidVote");
+        self.count::<BestRing>(is, "BestRing");
+        self.count::<BestOfRing>(is, "BestOfRing");
+        self.count::<PatronSelection>(is, "PatronSelection");
+        self.count::<DeterministicTieBreakerHash>(is, "DeterministicTieBreakerHash");
+        self.count::<DeterministicTieBreaker>(is, "DeterministicTieBreaker");
+        self.count::<Option<Winner>>(is, "Winner");
+        is.subscribe(self.inner.clone(), |s, e: &InvalidVote| {
+            lock(s).invalid[cause_idx(&e.cause)] += 1;
+        });
+        is.subscribe(self.inner.clone(), |s, e: &Progress| {
+            let mut s = lock(s);
+            let p = phase_idx(e.phase);
+            if e.processed == 0 {
+                s.phase_start[p] = Some(Instant::now());
+            }
+            if e.processed == e.total {
+                if let Some(start) = s.phase_start[p].take() {
+                    let nanos = start.elapsed().as_nanos();
+                    let h = &mut s.phases[p];
+                    let b = BUCKETS.iter().position(|(max, _)|nanos <= *max).unwrap_or(BUCKETS.len());
+                    h.buckets[b] += 1;
+                    h.sum_nanos += nanos;
+                    h.count += 1;
+                }
+            }
+        });
+    }
+
+    /// The metrics in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let s = lock(&self.inner);
+        let mut out = String::new();
+        // Writing to a String cannot fail
+        let _ = write!(out, "# HELP electorium_ballots_total Ballots ingested.\n\
+            # TYPE electorium_ballots_total counter\n\
+            electorium_ballots_total {}\n\
+            # HELP electorium_tallies_total Tallies run.\n\
+            # TYPE electorium_tallies_total counter\n\
+            electorium_tallies_total {}\n\
+            # HELP electorium_invalid_votes_total Invalid votes by cause.\n\
+            # TYPE electorium_invalid_votes_total counter\n", s.ballots, s.tallies);
+        for (c, n) in CAUSES.iter().zip(s.invalid) {
+            let _ = writeln!(out, "electorium_invalid_votes_total{{cause=\"{:?}\"}} {}", c, n);
+        }
+        out.push_str("# HELP electorium_events_total Introspector events by kind.\n\
+            # TYPE electorium_events_total counter\n");
+        for (e, n) in &s.events {
+            let _ = writeln!(out, "electorium_events_total{{event=\"{}\"}} {}", e, n);
+        }
+        out.push_str("# HELP electorium_phase_duration_seconds Time taken by each phase of a tally.\n\
+            # TYPE electorium_phase_duration_seconds histogram\n");
+        for (p, h) in PHASES.iter().zip(&s.phases) {
+            let mut cumulative = 0;
+            let les = BUCKETS.iter().map(|(_, le)|*le).chain(["+Inf"]);
+            for (le, n) in les.zip(h.buckets) {
+                cumulative += n;
+                let _ = writeln!(out, "electorium_phase_duration_seconds_bucket{{phase=\"{:?}\",le=\"{}\"}} {}",
+                    p, le, cumulative);
+            }
+            let _ = writeln!(out, "electorium_phase_duration_seconds_sum{{phase=\"{:?}\"}} {}", p, seconds(h.sum_nanos));
+            let _ = writeln!(out, "electorium_phase_duration_seconds_count{{phase=\"{:?}\"}} {}", p, h.count);
+        }
+        out
+    }
+}
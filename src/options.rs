@@ -0,0 +1,271 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! Options which change how an election is counted.
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::types::Weight;
+
+/// When the tenative winner received a majority of their votes from one candidate,
+/// that candidate is their "patron", and this rule decides whether the patron wins instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PatronRule {
+    /// The patron wins if they provide a majority of the tenative winner's votes and
+    /// have more votes than the runner-up.
+    #[default]
+    Enabled,
+    /// The tenative winner always wins, patrons are never considered.
+    Disabled,
+    /// As Enabled, but the patron must have more votes than the runner-up would have
+    /// if all of the tenative winner's votes which did not come from the patron went to
+    /// the runner-up.
+    ///
+    /// The patron does not need to beat the tenative winner's own total. The patron's
+    /// votes are part of that total, so the patron could never beat it and the rule would
+    /// be the same as Disabled. With no runner-up, a patron who provides the majority
+    /// wins under this rule, as under Enabled.
+    Strict,
+}
+
+/// The version of the counting algorithm. Organizations which need to re-verify an
+/// historic election select the version which was in force at the time, and the
+/// version is recorded in every result through the options.
+///
+/// The older counting program is not part of this crate. New versions will be added
+/// here rather than changing an existing one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum AlgorithmVersion {
+    /// Delegation with ring resolution, the patron rule and the hash tie-breaker
+    #[default]
+    V2,
+    /// As V2, except that best candidates who are in one delegation ring only through
+    /// voters who are not willing candidates, such as Alice -> U1 -> Bob -> U2 -> Alice,
+    /// are one ring, see VoteCounter::rings(). V2 takes them as separate rings which tie,
+    /// so the patron rule is skipped, while V3 applies it to the best of the ring, with
+    /// U1 and U2 excluded from being patrons like the rest of the ring. This can elect a
+    /// different winner.
+    V3,
+}
+
+/// What to do with a ballot which votes for someone who has no ballot of their own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum UnrecognizedVotePolicy {
+    /// The vote is treated as if they did not vote for anyone.
+    #[default]
+    Discard,
+    /// The target is treated as a voter who is not willing and did not vote, so the
+    /// votes are delegated to them and stop there, see VoteCounter::implicit_voters().
+    ImplicitVoter,
+    /// The tally fails with TallyError::UnrecognizedVote.
+    Error,
+}
+
+/// What to do when more than one ballot has the same voter_id. Willing candidates'
+/// ballots are read before those of other voters, so where this says first it means the
+/// first of the willing candidates' ballots if there are any.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DuplicatePolicy {
+    /// The first ballot is counted and the others are invalid.
+    #[default]
+    FirstWins,
+    /// The last ballot is counted and the others are invalid.
+    LastWins,
+    /// The first ballot is counted with the votes of all of them, the others are
+    /// invalid. This is for voters whose holdings are split over several ballots.
+    MergeWeights,
+    /// The tally fails with TallyError::DuplicateVoter.
+    Error,
+}
+
+/// What to do when the ballots carry so many votes in total that a delegated total could
+/// come near the limit of a Weight, see limits::MAX_TOTAL_VOTES.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum OverflowPolicy {
+    /// The tally fails with TallyError::WeightOverflow.
+    #[default]
+    Error,
+    /// Every ballot's votes are shifted right by the same number of bits, the fewest
+    /// which bring the total under the limit. The shift is in ElectionResult::weight_shift
+    /// and the WeightsRescaled event. Relative weights are kept, except that the bits
+    /// which are shifted out are lost, so ballots of fewer than 2^shift votes count as 0.
+    Rescale,
+}
+
+/// Options for counting an election, the Default is the standard rule set.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ElectionOptions {
+    /// Which version of the algorithm to count with
+    pub algorithm: AlgorithmVersion,
+    /// Whether and how a patron can displace the tenative winner
+    pub patron_rule: PatronRule,
+    /// What to do with votes for someone who has no ballot
+    pub unrecognized_vote: UnrecognizedVotePolicy,
+    /// What to do with more than one ballot from the same voter
+    pub duplicate_voter: DuplicatePolicy,
+    /// What to do when the total votes are too large to count exactly
+    pub overflow: OverflowPolicy,
+    /// Refuse to count ballots which would need more than this many bytes, as estimated
+    /// by limits::estimate_memory(), None for no limit
+    pub memory_limit: Option<usize>,
+    /// Refuse to count if any voter_id or vote_for is longer than this many bytes
+    pub max_id_len: Option<usize>,
+    /// Refuse to count more than this many ballots
+    pub max_ballots: Option<usize>,
+    /// The total votes of everyone who was entitled to vote, when there is a registry of
+    /// members, for computing turnout
+    pub registry_weight: Option<Weight>,
+    /// The fewest ballots which must be counted for there to be a winner, None for no
+    /// quorum. Duplicate ballots are not counted, see result::Engagement::valid_ballots.
+    pub min_ballots: Option<u64>,
+    /// The fewest votes which those ballots must carry in total for there to be a
+    /// winner, None for no quorum. See result::Engagement::valid_votes.
+    pub min_votes: Option<Weight>,
+    /// Bytes mixed into every tie-breaker hash, such as a block hash or a salt revealed
+    /// after ballots close (see salt::verify_salt()), so that the same tie does not
+    /// resolve the same way in every election. None for the unsalted hash.
+    pub tie_break_salt: Option<Vec<u8>>,
+}
+
+/// A combination of options which cannot be meant, see ElectionOptions::validate().
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OptionsError {
+    /// A limit of 0, which would refuse every election, the argument is the option's name
+    ZeroLimit(&'static str),
+    /// registry_weight is 0, so no one is entitled to vote
+    EmptyRegistry,
+    /// tie_break_salt is empty, which would count exactly as no salt
+    EmptySalt,
+    /// min_votes is more than registry_weight, so the quorum could never be reached
+    QuorumAboveRegistry { min_votes: Weight, registry_weight: Weight },
+}
+impl std::fmt::Display for OptionsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OptionsError::ZeroLimit(name) => write!(f, "{name} is 0, no election could be counted"),
+            OptionsError::EmptyRegistry => write!(f, "registry_weight is 0"),
+            OptionsError::EmptySalt => write!(f, "tie_break_salt is empty"),
+            OptionsError::QuorumAboveRegistry { min_votes, registry_weight } =>
+                write!(f, "min_votes {min_votes} is more than registry_weight {registry_weight}"),
+        }
+    }
+}
+impl std::error::Error for OptionsError {}
+
+impl ElectionOptions {
+    /// Check for options which contradict each other or could never allow a count.
+    /// VoteCounter checks this itself, so that invalid options fail the count with
+    /// TallyError::InvalidOptions, this is for checking a configuration up front.
+    pub fn validate(&self) -> Result<(), OptionsError> {
+        let limits = [
+            ("memory_limit", self.memory_limit),
+            ("max_id_len", self.max_id_len),
+            ("max_ballots", self.max_ballots),
+        ];
+        if let Some((name, _)) = limits.into_iter().find(|(_, l)|*l == Some(0)) {
+            return Err(OptionsError::ZeroLimit(name));
+        }
+        if self.registry_weight == Some(0) {
+            return Err(OptionsError::EmptyRegistry);
+        }
+        if self.tie_break_salt.as_ref().is_some_and(Vec::is_empty) {
+            return Err(OptionsError::EmptySalt);
+        }
+        if let (Some(min_votes), Some(registry_weight)) = (self.min_votes, self.registry_weight) {
+            if min_votes > registry_weight {
+                return Err(OptionsError::QuorumAboveRegistry { min_votes, registry_weight });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Vetted bundles of options for common kinds of election, see Preset::options().
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Preset {
+    /// Shareholder meetings: ballots are weighted by shares, and the ballots of a
+    /// shareholder whose shares are held in several accounts are counted as one with all
+    /// of the shares (DuplicatePolicy::MergeWeights). Every proxy must name a shareholder
+    /// of record, a proxy for anyone else fails the count, and a patron must beat the
+    /// runner-up under the strict rule so a large holder cannot take a seat on votes
+    /// which were not theirs.
+    CorporateProxy,
+    /// Membership organizations: one ballot per member, a second ballot from the same
+    /// member fails the count (DuplicatePolicy::Error) because it means the roll is
+    /// wrong, and votes for non-members are discarded.
+    OneMemberOneVote,
+    /// Token holders: ballots are weighted by holdings, which are rescaled when they add
+    /// up to more than can be counted exactly (OverflowPolicy::Rescale) as 18 decimal
+    /// balances do. A later ballot from the same address replaces the earlier one, and a
+    /// delegation to an address which did not file a ballot is kept there
+    /// (UnrecognizedVotePolicy::ImplicitVoter) rather than lost, since delegates often
+    /// do not vote themselves. Addresses are limited to 128 bytes.
+    TokenGovernance,
+    /// Liquid democracy: a voter may change their delegate at any time, so their last
+    /// ballot is the one counted (DuplicatePolicy::LastWins). Delegations are followed as
+    /// far as they go, including rings which pass through voters who are not candidates
+    /// (AlgorithmVersion::V3), and votes for non-members are discarded.
+    LiquidDemocracy,
+}
+impl Preset {
+    pub const ALL: [Preset; 4] = [
+        Preset::CorporateProxy,
+        Preset::OneMemberOneVote,
+        Preset::TokenGovernance,
+        Preset::LiquidDemocracy,
+    ];
+
+    /// The name used in configuration files, such as "corporate-proxy"
+    pub fn name(self) -> &'static str {
+        match self {
+            Preset::CorporateProxy => "corporate-proxy",
+            Preset::OneMemberOneVote => "one-member-one-vote",
+            Preset::TokenGovernance => "token-governance",
+            Preset::LiquidDemocracy => "liquid-democracy",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|p|p.name() == name)
+    }
+
+    pub fn options(self) -> ElectionOptions {
+        let standard = ElectionOptions::default();
+        match self {
+            Preset::CorporateProxy => ElectionOptions {
+                patron_rule: PatronRule::Strict,
+                unrecognized_vote: UnrecognizedVotePolicy::Error,
+                duplicate_voter: DuplicatePolicy::MergeWeights,
+                ..standard
+            },
+            Preset::OneMemberOneVote => ElectionOptions {
+                duplicate_voter: DuplicatePolicy::Error,
+                ..standard
+            },
+            Preset::TokenGovernance => ElectionOptions {
+                unrecognized_vote: UnrecognizedVotePolicy::ImplicitVoter,
+                duplicate_voter: DuplicatePolicy::LastWins,
+                overflow: OverflowPolicy::Rescale,
+                max_id_len: Some(128),
+                ..standard
+            },
+            Preset::LiquidDemocracy => ElectionOptions {
+                algorithm: AlgorithmVersion::V3,
+                duplicate_voter: DuplicatePolicy::LastWins,
+                ..standard
+            },
+        }
+    }
+}
+impl std::str::FromStr for Preset {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_name(s).ok_or_else(||format!("Unknown preset {s:?}"))
+    }
+}
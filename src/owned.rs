@@ -0,0 +1,234 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! A vote counter which owns its ballots, so it has no lifetime and can be returned from
+//! a function or kept in a long-lived struct.
+//!
+//! VoteCounter refers to the ballots rather than copying them, so the owned counter keeps
+//! the ballots and creates a VoteCounter over them whenever one is needed. The result of
+//! the count is kept so that repeated calls to tally() do not count again.
+//!
+//! IntoOwned converts what a VoteCounter returns into copies which do not borrow the
+//! ballots.
+use std::sync::OnceLock;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::builder::ElectionBuilder;
+use crate::groups::{GroupResult, GroupedResult};
+use crate::introspector::{self, Introspector};
+use crate::options::ElectionOptions;
+use crate::result::{self, ElectionResult};
+use crate::types::{Vote, Weight, TallyError};
+use crate::view::CandidateView;
+use crate::{VoteCounter, CandidateInfo, Standing, RankingPage};
+
+/// An election which has all of its ballots, see builder::ElectionBuilder
+pub type Election = OwnedVoteCounter;
+
+#[derive(Debug)]
+pub struct OwnedVoteCounter {
+    votes: Vec<Vote<'static>>,
+    options: ElectionOptions,
+    result: OnceLock<Result<ElectionResult, TallyError>>,
+}
+impl OwnedVoteCounter {
+    pub fn new(votes: Vec<Vote<'static>>, options: ElectionOptions) -> Self {
+        Self { votes, options, result: OnceLock::new() }
+    }
+
+    /// Collect the ballots from iter, such as a parser or a database cursor, without the
+    /// caller first making a Vec. Ballots which borrow are copied as they arrive.
+    pub fn from_ballots<'s>(iter: impl IntoIterator<Item = Vote<'s>>, options: ElectionOptions) -> Self {
+        Self::new(iter.into_iter().map(Vote::into_owned).collect(), options)
+    }
+
+    /// Start building the ballots of an election
+    pub fn builder() -> ElectionBuilder {
+        ElectionBuilder::new()
+    }
+
+    pub fn votes(&self) -> &[Vote<'static>] {
+        &self.votes
+    }
+
+    pub fn options(&self) -> &ElectionOptions {
+        &self.options
+    }
+
+    /// Take back the ballots
+    pub fn into_votes(self) -> Vec<Vote<'static>> {
+        self.votes
+    }
+
+    /// A VoteCounter over the ballots, for anything other than the result, such as
+    /// iter() or find(). Each call reads the ballots again.
+    pub fn counter<'a>(&'a self, is: Introspector<'a>) -> VoteCounter<'a> {
+        VoteCounter::with_options(&self.votes, is, self.options.clone())
+    }
+
+    /// The result of the count, counted the first time it is asked for
+    pub fn tally(&self) -> Result<ElectionResult, TallyError> {
+        self.result.get_or_init(||self.counter(Introspector::default()).tally()).clone()
+    }
+}
+/// Collect ballots into an election counted with the default options, see from_ballots().
+impl<'s> FromIterator<Vote<'s>> for OwnedVoteCounter {
+    fn from_iter<I: IntoIterator<Item = Vote<'s>>>(iter: I) -> Self {
+        Self::from_ballots(iter, ElectionOptions::default())
+    }
+}
+
+/// Owned copies of what the count returns and of events, which refer to ballots by
+/// voter_id so that they can be kept after the ballots are dropped, in the same way as
+/// record::EventRecord. Events are converted by reference, as they are received.
+/// Ballots become their voter_id, ranking entries (Weight, &Vote) become (Weight, String) and
+/// lists such as VoteCounter::rings() are converted element by element. The trait must
+/// be imported. Its method is not called into_owned() so that it is never confused with
+/// Vote::into_owned(), which copies the whole ballot.
+pub trait IntoOwned {
+    type Owned: 'static;
+    fn to_owned_ids(self) -> Self::Owned;
+}
+impl IntoOwned for &Vote<'_> {
+    type Owned = String;
+    fn to_owned_ids(self) -> String {
+        self.voter_id.to_string()
+    }
+}
+impl<T: IntoOwned> IntoOwned for (Weight, T) {
+    type Owned = (Weight, T::Owned);
+    fn to_owned_ids(self) -> Self::Owned {
+        (self.0, self.1.to_owned_ids())
+    }
+}
+impl<T: IntoOwned> IntoOwned for Vec<T> {
+    type Owned = Vec<T::Owned>;
+    fn to_owned_ids(self) -> Self::Owned {
+        self.into_iter().map(T::to_owned_ids).collect()
+    }
+}
+impl<T: IntoOwned> IntoOwned for Option<T> {
+    type Owned = Option<T::Owned>;
+    fn to_owned_ids(self) -> Self::Owned {
+        self.map(T::to_owned_ids)
+    }
+}
+impl IntoOwned for &introspector::Contributions<'_> {
+    type Owned = result::Contributions;
+    fn to_owned_ids(self) -> Self::Owned {
+        self.into()
+    }
+}
+
+/// An owned copy of introspector::Winner.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OwnedWinner {
+    pub candidate: String,
+    pub votes: Weight,
+    pub contributions: result::Contributions,
+}
+impl IntoOwned for &introspector::Winner<'_> {
+    type Owned = OwnedWinner;
+    fn to_owned_ids(self) -> OwnedWinner {
+        OwnedWinner {
+            candidate: self.candidate.to_owned_ids(),
+            votes: self.votes,
+            contributions: (&self.contributions).to_owned_ids(),
+        }
+    }
+}
+
+/// An owned copy of introspector::Seat.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OwnedSeat {
+    pub seat: usize,
+    pub candidate: String,
+    pub votes: Weight,
+}
+impl IntoOwned for &introspector::Seat<'_> {
+    type Owned = OwnedSeat;
+    fn to_owned_ids(self) -> OwnedSeat {
+        OwnedSeat { seat: self.seat, candidate: self.candidate.to_owned_ids(), votes: self.votes }
+    }
+}
+
+impl IntoOwned for CandidateInfo<'_> {
+    type Owned = CandidateView;
+    fn to_owned_ids(self) -> CandidateView {
+        (&self).into()
+    }
+}
+
+/// An owned copy of a Standing, see VoteCounter::standings().
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OwnedStanding {
+    pub position: usize,
+    pub candidate: String,
+    pub votes: Weight,
+    pub tied_with: Vec<String>,
+}
+impl IntoOwned for Standing<'_> {
+    type Owned = OwnedStanding;
+    fn to_owned_ids(self) -> OwnedStanding {
+        OwnedStanding {
+            position: self.position,
+            candidate: self.candidate.to_owned_ids(),
+            votes: self.votes,
+            tied_with: self.tied_with.to_owned_ids(),
+        }
+    }
+}
+
+/// An owned copy of a RankingPage, see VoteCounter::ranking_page().
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OwnedRankingPage {
+    pub total: usize,
+    pub entries: Vec<(Weight, String)>,
+}
+impl IntoOwned for RankingPage<'_> {
+    type Owned = OwnedRankingPage;
+    fn to_owned_ids(self) -> OwnedRankingPage {
+        OwnedRankingPage { total: self.total, entries: self.entries.to_owned_ids() }
+    }
+}
+
+/// An owned copy of a groups::GroupResult.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OwnedGroupResult {
+    pub group: String,
+    pub ballots: u64,
+    pub votes: Weight,
+    pub result: ElectionResult,
+    pub ranking: Vec<(Weight, String)>,
+}
+impl IntoOwned for GroupResult<'_> {
+    type Owned = OwnedGroupResult;
+    fn to_owned_ids(self) -> OwnedGroupResult {
+        OwnedGroupResult {
+            group: self.group,
+            ballots: self.ballots,
+            votes: self.votes,
+            result: self.result,
+            ranking: self.ranking.to_owned_ids(),
+        }
+    }
+}
+
+/// An owned copy of a groups::GroupedResult.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OwnedGroupedResult {
+    pub overall: ElectionResult,
+    pub groups: Vec<OwnedGroupResult>,
+}
+impl IntoOwned for GroupedResult<'_> {
+    type Owned = OwnedGroupedResult;
+    fn to_owned_ids(self) -> OwnedGroupedResult {
+        OwnedGroupedResult { overall: self.overall, groups: self.groups.to_owned_ids() }
+    }
+}
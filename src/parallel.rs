@@ -0,0 +1,113 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! Control over the parts of the count which may run on several threads, and a harness
+//! which checks that they give exactly the same answer as running on one.
+//!
+//! Parallelism never changes the result, so it is not one of the ElectionOptions and is
+//! not recorded in the RuleSet. Today the only parallel path is the hashing of a large
+//! tie-break, any path which is added later must be covered by Parallelism::Validate
+//! and by validate().
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::introspector::Introspector;
+use crate::options::ElectionOptions;
+use crate::record::{self, EventRecord};
+use crate::result::ElectionResult;
+use crate::types::{Vote, TallyError};
+use crate::VoteCounter;
+
+/// How many threads the count may use, see VoteCounter::set_parallelism()
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Parallelism {
+    /// Threads are used when the work is large enough to pay for them, up to the number
+    /// of CPUs which the machine reports
+    #[default]
+    Auto,
+    /// Everything runs on the calling thread
+    Serial,
+    /// Always this many threads, even for small work, for testing
+    Threads(usize),
+    /// Every parallel step is also run serially and the tally fails with
+    /// TallyError::ParallelMismatch if they differ, for cautious operators
+    Validate,
+}
+impl Parallelism {
+    /// The number of threads for a step with n items which starts paying for threads
+    /// at min items.
+    pub(crate) fn threads(self, n: usize, min: usize) -> usize {
+        match self {
+            Parallelism::Serial => 1,
+            Parallelism::Threads(t) => t.max(1),
+            Parallelism::Auto if n < min => 1,
+            Parallelism::Auto => available().min(n / min),
+            Parallelism::Validate => available().max(2),
+        }
+    }
+}
+
+fn available() -> usize {
+    std::thread::available_parallelism().map_or(1, |p|p.get())
+}
+
+/// Where a parallel count differed from the serial one, see validate()
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    /// The result, or the error, differed
+    Result { parallelism: Parallelism },
+    /// The events differed, either in number or in content
+    Events { parallelism: Parallelism, serial: usize, parallel: usize },
+}
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationError::Result { parallelism } =>
+                write!(f, "The result with {parallelism:?} differs from the serial count"),
+            ValidationError::Events { parallelism, serial, parallel } =>
+                write!(f, "The {parallel} events with {parallelism:?} differ from the {serial} of the serial count"),
+        }
+    }
+}
+impl std::error::Error for ValidationError {}
+
+fn count(
+    votes: &[Vote<'_>],
+    options: &ElectionOptions,
+    parallelism: Parallelism,
+) -> (Result<ElectionResult, TallyError>, Vec<EventRecord>) {
+    let records = Rc::new(RefCell::new(Vec::new()));
+    let mut is = Introspector::default();
+    let sink = records.clone();
+    record::subscribe_all(&mut is, move |r| sink.borrow_mut().push(r));
+    let mut vc = VoteCounter::with_options(votes, is, options.clone());
+    vc.set_parallelism(parallelism);
+    let res = vc.tally();
+    drop(vc);
+    let records = records.take();
+    (res, records)
+}
+
+/// Count the election serially and then with every parallel variant, forcing threads
+/// even where the work is too small for Auto to use them, and check that the results
+/// and every event are identical. This is for CI and for operators who want to check
+/// a machine before trusting it, the count is run several times.
+pub fn validate(
+    votes: &[Vote<'_>],
+    options: &ElectionOptions,
+) -> Result<Result<ElectionResult, TallyError>, ValidationError> {
+    let (serial, serial_events) = count(votes, options, Parallelism::Serial);
+    let variants = [2, 3, available().max(4)].map(Parallelism::Threads);
+    for parallelism in [Parallelism::Auto, Parallelism::Validate].into_iter().chain(variants) {
+        let (res, events) = count(votes, options, parallelism);
+        if res != serial {
+            return Err(ValidationError::Result { parallelism });
+        }
+        if events != serial_events {
+            return Err(ValidationError::Events {
+                parallelism,
+                serial: serial_events.len(),
+                parallel: events.len(),
+            });
+        }
+    }
+    Ok(serial)
+}
@@ -0,0 +1,61 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! Percentages for publication which always add up.
+//!
+//! Each share is computed exactly from the integer totals and rounded down, then the
+//! units which are left over are given one each to the shares with the largest
+//! remainders (the largest remainder method), earlier shares first when remainders are
+//! equal. So the published percentages sum to exactly 100, and no percentage is more
+//! than one unit from its exact value.
+use crate::types::{Weight, widen};
+
+/// A percentage with a fixed number of decimal places, units of 10^-decimals percent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Percent {
+    pub units: u64,
+    pub decimals: u32,
+}
+impl std::fmt::Display for Percent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let scale = 10_u64.pow(self.decimals);
+        if self.decimals == 0 {
+            write!(f, "{}%", self.units)
+        } else {
+            write!(f, "{}.{:0width$}%", self.units / scale, self.units % scale,
+                width = self.decimals as usize)
+        }
+    }
+}
+
+/// The share of each of totals in the sum of all of them, with decimals places, using
+/// the largest remainder method. If every total is zero, every share is zero.
+/// decimals is capped at 15 so that 100% fits in the units.
+pub fn largest_remainder(totals: &[Weight], decimals: u32) -> Vec<Percent> {
+    let decimals = decimals.min(15);
+    let scale = 100 * 10_u128.pow(decimals);
+    // Wide weights of 2^71 or more are all shifted right by the same number of bits so
+    // that none is, which changes the shares by far less than a unit
+    let widest = totals.iter().map(|&t|widen(t)).max().unwrap_or(0);
+    let shift = (u128::BITS - widest.leading_zeros()).saturating_sub(71);
+    let totals = totals.iter().map(|&t|widen(t) >> shift).collect::<Vec<_>>();
+    let sum: u128 = totals.iter().sum();
+    if sum == 0 {
+        return totals.iter().map(|_|Percent { units: 0, decimals }).collect();
+    }
+    // total * scale fits because total < 2^71 and scale < 2^57
+    let mut units = Vec::with_capacity(totals.len());
+    let mut remainders = Vec::with_capacity(totals.len());
+    for (i, &t) in totals.iter().enumerate() {
+        let exact = t * scale;
+        units.push(exact / sum);
+        remainders.push((exact % sum, i));
+    }
+    let left_over = scale - units.iter().sum::<u128>();
+    remainders.sort_by(|a, b|b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    for &(_, i) in remainders.iter().take(left_over as usize) {
+        units[i] += 1;
+    }
+    units.into_iter()
+        .map(|u|Percent { units: u64::try_from(u).unwrap_or(u64::MAX), decimals })
+        .collect()
+}
@@ -0,0 +1,97 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! A C ABI for event sinks which are loaded at runtime, so that an audit exporter can be
+//! added to a deployment without relinking the binary which does the tally.
+//!
+//! A plugin is a shared library which exports a function named PLUGIN_ENTRY of type
+//! PluginEntry. The host loads it with the platform's loader (dlopen, LoadLibrary),
+//! calls the entry and passes the PluginSink to subscribe_plugin(). Each event is then
+//! passed to on_record as one line of JSON, in the format described by
+//! schema::event_record_schema(), which is the only part of the crate the plugin sees.
+use std::ffi::c_void;
+use std::fmt;
+
+use crate::introspector::Introspector;
+use crate::record::{subscribe_all, subscribe_progress, EventRecord};
+
+/// The version of PluginSink and of the record format, bumped on any change to either.
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+/// The name of the function which a plugin exports.
+pub const PLUGIN_ENTRY: &str = "electorium_sink_v1";
+
+/// The type of the PLUGIN_ENTRY function.
+pub type PluginEntry = extern "C" fn() -> PluginSink;
+
+/// The sink which a plugin provides, ctx is passed back to each of its functions.
+///
+/// # Safety
+///
+/// abi_version is the first field in every version of the interface, and it is the only
+/// field which is read before it is checked. A sink with another abi_version is given
+/// back to no one: it is forgotten without reading its other fields, so release is not
+/// called and the plugin's ctx leaks.
+///
+/// A sink with this abi_version must have a ctx which stays valid until release is
+/// called, or forever if there is no release, and on_record and release must be safe to
+/// call with it from the thread which runs the count. json is only valid for the duration
+/// of the call to on_record. release is called at most once, when the sink is dropped,
+/// and no call to on_record follows it.
+#[repr(C)]
+pub struct PluginSink {
+    /// Must be PLUGIN_ABI_VERSION
+    pub abi_version: u32,
+    pub ctx: *mut c_void,
+    /// Called with each record as UTF-8 JSON of len bytes, without a terminating nul
+    pub on_record: extern "C" fn(ctx: *mut c_void, json: *const u8, len: usize),
+    /// Called once when no more records will be sent, if present
+    pub release: Option<extern "C" fn(ctx: *mut c_void)>,
+}
+impl Drop for PluginSink {
+    fn drop(&mut self) {
+        if let Some(release) = self.release {
+            release(self.ctx);
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PluginError {
+    /// The plugin was built for another version of the interface
+    AbiVersion(u32),
+}
+impl fmt::Display for PluginError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PluginError::AbiVersion(v) =>
+                write!(f, "plugin uses ABI version {}, expected {}", v, PLUGIN_ABI_VERSION),
+        }
+    }
+}
+impl std::error::Error for PluginError {}
+
+/// Pass every event emitted by is to the plugin's sink, and Progress events if progress
+/// is true. The sink is released when is is dropped, or never if its version is wrong,
+/// see PluginSink.
+pub fn subscribe_plugin(
+    is: &mut Introspector<'_>,
+    sink: PluginSink,
+    progress: bool,
+) -> Result<(), PluginError> {
+    if sink.abi_version != PLUGIN_ABI_VERSION {
+        let version = sink.abi_version;
+        // The rest of the layout is unknown, so Drop must not read release
+        std::mem::forget(sink);
+        return Err(PluginError::AbiVersion(version));
+    }
+    let sink = std::rc::Rc::new(sink);
+    let send = move |r: EventRecord| {
+        if let Ok(json) = serde_json::to_vec(&r) {
+            (sink.on_record)(sink.ctx, json.as_ptr(), json.len());
+        }
+    };
+    if progress {
+        subscribe_progress(is, send.clone());
+    }
+    subscribe_all(is, send);
+    Ok(())
+}
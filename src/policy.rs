@@ -0,0 +1,233 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! A hook for bylaws which the options cannot express, applied to the ballots before
+//! they are counted. The BallotPolicy can veto a ballot, redirect or reweight it, or make
+//! the voter ineligible for election, and each decision is emitted as a PolicyDecision
+//! event so that it appears in the record of the count.
+//!
+//! A policy written in rhai is RhaiPolicy, enabled by the rhai feature. A policy in
+//! another language, e.g. a wasm module, is embedded by implementing BallotPolicy over
+//! the script's entry point.
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+
+use crate::introspector::{Introspector, PolicyDecision};
+use crate::types::{Vote, Weight};
+
+/// What a BallotPolicy did with one ballot, with the reason it gives for the record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PolicyAction {
+    /// The ballot is not counted
+    Veto { reason: String },
+    /// The ballot votes for someone else
+    Redirect { vote_for: String, reason: String },
+    /// The ballot has a different number of votes
+    Reweight { number_of_votes: Weight, reason: String },
+    /// The ballot is counted but the voter cannot be elected
+    Ineligible { reason: String },
+}
+
+pub trait BallotPolicy {
+    /// What to do with one ballot, None leaves it as it is
+    fn decide(&mut self, vote: &Vote) -> Option<PolicyAction>;
+}
+impl<F: FnMut(&Vote) -> Option<PolicyAction>> BallotPolicy for F {
+    fn decide(&mut self, vote: &Vote) -> Option<PolicyAction> {
+        self(vote)
+    }
+}
+
+/// Candidates must nominate themselves by a deadline, so that no one can become a
+/// candidate after seeing the early standings. A willing candidate whose nomination was
+/// after the deadline, or who has no recorded nomination, is made Ineligible, their ballot
+/// is still counted. The times can be in any unit, such as unix seconds, so long as the
+/// deadline is in the same one, and a nomination exactly at the deadline is on time.
+#[derive(Debug, Clone, Default)]
+pub struct NominationDeadline {
+    pub deadline: u64,
+    /// When each voter set willing_candidate, by voter_id
+    pub nominated_at: HashMap<String, u64>,
+}
+impl NominationDeadline {
+    pub fn new(deadline: u64) -> Self {
+        Self { deadline, nominated_at: HashMap::new() }
+    }
+    /// Record when voter_id nominated themselves
+    pub fn nominated(mut self, voter_id: &str, at: u64) -> Self {
+        self.nominated_at.insert(voter_id.to_owned(), at);
+        self
+    }
+}
+impl BallotPolicy for NominationDeadline {
+    fn decide(&mut self, vote: &Vote) -> Option<PolicyAction> {
+        if !vote.willing_candidate {
+            return None;
+        }
+        let deadline = self.deadline;
+        let reason = match self.nominated_at.get(&*vote.voter_id) {
+            Some(&at) if at <= deadline => return None,
+            Some(at) => format!("Nominated at {at}, after the deadline of {deadline}"),
+            None => format!("No nomination before the deadline of {deadline}"),
+        };
+        Some(PolicyAction::Ineligible { reason })
+    }
+}
+
+/// Apply policy to every ballot, in order, returning the ballots to count. Each decision
+/// is emitted to is as a PolicyDecision.
+pub fn apply<'s>(
+    votes: &[Vote<'s>],
+    policy: &mut impl BallotPolicy,
+    is: &mut Introspector<'_>,
+) -> Vec<Vote<'s>> {
+    let mut out = Vec::with_capacity(votes.len());
+    for v in votes {
+        let mut vote = Vote {
+            voter_id: v.voter_id.clone(),
+            vote_for: v.vote_for.clone(),
+            number_of_votes: v.number_of_votes,
+            willing_candidate: v.willing_candidate,
+            metadata: v.metadata.clone(),
+            fallbacks: v.fallbacks.clone(),
+            abstain: v.abstain,
+        };
+        let Some(action) = policy.decide(v) else {
+            out.push(vote);
+            continue;
+        };
+        match &action {
+            PolicyAction::Veto { .. } => {}
+            PolicyAction::Redirect { vote_for, .. } => {
+                vote.vote_for = Some(vote_for.clone().into());
+                out.push(vote);
+            }
+            PolicyAction::Reweight { number_of_votes, .. } => {
+                vote.number_of_votes = *number_of_votes;
+                out.push(vote);
+            }
+            PolicyAction::Ineligible { .. } => {
+                vote.willing_candidate = false;
+                out.push(vote);
+            }
+        }
+        is.event(||PolicyDecision { voter_id: v.voter_id.to_string(), action });
+    }
+    out
+}
+
+/// Why a RhaiPolicy could not be made, or could not decide on a ballot.
+#[cfg(feature = "rhai")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScriptError {
+    /// The script does not compile or it does not define decide(ballot)
+    Compile(String),
+    /// decide() failed, or it returned something which is not a decision, for the
+    /// ballot of voter_id
+    Decide { voter_id: String, error: String },
+}
+#[cfg(feature = "rhai")]
+impl std::fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScriptError::Compile(e) => write!(f, "policy script: {e}"),
+            ScriptError::Decide { voter_id, error } => write!(f, "policy script, ballot of {voter_id}: {error}"),
+        }
+    }
+}
+#[cfg(feature = "rhai")]
+impl std::error::Error for ScriptError {}
+
+/// A BallotPolicy written in rhai. The script defines `fn decide(ballot)`, which is called
+/// with a map of the ballot's fields (voter_id, vote_for or () if they did not vote,
+/// number_of_votes, willing_candidate, abstain, fallbacks and metadata) and returns ()
+/// to leave it as it is or a map of the decision:
+///
+/// `#{ action: "veto" | "redirect" | "reweight" | "ineligible", reason: "..." }`
+///
+/// with the vote_for of a redirect and the number_of_votes of a reweight. For example
+/// `fn decide(b) { if b.metadata.status == "lapsed" { #{ action: "veto", reason: "lapsed" } } }`.
+///
+/// A ballot which decide() fails on is left as it is and the failure is kept in
+/// errors(), a count with errors did not apply the bylaws to every ballot.
+#[cfg(feature = "rhai")]
+pub struct RhaiPolicy {
+    engine: rhai::Engine,
+    ast: rhai::AST,
+    errors: Vec<ScriptError>,
+}
+#[cfg(feature = "rhai")]
+impl RhaiPolicy {
+    /// The most operations which one call of decide() may take, so that a script which
+    /// never returns fails instead of stopping the count
+    pub const MAX_OPERATIONS: u64 = 1_000_000;
+
+    pub fn new(script: &str) -> Result<Self, ScriptError> {
+        let mut engine = rhai::Engine::new();
+        engine.set_max_operations(Self::MAX_OPERATIONS);
+        let ast = engine.compile(script).map_err(|e|ScriptError::Compile(e.to_string()))?;
+        if !ast.iter_functions().any(|f|f.name == "decide" && f.params.len() == 1) {
+            return Err(ScriptError::Compile("there is no fn decide(ballot)".into()));
+        }
+        Ok(Self { engine, ast, errors: Vec::new() })
+    }
+
+    /// The ballots which decide() failed on, in order
+    pub fn errors(&self) -> &[ScriptError] {
+        &self.errors
+    }
+
+    fn ballot(vote: &Vote) -> Result<rhai::Map, String> {
+        let text = |s: &str|rhai::Dynamic::from(s.to_owned());
+        let number_of_votes = rhai::INT::try_from(vote.number_of_votes)
+            .map_err(|_|format!("{} votes are more than a script integer", vote.number_of_votes))?;
+        let mut ballot = rhai::Map::new();
+        ballot.insert("voter_id".into(), text(&vote.voter_id));
+        ballot.insert("vote_for".into(), vote.vote_for.as_deref().map_or(rhai::Dynamic::UNIT, text));
+        ballot.insert("number_of_votes".into(), number_of_votes.into());
+        ballot.insert("willing_candidate".into(), vote.willing_candidate.into());
+        ballot.insert("abstain".into(), vote.abstain.into());
+        ballot.insert("fallbacks".into(), vote.fallbacks.iter().map(|f|text(f)).collect::<rhai::Array>().into());
+        let metadata = vote.metadata.iter().map(|(k, v)|(k.into(), text(v))).collect::<rhai::Map>();
+        ballot.insert("metadata".into(), metadata.into());
+        Ok(ballot)
+    }
+
+    fn run(&mut self, vote: &Vote) -> Result<Option<PolicyAction>, String> {
+        let ballot = Self::ballot(vote)?;
+        let decision = self.engine.call_fn::<rhai::Dynamic>(&mut rhai::Scope::new(), &self.ast, "decide", (ballot,))
+            .map_err(|e|e.to_string())?;
+        if decision.is_unit() {
+            return Ok(None);
+        }
+        let Some(decision) = decision.try_cast::<rhai::Map>() else {
+            return Err("decide() returned something other than () or a map".into());
+        };
+        let text = |key: &str|decision.get(key).and_then(|v|v.clone().into_string().ok())
+            .ok_or_else(||format!("the decision has no {key} string"));
+        let reason = text("reason")?;
+        Ok(Some(match &*text("action")? {
+            "veto" => PolicyAction::Veto { reason },
+            "redirect" => PolicyAction::Redirect { vote_for: text("vote_for")?, reason },
+            "reweight" => {
+                let number_of_votes = decision.get("number_of_votes")
+                    .and_then(|n|n.as_int().ok())
+                    .and_then(|n|Weight::try_from(n).ok())
+                    .ok_or("the decision has no number_of_votes which is 0 or more")?;
+                PolicyAction::Reweight { number_of_votes, reason }
+            }
+            "ineligible" => PolicyAction::Ineligible { reason },
+            action => return Err(format!("unknown action {action}")),
+        }))
+    }
+}
+#[cfg(feature = "rhai")]
+impl BallotPolicy for RhaiPolicy {
+    fn decide(&mut self, vote: &Vote) -> Option<PolicyAction> {
+        self.run(vote).unwrap_or_else(|error|{
+            self.errors.push(ScriptError::Decide { voter_id: vote.voter_id.to_string(), error });
+            None
+        })
+    }
+}
@@ -0,0 +1,39 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! The supported types for counting an election, `use electorium::prelude::*` is enough
+//! to read ballots, count them, follow the count and keep the result.
+//!
+//! ```
+//! use electorium::prelude::*;
+//! let votes = vec![
+//!     Vote::borrowed("alice", Some("bob"), 1, true),
+//!     Vote::borrowed("bob", None, 2, true),
+//! ];
+//! let options = ElectionOptions::default();
+//! let mut vc = VoteCounter::with_options(&votes, Introspector::default(), options);
+//! let result: ElectionResult = vc.tally().unwrap();
+//! assert_eq!((result.winner.as_deref(), result.votes), (Some("bob"), 3));
+//! ```
+//!
+//! API stability: every public item of the crate with every feature, as listed by
+//! `cargo public-api`, is in public-api.txt and a test fails when they differ, so a
+//! change to the API is always part of the change under review. The test reads the
+//! API from rustdoc's JSON output, which needs a nightly toolchain. Changing or removing
+//! anything which is public is a breaking change, made only with a new minor version
+//! while the version is 0.x, additions are not.
+pub use crate::{
+    Vote, Metadata, Weight, VoteCounter, Election, TallyError, BallotError,
+    CandidateInfo, Standing, RankingPage, TallyState,
+};
+pub use crate::options::{
+    ElectionOptions, PatronRule, AlgorithmVersion, UnrecognizedVotePolicy, DuplicatePolicy, OverflowPolicy,
+    Preset, OptionsError,
+};
+pub use crate::result::{ElectionResult, RuleSet, SourceTotals, Engagement};
+pub use crate::builder::ElectionBuilder;
+pub use crate::owned::{OwnedVoteCounter, IntoOwned};
+pub use crate::vote_like::VoteLike;
+pub use crate::parallel::Parallelism;
+pub use crate::introspector::{
+    Introspector, Event, InvalidVote, InvalidVoteCause, VoteDelegation, Winner, Seat, NoQuorum,
+};
+pub use crate::record::EventRecord;
@@ -0,0 +1,132 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! Counting in two steps, for processes where the validity of the ballots is verified
+//! publicly before the outcome is revealed.
+//!
+//! preview() counts the ballots in full and publishes what was found about the ballots,
+//! with a commitment to the result in place of the result. The winner, their votes and
+//! the tie-break hashes stay in the SealedResult until it is finalized, and anyone can
+//! then check that the revealed result is the one which was committed to.
+use blake2::{Blake2b512, Digest};
+
+use crate::introspector::{Introspector, InvalidVoteCause, DeterministicTieBreaker};
+use crate::options::ElectionOptions;
+use crate::record::EventRecord;
+use crate::result::{ElectionResult, Engagement};
+use crate::signing::{ballots_fingerprint, put_bytes, put_u64, put_weight, signed_message};
+use crate::types::{Vote, TallyError};
+use crate::VoteCounter;
+
+const DOMAIN: &[u8] = b"electorium result commitment v2\0";
+
+/// What can be published before the outcome.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Preview {
+    /// The invalid votes, with the voter_id of each one
+    pub invalid: Vec<(InvalidVoteCause, String)>,
+    /// Turnout and engagement, with utilization_bp withheld as 0 because it reveals the
+    /// winner's votes
+    pub engagement: Engagement,
+    /// See ballots_fingerprint()
+    pub fingerprint: Vec<u8>,
+    /// See commitment()
+    pub commitment: Vec<u8>,
+}
+
+/// The outcome, withheld until finalize().
+#[derive(Debug)]
+pub struct SealedResult {
+    result: ElectionResult,
+    tie_breaks: Vec<EventRecord>,
+    fingerprint: Vec<u8>,
+    nonce: [u8; 32],
+}
+
+/// The revealed outcome.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Revealed {
+    pub result: ElectionResult,
+    /// The tie-breaks of the count, as EventRecord::TieBreaker
+    pub tie_breaks: Vec<EventRecord>,
+    pub fingerprint: Vec<u8>,
+    pub nonce: [u8; 32],
+}
+
+/// The blake2b hash which commits to a result and its tie-breaks, the nonce must be
+/// secret and random so that the possible results cannot be tried one by one against
+/// the commitment. Only EventRecord::TieBreaker records are committed to.
+pub fn commitment(
+    result: &ElectionResult,
+    tie_breaks: &[EventRecord],
+    fingerprint: &[u8],
+    nonce: &[u8; 32],
+) -> Vec<u8> {
+    let mut tb = Vec::new();
+    let tied = tie_breaks.iter().filter_map(|r| match r {
+        EventRecord::TieBreaker { votes, tied } => Some((votes, tied)),
+        _ => None,
+    });
+    put_u64(&mut tb, tied.clone().count() as u64);
+    for (votes, tied) in tied {
+        put_weight(&mut tb, *votes);
+        put_u64(&mut tb, tied.len() as u64);
+        for (id, hash) in tied {
+            put_bytes(&mut tb, id.as_bytes());
+            put_bytes(&mut tb, hash);
+        }
+    }
+    Blake2b512::new()
+        .chain_update(DOMAIN)
+        .chain_update(nonce)
+        .chain_update(signed_message(result, fingerprint))
+        .chain_update(tb)
+        .finalize()
+        .to_vec()
+}
+
+/// Count the ballots and publish only the Preview, the nonce must be random.
+pub fn preview(
+    votes: &[Vote],
+    options: ElectionOptions,
+    nonce: [u8; 32],
+) -> Result<(Preview, SealedResult), TallyError> {
+    let mut is = Introspector::default();
+    is.subscribe(Vec::<EventRecord>::new(), |tb, e: &DeterministicTieBreaker| {
+        tb.push(EventRecord::TieBreaker {
+            votes: e.votes,
+            tied: e.tied_candidates.iter().map(|(v, h)|(v.voter_id.to_string(), h.to_vec())).collect(),
+        });
+    });
+    let mut vc = VoteCounter::with_options(votes, is, options);
+    let result = vc.tally()?;
+    let invalid = vc.invalid_votes().iter().map(|(c, v)|(c.clone(), v.voter_id.to_string())).collect();
+    let tie_breaks: Vec<EventRecord> = vc.into_introspector().into_contexts::<Vec<EventRecord>>().into_iter().flatten().collect();
+    let fingerprint = ballots_fingerprint(votes);
+    let preview = Preview {
+        invalid,
+        engagement: Engagement { utilization_bp: 0, ..result.engagement.clone() },
+        fingerprint: fingerprint.clone(),
+        commitment: commitment(&result, &tie_breaks, &fingerprint, &nonce),
+    };
+    Ok((preview, SealedResult { result, tie_breaks, fingerprint, nonce }))
+}
+
+impl SealedResult {
+    /// Reveal the outcome
+    pub fn finalize(self) -> Revealed {
+        Revealed {
+            result: self.result,
+            tie_breaks: self.tie_breaks,
+            fingerprint: self.fingerprint,
+            nonce: self.nonce,
+        }
+    }
+}
+
+impl Revealed {
+    /// Whether this is the outcome which preview committed to, including its tie-breaks
+    pub fn matches(&self, preview: &Preview) -> bool {
+        self.fingerprint == preview.fingerprint
+            && self.tie_breaks.iter().all(|r| matches!(r, EventRecord::TieBreaker { .. }))
+            && commitment(&self.result, &self.tie_breaks, &self.fingerprint, &self.nonce) == preview.commitment
+    }
+}
@@ -0,0 +1,138 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! Keeping the ballots which were set aside, so that the registrar can contact the
+//! voters before the result is certified.
+//!
+//! The events say which ballots were not counted as cast, but only by voter_id. A
+//! Quarantine keeps each one in the form it was submitted, with the cause: a ballot which
+//! the count found invalid is kept as the Vote it was given, and a line which bulk::ingest()
+//! could not read as a ballot is kept as its text. Each one is passed to the sink as it is
+//! found, for instance to append it to a file with write_line(), and held until taken.
+//!
+//! A ballot which does not vote for anyone (InvalidVoteCause::NoVote) is not quarantined,
+//! not voting is a choice and the voter's own votes are still counted.
+use std::cell::RefCell;
+use std::fmt;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+use crate::bulk::{self, Rejection, RejectedRow};
+use crate::introspector::{Introspector, InvalidVote, InvalidVoteCause};
+use crate::types::Vote;
+
+/// Why a ballot was quarantined.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuarantineCause {
+    /// The count found the ballot invalid
+    Invalid(InvalidVoteCause),
+    /// The line could not be ingested
+    Rejected(Rejection),
+}
+impl fmt::Display for QuarantineCause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuarantineCause::Invalid(cause) => write!(f, "{cause:?}"),
+            QuarantineCause::Rejected(Rejection::Malformed(why)) => write!(f, "Malformed: {why}"),
+            QuarantineCause::Rejected(r) => write!(f, "{r:?}"),
+        }
+    }
+}
+
+/// A ballot in the form it was submitted.
+#[derive(Debug)]
+pub enum Original {
+    Ballot(Vote<'static>),
+    /// A line of an upload, with its line number
+    Line { line: u64, text: String },
+}
+
+/// One ballot which was set aside, with why.
+#[derive(Debug)]
+pub struct Quarantined {
+    pub cause: QuarantineCause,
+    pub original: Original,
+}
+impl Quarantined {
+    /// The cause, a tab, and the ballot as a line of CSV (see bulk::csv_line()) or the
+    /// line as it was read
+    pub fn to_line(&self) -> String {
+        match &self.original {
+            Original::Ballot(v) => format!("{}\t{}", self.cause, bulk::csv_line(v)),
+            Original::Line { text, .. } => format!("{}\t{}", self.cause, text),
+        }
+    }
+}
+
+/// Write q to out as a line, see Quarantined::to_line(), for use as a sink.
+pub fn write_line(out: &mut impl Write, q: &Quarantined) -> io::Result<()> {
+    writeln!(out, "{}", q.to_line())
+}
+
+type Sink = Box<dyn FnMut(&Quarantined)>;
+
+#[derive(Default)]
+struct Inner {
+    held: Vec<Quarantined>,
+    sink: Option<Sink>,
+}
+
+/// Collects quarantined ballots, see the module documentation. Clones share the same
+/// ballots and sink.
+#[derive(Clone, Default)]
+pub struct Quarantine {
+    inner: Rc<RefCell<Inner>>,
+}
+impl fmt::Debug for Quarantine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Quarantine").field("held", &self.inner.borrow().held).finish()
+    }
+}
+impl Quarantine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Also pass each ballot to sink as it is quarantined
+    pub fn with_sink(sink: impl FnMut(&Quarantined) + 'static) -> Self {
+        let out = Self::default();
+        out.inner.borrow_mut().sink = Some(Box::new(sink));
+        out
+    }
+    fn add(&self, q: Quarantined) {
+        let mut inner = self.inner.borrow_mut();
+        if let Some(sink) = &mut inner.sink {
+            sink(&q);
+        }
+        inner.held.push(q);
+    }
+    /// Quarantine each ballot which the count of is finds invalid
+    pub fn subscribe(&self, is: &mut Introspector<'_>) {
+        is.subscribe(self.clone(), |q, e: &InvalidVote| {
+            if e.cause != InvalidVoteCause::NoVote {
+                q.add(Quarantined {
+                    cause: QuarantineCause::Invalid(e.cause.clone()),
+                    original: Original::Ballot(e.vote.to_owned_vote()),
+                });
+            }
+        });
+    }
+    /// Quarantine the lines which bulk::ingest() rejected, from BulkReport::rejected
+    pub fn rejected(&self, rows: &[RejectedRow]) {
+        for r in rows {
+            self.add(Quarantined {
+                cause: QuarantineCause::Rejected(r.reason.clone()),
+                original: Original::Line { line: r.line, text: r.text.clone() },
+            });
+        }
+    }
+    /// The number of ballots held
+    pub fn len(&self) -> usize {
+        self.inner.borrow().held.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Every ballot which was quarantined since the last take(), in the order they were
+    /// found
+    pub fn take(&self) -> Vec<Quarantined> {
+        std::mem::take(&mut self.inner.borrow_mut().held)
+    }
+}
@@ -0,0 +1,261 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! Owned copies of the introspector events, which can outlive the ballots, be stored,
+//! compared, and (with the serde feature) serialized.
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::introspector::{
+    Introspector,
+    VoteDelegation,
+    VoteDelegationRing,
+    DeepDelegation,
+    ImplicitDelegation,
+    InvalidVote,
+    InvalidVoteCause,
+    Fallback, FallbackCause,
+    Abstention,
+    BestRing, BestOfRing,
+    PatronSelection, PatronSelectionReason, PatronMath,
+    DeterministicTieBreaker,
+    DeterministicTieBreakerHash,
+    Winner,
+    Seat,
+    NoQuorum,
+    PolicyDecision,
+    AliasApplied,
+    WeightsRescaled,
+    Phase,
+    Progress,
+};
+use crate::policy::PolicyAction;
+use crate::result::Contributions;
+use crate::types::{Vote, Weight};
+
+/// The outcome of considering a candidate as patron, see PatronSelectionReason.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PatronOutcome {
+    LoopCandidate,
+    NotWillingCandidate,
+    NotProvidingMajority(Weight),
+    NotBeatingSecondBest(Weight, String),
+    NotBeatingSecondBestOutright(Weight, String),
+    PatronFound,
+}
+impl<'a> From<&PatronSelectionReason<'a>> for PatronOutcome {
+    fn from(r: &PatronSelectionReason<'a>) -> Self {
+        match r {
+            PatronSelectionReason::LoopCandidate => PatronOutcome::LoopCandidate,
+            PatronSelectionReason::NotWillingCandidate => PatronOutcome::NotWillingCandidate,
+            PatronSelectionReason::NotProvidingMajority(mtb) =>
+                PatronOutcome::NotProvidingMajority(*mtb),
+            PatronSelectionReason::NotBeatingSecondBest(votes, v) =>
+                PatronOutcome::NotBeatingSecondBest(*votes, v.voter_id.to_string()),
+            PatronSelectionReason::NotBeatingSecondBestOutright(votes, v) =>
+                PatronOutcome::NotBeatingSecondBestOutright(*votes, v.voter_id.to_string()),
+            PatronSelectionReason::PatronFound => PatronOutcome::PatronFound,
+        }
+    }
+}
+
+/// An owned copy of one introspector event, candidates are referred to by voter_id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(tag = "event"))]
+pub enum EventRecord {
+    VoteDelegation { from: String, to: String, because_of: String, votes: Weight },
+    VoteDelegationRing { chain: Vec<String>, next: String, from: String, votes: Weight, stopped_at: String },
+    DeepDelegation { from: String, depth: usize, chain: Vec<String> },
+    ImplicitDelegation { from: String, to: String, because_of: String, votes: Weight },
+    InvalidVote { cause: InvalidVoteCause, voter_id: String, vote_for: Option<String> },
+    Fallback { voter_id: String, skipped: String, cause: FallbackCause, next: Option<String> },
+    Abstention { voter_id: String, votes: Weight },
+    BestRing { rings: Vec<Vec<String>>, votes: Weight },
+    BestOfRing { scores: Vec<(String, Weight)>, winners: Vec<String> },
+    PatronSelection { candidate: String, votes: Weight, outcome: PatronOutcome, math: PatronMath },
+    TieBreakerHash { candidate: String, bytes: Vec<u8>, votes: Weight },
+    /// Tied candidates and their hashes, in order, the first one wins
+    TieBreaker { votes: Weight, tied: Vec<(String, Vec<u8>)> },
+    /// The salt of the tie-breaker and the commitment published before ballots closed,
+    /// see salt::salt_record
+    TieBreakSalt { commitment: Vec<u8>, salt: Vec<u8> },
+    Winner { candidate: Option<String>, votes: Weight, contributions: Contributions },
+    Seat { seat: usize, candidate: String, votes: Weight },
+    NoQuorum { ballots: u64, votes: Weight, min_ballots: Option<u64>, min_votes: Option<Weight> },
+    PolicyDecision { voter_id: String, action: PolicyAction },
+    AliasApplied { voter_id: String, alias: String, resolved: String, table: String },
+    WeightsRescaled { shift: u32 },
+    /// Not part of the count, see subscribe_progress
+    Progress { phase: Phase, processed: u64, total: u64, estimated_remaining_ms: Option<u64> },
+}
+
+fn ids(votes: &[&Vote]) -> Vec<String> {
+    votes.iter().map(|v|v.voter_id.to_string()).collect()
+}
+
+type Sink = Rc<RefCell<dyn FnMut(EventRecord)>>;
+
+/// Subscribe to every event, passing an owned EventRecord of each one to sink.
+pub fn subscribe_all<'a>(is: &mut Introspector<'a>, sink: impl FnMut(EventRecord) + 'static) {
+    let sink: Sink = Rc::new(RefCell::new(sink));
+    is.subscribe(sink.clone(), |s, e: &VoteDelegation| {
+        (s.borrow_mut())(EventRecord::VoteDelegation {
+            from: e.from.voter_id.to_string(),
+            to: e.to.voter_id.to_string(),
+            because_of: e.because_of.voter_id.to_string(),
+            votes: e.votes,
+        });
+    });
+    is.subscribe(sink.clone(), |s, e: &VoteDelegationRing| {
+        (s.borrow_mut())(EventRecord::VoteDelegationRing {
+            chain: ids(&e.chain),
+            next: e.next.voter_id.to_string(),
+            from: e.from.voter_id.to_string(),
+            votes: e.votes,
+            stopped_at: e.stopped_at.voter_id.to_string(),
+        });
+    });
+    is.subscribe(sink.clone(), |s, e: &DeepDelegation| {
+        (s.borrow_mut())(EventRecord::DeepDelegation {
+            from: e.from.voter_id.to_string(),
+            depth: e.depth,
+            chain: ids(&e.chain),
+        });
+    });
+    is.subscribe(sink.clone(), |s, e: &ImplicitDelegation| {
+        (s.borrow_mut())(EventRecord::ImplicitDelegation {
+            from: e.from.voter_id.to_string(),
+            to: e.to.to_owned(),
+            because_of: e.because_of.voter_id.to_string(),
+            votes: e.votes,
+        });
+    });
+    is.subscribe(sink.clone(), |s, e: &InvalidVote| {
+        (s.borrow_mut())(EventRecord::InvalidVote {
+            cause: e.cause.clone(),
+            voter_id: e.vote.voter_id.to_string(),
+            vote_for: e.vote.vote_for.as_deref().map(str::to_owned),
+        });
+    });
+    is.subscribe(sink.clone(), |s, e: &Fallback| {
+        (s.borrow_mut())(EventRecord::Fallback {
+            voter_id: e.vote.voter_id.to_string(),
+            skipped: e.skipped.to_owned(),
+            cause: e.cause,
+            next: e.next.map(str::to_owned),
+        });
+    });
+    is.subscribe(sink.clone(), |s, e: &Abstention| {
+        (s.borrow_mut())(EventRecord::Abstention {
+            voter_id: e.vote.voter_id.to_string(),
+            votes: e.vote.number_of_votes,
+        });
+    });
+    is.subscribe(sink.clone(), |s, e: &BestRing| {
+        (s.borrow_mut())(EventRecord::BestRing {
+            rings: e.best_rings_members.iter().map(|r|ids(r)).collect(),
+            votes: e.best_total_delegated_votes,
+        });
+    });
+    is.subscribe(sink.clone(), |s, e: &BestOfRing| {
+        (s.borrow_mut())(EventRecord::BestOfRing {
+            scores: e.rings_member_scores.iter().map(|(v, s)|(v.voter_id.to_string(), *s)).collect(),
+            winners: ids(&e.winners),
+        });
+    });
+    is.subscribe(sink.clone(), |s, e: &PatronSelection| {
+        (s.borrow_mut())(EventRecord::PatronSelection {
+            candidate: e.potential_patron.voter_id.to_string(),
+            votes: e.potential_patron_votes,
+            outcome: (&e.selection).into(),
+            math: e.math,
+        });
+    });
+    is.subscribe(sink.clone(), |s, e: &DeterministicTieBreakerHash| {
+        (s.borrow_mut())(EventRecord::TieBreakerHash {
+            candidate: e.candidate.clone(),
+            bytes: e.bytes.clone(),
+            votes: e.total_indirect_votes,
+        });
+    });
+    is.subscribe(sink.clone(), |s, e: &DeterministicTieBreaker| {
+        (s.borrow_mut())(EventRecord::TieBreaker {
+            votes: e.votes,
+            tied: e.tied_candidates.iter().map(|(v, h)|(v.voter_id.to_string(), h.to_vec())).collect(),
+        });
+    });
+    is.subscribe(sink.clone(), |s, e: &PolicyDecision| {
+        (s.borrow_mut())(EventRecord::PolicyDecision {
+            voter_id: e.voter_id.to_string(),
+            action: e.action.clone(),
+        });
+    });
+    is.subscribe(sink.clone(), |s, e: &AliasApplied| {
+        (s.borrow_mut())(EventRecord::AliasApplied {
+            voter_id: e.voter_id.clone(),
+            alias: e.alias.clone(),
+            resolved: e.resolved.clone(),
+            table: e.table.clone(),
+        });
+    });
+    is.subscribe(sink.clone(), |s, e: &WeightsRescaled| {
+        (s.borrow_mut())(EventRecord::WeightsRescaled { shift: e.shift });
+    });
+    is.subscribe(sink.clone(), |s, e: &Option<Winner>| {
+        (s.borrow_mut())(EventRecord::Winner {
+            candidate: e.as_ref().map(|w|w.candidate.voter_id.to_string()),
+            votes: e.as_ref().map(|w|w.votes).unwrap_or(0),
+            contributions: e.as_ref().map(|w|(&w.contributions).into()).unwrap_or_default(),
+        });
+    });
+    is.subscribe(sink.clone(), |s, e: &NoQuorum| {
+        (s.borrow_mut())(EventRecord::NoQuorum {
+            ballots: e.ballots,
+            votes: e.votes,
+            min_ballots: e.min_ballots,
+            min_votes: e.min_votes,
+        });
+    });
+    is.subscribe(sink, |s, e: &Seat| {
+        (s.borrow_mut())(EventRecord::Seat {
+            seat: e.seat,
+            candidate: e.candidate.voter_id.to_string(),
+            votes: e.votes,
+        });
+    });
+}
+
+/// Subscribe to Progress events, these depend on timing so they are not included by
+/// subscribe_all and never appear in records of the count.
+pub fn subscribe_progress<'a>(is: &mut Introspector<'a>, sink: impl FnMut(EventRecord) + 'static) {
+    is.subscribe(sink, |s, e: &Progress| {
+        s(EventRecord::Progress {
+            phase: e.phase,
+            processed: e.processed,
+            total: e.total,
+            estimated_remaining_ms: e.estimated_remaining
+                .map(|d|u64::try_from(d.as_millis()).unwrap_or(u64::MAX)),
+        });
+    });
+}
+
+/// An Introspector which passes every event to callback as a line of JSON, for
+/// forwarding events to another language, for example to a JS function, see wasm/.
+/// If progress is true then Progress events are included, for showing a progress bar.
+#[cfg(feature = "json")]
+pub fn json_introspector<'a>(callback: impl FnMut(&str) + 'static, progress: bool) -> Introspector<'a> {
+    let callback = Rc::new(RefCell::new(callback));
+    let send = move |r: EventRecord| {
+        if let Ok(json) = serde_json::to_string(&r) {
+            (callback.borrow_mut())(&json);
+        }
+    };
+    let mut is = Introspector::default();
+    if progress {
+        subscribe_progress(&mut is, send.clone());
+    }
+    subscribe_all(&mut is, send);
+    is
+}
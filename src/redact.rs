@@ -0,0 +1,111 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! Public and administrator views of results and event records.
+//!
+//! Administrators see everything. The public view follows a RedactionPolicy: individual
+//! voters below a weight are not named, their votes are shown as one aggregate under the
+//! REDACTED name, and who each member delegated to can be hidden entirely. The winner,
+//! the totals and the rest of the count are never changed, so the public view still shows
+//! how the winner was determined.
+use crate::introspector::Introspector;
+use crate::record::{EventRecord, subscribe_all};
+use crate::result::{Contributions, ElectionResult};
+use crate::types::Weight;
+
+/// The name shown in place of a voter who is not named
+pub const REDACTED: &str = "[redacted]";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Administrator,
+    Public,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RedactionPolicy {
+    /// Voters who delegated fewer than this many votes are not named in public
+    pub min_votes_shown: Weight,
+    /// No individual delegation is shown in public, only aggregates
+    pub hide_delegation_targets: bool,
+}
+impl RedactionPolicy {
+    fn shown(&self, votes: Weight) -> bool {
+        !self.hide_delegation_targets && votes >= self.min_votes_shown
+    }
+
+    fn voter_list(&self, list: &[(String, Weight)]) -> Vec<(String, Weight)> {
+        let mut out = Vec::new();
+        let mut hidden = 0;
+        for (id, votes) in list {
+            if self.shown(*votes) {
+                out.push((id.clone(), *votes));
+            } else {
+                hidden += votes;
+            }
+        }
+        if hidden > 0 {
+            out.push((REDACTED.to_owned(), hidden));
+        }
+        out
+    }
+
+    fn contributions(&self, c: &Contributions) -> Contributions {
+        Contributions {
+            own: c.own,
+            supporters: self.voter_list(&c.supporters),
+            through_ring: self.voter_list(&c.through_ring),
+        }
+    }
+
+    /// The result as seen by role
+    pub fn result(&self, role: Role, r: &ElectionResult) -> ElectionResult {
+        let mut r = r.clone();
+        if role == Role::Public {
+            r.contributions = self.contributions(&r.contributions);
+        }
+        r
+    }
+
+    /// The record as seen by role, None if it is not shown at all
+    pub fn record(&self, role: Role, r: EventRecord) -> Option<EventRecord> {
+        if role == Role::Administrator {
+            return Some(r);
+        }
+        Some(match r {
+            EventRecord::VoteDelegation { votes, .. } |
+            EventRecord::VoteDelegationRing { votes, .. } |
+            EventRecord::ImplicitDelegation { votes, .. } if !self.shown(votes) => return None,
+            EventRecord::DeepDelegation { .. } if self.hide_delegation_targets => return None,
+            EventRecord::InvalidVote { cause, vote_for, .. } if self.hide_delegation_targets =>
+                EventRecord::InvalidVote {
+                    cause,
+                    voter_id: REDACTED.to_owned(),
+                    vote_for: vote_for.map(|_|REDACTED.to_owned()),
+                },
+            EventRecord::Fallback { cause, next, .. } if self.hide_delegation_targets =>
+                EventRecord::Fallback {
+                    voter_id: REDACTED.to_owned(),
+                    skipped: REDACTED.to_owned(),
+                    cause,
+                    next: next.map(|_|REDACTED.to_owned()),
+                },
+            EventRecord::AliasApplied { .. } if self.hide_delegation_targets => return None,
+            // An alias may be an email or an old member number, which is never shown
+            EventRecord::AliasApplied { voter_id, resolved, table, .. } =>
+                EventRecord::AliasApplied { voter_id, alias: REDACTED.to_owned(), resolved, table },
+            EventRecord::Winner { candidate, votes, contributions } =>
+                EventRecord::Winner { candidate, votes, contributions: self.contributions(&contributions) },
+            r => r,
+        })
+    }
+
+    /// Subscribe to every event, as record::subscribe_all(), passing the records which
+    /// role may see to sink
+    pub fn subscribe(&self, is: &mut Introspector<'_>, role: Role, mut sink: impl FnMut(EventRecord) + 'static) {
+        let policy = self.clone();
+        subscribe_all(is, move |r| {
+            if let Some(r) = policy.record(role, r) {
+                sink(r);
+            }
+        });
+    }
+}
@@ -0,0 +1,139 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! The owned result of an election, which always carries the rules it was counted under.
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::options::ElectionOptions;
+use crate::introspector;
+use crate::types::{Metadata, Weight};
+
+/// The version of the ElectionOptions format, this is incremented whenever an option
+/// is added or its meaning changes, so that a recorded RuleSet is never read as a
+/// different one.
+pub const OPTIONS_VERSION: u32 = 11;
+
+/// The exact rules which an election was counted under.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RuleSet {
+    /// The version of electorium which counted the election
+    pub crate_version: String,
+    /// The version of the ElectionOptions format, see OPTIONS_VERSION
+    pub options_version: u32,
+    /// The options which were in force
+    pub options: ElectionOptions,
+}
+impl RuleSet {
+    pub fn new(options: ElectionOptions) -> Self {
+        Self {
+            crate_version: env!("CARGO_PKG_VERSION").to_owned(),
+            options_version: OPTIONS_VERSION,
+            options,
+        }
+    }
+}
+
+/// Where the winner's votes came from, see introspector::Contributions.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Contributions {
+    /// The winner's own votes
+    pub own: Weight,
+    /// Each candidate who voted directly for the winner, with the votes they passed on
+    pub supporters: Vec<(String, Weight)>,
+    /// Each other member of the winner's ring, with the votes which arrived through them
+    pub through_ring: Vec<(String, Weight)>,
+}
+impl<'a> From<&introspector::Contributions<'a>> for Contributions {
+    fn from(c: &introspector::Contributions<'a>) -> Self {
+        let owned = |v: &[(&crate::Vote, Weight)]| {
+            v.iter().map(|(v, votes)|(v.voter_id.to_string(), *votes)).collect()
+        };
+        Self { own: c.own, supporters: owned(&c.supporters), through_ring: owned(&c.through_ring) }
+    }
+}
+
+/// The votes from the ballots of one source, such as online, paper or proxy-desk,
+/// see VoteCounter::tally_by_source().
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SourceTotals {
+    pub source: String,
+    /// The number of ballots which were counted
+    pub ballots: u64,
+    /// The sum of the votes on those ballots
+    pub votes: Weight,
+    /// The votes from those ballots which were delegated to the winner, the sum of
+    /// to_winner over all sources is the winner's total
+    pub to_winner: Weight,
+}
+
+/// Turnout and engagement, from the same count as the result. Rates are in basis points
+/// (1/100 of a percent) rounded down, and are 0 when there is nothing to divide by.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Engagement {
+    /// The ballots which were counted, every ballot except duplicates
+    pub valid_ballots: u64,
+    /// The sum of the votes on those ballots
+    pub valid_votes: Weight,
+    /// The ballots whose vote for someone else was counted
+    pub delegating_ballots: u64,
+    /// The ballots which were cast as abstentions, see Vote::abstain
+    pub abstentions: u64,
+    /// The sum of the votes on those ballots, they are part of valid_votes
+    pub abstaining_votes: Weight,
+    /// The ballots of willing candidates
+    pub candidates: u64,
+    /// valid_votes as a share of ElectionOptions::registry_weight, if there is a registry
+    pub turnout_bp: Option<u64>,
+    /// delegating_ballots as a share of valid_ballots
+    pub delegation_bp: u64,
+    /// The winner's votes as a share of valid_votes
+    pub utilization_bp: u64,
+}
+impl Engagement {
+    /// part / whole in basis points
+    pub fn bp(part: impl Into<u128>, whole: impl Into<u128>) -> u64 {
+        let (part, whole) = (part.into(), whole.into());
+        if whole == 0 {
+            return 0;
+        }
+        // Wide weights may not fit once multiplied, 10_000 is less than 1 << 14
+        let (part, whole) = match part.checked_mul(10_000) {
+            Some(_) => (part, whole),
+            None => (part >> 14, whole >> 14),
+        };
+        match whole {
+            0 => u64::MAX,
+            _ => u64::try_from(part * 10_000 / whole).unwrap_or(u64::MAX),
+        }
+    }
+}
+
+/// The outcome of an election. A result from VoteCounter::tally() carries the RuleSet it
+/// was counted under, so it can be interpreted under the right rules, but since the
+/// fields are public, a result from elsewhere may carry any rules.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ElectionResult {
+    /// The voter_id of the winner, if there is one
+    pub winner: Option<String>,
+    /// The total number of delegated votes which the winner received
+    pub votes: Weight,
+    /// Where the winner's votes came from, empty if there is no winner
+    pub contributions: Contributions,
+    /// The metadata on the winner's ballot, see Vote::metadata
+    pub winner_metadata: Metadata,
+    /// Every ballot's votes were shifted right by this many bits before counting, under
+    /// OverflowPolicy::Rescale, so votes and every other total are in units of
+    /// 2^weight_shift votes. 0 when the votes were counted as they are.
+    pub weight_shift: u32,
+    /// The votes from each source of ballots, ordered by source, empty unless the
+    /// election was counted with VoteCounter::tally_by_source()
+    pub sources: Vec<SourceTotals>,
+    /// Turnout and engagement
+    pub engagement: Engagement,
+    /// The rules which the election was counted under
+    pub rules: RuleSet,
+}
@@ -0,0 +1,58 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! Commit-reveal of a salt for the tie-breaker.
+//!
+//! Whoever supplies the salt publishes commit_salt() of it before ballots close, so
+//! that it cannot be chosen after the ballots are known, and reveals the salt only at
+//! tally time, so that nobody can compute how ties will resolve while voting is open.
+//! salt_record() checks the reveal against the commitment and gives the record which
+//! puts both in the audit log.
+use std::fmt;
+
+use blake2::{Blake2b512, Digest};
+
+use crate::record::EventRecord;
+
+const DOMAIN: &[u8] = b"electorium tie-break salt v1\0";
+
+/// Shorter salts could be found from the commitment by trying them all.
+pub const MIN_SALT_LEN: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SaltError {
+    /// The salt is shorter than MIN_SALT_LEN
+    TooShort(usize),
+    /// The revealed salt is not the one which was committed to
+    Mismatch,
+}
+impl fmt::Display for SaltError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaltError::TooShort(len) =>
+                write!(f, "salt is {} bytes, at least {} are required", len, MIN_SALT_LEN),
+            SaltError::Mismatch => write!(f, "revealed salt does not match the commitment"),
+        }
+    }
+}
+impl std::error::Error for SaltError {}
+
+/// The blake2b hash to publish before ballots close, the salt must be random.
+pub fn commit_salt(salt: &[u8]) -> Result<Vec<u8>, SaltError> {
+    if salt.len() < MIN_SALT_LEN {
+        return Err(SaltError::TooShort(salt.len()));
+    }
+    Ok(Blake2b512::new().chain_update(DOMAIN).chain_update(salt).finalize().to_vec())
+}
+
+/// Check that the salt revealed at tally time is the one which was committed to.
+pub fn verify_salt(commitment: &[u8], salt: &[u8]) -> Result<(), SaltError> {
+    if commit_salt(salt)? != commitment {
+        return Err(SaltError::Mismatch);
+    }
+    Ok(())
+}
+
+/// Verify the reveal and return the EventRecord::TieBreakSalt for the audit log.
+pub fn salt_record(commitment: &[u8], salt: &[u8]) -> Result<EventRecord, SaltError> {
+    verify_salt(commitment, salt)?;
+    Ok(EventRecord::TieBreakSalt { commitment: commitment.to_vec(), salt: salt.to_vec() })
+}
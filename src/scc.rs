@@ -0,0 +1,64 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! Strongly connected components of the delegation graph.
+//!
+//! Every node has at most one outgoing edge (the candidate they voted for), so the
+//! depth-first search of Tarjan's algorithm is a simple path and can be done without
+//! recursion, no matter how long the delegation chains are.
+
+/// Compute the strongly connected component of each node, where `next[i]` is the node
+/// which node i points to. Returns the component ID of each node. Component IDs are
+/// assigned in the order that components are completed, so a component's ID is always
+/// lower than the ID of every component which points to it.
+pub fn tarjan(next: &[Option<usize>]) -> Vec<usize> {
+    const UNVISITED: usize = usize::MAX;
+    let n = next.len();
+    let mut index = vec![UNVISITED; n];
+    let mut lowlink = vec![0; n];
+    let mut on_stack = vec![false; n];
+    let mut stack = Vec::new();
+    let mut component = vec![UNVISITED; n];
+    let mut next_index = 0;
+    let mut next_component = 0;
+    let mut path = Vec::new();
+
+    for root in 0..n {
+        if index[root] != UNVISITED {
+            continue;
+        }
+        // Walk forward until reaching a node which is already visited or has no edge
+        let mut v = root;
+        loop {
+            index[v] = next_index;
+            lowlink[v] = next_index;
+            next_index += 1;
+            stack.push(v);
+            on_stack[v] = true;
+            path.push(v);
+            match next[v] {
+                Some(w) if index[w] == UNVISITED => v = w,
+                Some(w) if on_stack[w] => {
+                    lowlink[v] = lowlink[v].min(index[w]);
+                    break;
+                }
+                _ => break,
+            }
+        }
+        // Unwind, completing components on the way back
+        while let Some(v) = path.pop() {
+            if lowlink[v] == index[v] {
+                while let Some(w) = stack.pop() {
+                    on_stack[w] = false;
+                    component[w] = next_component;
+                    if w == v {
+                        break;
+                    }
+                }
+                next_component += 1;
+            }
+            if let Some(&parent) = path.last() {
+                lowlink[parent] = lowlink[parent].min(lowlink[v]);
+            }
+        }
+    }
+    component
+}
@@ -0,0 +1,113 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! A plain text format for small elections, for tests, documentation and fuzz corpora.
+//!
+//! Each line is `name votes [vote_for] [candidate|voter]`. votes is the number_of_votes
+//! of the ballot, vote_for is who they vote for, or `-` or nothing if they did not vote,
+//! and the last word says whether they are a willing candidate, which they are unless it
+//! is `voter`. Words are separated by spaces so names cannot contain them, lines starting
+//! with `#` and empty lines are skipped. This is the .case format of the fuzzer, so the
+//! same files can be counted by the unit tests, by `electorium selftest --scenario` and
+//! compiled into fuzz inputs. Metadata, fallbacks and abstentions are not part of it.
+//!
+//! ```
+//! use electorium::{scenario, VoteCounter};
+//! let votes = scenario::parse("
+//!     ## Alice and Bob vote for each other, Dave is not a candidate
+//!     Alice 1 Bob
+//!     Bob 2 Alice
+//!     Charlie 5 Alice
+//!     Dave 3 Charlie voter
+//! ").unwrap();
+//! let mut vc = VoteCounter::new(&votes, Default::default());
+//! assert_eq!(vc.find_winner().map(|w|&*w.voter_id), Some("Charlie"));
+//! assert_eq!(scenario::parse(&scenario::to_text(&votes)).unwrap().len(), 4);
+//! ```
+use std::borrow::Cow;
+use std::fmt;
+use std::fmt::Write;
+
+use crate::types::Vote;
+
+/// What is wrong with a line, see ScenarioError.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScenarioProblem {
+    /// There are fewer than 2 or more than 4 words
+    Words,
+    /// The votes are not a number
+    Votes,
+    /// The last word is not candidate or voter
+    Role,
+}
+
+/// A line of a scenario which could not be parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScenarioError {
+    /// The line number, starting from 1
+    pub line: u64,
+    pub problem: ScenarioProblem,
+}
+impl fmt::Display for ScenarioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let problem = match self.problem {
+            ScenarioProblem::Words => "expected: name votes [vote_for] [candidate|voter]",
+            ScenarioProblem::Votes => "the number of votes is not a number",
+            ScenarioProblem::Role => "expected candidate or voter after vote_for",
+        };
+        write!(f, "line {}: {problem}", self.line)
+    }
+}
+impl std::error::Error for ScenarioError {}
+
+fn parse_line(line: &str) -> Result<Option<Vote<'static>>, ScenarioProblem> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    let words = line.split_whitespace().collect::<Vec<_>>();
+    let (name, votes, vote_for, role) = match words[..] {
+        [name, votes] => (name, votes, "-", "candidate"),
+        [name, votes, vote_for] => (name, votes, vote_for, "candidate"),
+        [name, votes, vote_for, role] => (name, votes, vote_for, role),
+        _ => return Err(ScenarioProblem::Words),
+    };
+    let number_of_votes = votes.parse().map_err(|_|ScenarioProblem::Votes)?;
+    let willing_candidate = match role {
+        "candidate" => true,
+        "voter" => false,
+        _ => return Err(ScenarioProblem::Role),
+    };
+    Ok(Some(Vote {
+        voter_id: Cow::Owned(name.to_owned()),
+        vote_for: (vote_for != "-").then(||Cow::Owned(vote_for.to_owned())),
+        number_of_votes,
+        willing_candidate,
+        metadata: Default::default(),
+        fallbacks: Vec::new(),
+        abstain: false,
+    }))
+}
+
+/// The ballots of a scenario, in the order of its lines
+pub fn parse(text: &str) -> Result<Vec<Vote<'static>>, ScenarioError> {
+    let mut out = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line_no = i as u64 + 1;
+        match parse_line(line) {
+            Ok(Some(v)) => out.push(v),
+            Ok(None) => {}
+            Err(problem) => return Err(ScenarioError { line: line_no, problem }),
+        }
+    }
+    Ok(out)
+}
+
+/// The ballots as a scenario, one line each, which parse() reads back as the same
+/// ballots as long as no ID is `-` or contains whitespace.
+pub fn to_text(votes: &[Vote<'_>]) -> String {
+    let mut out = String::new();
+    for v in votes {
+        let _ = write!(out, "{} {} {}", v.voter_id, v.number_of_votes, v.vote_for.as_deref().unwrap_or("-"));
+        out.push_str(if v.willing_candidate { "\n" } else { " voter\n" });
+    }
+    out
+}
@@ -0,0 +1,197 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! JSON Schema for the JSON forms of EventRecord and ElectionResult, as produced by
+//! record::json_introspector() and serde_json, so that consumers in other languages can
+//! generate types and validate streams. The schema `$id` carries the crate version, it
+//! describes exactly the records of this version.
+//!
+//! The schema is written alongside the types rather than derived from them, a test
+//! checks every kind of record against it.
+use serde_json::{json, Value};
+
+fn uint() -> Value {
+    json!({ "type": "integer", "minimum": 0 })
+}
+fn string() -> Value {
+    json!({ "type": "string" })
+}
+fn array(items: Value) -> Value {
+    json!({ "type": "array", "items": items })
+}
+fn pair(a: Value, b: Value) -> Value {
+    json!({ "type": "array", "prefixItems": [a, b], "items": false, "minItems": 2 })
+}
+fn nullable(v: Value) -> Value {
+    json!({ "anyOf": [v, { "type": "null" }] })
+}
+fn bytes() -> Value {
+    array(json!({ "type": "integer", "minimum": 0, "maximum": 255 }))
+}
+fn string_enum(names: &[&str]) -> Value {
+    json!({ "enum": names })
+}
+fn object(props: &[(&str, Value)]) -> Value {
+    let required = props.iter().map(|(k, _)|*k).collect::<Vec<_>>();
+    let properties = props.iter()
+        .map(|(k, v)|((*k).to_owned(), v.clone()))
+        .collect::<serde_json::Map<_, _>>();
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false,
+    })
+}
+fn id(name: &str) -> String {
+    format!("https://electorium/{}/{}.schema.json", env!("CARGO_PKG_VERSION"), name)
+}
+
+fn contributions() -> Value {
+    let from = array(pair(string(), uint()));
+    object(&[("own", uint()), ("supporters", from.clone()), ("through_ring", from)])
+}
+
+fn patron_outcome() -> Value {
+    let beaten = |name: &str| object(&[(name, pair(uint(), string()))]);
+    json!({ "oneOf": [
+        string_enum(&["LoopCandidate", "NotWillingCandidate", "PatronFound"]),
+        object(&[("NotProvidingMajority", uint())]),
+        beaten("NotBeatingSecondBest"),
+        beaten("NotBeatingSecondBestOutright"),
+    ] })
+}
+
+fn policy_action() -> Value {
+    let reason = || ("reason", string());
+    json!({ "oneOf": [
+        object(&[("Veto", object(&[reason()]))]),
+        object(&[("Redirect", object(&[("vote_for", string()), reason()]))]),
+        object(&[("Reweight", object(&[("number_of_votes", uint()), reason()]))]),
+        object(&[("Ineligible", object(&[reason()]))]),
+    ] })
+}
+
+fn event(name: &str, fields: &[(&str, Value)]) -> Value {
+    let mut props = vec![("event", json!({ "const": name }))];
+    props.extend(fields.iter().cloned());
+    object(&props)
+}
+
+/// The schema of one record as emitted by json_introspector(), the record kind is named
+/// by its "event" property.
+pub fn event_record_schema() -> Value {
+    let math = object(&[
+        ("tenative_winner_votes", uint()),
+        ("mark_to_beat", uint()),
+        ("runner_up_votes", nullable(uint())),
+    ]);
+    let events = vec![
+        event("VoteDelegation", &[
+            ("from", string()), ("to", string()), ("because_of", string()), ("votes", uint()),
+        ]),
+        event("VoteDelegationRing", &[
+            ("chain", array(string())), ("next", string()), ("from", string()),
+            ("votes", uint()), ("stopped_at", string()),
+        ]),
+        event("DeepDelegation", &[("from", string()), ("depth", uint()), ("chain", array(string()))]),
+        event("ImplicitDelegation", &[
+            ("from", string()), ("to", string()), ("because_of", string()), ("votes", uint()),
+        ]),
+        event("InvalidVote", &[
+            ("cause", string_enum(&["NoVote", "SelfVote", "UnrecognizedVote", "Duplicate"])),
+            ("voter_id", string()),
+            ("vote_for", nullable(string())),
+        ]),
+        event("Fallback", &[
+            ("voter_id", string()),
+            ("skipped", string()),
+            ("cause", string_enum(&["SelfVote", "Unrecognized", "Unwilling"])),
+            ("next", nullable(string())),
+        ]),
+        event("Abstention", &[("voter_id", string()), ("votes", uint())]),
+        event("BestRing", &[("rings", array(array(string()))), ("votes", uint())]),
+        event("BestOfRing", &[("scores", array(pair(string(), uint()))), ("winners", array(string()))]),
+        event("PatronSelection", &[
+            ("candidate", string()), ("votes", uint()), ("outcome", patron_outcome()), ("math", math),
+        ]),
+        event("TieBreakerHash", &[("candidate", string()), ("bytes", bytes()), ("votes", uint())]),
+        event("TieBreaker", &[("votes", uint()), ("tied", array(pair(string(), bytes())))]),
+        event("TieBreakSalt", &[("commitment", bytes()), ("salt", bytes())]),
+        event("Winner", &[
+            ("candidate", nullable(string())), ("votes", uint()), ("contributions", contributions()),
+        ]),
+        event("Seat", &[("seat", uint()), ("candidate", string()), ("votes", uint())]),
+        event("NoQuorum", &[
+            ("ballots", uint()), ("votes", uint()),
+            ("min_ballots", nullable(uint())), ("min_votes", nullable(uint())),
+        ]),
+        event("PolicyDecision", &[("voter_id", string()), ("action", policy_action())]),
+        event("AliasApplied", &[
+            ("voter_id", string()), ("alias", string()), ("resolved", string()), ("table", string()),
+        ]),
+        event("WeightsRescaled", &[("shift", uint())]),
+        event("Progress", &[
+            ("phase", string_enum(&["Ingestion", "Delegation", "Ranking"])),
+            ("processed", uint()),
+            ("total", uint()),
+            ("estimated_remaining_ms", nullable(uint())),
+        ]),
+    ];
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "$id": id("event-record"),
+        "title": "EventRecord",
+        "oneOf": events,
+    })
+}
+
+/// The schema of a serialized ElectionResult.
+pub fn election_result_schema() -> Value {
+    let options = object(&[
+        ("algorithm", string_enum(&["V2", "V3"])),
+        ("patron_rule", string_enum(&["Enabled", "Disabled", "Strict"])),
+        ("unrecognized_vote", string_enum(&["Discard", "ImplicitVoter", "Error"])),
+        ("duplicate_voter", string_enum(&["FirstWins", "LastWins", "MergeWeights", "Error"])),
+        ("overflow", string_enum(&["Error", "Rescale"])),
+        ("memory_limit", nullable(uint())),
+        ("max_id_len", nullable(uint())),
+        ("max_ballots", nullable(uint())),
+        ("registry_weight", nullable(uint())),
+        ("min_ballots", nullable(uint())),
+        ("min_votes", nullable(uint())),
+        ("tie_break_salt", nullable(bytes())),
+    ]);
+    let rules = object(&[
+        ("crate_version", string()),
+        ("options_version", uint()),
+        ("options", options),
+    ]);
+    let source = object(&[
+        ("source", string()), ("ballots", uint()), ("votes", uint()), ("to_winner", uint()),
+    ]);
+    let mut schema = object(&[
+        ("winner", nullable(string())),
+        ("votes", uint()),
+        ("contributions", contributions()),
+        ("winner_metadata", json!({ "type": "object", "additionalProperties": string() })),
+        ("weight_shift", uint()),
+        ("sources", array(source)),
+        ("engagement", object(&[
+            ("valid_ballots", uint()),
+            ("valid_votes", uint()),
+            ("delegating_ballots", uint()),
+            ("abstentions", uint()),
+            ("abstaining_votes", uint()),
+            ("candidates", uint()),
+            ("turnout_bp", nullable(uint())),
+            ("delegation_bp", uint()),
+            ("utilization_bp", uint()),
+        ])),
+        ("rules", rules),
+    ]);
+    if let Some(s) = schema.as_object_mut() {
+        s.insert("$schema".into(), json!("https://json-schema.org/draft/2020-12/schema"));
+        s.insert("$id".into(), json!(id("election-result")));
+        s.insert("title".into(), json!("ElectionResult"));
+    }
+    schema
+}
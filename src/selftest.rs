@@ -0,0 +1,154 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! A determinism self-test which operators can run before a count and attach to the
+//! minutes.
+//!
+//! The same ballots are counted several times, each time in a different order and with
+//! a different Parallelism, and every run must give the same ballots fingerprint and the
+//! same result. The shuffles are seeded from the round number so a report can be
+//! reproduced exactly on another machine. The `electorium selftest <file>` command runs
+//! this and prints the report.
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+use blake2::{Blake2b512, Digest};
+
+use crate::options::ElectionOptions;
+use crate::parallel::Parallelism;
+use crate::result::ElectionResult;
+use crate::signing;
+use crate::types::{Vote, TallyError};
+use crate::vote_like;
+use crate::VoteCounter;
+
+/// The Parallelism of each round, in turn
+const PARALLELISM: [Parallelism; 5] = [
+    Parallelism::Serial,
+    Parallelism::Threads(2),
+    Parallelism::Auto,
+    Parallelism::Threads(4),
+    Parallelism::Validate,
+];
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b|format!("{:02x}", b)).collect()
+}
+
+/// One count of the ballots, see run().
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelfTestRun {
+    /// The seed the ballots were shuffled with, 0 for the order they were given in
+    pub seed: u64,
+    pub parallelism: Parallelism,
+    /// See signing::ballots_fingerprint()
+    pub fingerprint: Vec<u8>,
+    pub result: Result<ElectionResult, TallyError>,
+    /// The blake2b hash of signing::signed_message() for the result, empty if the
+    /// count failed
+    pub digest: Vec<u8>,
+}
+
+/// Every run of a self-test, see run().
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelfTestReport {
+    pub ballots: usize,
+    pub runs: Vec<SelfTestRun>,
+}
+impl SelfTestReport {
+    /// Whether every run had the same fingerprint and the same result as the first
+    pub fn is_reproducible(&self) -> bool {
+        self.mismatches().is_empty()
+    }
+    /// The index of each run which differed from the first
+    pub fn mismatches(&self) -> Vec<usize> {
+        let Some(first) = self.runs.first() else {
+            return Vec::new();
+        };
+        self.runs.iter().enumerate().skip(1)
+            .filter(|(_, r)|r.fingerprint != first.fingerprint || r.result != first.result)
+            .map(|(i, _)|i)
+            .collect()
+    }
+}
+impl fmt::Display for SelfTestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "electorium {} determinism self-test", env!("CARGO_PKG_VERSION"))?;
+        writeln!(f, "ballots: {}", self.ballots)?;
+        if let Some(first) = self.runs.first() {
+            writeln!(f, "fingerprint: {}", hex(&first.fingerprint))?;
+        }
+        let mismatches = self.mismatches();
+        for (i, r) in self.runs.iter().enumerate() {
+            let outcome = match &r.result {
+                Ok(res) => format!("winner {} with {} votes", res.winner.as_deref().unwrap_or("(none)"), res.votes),
+                Err(e) => format!("error {e:?}"),
+            };
+            writeln!(f, "run {i}: seed {} {:?} {outcome} digest {}{}",
+                r.seed, r.parallelism, hex(&r.digest),
+                if mismatches.contains(&i) { " MISMATCH" } else { "" })?;
+        }
+        if mismatches.is_empty() {
+            write!(f, "REPRODUCIBLE: all {} runs are identical", self.runs.len())
+        } else {
+            write!(f, "NOT REPRODUCIBLE: {} of {} runs differ from run 0", mismatches.len(), self.runs.len())
+        }
+    }
+}
+
+/// Shuffle the ballots with a Fisher-Yates shuffle driven by blake2b of the seed and
+/// a counter, so that the same seed always gives the same order. The ballots of one
+/// voter keep their order: under DuplicatePolicy::FirstWins or LastWins it decides which
+/// of them counts, so it is part of the election rather than of the input order.
+fn shuffle<'a>(votes: &'a [Vote<'_>], seed: u64) -> Vec<Vote<'a>> {
+    let out = vote_like::borrow_all(votes);
+    if seed == 0 {
+        return out;
+    }
+    let mut order = (0..out.len()).collect::<Vec<_>>();
+    let mut block = [0_u8; 64];
+    for i in (1..order.len()).rev() {
+        let word = i % 8;
+        if word == 7 || i == order.len() - 1 {
+            let mut h = Blake2b512::new();
+            h.update(b"electorium selftest\0");
+            h.update(seed.to_be_bytes());
+            h.update((i as u64).to_be_bytes());
+            block.copy_from_slice(&h.finalize());
+        }
+        let mut b = [0_u8; 8];
+        b.copy_from_slice(&block[word * 8..word * 8 + 8]);
+        let j = (u64::from_be_bytes(b) % (i as u64 + 1)) as usize;
+        order.swap(i, j);
+    }
+    // Each voter's ballots take the places which the shuffle gave them, in their order
+    let mut by_voter: HashMap<&str, VecDeque<usize>> = HashMap::new();
+    for (i, v) in votes.iter().enumerate() {
+        by_voter.entry(&v.voter_id).or_default().push_back(i);
+    }
+    for i in &mut order {
+        if let Some(first) = by_voter.get_mut(&*votes[*i].voter_id).and_then(VecDeque::pop_front) {
+            *i = first;
+        }
+    }
+    let mut out = out.into_iter().map(Some).collect::<Vec<_>>();
+    order.into_iter().filter_map(|i|out[i].take()).collect()
+}
+
+/// Count the ballots rounds times, the first time in the order given and serially, then
+/// shuffled and with each Parallelism in turn.
+pub fn run(votes: &[Vote<'_>], options: &ElectionOptions, rounds: usize) -> SelfTestReport {
+    let runs = (0..rounds).map(|round| {
+        let seed = round as u64;
+        let parallelism = PARALLELISM[round % PARALLELISM.len()];
+        let ballots = shuffle(votes, seed);
+        let fingerprint = signing::ballots_fingerprint(&ballots);
+        let mut vc = VoteCounter::with_options(&ballots, Default::default(), options.clone());
+        vc.set_parallelism(parallelism);
+        let result = vc.tally();
+        let digest = match &result {
+            Ok(res) => Blake2b512::digest(signing::signed_message(res, &fingerprint)).to_vec(),
+            Err(_) => Vec::new(),
+        };
+        SelfTestRun { seed, parallelism, fingerprint, result, digest }
+    }).collect();
+    SelfTestReport { ballots: votes.len(), runs }
+}
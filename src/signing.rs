@@ -0,0 +1,287 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! Signing of election results by the tally operator.
+//!
+//! The signature covers a canonical encoding of every field of the ElectionResult and a fingerprint of
+//! the ballots it was counted from, so a signed result cannot be moved to other ballots
+//! or other rules. The signature scheme is supplied by the caller through ResultSigner
+//! and SignatureVerifier, with the ed25519 feature Ed25519Signer and Ed25519Verifier
+//! implement them with ed25519-dalek.
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use blake2::{Blake2b512, Digest};
+
+use crate::options::{
+    ElectionOptions, AlgorithmVersion, PatronRule, UnrecognizedVotePolicy, DuplicatePolicy, OverflowPolicy,
+};
+use crate::result::{ElectionResult, Contributions, Engagement, RuleSet, SourceTotals};
+use crate::types::{Vote, Weight, widen};
+
+const DOMAIN: &[u8] = b"electorium signed result v2\0";
+const FINGERPRINT_DOMAIN: &[u8] = b"electorium ballots v2\0";
+
+/// A signing key held by the tally operator.
+pub trait ResultSigner {
+    /// The public key which verifies signatures made by this signer
+    fn public_key(&self) -> Vec<u8>;
+    fn sign(&self, msg: &[u8]) -> Vec<u8>;
+}
+
+/// Verification for the signature scheme used by a ResultSigner.
+pub trait SignatureVerifier {
+    fn verify(&self, public_key: &[u8], msg: &[u8], signature: &[u8]) -> bool;
+}
+
+/// The blake2b hash of the ballots sorted by voter_id. Ballots of different voters may
+/// be given in any order, but those of one voter are hashed in the order given, since
+/// under DuplicatePolicy::FirstWins or LastWins that order decides which of them counts.
+/// Vote::metadata is not hashed, it does not affect the count.
+pub fn ballots_fingerprint(votes: &[Vote]) -> Vec<u8> {
+    let mut sorted = votes.iter().collect::<Vec<_>>();
+    // Stable, so the ballots of one voter keep their order
+    sorted.sort_by(|a, b|a.voter_id.cmp(&b.voter_id));
+    let mut out = Vec::from(FINGERPRINT_DOMAIN);
+    put_u64(&mut out, sorted.len() as u64);
+    for v in sorted {
+        put_bytes(&mut out, v.voter_id.as_bytes());
+        put_option(&mut out, &v.vote_for, |out, t|put_bytes(out, t.as_bytes()));
+        put_weight(&mut out, v.number_of_votes);
+        out.push(u8::from(v.willing_candidate) | u8::from(v.abstain) << 1);
+        put_u64(&mut out, v.fallbacks.len() as u64);
+        for f in &v.fallbacks {
+            put_bytes(&mut out, f.as_bytes());
+        }
+    }
+    Blake2b512::digest(out).to_vec()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SignedResult {
+    pub result: ElectionResult,
+    /// See ballots_fingerprint
+    pub fingerprint: Vec<u8>,
+    pub public_key: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+impl SignedResult {
+    /// Whether this result was counted from these ballots, this does not check the signature.
+    pub fn matches_ballots(&self, votes: &[Vote]) -> bool {
+        self.fingerprint == ballots_fingerprint(votes)
+    }
+}
+
+pub(crate) fn put_bytes(out: &mut Vec<u8>, b: &[u8]) {
+    out.extend_from_slice(&(b.len() as u64).to_be_bytes());
+    out.extend_from_slice(b);
+}
+
+pub(crate) fn put_u64(out: &mut Vec<u8>, n: u64) {
+    out.extend_from_slice(&n.to_be_bytes());
+}
+
+/// 8 bytes, as put_u64(), unless it is u64::MAX or more, then 8 bytes of 0xff followed by
+/// all 16 bytes of it, so a weight has the same encoding with or without wide-weights
+pub(crate) fn put_weight(out: &mut Vec<u8>, w: Weight) {
+    match u64::try_from(widen(w)) {
+        Ok(n) if n < u64::MAX => put_u64(out, n),
+        _ => {
+            put_u64(out, u64::MAX);
+            out.extend_from_slice(&widen(w).to_be_bytes());
+        }
+    }
+}
+
+fn put_option<T>(out: &mut Vec<u8>, o: &Option<T>, put: impl FnOnce(&mut Vec<u8>, &T)) {
+    match o {
+        Some(t) => {
+            out.push(1);
+            put(out, t);
+        }
+        None => out.push(0),
+    }
+}
+
+/// Each ID with its votes, as in Contributions
+fn put_id_votes(out: &mut Vec<u8>, v: &[(String, Weight)]) {
+    put_u64(out, v.len() as u64);
+    for (id, votes) in v {
+        put_bytes(out, id.as_bytes());
+        put_weight(out, *votes);
+    }
+}
+
+// The structs are taken apart without `..` here and in signed_message(), so that a field
+// which is added to them does not compile until it is signed.
+fn put_options(out: &mut Vec<u8>, o: &ElectionOptions) {
+    let ElectionOptions {
+        algorithm,
+        patron_rule,
+        unrecognized_vote,
+        duplicate_voter,
+        overflow,
+        memory_limit,
+        max_id_len,
+        max_ballots,
+        registry_weight,
+        min_ballots,
+        min_votes,
+        tie_break_salt,
+    } = o;
+    out.push(match algorithm {
+        AlgorithmVersion::V2 => 2,
+        AlgorithmVersion::V3 => 3,
+    });
+    out.push(match patron_rule {
+        PatronRule::Enabled => 0,
+        PatronRule::Disabled => 1,
+        PatronRule::Strict => 2,
+    });
+    out.push(match unrecognized_vote {
+        UnrecognizedVotePolicy::Discard => 0,
+        UnrecognizedVotePolicy::ImplicitVoter => 1,
+        UnrecognizedVotePolicy::Error => 2,
+    });
+    out.push(match duplicate_voter {
+        DuplicatePolicy::FirstWins => 0,
+        DuplicatePolicy::LastWins => 1,
+        DuplicatePolicy::MergeWeights => 2,
+        DuplicatePolicy::Error => 3,
+    });
+    out.push(match overflow {
+        OverflowPolicy::Error => 0,
+        OverflowPolicy::Rescale => 1,
+    });
+    let limits = [memory_limit, max_id_len, max_ballots].map(|l|l.map(|l|l as u64));
+    for limit in limits.iter().chain([min_ballots]) {
+        put_option(out, limit, |out, &l|put_u64(out, l));
+    }
+    for weight in [registry_weight, min_votes] {
+        put_option(out, weight, |out, &w|put_weight(out, w));
+    }
+    put_option(out, tie_break_salt, |out, salt|put_bytes(out, salt));
+}
+
+/// The exact bytes which are signed: every field of the result, the fingerprint of the
+/// ballots, and nothing else. Every field which varies in length is length-prefixed so
+/// that no two different results have the same encoding.
+pub fn signed_message(result: &ElectionResult, fingerprint: &[u8]) -> Vec<u8> {
+    let ElectionResult {
+        winner,
+        votes,
+        contributions,
+        winner_metadata,
+        weight_shift,
+        sources,
+        engagement,
+        rules,
+    } = result;
+    let mut out = Vec::from(DOMAIN);
+    put_option(&mut out, winner, |out, w|put_bytes(out, w.as_bytes()));
+    put_weight(&mut out, *votes);
+    out.extend_from_slice(&weight_shift.to_be_bytes());
+
+    let Contributions { own, supporters, through_ring } = contributions;
+    put_weight(&mut out, *own);
+    put_id_votes(&mut out, supporters);
+    put_id_votes(&mut out, through_ring);
+
+    // A BTreeMap, so in the order of the keys
+    put_u64(&mut out, winner_metadata.len() as u64);
+    for (key, value) in winner_metadata {
+        put_bytes(&mut out, key.as_bytes());
+        put_bytes(&mut out, value.as_bytes());
+    }
+
+    put_u64(&mut out, sources.len() as u64);
+    for SourceTotals { source, ballots, votes, to_winner } in sources {
+        put_bytes(&mut out, source.as_bytes());
+        put_u64(&mut out, *ballots);
+        put_weight(&mut out, *votes);
+        put_weight(&mut out, *to_winner);
+    }
+
+    let Engagement {
+        valid_ballots,
+        valid_votes,
+        delegating_ballots,
+        abstentions,
+        abstaining_votes,
+        candidates,
+        turnout_bp,
+        delegation_bp,
+        utilization_bp,
+    } = engagement;
+    put_u64(&mut out, *valid_ballots);
+    put_weight(&mut out, *valid_votes);
+    put_u64(&mut out, *delegating_ballots);
+    put_u64(&mut out, *abstentions);
+    put_weight(&mut out, *abstaining_votes);
+    put_u64(&mut out, *candidates);
+    put_option(&mut out, turnout_bp, |out, &bp|put_u64(out, bp));
+    put_u64(&mut out, *delegation_bp);
+    put_u64(&mut out, *utilization_bp);
+
+    let RuleSet { crate_version, options_version, options } = rules;
+    put_bytes(&mut out, crate_version.as_bytes());
+    out.extend_from_slice(&options_version.to_be_bytes());
+    put_options(&mut out, options);
+    put_bytes(&mut out, fingerprint);
+    out
+}
+
+/// Sign the result of counting votes.
+pub fn sign_result(result: ElectionResult, votes: &[Vote], signer: &dyn ResultSigner) -> SignedResult {
+    let fingerprint = ballots_fingerprint(votes);
+    let signature = signer.sign(&signed_message(&result, &fingerprint));
+    SignedResult { result, fingerprint, public_key: signer.public_key(), signature }
+}
+
+/// Check that a signed result was signed by the operator whose public key is
+/// trusted_key. The key carried in the SignedResult is not trusted, anyone can sign a
+/// forged result with a key of their own, it must be the same as trusted_key.
+pub fn verify_signed_result(
+    signed: &SignedResult,
+    trusted_key: &[u8],
+    verifier: &dyn SignatureVerifier,
+) -> bool {
+    if signed.public_key != trusted_key {
+        return false;
+    }
+    let msg = signed_message(&signed.result, &signed.fingerprint);
+    verifier.verify(trusted_key, &msg, &signed.signature)
+}
+
+/// An ed25519 signing key, the public key is the 32 byte verifying key.
+#[cfg(feature = "ed25519")]
+pub struct Ed25519Signer(pub ed25519_dalek::SigningKey);
+#[cfg(feature = "ed25519")]
+impl ResultSigner for Ed25519Signer {
+    fn public_key(&self) -> Vec<u8> {
+        self.0.verifying_key().to_bytes().to_vec()
+    }
+    fn sign(&self, msg: &[u8]) -> Vec<u8> {
+        use ed25519_dalek::Signer;
+        self.0.sign(msg).to_bytes().to_vec()
+    }
+}
+
+/// Verification of the signatures of an Ed25519Signer, a public key or signature of the
+/// wrong length does not verify.
+#[cfg(feature = "ed25519")]
+pub struct Ed25519Verifier;
+#[cfg(feature = "ed25519")]
+impl SignatureVerifier for Ed25519Verifier {
+    fn verify(&self, public_key: &[u8], msg: &[u8], signature: &[u8]) -> bool {
+        let Ok(key) = <[u8; 32]>::try_from(public_key) else {
+            return false;
+        };
+        let (Ok(key), Ok(signature)) = (
+            ed25519_dalek::VerifyingKey::from_bytes(&key),
+            ed25519_dalek::Signature::from_slice(signature),
+        ) else {
+            return false;
+        };
+        key.verify_strict(msg, &signature).is_ok()
+    }
+}
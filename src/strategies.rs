@@ -0,0 +1,220 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! Generators for realistic ballot sets, intended for property-testing code which
+//! integrates with electorium, enabled by the strategies feature.
+//!
+//! Every generator is a pure function of a `Seed`, so they can be plugged into any
+//! property-testing framework by mapping over a random `u64`, but shrinking a seed
+//! does not make the ballots any smaller. With the proptest feature, the `arb_*`
+//! functions are proptest `Strategy`s for the same shapes which shrink by dropping
+//! ballots, lowering weights and moving votes toward lower numbered candidates.
+use crate::types::{Vote, weight};
+
+/// A small deterministic PRNG (splitmix64) which drives the generators.
+#[derive(Debug, Clone)]
+pub struct Seed(u64);
+impl Seed {
+    pub fn new(seed: u64) -> Self {
+        Seed(seed)
+    }
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+    /// A number in the range 0..n, or 0 if n is 0
+    pub fn below(&mut self, n: u64) -> u64 {
+        if n == 0 {
+            0
+        } else {
+            self.next_u64() % n
+        }
+    }
+    /// A number in the range 0..=max
+    pub fn up_to(&mut self, max: u64) -> u64 {
+        match max.checked_add(1) {
+            Some(n) => self.below(n),
+            None => self.next_u64(),
+        }
+    }
+}
+
+/// The voter_id used for the nth generated candidate
+pub fn candidate_id(n: usize) -> String {
+    format!("c{n}")
+}
+
+/// The voter_id used for the nth generated non-candidate voter
+pub fn voter_id(n: usize) -> String {
+    format!("v{n}")
+}
+
+fn candidate(id: usize, vote_for: Option<String>, number_of_votes: u64) -> Vote<'static> {
+    Vote {
+        voter_id: candidate_id(id).into(),
+        vote_for: vote_for.map(Into::into),
+        number_of_votes: weight(number_of_votes),
+        willing_candidate: true,
+        metadata: Default::default(),
+        fallbacks: Vec::new(),
+        abstain: false,
+    }
+}
+
+fn voter(id: usize, vote_for: String, number_of_votes: u64) -> Vote<'static> {
+    Vote {
+        voter_id: voter_id(id).into(),
+        vote_for: Some(vote_for.into()),
+        number_of_votes: weight(number_of_votes),
+        willing_candidate: false,
+        metadata: Default::default(),
+        fallbacks: Vec::new(),
+        abstain: false,
+    }
+}
+
+/// Arbitrary ballots: candidates vote for a random candidate (possibly themselves,
+/// possibly nobody) and voters each vote for a random candidate with a weight in
+/// 1..=max_weight.
+pub fn ballots(s: &mut Seed, candidates: usize, voters: usize, max_weight: u64) -> Vec<Vote<'static>> {
+    let mut out = Vec::with_capacity(candidates + voters);
+    for c in 0..candidates {
+        // One in four candidates does not vote
+        let vote_for = if s.below(4) == 0 {
+            None
+        } else {
+            Some(candidate_id(s.below(candidates as u64) as usize))
+        };
+        out.push(candidate(c, vote_for, s.up_to(max_weight)));
+    }
+    for v in 0..voters {
+        let vote_for = candidate_id(s.below(candidates as u64) as usize);
+        out.push(voter(v, vote_for, 1 + s.below(max_weight)));
+    }
+    out
+}
+
+/// A delegation graph where every candidate's vote eventually reaches the same root
+/// candidate (c0), so the graph is a single tree with no rings.
+pub fn connected_graph(s: &mut Seed, candidates: usize, max_weight: u64) -> Vec<Vote<'static>> {
+    let mut out = Vec::with_capacity(candidates);
+    for c in 0..candidates {
+        // Only ever vote for a lower number, that way there cannot be a ring
+        let vote_for = if c == 0 {
+            None
+        } else {
+            Some(candidate_id(s.below(c as u64) as usize))
+        };
+        out.push(candidate(c, vote_for, s.up_to(max_weight)));
+    }
+    out
+}
+
+/// A ring of ring_len candidates who each vote for the next, with feeders additional
+/// candidates voting into random ring members.
+pub fn ring(s: &mut Seed, ring_len: usize, feeders: usize, max_weight: u64) -> Vec<Vote<'static>> {
+    let mut out = Vec::with_capacity(ring_len + feeders);
+    for c in 0..ring_len {
+        let vote_for = Some(candidate_id((c + 1) % ring_len));
+        out.push(candidate(c, vote_for, s.up_to(max_weight)));
+    }
+    for f in ring_len..(ring_len + feeders) {
+        // Feeders vote for a ring member or an earlier feeder, never forming a new ring
+        let vote_for = Some(candidate_id(s.below(f as u64) as usize));
+        out.push(candidate(f, vote_for, s.up_to(max_weight)));
+    }
+    out
+}
+
+/// Candidates who mostly do not vote, with weights which differ by at most `spread`
+/// from `base_weight` (but never more than u64::MAX), so that the winner is decided
+/// by small margins or ties.
+pub fn near_tie(s: &mut Seed, candidates: usize, base_weight: u64, spread: u64) -> Vec<Vote<'static>> {
+    let mut out = Vec::with_capacity(candidates);
+    for c in 0..candidates {
+        // One in eight delegates, which is enough to upset a near-tie
+        let vote_for = if s.below(8) == 0 {
+            Some(candidate_id(s.below(candidates as u64) as usize))
+        } else {
+            None
+        };
+        out.push(candidate(c, vote_for, base_weight.saturating_add(s.up_to(spread))));
+    }
+    out
+}
+
+#[cfg(feature = "proptest")]
+use proptest::{collection::vec, option, prelude::*, sample::Index};
+
+/// The proptest counterpart of `ballots`, with 1..=max_candidates candidates and
+/// up to max_voters voters.
+#[cfg(feature = "proptest")]
+pub fn arb_ballots(
+    max_candidates: usize,
+    max_voters: usize,
+    max_weight: u64,
+) -> impl Strategy<Value = Vec<Vote<'static>>> {
+    let candidates = vec((option::weighted(0.75, any::<Index>()), 0..=max_weight), 1..=max_candidates.max(1));
+    let voters = vec((any::<Index>(), 1..=max_weight.max(1)), 0..=max_voters);
+    (candidates, voters).prop_map(|(candidates, voters)| {
+        let n = candidates.len();
+        let mut out = Vec::with_capacity(n + voters.len());
+        for (c, (vote_for, w)) in candidates.into_iter().enumerate() {
+            out.push(candidate(c, vote_for.map(|i| candidate_id(i.index(n))), w));
+        }
+        for (v, (vote_for, w)) in voters.into_iter().enumerate() {
+            out.push(voter(v, candidate_id(vote_for.index(n)), w));
+        }
+        out
+    })
+}
+
+/// The proptest counterpart of `connected_graph`, with 1..=max_candidates candidates.
+#[cfg(feature = "proptest")]
+pub fn arb_connected_graph(max_candidates: usize, max_weight: u64) -> impl Strategy<Value = Vec<Vote<'static>>> {
+    vec((any::<Index>(), 0..=max_weight), 1..=max_candidates.max(1)).prop_map(|candidates| {
+        candidates
+            .into_iter()
+            .enumerate()
+            .map(|(c, (vote_for, w))| {
+                let vote_for = if c == 0 { None } else { Some(candidate_id(vote_for.index(c))) };
+                candidate(c, vote_for, w)
+            })
+            .collect()
+    })
+}
+
+/// The proptest counterpart of `ring`, with a ring of 2..=max_ring_len candidates
+/// and up to max_feeders feeders.
+#[cfg(feature = "proptest")]
+pub fn arb_ring(max_ring_len: usize, max_feeders: usize, max_weight: u64) -> impl Strategy<Value = Vec<Vote<'static>>> {
+    let ring = vec(0..=max_weight, 2..=max_ring_len.max(2));
+    let feeders = vec((any::<Index>(), 0..=max_weight), 0..=max_feeders);
+    (ring, feeders).prop_map(|(ring, feeders)| {
+        let ring_len = ring.len();
+        let mut out = Vec::with_capacity(ring_len + feeders.len());
+        for (c, w) in ring.into_iter().enumerate() {
+            out.push(candidate(c, Some(candidate_id((c + 1) % ring_len)), w));
+        }
+        for (f, (vote_for, w)) in (ring_len..).zip(feeders) {
+            out.push(candidate(f, Some(candidate_id(vote_for.index(f))), w));
+        }
+        out
+    })
+}
+
+/// The proptest counterpart of `near_tie`, with 1..=max_candidates candidates.
+#[cfg(feature = "proptest")]
+pub fn arb_near_tie(max_candidates: usize, base_weight: u64, spread: u64) -> impl Strategy<Value = Vec<Vote<'static>>> {
+    vec((option::weighted(0.125, any::<Index>()), 0..=spread), 1..=max_candidates.max(1)).prop_map(move |candidates| {
+        let n = candidates.len();
+        candidates
+            .into_iter()
+            .enumerate()
+            .map(|(c, (vote_for, w))| {
+                candidate(c, vote_for.map(|i| candidate_id(i.index(n))), base_weight.saturating_add(w))
+            })
+            .collect()
+    })
+}
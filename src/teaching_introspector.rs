@@ -0,0 +1,169 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! An introspector for the classroom, which narrates the count of a small election as
+//! a table of everyone's votes after each phase, rather than a line for every event.
+//! The output depends only on the ballots, so the same election always reads the same.
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use crate::introspector::{
+    Introspector,
+    VoteDelegation,
+    ImplicitDelegation,
+    InvalidVote,
+    InvalidVoteCause,
+    BestRing,
+    BestOfRing,
+    PatronSelection,
+    PatronSelectionReason,
+    DeterministicTieBreaker,
+    Winner,
+    Phase,
+    Progress,
+};
+use crate::types::{Vote, Weight};
+
+struct Teacher {
+    /// Each voter's own votes, in the order of the ballots
+    own: Vec<(String, Weight)>,
+    /// Votes received from others so far
+    received: BTreeMap<String, Weight>,
+    invalid: Vec<String>,
+    out: Box<dyn FnMut(&str)>,
+}
+impl Teacher {
+    fn say(&mut self, line: String) {
+        (self.out)(&line);
+    }
+    fn table(&mut self) {
+        let mut rows = self.own.iter()
+            .map(|(id, own)|{
+                let received = self.received.get(id).copied().unwrap_or(0);
+                (id.clone(), *own, received, own.saturating_add(received))
+            })
+            .collect::<Vec<_>>();
+        // Implicit voters have no ballot but can receive votes
+        for (id, received) in &self.received {
+            if !self.own.iter().any(|(o, _)|o == id) {
+                rows.push((id.clone(), 0, *received, *received));
+            }
+        }
+        rows.sort_by(|a, b|b.3.cmp(&a.3).then_with(||a.0.cmp(&b.0)));
+        let width = rows.iter().map(|r|r.0.len()).chain(["Voter".len()]).max().unwrap_or(0);
+        self.say(format!("    {:<width$}  {:>6}  {:>8}  {:>6}", "Voter", "Own", "Received", "Total"));
+        for (id, own, received, total) in rows {
+            self.say(format!("    {:<width$}  {:>6}  {:>8}  {:>6}", id, own, received, total));
+        }
+    }
+    fn phase(&mut self, phase: Phase, processed: u64, total: u64) {
+        if phase == Phase::Delegation && processed == 0 {
+            self.received.clear();
+        }
+        if processed != total {
+            return;
+        }
+        match phase {
+            Phase::Ingestion => {
+                self.say("Step 1: reading the ballots".into());
+                if self.invalid.is_empty() {
+                    self.say("    Every ballot votes for someone".into());
+                }
+                let invalid = std::mem::take(&mut self.invalid);
+                for line in invalid {
+                    self.say(format!("    {}", line));
+                }
+                self.say("    Everyone starts with their own votes:".into());
+                self.table();
+            }
+            Phase::Delegation => {
+                self.say("Step 2: everyone passes their votes along to who they voted for".into());
+                self.table();
+            }
+            Phase::Ranking => {
+                self.say("Step 3: choosing the winner".into());
+            }
+        }
+    }
+}
+
+/// An Introspector which narrates the count of votes to stdout, see module docs.
+pub fn new<'a>(votes: &'a [Vote<'a>]) -> Introspector<'a> {
+    with_output(votes, |line| println!("{}", line))
+}
+
+/// As new() but each line of the narration is passed to out.
+pub fn with_output<'a>(votes: &'a [Vote], out: impl FnMut(&str) + 'static) -> Introspector<'a> {
+    let mut own: Vec<(String, Weight)> = Vec::with_capacity(votes.len());
+    for v in votes {
+        if !own.iter().any(|(id, _)|*id == v.voter_id) {
+            own.push((v.voter_id.to_string(), v.number_of_votes));
+        }
+    }
+    let t = Rc::new(RefCell::new(Teacher {
+        own,
+        received: BTreeMap::new(),
+        invalid: Vec::new(),
+        out: Box::new(out),
+    }));
+    let mut is = Introspector::default();
+    is.subscribe(t.clone(), |t, e: &Progress| t.borrow_mut().phase(e.phase, e.processed, e.total));
+    is.subscribe(t.clone(), |t, e: &InvalidVote| {
+        let v = e.vote;
+        let why = match e.cause {
+            InvalidVoteCause::NoVote => "did not vote for anyone".to_owned(),
+            InvalidVoteCause::SelfVote => "voted for themselves".to_owned(),
+            InvalidVoteCause::UnrecognizedVote =>
+                format!("voted for {} who has no ballot", v.vote_for.as_deref().unwrap_or_default()),
+            InvalidVoteCause::Duplicate => "already has a ballot".to_owned(),
+        };
+        t.borrow_mut().invalid.push(format!("{} {}, so their ballot is not passed on", v.voter_id, why));
+    });
+    is.subscribe(t.clone(), |t, e: &VoteDelegation| {
+        *t.borrow_mut().received.entry(e.to.voter_id.to_string()).or_insert(0) += e.votes;
+    });
+    is.subscribe(t.clone(), |t, e: &ImplicitDelegation| {
+        *t.borrow_mut().received.entry(e.to.to_owned()).or_insert(0) += e.votes;
+    });
+    is.subscribe(t.clone(), |t, e: &BestRing| {
+        let names = e.best_rings_members.iter()
+            .map(|r|r.iter().map(|v|&*v.voter_id).collect::<Vec<_>>().join(" + "))
+            .collect::<Vec<_>>()
+            .join(", ");
+        t.borrow_mut().say(format!("    The most votes, {}, are held by: {}", e.best_total_delegated_votes, names));
+    });
+    is.subscribe(t.clone(), |t, e: &BestOfRing| {
+        if e.rings_member_scores.len() < 2 {
+            return;
+        }
+        let mut t = t.borrow_mut();
+        t.say("    They voted for each other, so they are compared without the votes they passed around:".into());
+        for (v, score) in &e.rings_member_scores {
+            t.say(format!("      {} has {}", v.voter_id, score));
+        }
+    });
+    is.subscribe(t.clone(), |t, e: &PatronSelection| {
+        let verdict = match &e.selection {
+            PatronSelectionReason::PatronFound => "Yes, they gave most of the votes",
+            PatronSelectionReason::NotProvidingMajority(_) => "No, they did not give most of the votes",
+            PatronSelectionReason::LoopCandidate => "No, they are in the same ring",
+            PatronSelectionReason::NotWillingCandidate => "No, they are not standing for election",
+            PatronSelectionReason::NotBeatingSecondBest(..) |
+            PatronSelectionReason::NotBeatingSecondBestOutright(..) => "No, they would not beat the runner-up",
+        };
+        t.borrow_mut().say(format!("    Could {} ({} votes) win instead? {}",
+            e.potential_patron.voter_id, e.potential_patron_votes, verdict));
+    });
+    is.subscribe(t.clone(), |t, e: &DeterministicTieBreaker| {
+        let order = e.tied_candidates.iter().map(|(v, _)|&*v.voter_id).collect::<Vec<_>>();
+        t.borrow_mut().say(format!("    {} are tied with {} votes, the tie-breaker draws {}",
+            order.join(" and "), e.votes, order.first().copied().unwrap_or("nobody")));
+    });
+    is.subscribe(t, |t, e: &Option<Winner>| {
+        let line = match e {
+            Some(w) => format!("The winner is {} with {} votes", w.candidate.voter_id, w.votes),
+            None => "There is no winner".to_owned(),
+        };
+        t.borrow_mut().say(line);
+    });
+    is
+}
@@ -1,11 +1,14 @@
 // SPDX-License-Identifier: MIT OR ISC
-use crate::{Vote, VoteCounter};
+use crate::{Vote, VoteCounter, ElectionOptions, PatronRule, AlgorithmVersion, Weight};
 
 #[derive(Default)]
 struct Votes {
-    v: Vec<Vote>,
+    v: Vec<Vote<'static>>,
     next_voter_id: u32,
     test_name: String,
+    options: ElectionOptions,
+    /// The number of times reset() was called, see check_case()
+    election: usize,
 }
 impl Votes {
     fn new(test_name: &str) -> Self {
@@ -16,30 +19,70 @@ impl Votes {
     }
     fn reset(&mut self) {
         self.v.clear();
+        self.election += 1;
     }
     fn candidate(&mut self, name: &str, vote_for: &str) {
         self.v.push(Vote{
-            voter_id: format!("{}/{}", self.test_name, name),
-            vote_for: format!("{}/{}", self.test_name, vote_for),
+            voter_id: format!("{}/{}", self.test_name, name).into(),
+            vote_for: Some(format!("{}/{}", self.test_name, vote_for).into()),
             number_of_votes: 1,
             willing_candidate: true,
+            metadata: Default::default(),
+            fallbacks: Vec::new(),
+            abstain: false,
         });
     }
+    fn unwilling(&mut self, name: &str, vote_for: &str) {
+        self.candidate(name, vote_for);
+        self.v.last_mut().unwrap().willing_candidate = false;
+    }
     fn voter(&mut self, vote_for: &str) {
         self.votes(vote_for, 1);
     }
-    fn votes(&mut self, vote_for: &str, num_votes: u64) {
+    fn votes(&mut self, vote_for: &str, num_votes: Weight) {
         self.v.push(Vote{
-            voter_id: format!("voter#{}", self.next_voter_id),
-            vote_for: format!("{}/{}", self.test_name, vote_for),
+            voter_id: format!("voter#{}", self.next_voter_id).into(),
+            vote_for: Some(format!("{}/{}", self.test_name, vote_for).into()),
             number_of_votes: num_votes,
             willing_candidate: false,
+            metadata: Default::default(),
+            fallbacks: Vec::new(),
+            abstain: false,
         });
         self.next_voter_id += 1;
     }
     fn expect_win(&self, winner: &str) {
+        self.check_case();
         self.check_winner(Some(winner), true);
     }
+    /// The ballots of the tests in CASES are the scenarios of the fuzz corpus, as .case
+    /// files in cases/ which fuzzable/src/corpus.rs compiles into seeds. They are written
+    /// when ELECTORIUM_UPDATE_CASES is set, otherwise they must be the same as the ballots.
+    /// The election after the nth reset() is in <test_name>.<n + 1>.case.
+    fn check_case(&self) {
+        if !CASES.contains(&&self.test_name[..]) {
+            return;
+        }
+        let prefix = format!("{}/", self.test_name);
+        let unprefixed = |id: &str|id.strip_prefix(&prefix).filter(|id|!id.is_empty()).unwrap_or(id).to_owned();
+        let ballots = self.v.iter().map(|v|Vote {
+            voter_id: unprefixed(&v.voter_id).into(),
+            vote_for: v.vote_for.as_deref().map(|t|unprefixed(t).into()),
+            ..v.to_owned_vote()
+        }).collect::<Vec<_>>();
+        let case = format!("# The ballots of {} in src/tests.rs\n{}",
+            self.test_name, crate::scenario::to_text(&ballots));
+        let file = match self.election {
+            0 => format!("{}.case", self.test_name),
+            n => format!("{}.{}.case", self.test_name, n + 1),
+        };
+        let path = format!("{}/cases/{file}", env!("CARGO_MANIFEST_DIR"));
+        if std::env::var_os("ELECTORIUM_UPDATE_CASES").is_some() {
+            std::fs::write(&path, &case).unwrap();
+        }
+        assert_eq!(std::fs::read_to_string(&path).unwrap_or_default(), case,
+            "{file} differs from the test, run the tests with ELECTORIUM_UPDATE_CASES=1 to update it");
+    }
     fn check_winner(&self, winner: Option<&str>, verbose: bool) {
         let is = if verbose {
             println!("Computing winner for: {}", self.test_name);
@@ -47,7 +90,7 @@ impl Votes {
         } else {
             crate::Introspector::default()
         };
-        let mut vc = VoteCounter::new(&self.v, is);
+        let mut vc = VoteCounter::with_options(&self.v, is, self.options.clone());
         if verbose {
             println!("Most possible votes per candidate:");
             for (votes, v) in vc.iter() {
@@ -84,11 +127,20 @@ impl Votes {
                 assert_eq!(winner, win.voter_id);
             }
         } else if let Some(winner) = winner {
-            assert!(winner == "");
+            assert!(winner.is_empty());
         }
     }
 }
 
+/// The tests whose ballots are written to cases/, see Votes::check_case()
+const CASES: [&str; 5] = [
+    "test_alice_alone",
+    "test_alice_bob_charlie",
+    "charlie_is_patron",
+    "ernist_is_patron",
+    "tennassee_capital_election",
+];
+
 #[test]
 fn test_noone() {
     let mut v = Votes::new("test_noone");
@@ -157,4 +209,3013 @@ fn tennassee_capital_election() {
     v.votes("Chattanooga", 17_000);
 
     v.expect_win("Nashville");
-}
\ No newline at end of file
+}
+#[test]
+fn generated_elections() {
+    use crate::strategies::{self, Seed};
+    for seed in 0..200 {
+        let mut s = Seed::new(seed);
+        for v in [
+            strategies::ballots(&mut s, 10, 30, 5),
+            strategies::connected_graph(&mut s, 12, 5),
+            strategies::ring(&mut s, 5, 6, 5),
+            strategies::near_tie(&mut s, 8, 10, 1),
+        ] {
+            let v = Votes { v, ..Default::default() };
+            v.check_winner(None, false);
+        }
+    }
+}
+
+#[test]
+fn generated_weights_do_not_overflow() {
+    use crate::strategies::{self, Seed};
+    let mut s = Seed::new(1);
+    for v in [
+        strategies::ballots(&mut s, 10, 30, u64::MAX),
+        strategies::connected_graph(&mut s, 12, u64::MAX),
+        strategies::ring(&mut s, 5, 6, u64::MAX),
+        strategies::near_tie(&mut s, 8, u64::MAX, u64::MAX),
+    ] {
+        assert!(!v.is_empty());
+    }
+    assert_eq!(Seed::new(1).up_to(u64::MAX), Seed::new(1).next_u64());
+}
+
+#[cfg(feature = "proptest")]
+#[test]
+fn proptest_strategies() {
+    use crate::strategies;
+    use proptest::strategy::{BoxedStrategy, Strategy};
+    use proptest::test_runner::{Config, RngAlgorithm, TestError, TestRng, TestRunner};
+    let runner = || {
+        let config = Config { failure_persistence: None, ..Config::default() };
+        TestRunner::new_with_rng(config, TestRng::deterministic_rng(RngAlgorithm::ChaCha))
+    };
+    let shapes: [BoxedStrategy<Vec<Vote<'static>>>; 4] = [
+        strategies::arb_ballots(10, 30, 5).boxed(),
+        strategies::arb_connected_graph(12, 5).boxed(),
+        strategies::arb_ring(5, 6, 5).boxed(),
+        strategies::arb_near_tie(8, 10, 1).boxed(),
+    ];
+    for shape in shapes {
+        runner()
+            .run(&shape, |v| {
+                let v = Votes { v, ..Default::default() };
+                v.check_winner(None, false);
+                Ok(())
+            })
+            .unwrap();
+    }
+    // A failure shrinks to the smallest ballots which still fail
+    let res = runner().run(&strategies::arb_ballots(10, 30, 5), |v| {
+        proptest::prop_assert!(v.iter().filter(|v| !v.willing_candidate).count() < 3);
+        Ok(())
+    });
+    let Err(TestError::Fail(_, v)) = res else { panic!("expected a failure, got {res:?}") };
+    let owned = v.iter().map(|v| (v.voter_id.as_ref(), v.vote_for.as_deref(), v.number_of_votes)).collect::<Vec<_>>();
+    assert_eq!(
+        owned,
+        [("c0", None, 0), ("v0", Some("c0"), 1), ("v1", Some("c0"), 1), ("v2", Some("c0"), 1)]
+    );
+}
+
+#[test]
+fn worst_case_shapes() {
+    use crate::worst_case;
+    let n = 300;
+    for (v, winner) in [
+        (worst_case::chain(n), Some(format!("c{}", n / 2))),
+        (worst_case::giant_ring(n), None),
+        (worst_case::star(n), Some("c0".to_owned())),
+        (worst_case::all_tied(n), None),
+    ] {
+        let v = Votes { v, ..Default::default() };
+        v.check_winner(None, false);
+        if let Some(winner) = winner {
+            let mut vc = VoteCounter::new(&v.v, crate::Introspector::default());
+            assert_eq!(vc.find_winner().map(|w|&*w.voter_id), Some(winner.as_str()));
+        }
+    }
+}
+
+#[test]
+fn rings_include_unwilling_members() {
+    // Alice and Bob are in one ring, but only through U1 and U2 who are not willing
+    let mut v = Votes::new("rings_unwilling");
+    v.candidate("Alice", "U1");
+    v.unwilling("U1", "Bob");
+    v.candidate("Bob", "U2");
+    v.unwilling("U2", "Alice");
+    v.candidate("Charlie", "Alice");
+    v.v[4].number_of_votes = 10;
+    v.candidate("Dave", "");
+    let vc = VoteCounter::new(&v.v, crate::Introspector::default());
+    let rings = vc.rings();
+    assert_eq!(rings.len(), 1);
+    let mut ring = rings[0].iter().map(|v|&*v.voter_id).collect::<Vec<_>>();
+    ring.sort();
+    assert_eq!(ring, ["rings_unwilling/Alice", "rings_unwilling/Bob", "rings_unwilling/U1", "rings_unwilling/U2"]);
+    // V2 takes Alice and Bob as two rings, so Alice, the better of them, wins without the
+    // patron rule. V3 takes them as one ring, and Charlie who gave Alice most of Alice's
+    // votes is the patron.
+    v.expect_win("Alice");
+    v.options.algorithm = AlgorithmVersion::V3;
+    v.expect_win("Charlie");
+}
+
+#[test]
+fn patron_rules() {
+    let mut v = Votes::new("patron_rules");
+    v.candidate("Alice", "Bob");
+    v.candidate("Bob", "Alice");
+    v.candidate("Charlie", "Alice");
+    v.candidate("Dave", "");
+    v.votes("Bob", 1);
+    v.votes("Charlie", 4);
+    v.votes("Dave", 1);
+    v.expect_win("Charlie");
+
+    // Charlie has 5 of Alice's 8 votes, but Dave's 2 plus Alice's other 3 would tie
+    v.options.patron_rule = PatronRule::Strict;
+    v.expect_win("Alice");
+
+    v.options.patron_rule = PatronRule::Disabled;
+    v.expect_win("Alice");
+
+    // Without Dave there is no runner-up and Charlie wins under Strict, although
+    // Charlie's 5 votes do not beat Alice's 8 outright, which only Disabled would give
+    let mut v = Votes::new("patron_rules_outright");
+    v.candidate("Alice", "Bob");
+    v.candidate("Bob", "Alice");
+    v.candidate("Charlie", "Alice");
+    v.votes("Bob", 1);
+    v.votes("Charlie", 4);
+    v.options.patron_rule = PatronRule::Strict;
+    v.expect_win("Charlie");
+    v.options.patron_rule = PatronRule::Disabled;
+    v.expect_win("Alice");
+}
+
+#[test]
+fn result_records_options() {
+    let mut v = Votes::new("result_records_options");
+    v.candidate("Alice", "Bob");
+    v.candidate("Bob", "Alice");
+    v.candidate("Charlie", "Alice");
+    v.votes("Bob", 1);
+    v.votes("Charlie", 4);
+    let options = ElectionOptions { patron_rule: PatronRule::Disabled, ..Default::default() };
+    let mut vc = VoteCounter::with_options(&v.v, crate::Introspector::default(), options.clone());
+    let res = vc.tally().unwrap();
+    assert_eq!(res.winner.as_deref(), Some("result_records_options/Alice"));
+    assert_eq!(res.votes, 8);
+    assert_eq!(res.rules.options, options);
+    assert_eq!(res.rules.options_version, crate::result::OPTIONS_VERSION);
+    assert_eq!(res.rules.crate_version, env!("CARGO_PKG_VERSION"));
+}
+
+#[test]
+fn algorithm_version_is_recorded() {
+    let mut v = Votes::new("algorithm_version_is_recorded");
+    v.candidate("Alice", "");
+    let options = ElectionOptions { algorithm: AlgorithmVersion::V2, ..Default::default() };
+    let mut vc = VoteCounter::with_options(&v.v, crate::Introspector::default(), options);
+    let res = vc.tally().unwrap();
+    assert_eq!(res.rules.options.algorithm, AlgorithmVersion::V2);
+}
+
+#[test]
+fn compare_patron_rules() {
+    let mut v = Votes::new("compare_patron_rules");
+    v.candidate("Alice", "Bob");
+    v.candidate("Bob", "Alice");
+    v.candidate("Charlie", "Alice");
+    v.votes("Bob", 1);
+    v.votes("Charlie", 4);
+    let disabled = ElectionOptions { patron_rule: PatronRule::Disabled, ..Default::default() };
+    let cmp = crate::compare::compare(&v.v, ElectionOptions::default(), disabled).unwrap();
+    assert!(cmp.winner_changed());
+    assert_eq!(cmp.a.winner.as_deref(), Some("compare_patron_rules/Charlie"));
+    assert_eq!(cmp.b.winner.as_deref(), Some("compare_patron_rules/Alice"));
+    assert!(cmp.ranking_changes.is_empty());
+    // Both agree on the best ring and the within-ring tie-break, then the patron differs
+    assert_eq!(cmp.first_divergence(), Some(2));
+}
+
+#[test]
+fn event_records() {
+    use crate::record::{self, EventRecord, PatronOutcome};
+    use std::{cell::RefCell, rc::Rc};
+    let mut v = Votes::new("event_records");
+    v.candidate("Alice", "Bob");
+    v.candidate("Bob", "Alice");
+    v.candidate("Charlie", "Alice");
+    v.votes("Bob", 1);
+    v.votes("Charlie", 4);
+    let records = Rc::new(RefCell::new(Vec::new()));
+    let mut is = crate::Introspector::default();
+    let out = records.clone();
+    record::subscribe_all(&mut is, move |r|out.borrow_mut().push(r));
+    VoteCounter::new(&v.v, is).find_winner();
+    let records = records.take();
+    assert!(records.contains(&EventRecord::PatronSelection {
+        candidate: "event_records/Charlie".into(),
+        votes: 5,
+        outcome: PatronOutcome::PatronFound,
+        math: crate::introspector::PatronMath {
+            tenative_winner_votes: 8,
+            mark_to_beat: 4,
+            runner_up_votes: None,
+        },
+    }));
+    assert_eq!(records.last(), Some(&EventRecord::Winner {
+        candidate: Some("event_records/Charlie".into()),
+        votes: 5,
+        contributions: crate::result::Contributions {
+            own: 1,
+            supporters: vec![("voter#1".into(), 4)],
+            through_ring: Vec::new(),
+        },
+    }));
+}
+
+#[test]
+fn archive_round_trip() {
+    use crate::archive::{self, ArchiveError};
+    use crate::strategies::{self, Seed};
+    let canonical = |v: &[Vote]| {
+        let mut v = v.iter()
+            .map(|v|(v.voter_id.to_string(), v.vote_for.as_deref().map(str::to_owned), v.number_of_votes, v.willing_candidate))
+            .collect::<Vec<_>>();
+        v.sort();
+        v
+    };
+    for seed in 0..50 {
+        let mut s = Seed::new(seed);
+        let mut v = strategies::ballots(&mut s, 10, 30, 1000);
+        let data = archive::compress(&v);
+        assert_eq!(canonical(&archive::decompress(&data).unwrap()), canonical(&v));
+        v.reverse();
+        assert_eq!(archive::compress(&v), data);
+        assert_eq!(archive::decompress(&data[..data.len() - 1]), Err(ArchiveError::Truncated));
+    }
+    assert_eq!(archive::decompress(b"nope"), Err(ArchiveError::BadMagic));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn audit_log() {
+    use crate::audit::{AuditLog, audit_log_rules, verify_audit_log};
+    use crate::introspector::Introspector;
+    use crate::RuleSet;
+    let path = std::env::temp_dir().join(format!("electorium-audit-{}.jsonl", std::process::id()));
+    let mut v = Votes::new("audit_log");
+    v.candidate("Alice", "");
+    v.candidate("Bob", "Alice");
+    v.votes("Bob", 3);
+    let rules = RuleSet::new(ElectionOptions { patron_rule: PatronRule::Strict, ..Default::default() });
+    let log = AuditLog::create(&path, &rules, 2).unwrap();
+    let mut is = Introspector::default();
+    log.subscribe(&mut is);
+    VoteCounter::with_options(&v.v, is, rules.options.clone()).find_winner();
+    log.finalize().unwrap();
+    assert!(verify_audit_log(&path).unwrap());
+    assert_eq!(audit_log_rules(&path).unwrap(), rules);
+
+    let text = std::fs::read_to_string(&path).unwrap();
+    std::fs::write(&path, text.replacen("Bob", "Eve", 1)).unwrap();
+    assert!(!verify_audit_log(&path).unwrap());
+
+    // The log cannot be passed off as a count under other rules
+    let (header, rest) = text.split_once('\n').unwrap();
+    assert!(header.contains("\"Strict\""));
+    std::fs::write(&path, format!("{}\n{rest}", header.replacen("\"Strict\"", "\"Disabled\"", 1))).unwrap();
+    assert!(audit_log_rules(&path).is_ok());
+    assert!(!verify_audit_log(&path).unwrap());
+    std::fs::write(&path, rest).unwrap();
+    assert!(!verify_audit_log(&path).unwrap());
+    std::fs::remove_file(&path).unwrap();
+}
+
+/// Not a real signature scheme, the "public key" is the secret
+struct Mac(Vec<u8>);
+fn mac(key: &[u8], msg: &[u8]) -> Vec<u8> {
+    use blake2::{Blake2b512, Digest};
+    Blake2b512::new().chain_update(key).chain_update(msg).finalize().to_vec()
+}
+impl crate::signing::ResultSigner for Mac {
+    fn public_key(&self) -> Vec<u8> { self.0.clone() }
+    fn sign(&self, msg: &[u8]) -> Vec<u8> { mac(&self.0, msg) }
+}
+impl crate::signing::SignatureVerifier for Mac {
+    fn verify(&self, public_key: &[u8], msg: &[u8], signature: &[u8]) -> bool {
+        mac(public_key, msg) == signature
+    }
+}
+
+#[test]
+fn signed_results() {
+    use crate::signing;
+    let mut v = Votes::new("signed_results");
+    v.candidate("Alice", "");
+    v.candidate("Bob", "Alice");
+    v.votes("Bob", 3);
+    let result = VoteCounter::new(&v.v, Default::default()).tally().unwrap();
+    let key = Mac(b"operator".to_vec());
+    let signed = signing::sign_result(result, &v.v, &key);
+    assert!(signing::verify_signed_result(&signed, b"operator", &key));
+    assert!(signed.matches_ballots(&v.v));
+
+    // A forgery signed with another key, which it carries, is not from the operator
+    let mallory = Mac(b"mallory".to_vec());
+    let mut forged = signed.result.clone();
+    forged.votes += 1;
+    let forged = signing::sign_result(forged, &v.v, &mallory);
+    assert!(!signing::verify_signed_result(&forged, b"operator", &key));
+
+    let mut forged = signed.clone();
+    forged.result.votes += 1;
+    assert!(!signing::verify_signed_result(&forged, b"operator", &key));
+    // The votes are in units of 2^weight_shift, so the shift is signed too
+    let mut forged = signed.clone();
+    forged.result.weight_shift = 1;
+    assert!(!signing::verify_signed_result(&forged, b"operator", &key));
+    // So is every other field
+    let forgeries: [fn(&mut crate::ElectionResult); 4] = [
+        |r|r.contributions.supporters[0].1 += 1,
+        |r|r.sources.push(crate::SourceTotals { source: "paper".into(), ballots: 1, votes: 1, to_winner: 0 }),
+        |r|r.engagement.abstentions += 1,
+        |r|{ r.winner_metadata.insert("name".into(), "Mallory".into()); },
+    ];
+    for forge in forgeries {
+        let mut forged = signed.clone();
+        forge(&mut forged.result);
+        assert!(!signing::verify_signed_result(&forged, b"operator", &key));
+    }
+    v.votes("Alice", 1);
+    assert!(!signed.matches_ballots(&v.v));
+
+    // Under FirstWins the order of one voter's ballots decides the count, so it is
+    // part of the fingerprint, the order of different voters is not
+    let copy = || v.v.iter().map(Vote::to_owned_vote).collect::<Vec<_>>();
+    let mut reordered = copy();
+    reordered.reverse();
+    assert_eq!(signing::ballots_fingerprint(&reordered), signing::ballots_fingerprint(&v.v));
+    let mut dup = copy();
+    dup.push(Vote { vote_for: None, ..v.v[1].to_owned_vote() });
+    let mut swapped = dup.iter().map(Vote::to_owned_vote).collect::<Vec<_>>();
+    let last = swapped.len() - 1;
+    swapped.swap(1, last);
+    assert_ne!(signing::ballots_fingerprint(&swapped), signing::ballots_fingerprint(&dup));
+}
+
+#[cfg(feature = "ed25519")]
+#[test]
+fn ed25519_signed_results() {
+    use crate::signing::{self, Ed25519Signer, Ed25519Verifier, ResultSigner};
+    let mut v = Votes::new("ed25519_signed_results");
+    v.candidate("Alice", "");
+    v.candidate("Bob", "Alice");
+    let result = VoteCounter::new(&v.v, Default::default()).tally().unwrap();
+    let operator = Ed25519Signer(ed25519_dalek::SigningKey::from_bytes(&[7; 32]));
+    let mallory = Ed25519Signer(ed25519_dalek::SigningKey::from_bytes(&[9; 32]));
+    let trusted = operator.public_key();
+    let signed = signing::sign_result(result, &v.v, &operator);
+    assert!(signing::verify_signed_result(&signed, &trusted, &Ed25519Verifier));
+
+    let mut forged = signed.clone();
+    forged.result.votes += 1;
+    assert!(!signing::verify_signed_result(&forged, &trusted, &Ed25519Verifier));
+    let forged = signing::sign_result(forged.result, &v.v, &mallory);
+    assert!(signing::verify_signed_result(&forged, &mallory.public_key(), &Ed25519Verifier));
+    assert!(!signing::verify_signed_result(&forged, &trusted, &Ed25519Verifier));
+    let mut truncated = signed.clone();
+    truncated.signature.pop();
+    assert!(!signing::verify_signed_result(&truncated, &trusted, &Ed25519Verifier));
+}
+
+#[test]
+fn threshold_attestation() {
+    use crate::attestation::{verify_threshold, AttestationError};
+    use crate::signing;
+    let mut v = Votes::new("threshold_attestation");
+    v.candidate("Alice", "");
+    v.candidate("Bob", "");
+    v.votes("Bob", 3);
+    let keys = (0..3).map(|i|Mac(vec![i])).collect::<Vec<_>>();
+    let operators = keys.iter().map(|k|k.0.clone()).collect::<Vec<_>>();
+    let honest = VoteCounter::new(&v.v, Default::default()).tally().unwrap();
+    let mut wrong = honest.clone();
+    wrong.winner = Some("threshold_attestation/Alice".into());
+    let attestations = vec![
+        signing::sign_result(honest.clone(), &v.v, &keys[0]),
+        signing::sign_result(honest.clone(), &v.v, &keys[0]),
+        signing::sign_result(wrong, &v.v, &keys[1]),
+        signing::sign_result(honest.clone(), &v.v, &keys[2]),
+        signing::sign_result(honest.clone(), &v.v, &Mac(vec![9])),
+    ];
+    let agreed = verify_threshold(&attestations, &operators, 2, &keys[0]).unwrap();
+    assert_eq!(agreed.result, honest);
+    assert_eq!(agreed.operators.len(), 2);
+    assert_eq!(
+        verify_threshold(&attestations, &operators, 3, &keys[0]),
+        Err(AttestationError::NotEnough { best: 2, threshold: 3 }));
+}
+
+#[test]
+fn progress_events() {
+    use crate::introspector::{Introspector, Phase};
+    use crate::record::{self, EventRecord};
+    use crate::strategies::{self, Seed};
+    use std::{cell::RefCell, rc::Rc};
+    let v = strategies::ballots(&mut Seed::new(1), 100, 5000, 5);
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let mut is = Introspector::default();
+    is.set_progress_interval(std::time::Duration::ZERO);
+    let out = events.clone();
+    record::subscribe_progress(&mut is, move |r| out.borrow_mut().push(r));
+    VoteCounter::new(&v, is).find_winner();
+    let events = events.take();
+    for phase in [Phase::Ingestion, Phase::Delegation, Phase::Ranking] {
+        let processed = events.iter().filter_map(|e| match e {
+            EventRecord::Progress { phase: p, processed, total, .. } if *p == phase =>
+                Some((*processed, *total)),
+            _ => None,
+        }).collect::<Vec<_>>();
+        assert!(processed.len() >= 2, "{:?}", phase);
+        assert_eq!(processed[0].0, 0);
+        let &(last, total) = processed.last().unwrap();
+        assert_eq!(last, total);
+        assert!(processed.windows(2).all(|w|w[0].0 < w[1].0));
+    }
+}
+
+#[test]
+fn winner_contributions() {
+    use crate::strategies::{self, Seed};
+    let mut v = Votes::new("winner_contributions");
+    v.candidate("Alice", "Bob");
+    v.candidate("Bob", "Alice");
+    v.candidate("Charlie", "Alice");
+    v.votes("Alice", 2);
+    v.votes("Bob", 3);
+    v.votes("Charlie", 4);
+    let result = VoteCounter::new(&v.v, Default::default()).tally().unwrap();
+    let c = &result.contributions;
+    assert_eq!(c.own, 1);
+    assert_eq!(c.supporters, vec![
+        ("winner_contributions/Charlie".to_owned(), 5),
+        ("voter#0".to_owned(), 2),
+    ]);
+    assert_eq!(c.through_ring, vec![("winner_contributions/Bob".to_owned(), 4)]);
+
+    // The parts always add up to the total
+    for seed in 0..200 {
+        let mut s = Seed::new(seed);
+        for v in [strategies::ballots(&mut s, 10, 30, 5), strategies::ring(&mut s, 5, 6, 5)] {
+            let r = VoteCounter::new(&v, Default::default()).tally().unwrap();
+            let c = &r.contributions;
+            let parts = c.own + c.supporters.iter().chain(&c.through_ring).map(|s|s.1).sum::<Weight>();
+            assert_eq!(parts, if r.winner.is_some() { r.votes } else { 0 });
+        }
+    }
+}
+
+#[test]
+fn events_without_subscribers_are_not_built() {
+    use crate::introspector::{Introspector, Progress, BestRing};
+    let mut is = Introspector::default();
+    assert!(!is.is_subscribed::<BestRing>());
+    is.event(|| -> BestRing { panic!("event built without a subscriber") });
+    is.subscribe((), |(), _: &Progress| {});
+    assert!(is.is_subscribed::<Progress>());
+    assert!(!is.is_subscribed::<BestRing>());
+}
+
+#[test]
+fn unrecognized_vote_policy() {
+    use crate::{TallyError, UnrecognizedVotePolicy};
+    let mut v = Votes::new("unrecognized_vote_policy");
+    v.v.push(Vote {
+        voter_id: "unrecognized_vote_policy/Alice".into(),
+        vote_for: None,
+        number_of_votes: 1,
+        willing_candidate: true,
+        metadata: Default::default(),
+        fallbacks: Vec::new(),
+        abstain: false,
+    });
+    v.candidate("Bob", "Ghost");
+    v.votes("Alice", 4);
+    v.votes("Bob", 2);
+    v.votes("Ghost", 3);
+    let count = |policy| {
+        let options = ElectionOptions { unrecognized_vote: policy, ..Default::default() };
+        let mut vc = VoteCounter::with_options(&v.v, Default::default(), options);
+        (vc.tally(), vc.implicit_voters().iter().map(|(id, n)|(id.to_string(), *n)).collect::<Vec<_>>())
+    };
+    let (result, implicit) = count(UnrecognizedVotePolicy::Discard);
+    assert_eq!(result.unwrap().winner.as_deref(), Some("unrecognized_vote_policy/Alice"));
+    assert!(implicit.is_empty());
+
+    let (result, implicit) = count(UnrecognizedVotePolicy::ImplicitVoter);
+    assert_eq!(result.unwrap().winner.as_deref(), Some("unrecognized_vote_policy/Alice"));
+    assert_eq!(implicit, vec![("unrecognized_vote_policy/Ghost".to_owned(), 6)]);
+
+    let (result, _) = count(UnrecognizedVotePolicy::Error);
+    assert_eq!(result, Err(TallyError::UnrecognizedVote(
+        "unrecognized_vote_policy/Bob".into(), "unrecognized_vote_policy/Ghost".into())));
+}
+
+#[test]
+fn implicit_ballots() {
+    use crate::ingest;
+    let mut v = Votes::new("implicit_ballots");
+    v.candidate("Alice", "Bob");
+    v.votes("Alice", 2);
+    v.votes("Charlie", 1);
+    let mut votes = v.v;
+    votes.push(Vote {
+        voter_id: "implicit_ballots/Charlie".into(),
+        vote_for: None,
+        number_of_votes: 1,
+        willing_candidate: true,
+        metadata: Default::default(),
+        fallbacks: Vec::new(),
+        abstain: false,
+    });
+    let implicit = ingest::implicit_ballots(&votes);
+    assert_eq!(implicit.iter().map(|v|&*v.voter_id).collect::<Vec<_>>(), vec!["implicit_ballots/Bob"]);
+    votes.extend(implicit);
+    assert!(ingest::implicit_ballots(&votes).is_empty());
+    let counts = |votes: &[Vote]| {
+        let is = crate::Introspector::default();
+        let mut vc = VoteCounter::new(votes, is);
+        let winner = vc.find_winner().map(|w|w.voter_id.to_string());
+        (winner, vc.iter().map(|(n, v)|(n, v.voter_id.to_string())).collect::<Vec<_>>())
+    };
+    // Bob is not willing so the result is the same, but now Bob's ballot can be
+    // replaced when it arrives
+    let (winner, _) = counts(&votes);
+    assert_eq!(winner.as_deref(), Some("implicit_ballots/Alice"));
+    let bob = votes.len() - 1;
+    votes[bob].vote_for = Some("implicit_ballots/Charlie".into());
+    let (winner, ranking) = counts(&votes);
+    assert_eq!(winner.as_deref(), Some("implicit_ballots/Charlie"));
+    assert_eq!(ranking[0], (5, "implicit_ballots/Charlie".to_owned()));
+}
+
+#[test]
+fn ring_records_where_votes_stopped() {
+    use crate::record::{self, EventRecord};
+    use std::{cell::RefCell, rc::Rc};
+    let mut v = Votes::new("ring_stop");
+    v.candidate("Alice", "Bob");
+    v.candidate("Bob", "Alice");
+    v.votes("Alice", 3);
+    let records = Rc::new(RefCell::new(Vec::new()));
+    let mut is = crate::Introspector::default();
+    let out = records.clone();
+    record::subscribe_all(&mut is, move |r|out.borrow_mut().push(r));
+    VoteCounter::new(&v.v, is).find_winner();
+    assert!(records.borrow().contains(&EventRecord::VoteDelegationRing {
+        chain: vec!["voter#0".into(), "ring_stop/Alice".into(), "ring_stop/Bob".into()],
+        next: "ring_stop/Alice".into(),
+        from: "voter#0".into(),
+        votes: 3,
+        stopped_at: "ring_stop/Bob".into(),
+    }));
+}
+
+#[test]
+fn invalid_votes_are_listed() {
+    use crate::introspector::InvalidVoteCause;
+    let mut v = Votes::new("invalid_votes");
+    v.candidate("Alice", "Alice");
+    v.candidate("Bob", "Nobody");
+    v.candidate("Bob", "Alice");
+    let vc = VoteCounter::new(&v.v, Default::default());
+    let invalid = vc.invalid_votes().iter()
+        .map(|(cause, v)|(cause.clone(), &*v.voter_id))
+        .collect::<Vec<_>>();
+    assert_eq!(invalid, vec![
+        (InvalidVoteCause::Duplicate, "invalid_votes/Bob"),
+        (InvalidVoteCause::SelfVote, "invalid_votes/Alice"),
+        (InvalidVoteCause::UnrecognizedVote, "invalid_votes/Bob"),
+    ]);
+}
+
+#[test]
+fn subscribe_after_construction() {
+    use crate::introspector::Introspector;
+    use crate::record::{self, EventRecord};
+    use crate::strategies::{self, Seed};
+    use std::{cell::RefCell, rc::Rc};
+    let collect = |records: &Rc<RefCell<Vec<EventRecord>>>| {
+        let mut is = Introspector::default();
+        let out = records.clone();
+        record::subscribe_all(&mut is, move |r|out.borrow_mut().push(r));
+        is
+    };
+    let v = strategies::ring(&mut Seed::new(3), 5, 6, 5);
+    let (early, late) = (Rc::new(RefCell::new(Vec::new())), Rc::new(RefCell::new(Vec::new())));
+    VoteCounter::new(&v, collect(&early)).find_winner();
+    let mut vc = VoteCounter::new(&v, Introspector::default());
+    vc.replay(collect(&late));
+    vc.find_winner();
+    assert!(!late.borrow().is_empty());
+    assert_eq!(early.take(), late.take());
+}
+
+#[test]
+fn contexts_can_be_taken_back() {
+    use crate::introspector::{Introspector, VoteDelegation, InvalidVote};
+    #[derive(Default, Debug, PartialEq)]
+    struct Metrics {
+        delegations: usize,
+        invalid: usize,
+    }
+    let mut v = Votes::new("contexts");
+    v.candidate("Alice", "Bob");
+    v.candidate("Bob", "");
+    v.votes("Alice", 3);
+    let mut is = Introspector::default();
+    is.subscribe(Metrics::default(), |m, _: &VoteDelegation| m.delegations += 1);
+    is.subscribe(Metrics::default(), |m, _: &InvalidVote| m.invalid += 1);
+    let mut vc = VoteCounter::new(&v.v, is);
+    vc.find_winner();
+    assert_eq!(vc.introspector_mut().contexts_mut::<Metrics>().len(), 2);
+    let metrics = vc.into_introspector().into_contexts::<Metrics>();
+    assert_eq!(metrics, vec![
+        Metrics { delegations: 3, invalid: 0 },
+        Metrics { delegations: 0, invalid: 1 },
+    ]);
+}
+
+#[test]
+fn ranking_pages() {
+    use crate::strategies::{self, Seed};
+    let v = strategies::ballots(&mut Seed::new(7), 40, 100, 5);
+    let vc = VoteCounter::new(&v, Default::default());
+    let all = vc.iter().collect::<Vec<_>>();
+    let mut paged = Vec::new();
+    for offset in (0..all.len() + 7).step_by(7) {
+        let page = vc.ranking_page(offset, 7);
+        assert_eq!(page.total, all.len());
+        paged.extend(page.entries);
+    }
+    assert_eq!(paged, all);
+}
+
+#[test]
+fn find_candidates() {
+    let mut v = Votes::new("find");
+    v.candidate("Alice", "Bob");
+    v.candidate("Bob", "Alice");
+    v.candidate("Albert", "Alice");
+    v.votes("Albert", 2);
+    let vc = VoteCounter::new(&v.v, Default::default());
+    let found = vc.search_prefix("find/Al");
+    let found = found.iter()
+        .map(|i|(&*i.vote.voter_id, i.total_votes, i.rank, i.in_ring))
+        .collect::<Vec<_>>();
+    assert_eq!(found, vec![("find/Alice", 5, Some(1), true), ("find/Albert", 3, Some(2), false)]);
+    let voters = vc.find(|i|i.rank.is_none());
+    assert_eq!(voters.len(), 1);
+    assert_eq!(voters[0].vote_for.map(|v|&*v.voter_id), Some("find/Albert"));
+}
+
+#[test]
+fn deep_delegation_events() {
+    use crate::introspector::{Introspector, DeepDelegation};
+    let v = crate::worst_case::chain(30);
+    let mut is = Introspector::default();
+    is.set_deep_delegation_depth(25);
+    is.subscribe(Vec::new(), |deep: &mut Vec<(String, usize)>, e: &DeepDelegation| {
+        assert_eq!(e.chain.len(), e.depth + 1);
+        deep.push((e.from.voter_id.to_string(), e.depth));
+    });
+    let deep = VoteCounter::new(&v, is).into_introspector().into_contexts::<Vec<(String, usize)>>();
+    assert_eq!(deep, vec![vec![
+        ("c0".to_owned(), 29),
+        ("c1".to_owned(), 28),
+        ("c2".to_owned(), 27),
+        ("c3".to_owned(), 26),
+    ]]);
+}
+
+#[test]
+fn totals_by_source() {
+    use crate::SourceTotals;
+    use crate::strategies::{self, Seed};
+    let mut v = Votes::new("sources");
+    v.candidate("Alice", "");
+    v.candidate("Bob", "Alice");
+    v.votes("Alice", 2);
+    v.votes("Bob", 3);
+    v.votes("Carol", 4);
+    // Candidates voted online and the other voters on paper
+    let source = |v: &Vote| if v.willing_candidate { "online" } else { "paper" };
+    let result = VoteCounter::new(&v.v, Default::default()).tally_by_source(source).unwrap();
+    // Bob wins as Alice's patron, with his own vote and the 3 from paper
+    assert_eq!(result.winner.as_deref(), Some("sources/Bob"));
+    assert_eq!(result.sources, vec![
+        SourceTotals { source: "online".into(), ballots: 2, votes: 2, to_winner: 1 },
+        SourceTotals { source: "paper".into(), ballots: 3, votes: 9, to_winner: 3 },
+    ]);
+    for seed in 0..100 {
+        let v = strategies::ballots(&mut Seed::new(seed), 10, 30, 5);
+        let source = |v: &Vote| if v.voter_id.len().is_multiple_of(2) { "even" } else { "odd" };
+        let r = VoteCounter::new(&v, Default::default()).tally_by_source(source).unwrap();
+        assert_eq!(r.sources.iter().map(|s|s.to_winner).sum::<Weight>(), r.votes);
+    }
+}
+
+#[test]
+fn results_by_group() {
+    use crate::groups::tally_by_group;
+    use crate::strategies::{self, Seed};
+    let copy = |v: &Vote| v.to_owned_vote();
+    for seed in 0..50 {
+        let v = strategies::ballots(&mut Seed::new(seed), 10, 30, 5);
+        let group_of = |v: &Vote| match v.voter_id.len() % 3 {
+            0 => None,
+            1 => Some("north"),
+            _ => Some("south"),
+        };
+        let grouped = tally_by_group(&v, Default::default(), group_of).unwrap();
+        assert_eq!(grouped.overall, VoteCounter::new(&v, Default::default()).tally().unwrap());
+        for g in &grouped.groups {
+            let members = v.iter().filter(|v|group_of(v) == Some(&g.group)).map(copy).collect::<Vec<_>>();
+            let mut vc = VoteCounter::new(&members, Default::default());
+            assert_eq!(g.result, vc.tally().unwrap());
+            let ranking = vc.iter().map(|(n, v)|(n, &*v.voter_id)).collect::<Vec<_>>();
+            assert_eq!(g.ranking.iter().map(|(n, v)|(*n, &*v.voter_id)).collect::<Vec<_>>(), ranking);
+        }
+    }
+
+    // A group whose votes add up to more than a Weight, which the count rescales
+    let mut v = Votes::new("results_by_group");
+    v.candidate("Alice", "");
+    v.votes("Alice", Weight::MAX / 2 + 1);
+    v.votes("Alice", Weight::MAX / 2 + 1);
+    let options = ElectionOptions { overflow: crate::OverflowPolicy::Rescale, ..Default::default() };
+    let grouped = tally_by_group(&v.v, options, |_|Some("all")).unwrap();
+    assert_eq!(grouped.groups[0].votes, Weight::MAX);
+    assert_eq!(grouped.groups[0].result.winner.as_deref(), Some("results_by_group/Alice"));
+}
+
+#[test]
+fn quorum_by_group() {
+    use crate::groups::{tally_by_group, check_quorum, QuorumShortfall};
+    let mut v = Votes::new("quorum");
+    v.candidate("Alice", "");
+    v.candidate("Bob", "");
+    v.votes("Alice", 1);
+    v.votes("Bob", 1);
+    // Alice and her voter are in the north, Bob and his voter in the south
+    let group_of = |v: &Vote| Some(if v.voter_id == "voter#0" || v.voter_id.ends_with("Alice") {
+        "north"
+    } else {
+        "south"
+    });
+    let grouped = tally_by_group(&v.v, Default::default(), group_of).unwrap();
+    let eligible = [("north", 5), ("south", 8), ("west", 3)]
+        .into_iter()
+        .map(|(g, n)|(g.to_owned(), n))
+        .collect();
+    let failure = check_quorum(&grouped, &eligible, 30).unwrap_err();
+    assert_eq!(failure.shortfalls, vec![
+        QuorumShortfall { group: "south".into(), participating: 2, eligible: 8, required: 3 },
+        QuorumShortfall { group: "west".into(), participating: 0, eligible: 3, required: 1 },
+    ]);
+    assert!(check_quorum(&grouped, &eligible, 0).is_ok());
+}
+
+#[test]
+fn percentages_add_up() {
+    use crate::percent::largest_remainder;
+    let p = largest_remainder(&[1, 1, 1], 2);
+    assert_eq!(p.iter().map(|p|p.to_string()).collect::<Vec<_>>(), vec!["33.34%", "33.33%", "33.33%"]);
+    let p = largest_remainder(&[Weight::MAX, Weight::MAX, 1], 3);
+    assert_eq!(p.iter().map(|p|p.units).sum::<u64>(), 100_000);
+    assert_eq!(p[2].to_string(), "0.000%");
+    assert!(largest_remainder(&[0, 0], 1).iter().all(|p|p.units == 0));
+
+    let mut v = Votes::new("percent");
+    v.candidate("Alice", "");
+    v.candidate("Bob", "");
+    v.candidate("Carol", "");
+    v.votes("Alice", 1);
+    let vc = VoteCounter::new(&v.v, Default::default());
+    let p = vc.percentages(0);
+    assert_eq!(p.iter().map(|(p, _)|p.units).collect::<Vec<_>>(), vec![50, 25, 25]);
+}
+
+/// Just enough of JSON Schema for the schemas in crate::schema
+#[cfg(feature = "json")]
+fn conforms(v: &serde_json::Value, s: &serde_json::Value) -> bool {
+    use serde_json::Value;
+    if let Value::Bool(b) = s {
+        return *b;
+    }
+    if let Some(c) = s.get("const") {
+        return v == c;
+    }
+    if let Some(Value::Array(e)) = s.get("enum") {
+        return e.contains(v);
+    }
+    if let Some(Value::Array(any)) = s.get("anyOf") {
+        return any.iter().any(|s|conforms(v, s));
+    }
+    if let Some(Value::Array(one)) = s.get("oneOf") {
+        return one.iter().filter(|s|conforms(v, s)).count() == 1;
+    }
+    match (s.get("type").and_then(Value::as_str), v) {
+        (Some("string"), Value::String(_)) | (Some("null"), Value::Null) => true,
+        (Some("integer"), Value::Number(n)) => n.as_u64().is_some_and(|n|
+            s.get("maximum").and_then(Value::as_u64).is_none_or(|max|n <= max)),
+        (Some("array"), Value::Array(a)) => {
+            let prefix = s.get("prefixItems").and_then(Value::as_array).map_or(&[][..], |p|p);
+            a.len() >= prefix.len()
+                && a.iter().zip(prefix).all(|(v, s)|conforms(v, s))
+                && a[prefix.len()..].iter().all(|v|conforms(v, &s["items"]))
+        }
+        (Some("object"), Value::Object(o)) if s.get("properties").is_none() =>
+            o.values().all(|v|conforms(v, &s["additionalProperties"])),
+        (Some("object"), Value::Object(o)) => {
+            let props = s["properties"].as_object().unwrap();
+            s["required"].as_array().unwrap().iter().all(|r|o.contains_key(r.as_str().unwrap()))
+                && o.iter().all(|(k, v)|props.get(k).is_some_and(|s|conforms(v, s)))
+        }
+        _ => false,
+    }
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn records_match_schema() {
+    use crate::introspector::{InvalidVoteCause, FallbackCause, PatronMath, Phase};
+    use crate::record::{EventRecord, PatronOutcome};
+    use crate::policy::PolicyAction;
+    use crate::schema::{event_record_schema, election_result_schema};
+    let s = |x: &str|x.to_owned();
+    let math = PatronMath { tenative_winner_votes: 10, mark_to_beat: 5, runner_up_votes: None };
+    let records = vec![
+        EventRecord::VoteDelegation { from: s("a"), to: s("b"), because_of: s("c"), votes: 1 },
+        EventRecord::VoteDelegationRing {
+            chain: vec![s("a"), s("b")], next: s("a"), from: s("c"), votes: 2, stopped_at: s("b"),
+        },
+        EventRecord::DeepDelegation { from: s("a"), depth: 21, chain: vec![s("b")] },
+        EventRecord::ImplicitDelegation { from: s("a"), to: s("x"), because_of: s("a"), votes: 1 },
+        EventRecord::InvalidVote { cause: InvalidVoteCause::SelfVote, voter_id: s("a"), vote_for: Some(s("a")) },
+        EventRecord::Fallback {
+            voter_id: s("a"), skipped: s("b"), cause: FallbackCause::Unwilling, next: Some(s("c")),
+        },
+        EventRecord::Abstention { voter_id: s("a"), votes: 2 },
+        EventRecord::BestRing { rings: vec![vec![s("a"), s("b")]], votes: 3 },
+        EventRecord::BestOfRing { scores: vec![(s("a"), 2)], winners: vec![s("a")] },
+        EventRecord::PatronSelection {
+            candidate: s("a"), votes: 6, outcome: PatronOutcome::PatronFound, math,
+        },
+        EventRecord::PatronSelection {
+            candidate: s("a"), votes: 6, outcome: PatronOutcome::NotBeatingSecondBest(6, s("b")),
+            math: PatronMath { runner_up_votes: Some(7), ..math },
+        },
+        EventRecord::TieBreakerHash { candidate: s("a"), bytes: vec![0, 255], votes: 1 },
+        EventRecord::TieBreaker { votes: 1, tied: vec![(s("a"), vec![1]), (s("b"), vec![2])] },
+        EventRecord::TieBreakSalt { commitment: vec![1], salt: vec![2] },
+        EventRecord::Winner { candidate: None, votes: 0, contributions: Default::default() },
+        EventRecord::PolicyDecision {
+            voter_id: s("a"),
+            action: PolicyAction::Redirect { vote_for: s("b"), reason: s("bylaw 4") },
+        },
+        EventRecord::AliasApplied { voter_id: s("a"), alias: s("1001"), resolved: s("b"), table: s("numbers") },
+        EventRecord::Seat { seat: 2, candidate: s("a"), votes: 4 },
+        EventRecord::NoQuorum { ballots: 2, votes: 3, min_ballots: Some(5), min_votes: None },
+        EventRecord::WeightsRescaled { shift: 3 },
+        EventRecord::Progress {
+            phase: Phase::Delegation, processed: 1, total: 2, estimated_remaining_ms: Some(3),
+        },
+    ];
+    let schema = event_record_schema();
+    for r in records {
+        let json = serde_json::to_value(&r).unwrap();
+        assert!(conforms(&json, &schema), "{}", json);
+    }
+    let bad = serde_json::json!({ "event": "BestRing", "rings": [], "votes": -1 });
+    assert!(!conforms(&bad, &schema));
+
+    let mut v = Votes::new("schema");
+    v.candidate("Alice", "");
+    v.candidate("Bob", "Alice");
+    let result = VoteCounter::new(&v.v, Default::default()).tally().unwrap();
+    assert!(conforms(&serde_json::to_value(&result).unwrap(), &election_result_schema()));
+}
+
+#[test]
+fn compact_frames() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    let frames = Rc::new(RefCell::new(Vec::new()));
+    let f = frames.clone();
+    let mut v = Votes::new("compact");
+    v.candidate("Alice", "Alice");
+    v.candidate("Bob", "Alice");
+    let is = crate::compact_introspector::new(move |frame| f.borrow_mut().push(frame.to_vec()));
+    VoteCounter::new(&v.v, is).find_winner();
+    let frames = frames.borrow();
+    assert_eq!(frames.len(), 2);
+    assert_eq!(&frames[0][..4], &[b'I', 1, 13, 0]);
+    assert_eq!(&frames[0][4..], b"compact/Alice");
+    assert_eq!(frames[1][0], b'W');
+    let w = std::mem::size_of::<Weight>();
+    assert_eq!(Weight::from_le_bytes(frames[1][1..1 + w].try_into().unwrap()), 2);
+    assert_eq!(&frames[1][3 + w..], b"compact/Alice");
+}
+
+#[test]
+fn memory_limit() {
+    use crate::limits::estimate_memory;
+    assert!(estimate_memory(1000, 20) < estimate_memory(1000, 40));
+    assert!(estimate_memory(1000, 20) < estimate_memory(2000, 20));
+    assert_eq!(estimate_memory(usize::MAX, 1), usize::MAX);
+    let mut v = Votes::new("memory");
+    v.candidate("Alice", "");
+    v.votes("Alice", 10);
+    // "memory/Alice" and friends are about 14 bytes
+    let needed = estimate_memory(v.v.len(), 14);
+    let limited = |limit| ElectionOptions { memory_limit: Some(limit), ..Default::default() };
+    let res = VoteCounter::with_options(&v.v, Default::default(), limited(needed)).tally();
+    assert_eq!(res.unwrap().winner.as_deref(), Some("memory/Alice"));
+    let res = VoteCounter::with_options(&v.v, Default::default(), limited(needed / 2)).tally();
+    assert!(matches!(res, Err(crate::TallyError::MemoryLimit { limit, .. }) if limit == needed / 2));
+    // Fallbacks take memory too
+    let last = v.v.len() - 1;
+    v.v[last].fallbacks = vec!["memory/Bob".into(); 1000];
+    let res = VoteCounter::with_options(&v.v, Default::default(), limited(needed)).tally();
+    assert!(matches!(res, Err(crate::TallyError::MemoryLimit { limit, .. }) if limit == needed));
+}
+
+#[test]
+fn ballot_limits() {
+    use crate::TallyError;
+    let mut v = Votes::new("limits");
+    v.candidate("Alice", "");
+    v.candidate("Bob-with-a-long-name", "Alice");
+    v.voter("Alice");
+    let count = |options: ElectionOptions| VoteCounter::with_options(&v.v, Default::default(), options).tally();
+    let res = count(ElectionOptions { max_ballots: Some(2), ..Default::default() });
+    assert_eq!(res.unwrap_err(), TallyError::TooManyBallots { count: 3, limit: 2 });
+    let res = count(ElectionOptions { max_id_len: Some(20), ..Default::default() });
+    assert_eq!(res.unwrap_err(), TallyError::IdTooLong { ballot: 1, len: 27, limit: 20 });
+    let res = count(ElectionOptions { max_ballots: Some(3), max_id_len: Some(27), ..Default::default() });
+    assert_eq!(res.unwrap().winner.as_deref(), Some("limits/Alice"));
+    // A fallback is an ID like the others
+    v.v[2].fallbacks = vec!["limits/Carol-with-an-even-longer-name".into()];
+    let options = ElectionOptions { max_id_len: Some(27), ..Default::default() };
+    let res = VoteCounter::with_options(&v.v, Default::default(), options).tally();
+    assert_eq!(res.unwrap_err(), TallyError::IdTooLong { ballot: 2, len: 37, limit: 27 });
+}
+
+#[test]
+fn live_readers() {
+    use crate::live::LiveTally;
+    let mut live = LiveTally::new(Default::default());
+    let reader = live.reader();
+    assert_eq!(reader.snapshot().generation, 0);
+    let ballot = |id: &str, vote_for: Option<&str>| Vote {
+        voter_id: id.to_owned().into(),
+        vote_for: vote_for.map(|v|v.to_owned().into()),
+        number_of_votes: 1,
+        willing_candidate: true,
+        metadata: Default::default(),
+        fallbacks: Vec::new(),
+        abstain: false,
+    };
+    live.add(ballot("Alice", None)).unwrap();
+    live.add(ballot("Bob", Some("Alice"))).unwrap();
+    live.publish().unwrap();
+    let first = reader.snapshot();
+    let watcher = {
+        let reader = reader.clone();
+        std::thread::spawn(move || {
+            // Generations only ever move forward
+            let mut last = 0;
+            while last < 2 {
+                let g = reader.snapshot().generation;
+                assert!(g >= last);
+                last = g;
+                std::thread::yield_now();
+            }
+        })
+    };
+    live.add(ballot("Carol", Some("Bob"))).unwrap();
+    live.add(ballot("Dave", Some("Bob"))).unwrap();
+    live.publish().unwrap();
+    watcher.join().unwrap();
+    // An old snapshot is unchanged
+    assert_eq!((first.leader.as_deref(), first.votes, first.ballots), (Some("Alice"), 2, 2));
+    let now = reader.snapshot();
+    assert_eq!((now.generation, now.ballots), (2, 4));
+    assert_eq!(now.ranking[0], ("Alice".to_owned(), 4));
+}
+
+#[test]
+fn live_recovery() {
+    use crate::live::LiveTally;
+    use std::sync::{Arc, Mutex};
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+    impl std::io::Write for Shared {
+        fn write(&mut self, b: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(b)
+        }
+        fn flush(&mut self) -> std::io::Result<()> { Ok(()) }
+    }
+    let mut v = Votes::new("recover");
+    v.candidate("Alice", "");
+    v.candidate("Bob", "Alice");
+    v.votes("Bob", 3);
+    let log = Shared::default();
+    let mut live = LiveTally::new(Default::default());
+    live.log_to(log.clone());
+    for b in &v.v {
+        live.add(b.to_owned_vote()).unwrap();
+    }
+    let before = live.publish().unwrap();
+    let mut bytes = log.0.lock().unwrap().clone();
+    let (recovered, len) = LiveTally::recover(Default::default(), &bytes).unwrap();
+    assert_eq!(len, bytes.len());
+    let mut recovered = recovered;
+    let after = recovered.publish().unwrap();
+    assert_eq!((after.leader.clone(), after.ranking.clone()), (before.leader.clone(), before.ranking.clone()));
+
+    // A crash in the middle of the last ballot loses only that ballot
+    bytes.truncate(bytes.len() - 3);
+    let (recovered, len) = LiveTally::recover(Default::default(), &bytes).unwrap();
+    assert_eq!(recovered.ballots().len(), v.v.len() - 1);
+    assert!(len < bytes.len());
+    bytes[0] = b'?';
+    assert!(LiveTally::recover(Default::default(), &bytes).is_err());
+}
+
+#[test]
+fn many_elections() {
+    use crate::elections::{Elections, ElectionError, Stage};
+    #[cfg(feature = "json")]
+    let dir = std::env::temp_dir().join(format!("electorium-elections-{}", std::process::id()));
+    #[cfg(feature = "json")]
+    let mut el = {
+        std::fs::create_dir_all(&dir).unwrap();
+        Elections::with_audit_dir(&dir)
+    };
+    #[cfg(not(feature = "json"))]
+    let mut el = Elections::new();
+    let ballot = |id: &str, vote_for: Option<&str>| Vote {
+        voter_id: id.to_owned().into(),
+        vote_for: vote_for.map(|v|v.to_owned().into()),
+        number_of_votes: 1,
+        willing_candidate: true,
+        metadata: Default::default(),
+        fallbacks: Vec::new(),
+        abstain: false,
+    };
+    el.open("north", Default::default()).unwrap();
+    el.open("south", ElectionOptions { patron_rule: PatronRule::Disabled, ..Default::default() }).unwrap();
+    assert!(matches!(el.open("north", Default::default()), Err(ElectionError::AlreadyExists(_))));
+    assert!(matches!(el.open("../x", Default::default()), Err(ElectionError::BadName(_))));
+    el.add("north", ballot("Alice", None)).unwrap();
+    el.add("north", ballot("Bob", Some("Alice"))).unwrap();
+    el.add("south", ballot("Carol", None)).unwrap();
+    assert!(matches!(el.tally("north"), Err(ElectionError::WrongStage { stage: Stage::Open, .. })));
+    el.close("north").unwrap();
+    assert!(matches!(el.add("north", ballot("Eve", None)), Err(ElectionError::WrongStage { .. })));
+    let res = el.tally("north").unwrap();
+    assert_eq!(res.winner.as_deref(), Some("Alice"));
+    assert_eq!(el.stage("south"), Some(Stage::Open));
+    let archived = el.archive("north").unwrap();
+    assert_eq!(crate::archive::decompress(&archived).unwrap().len(), 2);
+    assert_eq!(el.result("north"), Some(&res));
+    assert_eq!(el.names().collect::<Vec<_>>(), vec!["north", "south"]);
+    #[cfg(feature = "json")]
+    {
+        let log = dir.join("north.audit.jsonl");
+        assert!(crate::audit::verify_audit_log(&log).unwrap());
+        assert!(!dir.join("south.audit.jsonl").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
+#[test]
+fn prometheus_metrics() {
+    use crate::live::LiveTally;
+    use crate::metrics::Metrics;
+    let metrics = Metrics::new();
+    let mut live = LiveTally::new(Default::default());
+    live.set_metrics(metrics.clone());
+    let ballot = |id: &str, vote_for: Option<&str>| Vote {
+        voter_id: id.to_owned().into(),
+        vote_for: vote_for.map(|v|v.to_owned().into()),
+        number_of_votes: 1,
+        willing_candidate: true,
+        metadata: Default::default(),
+        fallbacks: Vec::new(),
+        abstain: false,
+    };
+    live.add(ballot("Alice", Some("Alice"))).unwrap();
+    live.add(ballot("Bob", Some("Alice"))).unwrap();
+    live.add(ballot("Carol", Some("Nobody"))).unwrap();
+    live.publish().unwrap();
+    let text = metrics.render();
+    for line in [
+        "electorium_ballots_total 3",
+        "electorium_tallies_total 1",
+        "electorium_invalid_votes_total{cause=\"SelfVote\"} 1",
+        "electorium_invalid_votes_total{cause=\"UnrecognizedVote\"} 1",
+        "electorium_invalid_votes_total{cause=\"Duplicate\"} 0",
+        "electorium_events_total{event=\"VoteDelegation\"} 1",
+        "electorium_events_total{event=\"Winner\"} 1",
+        "electorium_phase_duration_seconds_bucket{phase=\"Ranking\",le=\"+Inf\"} 1",
+        "electorium_phase_duration_seconds_count{phase=\"Delegation\"} 1",
+    ] {
+        assert!(text.lines().any(|l|l == line), "{} not in\n{}", line, text);
+    }
+}
+
+#[test]
+fn bulk_ingestion() {
+    use std::ops::ControlFlow;
+    use crate::bulk::{ingest, Format, Rejection};
+    use crate::live::LiveTally;
+    let csv = "Alice,,1,true\n\
+        Bob,Alice,2,1\n\
+        \n\
+        \"Carol, Jr.\",Bob,1,false\n\
+        Bob,Alice,1,true\n\
+        Dave,Alice,many,true\n\
+        ,Alice,1,true\n\
+        Erin,Alice,1,false\n";
+    let mut live = LiveTally::new(Default::default());
+    let mut chunks = Vec::new();
+    let report = ingest(csv.as_bytes(), Format::Csv, &mut live, 3, |c| {
+        chunks.push((c.lines, c.accepted, c.rejected.len()));
+        ControlFlow::Continue(())
+    }).unwrap();
+    assert_eq!(chunks, vec![((1, 4), 2, 0), ((4, 7), 1, 2), ((7, 9), 1, 1)]);
+    assert_eq!(report.accepted, 4);
+    assert!(!report.stopped);
+    assert_eq!(report.rejected.iter().map(|r|(r.line, r.reason.clone())).collect::<Vec<_>>(), vec![
+        (5, Rejection::Duplicate),
+        (6, Rejection::Malformed("bad number of votes \"many\"".into())),
+        (7, Rejection::EmptyVoterId),
+    ]);
+    assert_eq!(live.ballots()[0].vote_for, None);
+    assert_eq!(live.ballots()[2].voter_id, "Carol, Jr.");
+    let archived = crate::archive::decompress(&crate::archive::compress(live.ballots())).unwrap();
+    assert!(archived.iter().any(|v|v.voter_id == "Alice" && v.vote_for.is_none()));
+    assert_eq!(live.publish().unwrap().leader.as_deref(), Some("Bob"));
+
+    // A second ballot from V reaches the count under the policies which use it
+    let dup = "Alice,,1,true\nBob,,1,true\nV,Alice,2,false\nV,Bob,3,false\n";
+    for (duplicate_voter, leader, votes) in [
+        (crate::DuplicatePolicy::LastWins, "Bob", 4),
+        (crate::DuplicatePolicy::MergeWeights, "Alice", 6),
+    ] {
+        let mut live = LiveTally::new(ElectionOptions { duplicate_voter, ..Default::default() });
+        let report = ingest(dup.as_bytes(), Format::Csv, &mut live, 10, |_|ControlFlow::Continue(())).unwrap();
+        assert_eq!((report.accepted, report.rejected.len()), (4, 0));
+        let mut direct = LiveTally::new(live.options().clone());
+        for v in live.ballots() {
+            direct.add(v.to_owned_vote()).unwrap();
+        }
+        let published = live.publish().unwrap();
+        assert_eq!((published.leader.as_deref(), published.votes), (Some(leader), votes));
+        assert_eq!(direct.publish().unwrap(), published);
+    }
+
+    // Stopping after the first chunk
+    let mut live = LiveTally::new(Default::default());
+    let report = ingest(csv.as_bytes(), Format::Csv, &mut live, 2, |_|ControlFlow::Break(())).unwrap();
+    assert!(report.stopped);
+    assert_eq!(live.ballots().len(), 2);
+
+    #[cfg(feature = "json")]
+    {
+        let ndjson = "{\"voter_id\":\"A\",\"vote_for\":\"\",\"number_of_votes\":1,\"willing_candidate\":true}\n\
+            {\"voter_id\":\"B\"}\n";
+        let mut live = LiveTally::new(Default::default());
+        let report = ingest(ndjson.as_bytes(), Format::NdJson, &mut live, 10, |_|ControlFlow::Continue(())).unwrap();
+        assert_eq!(report.accepted, 1);
+        assert!(matches!(report.rejected[..], [crate::bulk::RejectedRow { line: 2, reason: Rejection::Malformed(_), .. }]));
+
+        // A fallback is held to max_id_len like the other IDs
+        let ndjson = "{\"voter_id\":\"A\",\"number_of_votes\":1,\"willing_candidate\":true,\
+            \"fallbacks\":[\"a-fallback-which-is-too-long\"]}\n";
+        let mut live = LiveTally::new(ElectionOptions { max_id_len: Some(20), ..Default::default() });
+        let report = ingest(ndjson.as_bytes(), Format::NdJson, &mut live, 10, |_|ControlFlow::Continue(())).unwrap();
+        assert_eq!(report.rejected[0].reason, Rejection::IdTooLong);
+    }
+}
+
+#[test]
+fn election_notifications() {
+    use std::sync::{Arc, Mutex};
+    use crate::elections::{Elections, Notifier};
+    use crate::signing::{SignedResult, verify_signed_result};
+    #[derive(Clone, Default)]
+    struct Hook(Arc<Mutex<(Vec<String>, Vec<SignedResult>)>>);
+    impl Notifier for Hook {
+        fn leader_changed(&mut self, election: &str, previous: Option<&str>, leader: Option<&str>, votes: Weight) {
+            self.0.lock().unwrap().0.push(format!("{election}: {previous:?} -> {leader:?} ({votes})"));
+        }
+        fn finalized(&mut self, _election: &str, result: &SignedResult) {
+            self.0.lock().unwrap().1.push(result.clone());
+        }
+    }
+    let ballot = |id: &str, vote_for: Option<&str>| Vote {
+        voter_id: id.to_owned().into(),
+        vote_for: vote_for.map(|v|v.to_owned().into()),
+        number_of_votes: 1,
+        willing_candidate: true,
+        metadata: Default::default(),
+        fallbacks: Vec::new(),
+        abstain: false,
+    };
+    let hook = Hook::default();
+    let mut el = Elections::new();
+    el.set_notifier(hook.clone(), Mac(b"operator".to_vec()));
+    el.open("club", Default::default()).unwrap();
+    el.add("club", ballot("Alice", None)).unwrap();
+    el.publish("club").unwrap();
+    el.add("club", ballot("Bob", Some("Alice"))).unwrap();
+    el.publish("club").unwrap();
+    el.add("club", ballot("Carol", Some("Dave"))).unwrap();
+    el.add("club", ballot("Dave", Some("Carol"))).unwrap();
+    el.add("club", ballot("Erin", Some("Carol"))).unwrap();
+    el.add("club", ballot("Frank", Some("Carol"))).unwrap();
+    el.publish("club").unwrap();
+    el.close("club").unwrap();
+    el.tally("club").unwrap();
+    let (changes, finalized) = hook.0.lock().unwrap().clone();
+    assert_eq!(changes, vec![
+        "club: None -> Some(\"Alice\") (1)",
+        "club: Some(\"Alice\") -> Some(\"Carol\") (4)",
+    ]);
+    assert_eq!(finalized.len(), 1);
+    assert_eq!(finalized[0].result.winner.as_deref(), Some("Carol"));
+    assert!(verify_signed_result(&finalized[0], b"operator", &Mac(Vec::new())));
+}
+
+#[test]
+fn redacted_views() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use crate::introspector::Introspector;
+    use crate::record::EventRecord;
+    use crate::redact::{RedactionPolicy, Role, REDACTED};
+    let mut v = Votes::new("redact");
+    v.candidate("Alice", "");
+    v.candidate("Bob", "Alice");
+    v.votes("Bob", 5);
+    v.candidate("Carol", "Alice");
+    v.votes("Alice", 10);
+    let policy = RedactionPolicy { min_votes_shown: 2, hide_delegation_targets: false };
+    let records = Rc::new(RefCell::new(Vec::new()));
+    let mut is = Introspector::default();
+    let r = records.clone();
+    policy.subscribe(&mut is, Role::Public, move |rec| r.borrow_mut().push(rec));
+    let result = VoteCounter::new(&v.v, is).tally().unwrap();
+    assert_eq!(policy.result(Role::Administrator, &result), result);
+    let public = policy.result(Role::Public, &result);
+    assert_eq!((public.winner.clone(), public.votes), (result.winner.clone(), result.votes));
+    let mut supporters = public.contributions.supporters.clone();
+    supporters.sort();
+    assert_eq!(supporters, vec![
+        (REDACTED.to_owned(), 1),
+        ("redact/Bob".to_owned(), 6),
+        ("voter#1".to_owned(), 10),
+    ]);
+    // Only the delegations of at least 2 votes are shown
+    let records = records.borrow();
+    let mut delegations = records.iter()
+        .filter_map(|r|match r {
+            EventRecord::VoteDelegation { from, .. } => Some(from.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    delegations.sort();
+    assert_eq!(delegations, vec!["voter#0", "voter#0", "voter#1"]);
+
+    let strict = RedactionPolicy { hide_delegation_targets: true, ..Default::default() };
+    assert_eq!(strict.result(Role::Public, &result).contributions.supporters, vec![(REDACTED.to_owned(), 17)]);
+}
+
+#[test]
+fn differential_privacy_noise() {
+    use crate::dp::{noisy_ranking, noisy_totals, NoiseOptions};
+    let mut v = Votes::new("dp");
+    v.candidate("Alice", "");
+    v.candidate("Bob", "");
+    v.votes("Alice", 3);
+    let vc = VoteCounter::new(&v.v, Default::default());
+    let options = NoiseOptions { epsilon_milli: 500, sensitivity: 1, seed: 7 };
+    let noisy = noisy_ranking(vc.iter(), &options);
+    assert_eq!(noisy, noisy_ranking(vc.iter(), &options));
+    assert_eq!(noisy.iter().map(|(id, _)|id.as_str()).collect::<Vec<_>>(), vec!["dp/Alice", "dp/Bob"]);
+
+    // The noise averages out to nothing and is wider with a smaller epsilon
+    let totals = vec![("x".to_owned(), 1000); 2000];
+    let spread = |epsilon_milli| {
+        let noisy = noisy_totals(&totals, &NoiseOptions { epsilon_milli, sensitivity: 1, seed: 1 });
+        let sum = noisy.iter().map(|(_, t)|*t as i64 - 1000).sum::<i64>();
+        let abs = noisy.iter().map(|(_, t)|(*t as i64 - 1000).abs()).sum::<i64>();
+        (sum / 2000, abs / 2000)
+    };
+    let (mean_wide, abs_wide) = spread(200);
+    let (mean_narrow, abs_narrow) = spread(2000);
+    assert!(mean_wide.abs() <= 1 && mean_narrow.abs() <= 1);
+    assert!(abs_wide > abs_narrow);
+    assert!(abs_narrow <= 1);
+}
+
+#[test]
+fn anonymized_graph() {
+    use crate::graph::{export, export_anonymized, Suppressed};
+    let mut v = Votes::new("graph");
+    v.candidate("Alice", "");
+    v.candidate("Bob", "Alice");
+    for _ in 0..3 {
+        v.voter("Alice");
+    }
+    v.votes("Bob", 4);
+    let vc = VoteCounter::new(&v.v, Default::default());
+    let full = export(&vc);
+    assert!(full.contains("\"voter#3\" -> \"graph/Bob\" [label=4];"));
+    let (anon, suppressed) = export_anonymized(&vc, 2);
+    assert!(!anon.contains("voter#"));
+    assert!(anon.contains("\"3 voters of graph/Alice\" -> \"graph/Alice\" [label=3];"));
+    assert!(anon.contains("\"graph/Bob\" -> \"graph/Alice\";"));
+    assert_eq!(suppressed, vec![Suppressed { target: "graph/Bob".into(), voters: 1, votes: 4 }]);
+}
+
+#[test]
+fn delegation_matrix() {
+    use crate::analysis::DelegationMatrix;
+    let mut v = Votes::new("matrix");
+    v.candidate("Alice", "Bob");
+    v.candidate("Bob", "Alice");
+    v.candidate("Carol", "Bob");
+    v.votes("Carol", 2);
+    let vc = VoteCounter::new(&v.v, Default::default());
+    let m = DelegationMatrix::new(&vc);
+    let at = |from: &str, to: &str| {
+        let i = |id: &str| m.candidates.iter().position(|c|c == &format!("matrix/{id}")).unwrap();
+        m.weights[i(from)][i(to)]
+    };
+    assert_eq!((at("Carol", "Bob"), at("Carol", "Alice"), at("Carol", "Carol")), (1, 1, 1));
+    assert_eq!((at("Alice", "Bob"), at("Bob", "Alice"), at("Alice", "Carol")), (1, 1, 0));
+    let csv = m.to_csv();
+    assert_eq!(csv.lines().count(), 4);
+    assert!(csv.starts_with("from,matrix/"));
+}
+
+#[test]
+fn trends_across_archives() {
+    use crate::analysis::{trends, Churn};
+    use crate::archive::compress;
+    let mut first = Votes::new("t");
+    first.candidate("Alice", "Bob");
+    first.candidate("Bob", "Alice");
+    first.voter("Alice");
+    let mut second = Votes::new("t");
+    second.candidate("Alice", "Bob");
+    second.candidate("Bob", "Alice");
+    second.candidate("Carol", "Alice");
+    second.voter("Carol");
+    second.voter("Carol");
+    let (a, b) = (compress(&first.v), compress(&second.v));
+    let t = trends([("2024", &a[..]), ("2025", &b[..])], &Default::default()).unwrap();
+    assert_eq!(t.turnout.iter().map(|t|(t.ballots, t.votes)).collect::<Vec<_>>(), vec![(3, 3), (5, 5)]);
+    assert_eq!(t.weight["t/Alice"], vec![Some(3), Some(5)]);
+    assert_eq!(t.weight["t/Carol"], vec![None, Some(3)]);
+    assert_eq!(t.churn, vec![Churn { voters_in_both: 3, changed: 1 }]);
+    assert_eq!(t.rings.len(), 1);
+    assert_eq!(t.rings[0].elections, vec![0, 1]);
+    assert!(trends([("bad", &b"nope"[..])], &Default::default()).is_err());
+
+    // Votes which add up to more than a Weight, which the count rescales
+    let mut big = Votes::new("t");
+    big.candidate("Alice", "");
+    big.votes("Alice", Weight::MAX / 2 + 1);
+    big.votes("Alice", Weight::MAX / 2 + 1);
+    let c = compress(&big.v);
+    let options = ElectionOptions { overflow: crate::OverflowPolicy::Rescale, ..Default::default() };
+    let t = trends([("2026", &c[..])], &options).unwrap();
+    assert_eq!(t.turnout[0].votes, Weight::MAX);
+    assert_eq!(t.turnout[0].winner.as_deref(), Some("t/Alice"));
+}
+
+#[test]
+fn owned_counter() {
+    use crate::owned::OwnedVoteCounter;
+    struct Service {
+        counter: OwnedVoteCounter,
+    }
+    fn build() -> Service {
+        let mut v = Votes::new("owned");
+        v.candidate("Alice", "");
+        v.candidate("Bob", "Alice");
+        Service { counter: OwnedVoteCounter::new(v.v, Default::default()) }
+    }
+    let s = build();
+    let res = s.counter.tally().unwrap();
+    assert_eq!(res.winner.as_deref(), Some("owned/Alice"));
+    assert_eq!(s.counter.tally().unwrap(), res);
+    assert_eq!(s.counter.counter(Default::default()).iter().count(), 2);
+    assert_eq!(s.counter.into_votes().len(), 2);
+}
+
+#[test]
+fn engagement_metrics() {
+    let mut v = Votes::new("engage");
+    v.candidate("Alice", "");
+    v.candidate("Bob", "Alice");
+    v.votes("Alice", 2);
+    v.votes("Nobody", 4);
+    let options = ElectionOptions { registry_weight: Some(20), ..Default::default() };
+    let res = VoteCounter::with_options(&v.v, Default::default(), options).tally().unwrap();
+    let e = res.engagement;
+    assert_eq!((e.valid_ballots, e.valid_votes, e.delegating_ballots, e.candidates), (4, 8, 2, 2));
+    assert_eq!(e.turnout_bp, Some(4000));
+    assert_eq!(e.delegation_bp, 5000);
+    assert_eq!(e.utilization_bp, 5000);
+    let res = VoteCounter::new(&v.v, Default::default()).tally().unwrap();
+    assert_eq!(res.engagement.turnout_bp, None);
+}
+
+#[test]
+fn presets() {
+    use crate::Preset;
+    for p in Preset::ALL {
+        assert_eq!(p.name().parse::<Preset>(), Ok(p));
+    }
+    assert!("majority".parse::<Preset>().is_err());
+    let mut v = Votes::new("preset");
+    v.candidate("Alice", "Nobody");
+    v.votes("Alice", 2);
+    let count = |p: Preset| VoteCounter::with_options(&v.v, Default::default(), p.options()).tally();
+    assert!(count(Preset::CorporateProxy).is_err());
+    assert_eq!(count(Preset::LiquidDemocracy).unwrap().winner.as_deref(), Some("preset/Alice"));
+    assert_eq!(count(Preset::TokenGovernance).unwrap().rules.options, Preset::TokenGovernance.options());
+    // No preset is the standard rules under another name
+    for (i, p) in Preset::ALL.into_iter().enumerate() {
+        assert_ne!(p.options(), ElectionOptions::default(), "{p:?}");
+        assert!(Preset::ALL[..i].iter().all(|q|q.options() != p.options()), "{p:?}");
+    }
+    // More ballots from Bob, who changed their delegate or holds more shares
+    v.reset();
+    v.candidate("Alice", "");
+    v.candidate("Carol", "");
+    v.votes("Carol", 2);
+    v.candidate("Bob", "Alice");
+    v.candidate("Bob", "Alice");
+    v.candidate("Bob", "Carol");
+    v.v[0].vote_for = None;
+    v.v[1].vote_for = None;
+    let count = |p: Preset| VoteCounter::with_options(&v.v, Default::default(), p.options()).tally();
+    assert_eq!(count(Preset::OneMemberOneVote), Err(crate::TallyError::DuplicateVoter("preset/Bob".into())));
+    assert_eq!(count(Preset::LiquidDemocracy).unwrap().winner.as_deref(), Some("preset/Carol"));
+    let merged = count(Preset::CorporateProxy).unwrap();
+    assert_eq!(merged.winner.as_deref(), Some("preset/Alice"));
+    assert_eq!(merged.votes, 4);
+    // Token balances which add up to more than can be counted exactly
+    let mut v = Votes::new("preset");
+    v.candidate("Alice", "");
+    v.votes("Alice", crate::limits::MAX_TOTAL_VOTES);
+    v.votes("Alice", crate::limits::MAX_TOTAL_VOTES);
+    let count = |p: Preset| VoteCounter::with_options(&v.v, Default::default(), p.options()).tally();
+    assert!(matches!(count(Preset::OneMemberOneVote), Err(crate::TallyError::WeightOverflow { .. })));
+    let rescaled = count(Preset::TokenGovernance).unwrap();
+    assert_eq!((rescaled.winner.as_deref(), rescaled.weight_shift), (Some("preset/Alice"), 1));
+}
+
+#[test]
+fn options_are_validated() {
+    use crate::{OptionsError, TallyError};
+    assert_eq!(ElectionOptions::default().validate(), Ok(()));
+    let zero = ElectionOptions { max_id_len: Some(0), ..Default::default() };
+    assert_eq!(zero.validate(), Err(OptionsError::ZeroLimit("max_id_len")));
+    let empty = ElectionOptions { registry_weight: Some(0), ..Default::default() };
+    assert_eq!(empty.validate(), Err(OptionsError::EmptyRegistry));
+    let mut v = Votes::new("validate");
+    v.candidate("Alice", "");
+    let res = VoteCounter::with_options(&v.v, Default::default(), empty).tally();
+    assert_eq!(res, Err(TallyError::InvalidOptions(OptionsError::EmptyRegistry)));
+    let quorum = ElectionOptions { registry_weight: Some(10), min_votes: Some(11), ..Default::default() };
+    let err = OptionsError::QuorumAboveRegistry { min_votes: 11, registry_weight: 10 };
+    assert_eq!(quorum.validate(), Err(err.clone()));
+    assert_eq!(err.to_string(), "min_votes 11 is more than registry_weight 10");
+    let res = VoteCounter::with_options(&v.v, Default::default(), quorum).tally();
+    assert_eq!(res, Err(TallyError::InvalidOptions(err)));
+    let reachable = ElectionOptions { registry_weight: Some(10), min_votes: Some(10), ..Default::default() };
+    assert_eq!(reachable.validate(), Ok(()));
+}
+
+#[test]
+fn election_builder() {
+    use crate::Election;
+    let election = Election::builder()
+        .candidate("Alice")
+        .candidate_voting_for("Bob", "Alice")
+        .weighted_candidate("Carol", None, 3)
+        .voter("v1", "Alice", 3)
+        .options(ElectionOptions { patron_rule: PatronRule::Disabled, ..Default::default() })
+        .build();
+    let votes = election.votes();
+    assert_eq!((votes[0].vote_for.as_deref(), votes[0].willing_candidate), (None, true));
+    assert_eq!((votes[3].number_of_votes, votes[3].willing_candidate), (3, false));
+    let res = election.tally().unwrap();
+    assert_eq!((res.winner.as_deref(), res.votes), (Some("Alice"), 5));
+    assert_eq!(res.rules.options.patron_rule, PatronRule::Disabled);
+}
+
+#[test]
+fn masked_preview() {
+    use crate::preview::preview;
+    use crate::record::EventRecord;
+    let mut v = Votes::new("preview");
+    v.candidate("Alice", "");
+    v.candidate("Bob", "");
+    v.candidate("Carol", "Carol");
+    let (p, sealed) = preview(&v.v, Default::default(), [7; 32]).unwrap();
+    assert_eq!(p.invalid.len(), 3);
+    assert_eq!(p.engagement.utilization_bp, 0);
+    let revealed = sealed.finalize();
+    assert!(revealed.matches(&p));
+    assert_eq!(revealed.tie_breaks.len(), 1);
+    let expected = VoteCounter::new(&v.v, Default::default()).tally().unwrap();
+    assert_eq!(revealed.result, expected);
+    let mut forged = revealed.clone();
+    forged.result.winner = Some("preview/Nobody".into());
+    assert!(!forged.matches(&p));
+    let mut forged = revealed.clone();
+    if let Some(EventRecord::TieBreaker { tied, .. }) = forged.tie_breaks.first_mut() {
+        tied.reverse();
+    }
+    assert!(!forged.matches(&p));
+    let mut forged = revealed.clone();
+    forged.tie_breaks.clear();
+    assert!(!forged.matches(&p));
+    let mut forged = revealed.clone();
+    forged.tie_breaks.push(EventRecord::WeightsRescaled { shift: 1 });
+    assert!(!forged.matches(&p));
+}
+
+#[test]
+fn salt_commit_reveal() {
+    use crate::record::EventRecord;
+    use crate::salt::{commit_salt, verify_salt, salt_record, SaltError};
+    let salt = [3_u8; 32];
+    let commitment = commit_salt(&salt).unwrap();
+    assert_eq!(verify_salt(&commitment, &salt), Ok(()));
+    assert_eq!(verify_salt(&commitment, &[4; 32]), Err(SaltError::Mismatch));
+    assert_eq!(commit_salt(&[1; 8]), Err(SaltError::TooShort(8)));
+    assert_eq!(salt_record(&commitment, &salt), Ok(EventRecord::TieBreakSalt {
+        commitment: commitment.clone(),
+        salt: salt.to_vec(),
+    }));
+}
+
+#[test]
+fn revoke_by_id() {
+    let mut v = Votes::new("revoke_by_id");
+    v.candidate("Alice", "Bob");
+    v.candidate("Bob", "");
+    v.voter("Alice");
+    v.voter("Alice");
+    let totals = |vc: &VoteCounter| vc.iter().map(|(n, v)|(n, v.voter_id.to_string())).collect::<Vec<_>>();
+    let alice = &v.v[0];
+    let copy = alice.to_owned_vote();
+    assert!(copy.same_as(alice) && copy != *alice);
+
+    let mut by_ref = VoteCounter::new(&v.v, Default::default());
+    let before = totals(&by_ref);
+    by_ref.revoke_vote(&copy);
+    assert_eq!(totals(&by_ref), before);
+    by_ref.revoke_vote(alice);
+    assert_ne!(totals(&by_ref), before);
+
+    let mut by_id = VoteCounter::new(&v.v, Default::default());
+    by_id.revoke_vote_by_id(&copy.voter_id);
+    assert_eq!(totals(&by_id), totals(&by_ref));
+}
+
+#[test]
+fn ballot_policy() {
+    use crate::introspector::{Introspector, PolicyDecision};
+    use crate::policy::{apply, PolicyAction};
+    let mut v = Votes::new("policy");
+    v.candidate("Alice", "");
+    v.candidate("Bob", "");
+    v.candidate("Carol", "");
+    v.votes("Alice", 5);
+    v.voter("Bob");
+    v.voter("Bob");
+    let mut is = Introspector::default();
+    is.subscribe(Vec::<(String, PolicyAction)>::new(), |d, e: &PolicyDecision| {
+        d.push((e.voter_id.to_string(), e.action.clone()));
+    });
+    let reason = |r: &str| r.to_owned();
+    let mut policy = |vote: &Vote| match &*vote.voter_id {
+        "policy/Alice" => Some(PolicyAction::Ineligible { reason: reason("not a member") }),
+        "voter#0" => Some(PolicyAction::Redirect { vote_for: "policy/Carol".into(), reason: reason("proxy") }),
+        "voter#1" => Some(PolicyAction::Veto { reason: reason("late") }),
+        _ => None,
+    };
+    let counted = apply(&v.v, &mut policy, &mut is);
+    assert_eq!(counted.len(), v.v.len() - 1);
+    assert!(!counted[0].willing_candidate);
+    assert_eq!(counted[3].vote_for.as_deref(), Some("policy/Carol"));
+    let decisions = is.into_contexts::<Vec<(String, PolicyAction)>>().remove(0);
+    assert_eq!(decisions.iter().map(|(id, _)|id.as_str()).collect::<Vec<_>>(),
+        vec!["policy/Alice", "voter#0", "voter#1"]);
+    let result = VoteCounter::new(&counted, Default::default()).tally().unwrap();
+    assert_eq!(result.winner.as_deref(), Some("policy/Carol"));
+}
+
+#[cfg(feature = "rhai")]
+#[test]
+fn rhai_ballot_policy() {
+    use crate::introspector::{Introspector, PolicyDecision};
+    use crate::policy::{apply, PolicyAction, RhaiPolicy, ScriptError};
+    let mut v = Votes::new("rhai");
+    v.candidate("Alice", "");
+    v.candidate("Bob", "");
+    v.candidate("Carol", "");
+    v.votes("Alice", 5);
+    v.voter("Bob");
+    v.voter("Bob");
+    v.voter("Bob");
+    v.v[0].metadata = std::borrow::Cow::Owned([("member".to_owned(), "no".to_owned())].into_iter().collect());
+    let script = r#"
+        fn decide(b) {
+            if b.metadata.member == "no" {
+                return #{ action: "ineligible", reason: "not a member" };
+            }
+            switch b.voter_id {
+                "voter#0" => #{ action: "redirect", vote_for: "rhai/Carol", reason: "proxy" },
+                "voter#1" => #{ action: "veto", reason: "late" },
+                "voter#2" => #{ action: "reweight", number_of_votes: b.number_of_votes * 3, reason: "shares" },
+                "rhai/Bob" => 1 / 0,
+            }
+        }
+    "#;
+    let mut policy = RhaiPolicy::new(script).unwrap();
+    let mut is = Introspector::default();
+    is.subscribe(Vec::<(String, PolicyAction)>::new(), |d, e: &PolicyDecision| {
+        d.push((e.voter_id.to_string(), e.action.clone()));
+    });
+    let counted = apply(&v.v, &mut policy, &mut is);
+    assert_eq!(counted.len(), v.v.len() - 1);
+    assert!(!counted[0].willing_candidate);
+    assert_eq!(counted[3].vote_for.as_deref(), Some("rhai/Carol"));
+    assert_eq!(counted[4].number_of_votes, 3);
+    let decisions = is.into_contexts::<Vec<(String, PolicyAction)>>().remove(0);
+    assert_eq!(decisions.iter().map(|(id, _)|id.as_str()).collect::<Vec<_>>(),
+        vec!["rhai/Alice", "voter#0", "voter#1", "voter#2"]);
+    // Bob's ballot is left as it is, with the failure kept
+    assert!(counted[1].willing_candidate);
+    assert!(matches!(policy.errors(), [ScriptError::Decide { voter_id, .. }] if voter_id == "rhai/Bob"));
+    let result = VoteCounter::new(&counted, Default::default()).tally().unwrap();
+    assert_eq!(result.winner.as_deref(), Some("rhai/Carol"));
+
+    assert!(matches!(RhaiPolicy::new("fn decide(b) {"), Err(ScriptError::Compile(_))));
+    assert!(matches!(RhaiPolicy::new("fn choose(b) { () }"), Err(ScriptError::Compile(_))));
+    let mut forever = RhaiPolicy::new("fn decide(b) { loop {} }").unwrap();
+    let mut unknown = RhaiPolicy::new(r#"fn decide(b) { #{ action: "elect", reason: "" } }"#).unwrap();
+    for policy in [&mut forever, &mut unknown] {
+        assert_eq!(apply(&v.v[..1], policy, &mut Introspector::default()).len(), 1);
+        assert_eq!(policy.errors().len(), 1);
+    }
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn plugin_sink() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use crate::plugin::{subscribe_plugin, PluginSink, PluginError, PLUGIN_ABI_VERSION};
+    static RECORDS: AtomicUsize = AtomicUsize::new(0);
+    static RELEASED: AtomicUsize = AtomicUsize::new(0);
+    extern "C" fn on_record(_: *mut std::ffi::c_void, json: *const u8, len: usize) {
+        assert!(!json.is_null() && len > 0);
+        RECORDS.fetch_add(1, Ordering::SeqCst);
+    }
+    extern "C" fn release(_: *mut std::ffi::c_void) {
+        RELEASED.fetch_add(1, Ordering::SeqCst);
+    }
+    let sink = |abi_version| PluginSink {
+        abi_version,
+        ctx: std::ptr::null_mut(),
+        on_record,
+        release: Some(release),
+    };
+    let mut v = Votes::new("plugin");
+    v.candidate("Alice", "");
+    v.candidate("Bob", "Alice");
+    v.voter("Alice");
+
+    let mut is = crate::Introspector::default();
+    // A sink of another version is never released, its release may not be where it seems
+    assert_eq!(subscribe_plugin(&mut is, sink(0), false), Err(PluginError::AbiVersion(0)));
+    assert_eq!(RELEASED.load(Ordering::SeqCst), 0);
+    subscribe_plugin(&mut is, sink(PLUGIN_ABI_VERSION), false).unwrap();
+    let mut vc = VoteCounter::with_options(&v.v, is, Default::default());
+    vc.find_winner();
+    assert!(RECORDS.load(Ordering::SeqCst) > 0);
+    assert_eq!(RELEASED.load(Ordering::SeqCst), 0);
+    drop(vc);
+    assert_eq!(RELEASED.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn interned_ballots() {
+    use crate::intern::InternedBallots;
+    let mut v = Votes::new("intern");
+    v.candidate("Alice", "");
+    v.candidate("Bob", "Alice");
+    v.voter("Alice");
+    v.voter("Bob");
+    let profile = [("name".to_owned(), "Alice A.".to_owned())].into_iter().collect();
+    v.v[0].metadata = std::borrow::Cow::Owned(profile);
+    let interned = InternedBallots::from_votes(&v.v).unwrap();
+    // Only the ballots which have metadata keep a copy of it
+    assert_eq!(interned.metadata.keys().collect::<Vec<_>>(), vec![&0]);
+    // Each voter plus "intern/", which Alice voted for
+    assert_eq!(interned.ids.len(), 5);
+    assert_eq!(interned.ballots[1].vote_for, Some(interned.ballots[0].voter));
+    assert_eq!(interned.ballots[3].vote_for, Some(interned.ballots[1].voter));
+    let votes = interned.to_votes();
+    assert!(votes.iter().zip(&v.v).all(|(a, b)|a.same_as(b)));
+    let result = VoteCounter::new(&votes, Default::default()).tally().unwrap();
+    assert_eq!(result.winner.as_deref(), Some("intern/Alice"));
+    assert_eq!(result.winner_metadata["name"], "Alice A.");
+}
+
+#[test]
+fn count_by_handle() {
+    use crate::intern::InternedBallots;
+    use crate::introspector::Introspector;
+    use crate::record::{self, EventRecord};
+    use crate::strategies::{self, Seed};
+    use crate::{DuplicatePolicy, UnrecognizedVotePolicy};
+    use std::{cell::RefCell, rc::Rc};
+    let mut v = Votes::new("handles");
+    v.candidate("Alice", "Bob");
+    v.candidate("Bob", "Carol");
+    v.candidate("Carol", "Bob");
+    v.candidate("Dave", "Dave");
+    v.unwilling("Erin", "Frank");
+    v.voter("Carol");
+    v.voter("Alice");
+    v.votes("Alice", 3);
+    v.v[3].fallbacks = vec!["handles/Dave".into(), "handles/Erin".into(), "handles/Alice".into()];
+    let mut v = v.v;
+    v.push(v[1].to_owned_vote());
+    v.extend(strategies::ballots(&mut Seed::new(5), 10, 30, 5));
+    let interned = InternedBallots::from_votes(&v).unwrap();
+    let votes = interned.to_votes();
+    assert!(interned.describes(&votes));
+    assert!(!interned.describes(&votes[1..]));
+    let count = |vc: &mut VoteCounter, records: Rc<RefCell<Vec<EventRecord>>>| {
+        let res = vc.tally();
+        (res, records.take(), vc.invalid_votes().iter().map(|(c, v)|(c.clone(), v.voter_id.to_string())).collect::<Vec<_>>())
+    };
+    for duplicate_voter in [DuplicatePolicy::FirstWins, DuplicatePolicy::LastWins, DuplicatePolicy::MergeWeights] {
+        for unrecognized_vote in [UnrecognizedVotePolicy::Discard, UnrecognizedVotePolicy::ImplicitVoter] {
+            let options = ElectionOptions { duplicate_voter, unrecognized_vote, ..Default::default() };
+            let collect = || {
+                let records = Rc::new(RefCell::new(Vec::new()));
+                let mut is = Introspector::default();
+                let out = records.clone();
+                record::subscribe_all(&mut is, move |r|out.borrow_mut().push(r));
+                (is, records)
+            };
+            let (is, records) = collect();
+            let by_id = count(&mut VoteCounter::with_options(&votes, is, options.clone()), records);
+            let (is, records) = collect();
+            let by_handle = count(&mut VoteCounter::with_interned(&interned, &votes, is, options), records);
+            assert!(!by_id.1.is_empty());
+            assert_eq!(by_id, by_handle);
+        }
+    }
+    // Once a ballot is added, people are found by ID
+    let mut late = Votes::new("handles");
+    late.unwilling("Frank", "Alice");
+    let late = late.v.remove(0);
+    let mut vc = VoteCounter::with_interned(&interned, &votes, Default::default(), Default::default());
+    vc.add_vote(&late);
+    let mut all = votes.iter().map(|v|v.to_owned_vote()).collect::<Vec<_>>();
+    all.push(late.to_owned_vote());
+    assert_eq!(vc.tally(), VoteCounter::new(&all, Default::default()).tally());
+}
+
+#[test]
+fn validate_ballots() {
+    use crate::{BallotError, TallyError, UnrecognizedVotePolicy};
+    let mut v = Votes::new("validate");
+    v.candidate("Alice", "Alice");
+    v.candidate("Bob", "Alice");
+    v.candidate("Bob", "Alice");
+    v.voter("Nobody");
+    let errors = VoteCounter::validate(&v.v, &Default::default()).unwrap();
+    assert_eq!(errors, vec![
+        BallotError::SelfVote { index: 0, voter_id: "validate/Alice".into() },
+        BallotError::Duplicate { index: 2, voter_id: "validate/Bob".into() },
+        BallotError::UnrecognizedVote {
+            index: 3, voter_id: "voter#0".into(), vote_for: "validate/Nobody".into(),
+        },
+    ]);
+    let counted = VoteCounter::new(&v.v, Default::default());
+    assert_eq!(counted.invalid_votes().len(), errors.len());
+    let options = ElectionOptions {
+        unrecognized_vote: UnrecognizedVotePolicy::ImplicitVoter,
+        ..Default::default()
+    };
+    assert_eq!(VoteCounter::validate(&v.v, &options).unwrap().len(), 2);
+    // The limits which refuse the count before any ballot is read
+    let limited = |options: ElectionOptions| VoteCounter::validate(&v.v, &options);
+    assert_eq!(limited(ElectionOptions { max_ballots: Some(3), ..Default::default() }),
+        Err(TallyError::TooManyBallots { count: 4, limit: 3 }));
+    assert_eq!(limited(ElectionOptions { max_id_len: Some(10), ..Default::default() }),
+        Err(TallyError::IdTooLong { ballot: 0, len: 14, limit: 10 }));
+    assert_eq!(limited(ElectionOptions { registry_weight: Some(0), ..Default::default() }),
+        Err(TallyError::InvalidOptions(crate::OptionsError::EmptyRegistry)));
+    // Under a rescale, the ballots are read as they are counted
+    let mut heavy = v.v.iter().map(Vote::to_owned_vote).collect::<Vec<_>>();
+    heavy[1].number_of_votes = crate::limits::MAX_TOTAL_VOTES;
+    heavy[3].number_of_votes = crate::limits::MAX_TOTAL_VOTES;
+    let rescale = ElectionOptions { overflow: crate::OverflowPolicy::Rescale, ..Default::default() };
+    assert_eq!(VoteCounter::validate(&heavy, &rescale), Ok(errors));
+    assert!(matches!(VoteCounter::validate(&heavy, &Default::default()), Err(TallyError::WeightOverflow { .. })));
+}
+
+#[test]
+fn replay_frames() {
+    use std::cell::RefCell;
+    use std::collections::BTreeMap;
+    use std::rc::Rc;
+    use crate::frames::{replay, Stage};
+    use crate::record::{self, EventRecord};
+    let mut v = Votes::new("frames");
+    v.candidate("Alice", "");
+    v.candidate("Bob", "Alice");
+    v.candidate("Carol", "Bob");
+    v.votes("Carol", 3);
+    let records = Rc::new(RefCell::new(Vec::new()));
+    let mut is = crate::Introspector::default();
+    let out = records.clone();
+    record::subscribe_all(&mut is, move |r| out.borrow_mut().push(r));
+    let mut vc = VoteCounter::with_options(&v.v, is, Default::default());
+    vc.find_winner();
+    let expected = vc.iter().map(|(n, v)|(v.voter_id.to_string(), n)).collect::<BTreeMap<_, _>>();
+
+    let r = replay(&v.v, &records.borrow());
+    assert_eq!(r.initial.len(), v.v.len());
+    assert_eq!(r.frames.len(), records.borrow().len());
+    let mut totals = r.initial.iter().cloned().collect::<BTreeMap<_, _>>();
+    for f in &r.frames {
+        totals.extend(f.changes.iter().cloned());
+    }
+    for (id, n) in &expected {
+        assert_eq!(totals.get(id), Some(n), "{}", id);
+    }
+    assert_eq!(r.frames.first().map(|f|f.stage), Some(Stage::Ingestion));
+    let last = r.frames.last().unwrap();
+    assert!(matches!(last.event, EventRecord::Winner { .. }) && last.stage == Stage::Decision);
+}
+
+#[test]
+fn duplicate_policy() {
+    use crate::{DuplicatePolicy, TallyError};
+    let mut v = Votes::new("dup");
+    v.candidate("Alice", "");
+    v.candidate("Bob", "");
+    for (vote_for, votes) in [("dup/Alice", 2), ("dup/Bob", 3)] {
+        v.v.push(Vote {
+            voter_id: "dup/V".into(),
+            vote_for: Some(vote_for.into()),
+            number_of_votes: votes,
+            willing_candidate: false,
+            metadata: Default::default(),
+            fallbacks: Vec::new(),
+            abstain: false,
+        });
+    }
+    let tally = |duplicate_voter| {
+        let options = ElectionOptions { duplicate_voter, ..Default::default() };
+        VoteCounter::with_options(&v.v, Default::default(), options).tally()
+            .map(|r|(r.winner.unwrap_or_default(), r.votes))
+    };
+    assert_eq!(tally(DuplicatePolicy::FirstWins), Ok(("dup/Alice".into(), 3)));
+    assert_eq!(tally(DuplicatePolicy::LastWins), Ok(("dup/Bob".into(), 4)));
+    assert_eq!(tally(DuplicatePolicy::MergeWeights), Ok(("dup/Alice".into(), 6)));
+    assert_eq!(tally(DuplicatePolicy::Error), Err(TallyError::DuplicateVoter("dup/V".into())));
+}
+
+#[test]
+fn teaching_narration() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    let mut v = Votes::new("class");
+    v.candidate("Alice", "");
+    v.candidate("Bob", "Alice");
+    v.voter("Bob");
+    v.voter("Bob");
+    let lines = Rc::new(RefCell::new(Vec::new()));
+    let out = lines.clone();
+    let is = crate::teaching_introspector::with_output(&v.v, move |l| out.borrow_mut().push(l.to_owned()));
+    let mut vc = VoteCounter::with_options(&v.v, is, Default::default());
+    vc.find_winner();
+    drop(vc);
+    let lines = lines.borrow();
+    assert!(lines.contains(&"    Voter           Own  Received   Total".to_owned()));
+    assert!(lines.contains(&"    class/Alice       1         3       4".to_owned()));
+    // Bob gave Alice most of her votes, so the patron rule elects him
+    assert_eq!(lines.last().map(String::as_str), Some("The winner is class/Bob with 3 votes"));
+}
+
+#[test]
+fn borrowed_ballots() {
+    let mut v = Votes::new("borrowed");
+    v.candidate("Alice", "");
+    v.candidate("Bob", "Alice");
+    v.voter("Bob");
+    v.voter("Alice");
+    // As if the ballots were lines of a mapped file
+    let text = v.v.iter()
+        .map(|v|format!("{},{},{},{}", v.voter_id, v.vote_for.as_deref().unwrap_or_default(), v.number_of_votes, v.willing_candidate))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let borrowed = text.lines().map(|line| {
+        let f = line.split(',').collect::<Vec<_>>();
+        Vote::borrowed(f[0], Some(f[1]), f[2].parse().unwrap(), f[3] == "true")
+    }).collect::<Vec<_>>();
+    assert!(borrowed.iter().all(|b|matches!(b.voter_id, std::borrow::Cow::Borrowed(_))));
+    let expected = VoteCounter::new(&v.v, Default::default()).tally().unwrap();
+    assert_eq!(VoteCounter::new(&borrowed, Default::default()).tally().unwrap(), expected);
+}
+
+#[test]
+fn candidate_metadata() {
+    use std::borrow::Cow;
+    use crate::introspector::{Introspector, Winner};
+    use crate::Metadata;
+    let party = |p: &str|Metadata::from([("party".to_owned(), p.to_owned())]);
+    let (green, blue) = (party("Green"), party("Blue"));
+    let mut v = Votes::new("metadata");
+    v.candidate("Alice", "");
+    v.candidate("Bob", "Alice");
+    v.voter("Bob");
+    let votes = v.v.into_iter()
+        .map(|b| {
+            let m = if &*b.voter_id == "metadata/Alice" { &green } else { &blue };
+            b.with_metadata(Cow::Borrowed(m))
+        })
+        .collect::<Vec<_>>();
+    let mut is = Introspector::default();
+    is.subscribe(None, |m: &mut Option<Metadata>, w: &Option<Winner>| {
+        *m = w.as_ref().map(|w|(*w.candidate.metadata).clone());
+    });
+    let mut vc = VoteCounter::new(&votes, is);
+    let res = vc.tally().unwrap();
+    assert_eq!(res.winner.as_deref(), Some("metadata/Bob"));
+    assert_eq!(res.winner_metadata, blue);
+    assert_eq!(vc.into_introspector().into_contexts::<Option<Metadata>>(), vec![Some(blue.clone())]);
+    // Metadata is not part of the count
+    let plain = votes.iter().map(|b|b.to_owned_vote().with_metadata(Cow::Owned(Metadata::new()))).collect::<Vec<_>>();
+    let plain_res = VoteCounter::new(&plain, Default::default()).tally().unwrap();
+    assert_eq!((plain_res.winner, plain_res.votes), (res.winner, res.votes));
+}
+
+#[test]
+fn parallel_tie_break_hashes() {
+    let names = (0..10_000).map(|i|format!("c{i}")).collect::<Vec<_>>();
+    let keys = names.iter().map(|n|(n.as_str(), 0)).collect::<Vec<_>>();
+    let serial = crate::tie_breaker_hashes(&keys, &[], 1);
+    for threads in [2, 3, 8, 64] {
+        assert!(crate::tie_breaker_hashes(&keys, &[], threads) == serial, "{threads} threads");
+    }
+    // Enough tied candidates to take the parallel path, all with zero votes
+    let votes = (0..10_000).map(|i|Vote {
+        voter_id: format!("c{i}").into(),
+        vote_for: None,
+        number_of_votes: 0,
+        willing_candidate: true,
+        metadata: Default::default(),
+        fallbacks: Vec::new(),
+        abstain: false,
+    }).collect::<Vec<_>>();
+    let mut order = (0..keys.len()).collect::<Vec<_>>();
+    order.sort_by(|&a, &b|serial[a].cmp(&serial[b]).then(a.cmp(&b)));
+    let winner = VoteCounter::new(&votes, Default::default()).find_winner().map(|w|w.voter_id.to_string());
+    assert_eq!(winner, Some(names[order[0]].clone()));
+}
+
+#[test]
+fn election_from_iterator() {
+    use crate::Election;
+    let mut v = Votes::new("from_iter");
+    v.candidate("Alice", "");
+    v.candidate("Bob", "Alice");
+    v.votes("Bob", 3);
+    let text = v.v.iter()
+        .map(|v|format!("{},{},{}", v.voter_id, v.vote_for.as_deref().unwrap_or_default(), v.number_of_votes))
+        .collect::<Vec<_>>()
+        .join("\n");
+    // Parsed one line at a time, the lines are never collected into a Vec
+    let election = text.lines()
+        .map(|line| {
+            let f = line.split(',').collect::<Vec<_>>();
+            Vote::borrowed(f[0], Some(f[1]), f[2].parse().unwrap(), !f[0].starts_with("voter#"))
+        })
+        .collect::<Election>();
+    drop(text);
+    let expected = VoteCounter::new(&v.v, Default::default()).tally().unwrap();
+    assert_eq!(election.tally().unwrap(), expected);
+    let options = ElectionOptions { max_ballots: Some(2), ..Default::default() };
+    let limited = Election::from_ballots(v.v.iter().map(Vote::to_owned_vote), options);
+    assert_eq!(limited.tally(), Err(crate::TallyError::TooManyBallots { count: 3, limit: 2 }));
+}
+
+#[test]
+fn parallel_validation() {
+    use crate::parallel::{self, Parallelism};
+    use crate::{strategies, worst_case};
+    let options = ElectionOptions::default();
+    for votes in [worst_case::all_tied(5000), strategies::ballots(&mut strategies::Seed::new(3), 50, 200, 4)] {
+        let serial = {
+            let mut vc = VoteCounter::new(&votes, Default::default());
+            vc.set_parallelism(Parallelism::Serial);
+            vc.tally()
+        };
+        assert_eq!(parallel::validate(&votes, &options), Ok(serial.clone()));
+        let mut vc = VoteCounter::new(&votes, Default::default());
+        vc.set_parallelism(Parallelism::Validate);
+        assert_eq!(vc.tally(), serial);
+    }
+}
+
+#[test]
+fn overflow_rescale() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use crate::{OverflowPolicy, TallyError, limits::MAX_TOTAL_VOTES};
+    use crate::record::{self, EventRecord};
+    let mut v = Votes::new("overflow");
+    v.candidate("Alice", "");
+    v.candidate("Bob", "");
+    v.votes("Alice", Weight::MAX / 4);
+    v.v[0].number_of_votes = Weight::MAX / 2;
+    v.v[1].number_of_votes = Weight::MAX / 2 + 10;
+    let total = v.v.iter().map(|v|crate::types::widen(v.number_of_votes)).fold(0, u128::saturating_add);
+    let res = VoteCounter::new(&v.v, Default::default()).tally();
+    assert_eq!(res, Err(TallyError::WeightOverflow { total, limit: MAX_TOTAL_VOTES }));
+
+    v.options.overflow = OverflowPolicy::Rescale;
+    let records = Rc::new(RefCell::new(Vec::new()));
+    let mut is = crate::Introspector::default();
+    let sink = records.clone();
+    record::subscribe_all(&mut is, move |r| sink.borrow_mut().push(r));
+    let res = VoteCounter::with_options(&v.v, is, v.options.clone()).tally().unwrap();
+    assert_eq!(res.weight_shift, 3);
+    assert_eq!(records.borrow()[0], EventRecord::WeightsRescaled { shift: 3 });
+    assert_eq!(res.winner.as_deref(), Some("overflow/Alice"));
+    // The same as counting the shifted weights
+    let shifted = v.v.iter().map(|b| {
+        let mut b = b.to_owned_vote();
+        b.number_of_votes >>= 3;
+        b
+    }).collect::<Vec<_>>();
+    let expected = VoteCounter::new(&shifted, Default::default()).tally().unwrap();
+    assert_eq!((res.winner, res.votes, res.contributions), (expected.winner, expected.votes, expected.contributions));
+    v.expect_win("Alice");
+}
+
+#[cfg(feature = "wide-weights")]
+#[test]
+fn wide_weights() {
+    // 18 decimal token balances, far more than a u64 holds, where Alice wins by one unit
+    let token: Weight = 10_u128.pow(18);
+    let mut v = Votes::new("wide");
+    v.candidate("Alice", "");
+    v.candidate("Bob", "Alice");
+    v.candidate("Carol", "");
+    v.v[0].number_of_votes = 20_000_000 * token + 1;
+    v.v[1].number_of_votes = 14_000_000 * token + 1;
+    v.v[2].number_of_votes = 34_000_000 * token + 1;
+    let res = VoteCounter::new(&v.v, Default::default()).tally().unwrap();
+    assert_eq!((res.winner.as_deref(), res.votes, res.weight_shift), (Some("wide/Alice"), 34_000_000 * token + 2, 0));
+    assert_eq!(res.contributions.supporters, vec![("wide/Bob".to_owned(), 14_000_000 * token + 1)]);
+    v.expect_win("Alice");
+    let archived = crate::archive::decompress(&crate::archive::compress(&v.v)).unwrap();
+    assert!(archived.iter().zip(&v.v).all(|(a, b)|a.same_as(b)));
+}
+
+#[test]
+fn vote_like_ballots() {
+    use crate::vote_like::{self, VoteLike};
+    struct Member {
+        id: String,
+        proxy: Option<String>,
+        shares: Weight,
+        standing: bool,
+    }
+    impl VoteLike for Member {
+        fn voter_id(&self) -> &str {
+            &self.id
+        }
+        fn vote_for(&self) -> Option<&str> {
+            self.proxy.as_deref()
+        }
+        fn weight(&self) -> Weight {
+            self.shares
+        }
+        fn willing(&self) -> bool {
+            self.standing
+        }
+    }
+    let mut v = Votes::new("vote_like");
+    v.candidate("Alice", "");
+    v.candidate("Bob", "Alice");
+    v.votes("Bob", 3);
+    let members = v.v.iter().map(|b|Member {
+        id: b.voter_id.to_string(),
+        proxy: b.vote_for.as_deref().filter(|p|!p.ends_with('/')).map(str::to_owned),
+        shares: b.number_of_votes,
+        standing: b.willing_candidate,
+    }).collect::<Vec<_>>();
+    let votes = vote_like::borrow_all(&members);
+    assert!(votes.iter().all(|b|matches!(b.voter_id, std::borrow::Cow::Borrowed(_))));
+    let expected = VoteCounter::new(&v.v, Default::default()).tally().unwrap();
+    assert_eq!(vote_like::tally(&members, Default::default()).unwrap(), expected);
+    assert_eq!(vote_like::tally(&v.v, Default::default()).unwrap(), expected);
+}
+
+#[test]
+fn nomination_deadline() {
+    use crate::introspector::{Introspector, PolicyDecision};
+    use crate::policy::{apply, NominationDeadline, PolicyAction};
+    let mut v = Votes::new("deadline");
+    v.candidate("Alice", "");
+    v.candidate("Bob", "Alice");
+    v.candidate("Carol", "");
+    v.votes("Carol", 4);
+    v.votes("Alice", 2);
+    // Carol flipped her flag after the early standings were published
+    let mut deadline = NominationDeadline::new(100)
+        .nominated("deadline/Alice", 10)
+        .nominated("deadline/Bob", 100)
+        .nominated("deadline/Carol", 101);
+    let mut is = Introspector::default();
+    is.subscribe(Vec::new(), |d: &mut Vec<String>, e: &PolicyDecision| {
+        assert!(matches!(e.action, PolicyAction::Ineligible { .. }));
+        d.push(e.voter_id.clone());
+    });
+    let counted = apply(&v.v, &mut deadline, &mut is);
+    assert_eq!(is.into_contexts::<Vec<String>>(), vec![vec!["deadline/Carol".to_owned()]]);
+    assert_eq!(counted.iter().filter(|v|v.willing_candidate).count(), 2);
+    v.expect_win("Carol");
+    let result = VoteCounter::new(&counted, Default::default()).tally().unwrap();
+    assert_eq!(result.winner.as_deref(), Some("deadline/Alice"));
+}
+
+#[test]
+fn committee_seats() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use crate::record::{self, EventRecord};
+    let mut v = Votes::new("committee");
+    v.candidate("Alice", "Carol");
+    v.candidate("Bob", "");
+    v.candidate("Carol", "");
+    v.votes("Alice", 5);
+    v.votes("Bob", 4);
+    v.votes("Carol", 1);
+    let records = Rc::new(RefCell::new(Vec::new()));
+    let mut is = crate::Introspector::default();
+    let sink = records.clone();
+    record::subscribe_all(&mut is, move |r| sink.borrow_mut().push(r));
+    let mut vc = VoteCounter::new(&v.v, is);
+    let seats = vc.find_winners(5).iter().map(|w|w.voter_id.to_string()).collect::<Vec<_>>();
+    // Alice is Carol's patron, once Alice is seated her votes pass on to Carol
+    assert_eq!(seats, vec!["committee/Alice", "committee/Carol", "committee/Bob"]);
+    let seat_records = records.borrow().iter().filter_map(|r|match r {
+        EventRecord::Seat { seat, candidate, votes } => Some((*seat, candidate.clone(), *votes)),
+        _ => None,
+    }).collect::<Vec<_>>();
+    assert_eq!(seat_records, vec![
+        (1, "committee/Alice".to_owned(), 6),
+        (2, "committee/Carol".to_owned(), 8),
+        (3, "committee/Bob".to_owned(), 5),
+    ]);
+    assert!(VoteCounter::new(&v.v, Default::default()).find_winners(0).is_empty());
+    assert_eq!(VoteCounter::new(&v.v, Default::default()).find_winners(1), vec![&v.v[0]]);
+}
+
+#[test]
+fn final_standings() {
+    let mut v = Votes::new("standings");
+    v.candidate("Alice", "Carol");
+    v.candidate("Bob", "");
+    v.candidate("Carol", "");
+    v.candidate("Dave", "");
+    v.votes("Alice", 5);
+    v.votes("Bob", 4);
+    v.votes("Carol", 1);
+    v.votes("Dave", 4);
+    let vc = VoteCounter::new(&v.v, Default::default());
+    let standings = vc.standings().unwrap();
+    let order = standings.iter()
+        .map(|s|(s.position, s.candidate.voter_id.to_string(), s.votes, s.tied_with.len()))
+        .collect::<Vec<_>>();
+    // iter() would put Carol first with all of Alice's votes, but Alice is Carol's patron
+    assert_eq!(vc.iter().next().map(|(_, v)|v), Some(&v.v[2]));
+    assert_eq!(order[0], (1, "standings/Alice".to_owned(), 6, 0));
+    assert_eq!(order[1], (2, "standings/Carol".to_owned(), 8, 0));
+    // Bob and Dave are tied and the loser of the tie-break takes the next position
+    assert_eq!(standings[2].votes, 5);
+    assert_eq!(standings[2].tied_with.len(), 1);
+    assert_eq!(standings[3].votes, 5);
+    assert_eq!(standings[3].candidate, standings[2].tied_with[0]);
+    assert_eq!(standings.len(), 4);
+
+    // The same after ballots are added and removed without reading them again
+    let summary = |standings: Vec<crate::Standing>| standings.into_iter()
+        .map(|s|(s.position, s.candidate.voter_id.to_string(), s.votes,
+            s.tied_with.iter().map(|v|v.voter_id.to_string()).collect::<Vec<_>>()))
+        .collect::<Vec<_>>();
+    let mut late = Votes::new("standings");
+    late.candidate("Erin", "");
+    late.v[0].vote_for = None;
+    let mut vc = VoteCounter::new(&v.v, Default::default());
+    vc.add_vote(&late.v[0]);
+    assert!(vc.remove_vote("voter#0"));
+    let mut remaining = v.v.iter().filter(|v|v.voter_id != "voter#0").map(Vote::to_owned_vote).collect::<Vec<_>>();
+    remaining.push(late.v[0].to_owned_vote());
+    let expected = summary(VoteCounter::new(&remaining, Default::default()).standings().unwrap());
+    assert_eq!(summary(vc.standings().unwrap()), expected);
+    assert_eq!(expected.len(), 5);
+}
+
+#[test]
+fn determinism_selftest() {
+    let mut v = Votes::new("selftest");
+    for (i, name) in ["Alice", "Bob", "Carol", "Dave", "Erin", "Frank"].iter().enumerate() {
+        v.candidate(name, if i % 2 == 0 { "Bob" } else { "Erin" });
+        v.votes(name, 3);
+    }
+    v.candidate("Bob", "Erin");
+    v.candidate("Erin", "Bob");
+    // Under FirstWins the first of Carol's ballots counts, in every order
+    v.candidate("Carol", "Alice");
+    v.votes("Carol", 9);
+    v.candidate("Carol", "Frank");
+    let report = crate::selftest::run(&v.v, &Default::default(), 7);
+    assert_eq!(report.runs.len(), 7);
+    assert!(report.is_reproducible());
+    assert!(report.runs.iter().all(|r|r.digest == report.runs[0].digest));
+    let text = report.to_string();
+    assert!(text.contains("REPRODUCIBLE: all 7 runs are identical"), "{text}");
+    // A run which differs is reported
+    let mut bad = report.clone();
+    bad.runs[3].fingerprint = vec![0];
+    assert_eq!(bad.mismatches(), vec![3]);
+    assert!(bad.to_string().contains("NOT REPRODUCIBLE: 1 of 7"));
+}
+
+#[test]
+fn ranked_fallbacks() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use crate::introspector::FallbackCause;
+    use crate::record::{self, EventRecord};
+    let mut v = Votes::new("fallback");
+    v.candidate("Alice", "");
+    v.candidate("Bob", "");
+    v.votes("Alice", 3);
+    v.votes("Bob", 1);
+    v.v.push(Vote::borrowed("fallback/Dan", None, 1, false));
+    v.v.push(Vote::borrowed("ranked", Some("fallback/Zed"), 4, false));
+    v.expect_win("Alice");
+    let s = |id: &str| format!("fallback/{id}");
+    let last = v.v.len() - 1;
+    v.v[last].fallbacks = vec![s("Dan").into(), s("Bob").into(), s("Alice").into()];
+    let records = Rc::new(RefCell::new(Vec::new()));
+    let mut is = crate::Introspector::default();
+    let sink = records.clone();
+    record::subscribe_all(&mut is, move |r| sink.borrow_mut().push(r));
+    let mut vc = VoteCounter::new(&v.v, is);
+    assert_eq!(vc.find_winner().map(|w|&*w.voter_id), Some("fallback/Bob"));
+    drop(vc);
+    let fallbacks = records.borrow().iter().filter_map(|r|match r {
+        EventRecord::Fallback { skipped, cause, next, .. } => Some((skipped.clone(), *cause, next.clone())),
+        _ => None,
+    }).collect::<Vec<_>>();
+    assert_eq!(fallbacks, vec![
+        (s("Zed"), FallbackCause::Unrecognized, Some(s("Dan"))),
+        (s("Dan"), FallbackCause::Unwilling, Some(s("Bob"))),
+    ]);
+    // Fallbacks change the count, so they are archived and part of the fingerprint
+    let restored = crate::archive::decompress(&crate::archive::compress(&v.v)).unwrap();
+    assert!(restored.iter().any(|r|r.same_as(&v.v[last])));
+    let mut one_choice = crate::archive::decompress(&crate::archive::compress(&v.v)).unwrap();
+    one_choice.iter_mut().for_each(|r|r.fallbacks.clear());
+    assert_ne!(crate::signing::ballots_fingerprint(&one_choice), crate::signing::ballots_fingerprint(&v.v));
+    // Once Bob holds a seat the vote falls through to Alice
+    let seats = VoteCounter::new(&v.v, Default::default()).find_winners(2);
+    assert_eq!(seats.iter().map(|w|&*w.voter_id).collect::<Vec<_>>(), vec!["fallback/Bob", "fallback/Alice"]);
+}
+
+#[test]
+fn detached_view() {
+    use crate::record::EventRecord;
+    use crate::view::TallyView;
+    fn send_sync<T: Send + Sync>(_: &T) {}
+    let view: TallyView = {
+        let mut v = Votes::new("view");
+        v.candidate("Alice", "Bob");
+        v.candidate("Bob", "");
+        v.candidate("Carol", "");
+        v.votes("Bob", 2);
+        v.votes("Carol", 4);
+        let vc = VoteCounter::new(&v.v, Default::default());
+        vc.view()
+    };
+    // The ballots are gone and the view can be shared between threads
+    send_sync(&view);
+    let shared = view.clone();
+    let winner = std::thread::spawn(move||shared.winner().map(str::to_owned)).join().unwrap();
+    assert_eq!(winner.as_deref(), Some("view/Carol"));
+    assert_eq!(view.result().map(|r|r.votes), Ok(5));
+    assert_eq!(view.ranking()[1], ("view/Bob".to_owned(), 4));
+    let alice = view.candidate("view/Alice").unwrap();
+    assert_eq!(alice.vote_for.as_deref(), Some("view/Bob"));
+    assert_eq!(alice.rank, Some(2));
+    assert!(view.candidate("view/Dave").is_none());
+    assert!(matches!(view.trail().last(), Some(EventRecord::Winner { .. })));
+}
+
+#[test]
+fn owned_conversions() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use crate::introspector::Winner;
+    use crate::owned::{IntoOwned, OwnedWinner};
+    let winner: Rc<RefCell<Option<OwnedWinner>>> = Default::default();
+    let (rings, page, standings, info) = {
+        let mut v = Votes::new("owned");
+        v.candidate("Alice", "Bob");
+        v.candidate("Bob", "Alice");
+        v.candidate("Carol", "Alice");
+        v.votes("Carol", 2);
+        let mut is = crate::Introspector::default();
+        is.subscribe(winner.clone(), |w, e: &Option<Winner>| *w.borrow_mut() = e.as_ref().to_owned_ids());
+        // A ballot becomes its voter_id, which is not the same as copying it
+        assert_eq!((&v.v[0]).to_owned_ids(), "owned/Alice");
+        assert!(v.v[0].to_owned_vote().into_owned().same_as(&v.v[0]));
+        let mut vc = VoteCounter::new(&v.v, is);
+        vc.find_winner();
+        (
+            vc.rings().to_owned_ids(),
+            vc.ranking_page(0, 2).to_owned_ids(),
+            vc.standings().unwrap().to_owned_ids(),
+            vc.search_prefix("owned/Carol").to_owned_ids(),
+        )
+    };
+    // Everything outlives the ballots
+    assert_eq!(rings, vec![vec!["owned/Alice".to_owned(), "owned/Bob".to_owned()]]);
+    assert_eq!(page.total, 3);
+    assert_eq!(page.entries.len(), 2);
+    assert_eq!(info[0].vote_for.as_deref(), Some("owned/Alice"));
+    let winner = winner.take().unwrap();
+    assert_eq!(winner.candidate, standings[0].candidate);
+    assert_eq!(winner.votes, standings[0].votes);
+    assert_eq!(winner.contributions.own, 1);
+    assert!(!winner.contributions.supporters.is_empty());
+}
+
+#[test]
+fn split_delegation() {
+    use crate::ingest::{split_ballot, SplitError};
+    let mut v = Votes::new("split");
+    v.candidate("Alice", "");
+    v.candidate("Bob", "");
+    v.votes("Alice", 5);
+    v.votes("Bob", 4);
+    let whale = Vote::borrowed("whale", None, 10, false);
+    let parts = split_ballot(&v.v, &whale, &[("split/Alice", 60), ("split/Bob", 40)]).unwrap();
+    let weights = parts.iter()
+        .map(|p|(&*p.voter_id, p.vote_for.as_deref(), p.number_of_votes))
+        .collect::<Vec<_>>();
+    assert_eq!(weights, vec![("whale", Some("split/Alice"), 6), ("whale#1", Some("split/Bob"), 4)]);
+    // Alice 1 + 5 + 6, Bob 1 + 4 + 4
+    v.v.extend(parts);
+    v.expect_win("Alice");
+    v.v.truncate(v.v.len() - 2);
+    let parts = split_ballot(&v.v, &whale, &[("split/Alice", 10), ("split/Bob", 90)]).unwrap();
+    v.v.extend(parts);
+    v.expect_win("Bob");
+    v.v.truncate(v.v.len() - 2);
+    // The votes which are left after rounding down go to the largest remainders
+    let thirds = split_ballot(&v.v, &whale, &[("a", 1), ("b", 1), ("c", 1)]).unwrap();
+    assert_eq!(thirds.iter().map(|p|p.number_of_votes).collect::<Vec<_>>(), vec![4, 3, 3]);
+    assert_eq!(split_ballot(&v.v, &whale, &[("a", 0)]).unwrap_err(), SplitError::NoShares);
+    assert_eq!(split_ballot(&v.v, &whale, &[("whale", 1)]).unwrap_err(), SplitError::SelfShare);
+
+    // The shares add up to number_of_votes however large the ballot and the parts are
+    let big = u64::MAX;
+    let mut ballot = whale.to_owned_vote();
+    for number_of_votes in [0, 1, 10, Weight::from(big), Weight::MAX / 3, Weight::MAX] {
+        ballot.number_of_votes = number_of_votes;
+        for parts in [&[1, 1, 1][..], &[big, 0], &[big - 1, 1], &[big / 2, big / 2], &[big / 3; 3], &[3, 0, 5]] {
+            let shares = parts.iter().enumerate().map(|(i, &p)|(["a", "b", "c"][i], p)).collect::<Vec<_>>();
+            let split = split_ballot(&v.v, &ballot, &shares).unwrap();
+            assert_eq!(split.iter().map(|p|p.number_of_votes).sum::<Weight>(), number_of_votes, "{parts:?}");
+        }
+        let shares = [("a", big), ("b", big), ("c", big)];
+        assert_eq!(split_ballot(&v.v, &ballot, &shares).unwrap_err(), SplitError::TooManyParts);
+    }
+
+    // Every share keeps the fallbacks, Carol is not a candidate so the share for Carol goes
+    // to Bob, 1 + 4 + 6 against 1 + 5 + 4
+    let hedged = whale.to_owned_vote().with_fallbacks(vec!["split/Bob".into()]);
+    let parts = split_ballot(&v.v, &hedged, &[("split/Carol", 60), ("split/Alice", 40)]).unwrap();
+    assert!(parts.iter().all(|p|p.fallbacks == ["split/Bob"]));
+    v.v.extend(parts);
+    v.expect_win("Bob");
+    v.v.truncate(v.v.len() - 2);
+
+    // Only a voter who nobody delegates to and who is not a candidate can be split
+    let candidate = Vote::borrowed("split/Alice", None, 1, true);
+    assert_eq!(split_ballot(&v.v, &candidate, &[("split/Bob", 1)]).unwrap_err(), SplitError::Candidate);
+    let delegator = Vote::borrowed("minnow", Some("whale"), 1, false);
+    v.v.push(delegator.to_owned_vote());
+    assert_eq!(
+        split_ballot(&v.v, &whale, &[("split/Alice", 1)]).unwrap_err(),
+        SplitError::Delegated("minnow".into()),
+    );
+    v.v.pop();
+    v.v.push(delegator.with_fallbacks(vec!["whale".into()]));
+    assert_eq!(
+        split_ballot(&v.v, &whale, &[("split/Alice", 1)]).unwrap_err(),
+        SplitError::Delegated("minnow".into()),
+    );
+    v.v.pop();
+
+    // A share ID which is already used is refused, as a voter or as someone voted for
+    for taken in [Vote::borrowed("whale#1", None, 1, false), Vote::borrowed("x", Some("whale#1"), 1, false)] {
+        v.v.push(taken);
+        assert_eq!(
+            split_ballot(&v.v, &whale, &[("split/Alice", 1), ("split/Bob", 1)]).unwrap_err(),
+            SplitError::IdTaken("whale#1".into()),
+        );
+        v.v.pop();
+    }
+}
+
+#[test]
+fn expect_events() {
+    use crate::expect::{Expect, ExpectEvents};
+    use crate::introspector::InvalidVoteCause;
+    let mut v = Votes::new("expect");
+    v.candidate("Alice", "Bob");
+    v.candidate("Bob", "Alice");
+    v.candidate("Carol", "Carol");
+    v.votes("Alice", 3);
+    let mut is = crate::Introspector::default();
+    let events = ExpectEvents::subscribe(&mut is);
+    VoteCounter::new(&v.v, is).find_winner();
+    events.check(&[
+        Expect::invalid_vote(InvalidVoteCause::SelfVote),
+        Expect::delegation("expect/Alice", "expect/Bob"),
+        Expect::best_ring(2),
+        Expect::winner(Some("expect/Alice")),
+    ]).unwrap();
+    // The order matters
+    let err = events.check(&[
+        Expect::winner(Some("expect/Alice")),
+        Expect::best_ring(2),
+    ]).unwrap_err();
+    assert_eq!((err.missing.as_str(), err.found), ("BestRing with 2 members", 1));
+    assert_eq!(err.events, events.events());
+    assert!(events.check(&[Expect::winner(Some("expect/Bob"))]).is_err());
+}
+
+#[test]
+fn quorum() {
+    use crate::expect::{Expect, ExpectEvents};
+    use crate::record::EventRecord;
+    use crate::TallyError;
+    let mut v = Votes::new("quorum_rule");
+    v.candidate("Alice", "");
+    v.votes("Alice", 3);
+    // 2 ballots carrying 4 votes
+    let count = |options: ElectionOptions| {
+        let mut is = crate::Introspector::default();
+        let events = ExpectEvents::subscribe(&mut is);
+        let res = VoteCounter::with_options(&v.v, is, options).try_find_winner().map(|w|w.is_some());
+        (res, events)
+    };
+    let (res, _) = count(ElectionOptions { min_ballots: Some(2), min_votes: Some(4), ..Default::default() });
+    assert_eq!(res, Ok(true));
+    let (res, events) = count(ElectionOptions { min_ballots: Some(3), ..Default::default() });
+    assert_eq!(res, Err(TallyError::NoQuorum { ballots: 2, votes: 4 }));
+    events.check(&[Expect::matching("NoQuorum", |r|matches!(r, EventRecord::NoQuorum { .. }))]).unwrap();
+    assert!(events.check(&[Expect::winner(None)]).is_err());
+    let (res, _) = count(ElectionOptions { min_votes: Some(5), ..Default::default() });
+    assert_eq!(res, Err(TallyError::NoQuorum { ballots: 2, votes: 4 }));
+    let mut vc = VoteCounter::with_options(&v.v, Default::default(),
+        ElectionOptions { min_votes: Some(5), ..Default::default() });
+    assert!(vc.find_winner().is_none());
+}
+
+#[test]
+fn abstention() {
+    use crate::expect::{Expect, ExpectEvents};
+    use crate::record::EventRecord;
+    let mut v = Votes::new("abstention");
+    v.candidate("Alice", "");
+    v.candidate("Bob", "");
+    v.votes("Bob", 2);
+    // Carol abstains though she names Alice and is willing, neither counts
+    v.v.push(Vote::borrowed("Carol", Some("abstention/Alice"), 5, true).as_abstention());
+    let count = |options: ElectionOptions| {
+        let mut is = crate::Introspector::default();
+        let events = ExpectEvents::subscribe(&mut is);
+        let res = VoteCounter::with_options(&v.v, is, options).tally();
+        (res, events)
+    };
+    let (res, events) = count(ElectionOptions { min_ballots: Some(4), min_votes: Some(9), ..Default::default() });
+    let res = res.unwrap();
+    assert_eq!(res.winner.as_deref(), Some("abstention/Bob"));
+    assert_eq!(res.votes, 3);
+    let e = &res.engagement;
+    assert_eq!((e.valid_ballots, e.valid_votes, e.abstentions, e.abstaining_votes), (4, 9, 1, 5));
+    assert_eq!((e.delegating_ballots, e.candidates), (1, 2));
+    events.check(&[
+        Expect::matching("Abstention of Carol", |r|
+            matches!(r, EventRecord::Abstention { voter_id, votes: 5 } if voter_id == "Carol")),
+        Expect::winner(Some("abstention/Bob")),
+    ]).unwrap();
+    assert!(events.check(&[Expect::invalid_vote(crate::introspector::InvalidVoteCause::NoVote)]).is_err());
+    // Without Carol there is no quorum
+    let (res, _) = count(ElectionOptions { min_votes: Some(10), ..Default::default() });
+    assert!(res.is_err());
+    let copy = crate::archive::decompress(&crate::archive::compress(&v.v)).unwrap();
+    assert!(copy.iter().any(|c|c.voter_id == "Carol" && c.abstain && c.willing_candidate));
+    assert_eq!(copy.iter().filter(|c|c.abstain).count(), 1);
+}
+
+#[test]
+fn scenario_text() {
+    use crate::scenario::{self, ScenarioError, ScenarioProblem};
+    let votes = scenario::parse("
+        # The corpus scenario of charlie_is_patron
+        Alice 1 Bob
+        Bob 2 Alice
+        Charlie 5 Alice
+    ").unwrap();
+    let mut vc = VoteCounter::new(&votes, Default::default());
+    assert_eq!(vc.find_winner().map(|w|w.voter_id.to_string()), Some("Charlie".into()));
+
+    let votes = scenario::parse("Alice 3\nBob 1 -\nCarol 4 Bob voter\n").unwrap();
+    assert_eq!(votes.iter().map(|v|(v.vote_for.as_deref(), v.willing_candidate)).collect::<Vec<_>>(),
+        vec![(None, true), (None, true), (Some("Bob"), false)]);
+    let again = scenario::parse(&scenario::to_text(&votes)).unwrap();
+    assert!(again.iter().zip(&votes).all(|(a, b)|a.same_as(b)));
+
+    let err = |line, problem| Err(ScenarioError { line, problem });
+    assert_eq!(scenario::parse("Alice\n").map(|_|()), err(1, ScenarioProblem::Words));
+    assert_eq!(scenario::parse("\nAlice many Bob\n").map(|_|()), err(2, ScenarioProblem::Votes));
+    assert_eq!(scenario::parse("Alice 1 Bob maybe\n").map(|_|()), err(1, ScenarioProblem::Role));
+}
+
+#[test]
+fn explain_result() {
+    use crate::explain::Sentence;
+    use crate::record::PatronOutcome;
+    let votes = crate::scenario::parse("
+        Alice 1 Bob
+        Bob 2 Alice
+        Charlie 5 Alice
+        Dave 3 Bob
+        Erin 1 Erin
+    ").unwrap();
+    let explanation = VoteCounter::new(&votes, Default::default()).view().explain();
+    assert_eq!(explanation.sentences, vec![
+        Sentence::Invalid { cause: crate::introspector::InvalidVoteCause::SelfVote, ballots: 1 },
+        Sentence::Won { candidate: "Alice".into(), votes: 11 },
+        Sentence::Ring { members: vec!["Alice".into(), "Bob".into()], votes: 11 },
+        Sentence::OutscoredInRing { candidate: "Bob".into(), votes: 5, by: vec!["Alice".into()] },
+        Sentence::NotPatron {
+            candidate: "Charlie".into(), votes: 5, outcome: PatronOutcome::NotProvidingMajority(5),
+        },
+    ]);
+    assert_eq!(explanation.candidates(), vec!["Alice", "Bob", "Charlie"]);
+    let text = explanation.to_string();
+    assert!(text.starts_with("1 ballot voted for themselves, so their votes were not delegated.\n\n\
+        Alice won with 11 delegated votes. Alice and Bob delegated to one another"), "{text}");
+    assert!(text.ends_with("Charlie could not act as patron because their 5 votes were not more than 5, \
+        half of the ring's votes."), "{text}");
+}
+
+#[test]
+fn alias_tables() {
+    use crate::alias::{apply, Aliases, AliasTable};
+    use crate::expect::{Expect, ExpectEvents};
+    use crate::introspector::{Introspector, InvalidVoteCause};
+    use crate::record::EventRecord;
+    let votes = crate::scenario::parse("
+        Alice 1 Bob
+        Bob 2
+        Carol 1 1042 voter
+        Dave 3 dave@example.org voter
+        Erin 1 Alice voter
+        Frank 1 9999 voter
+    ").unwrap();
+    let aliases = Aliases::new()
+        .table(AliasTable::new("member numbers").alias("1042", "Bob").alias("Alice", "Bob"))
+        .table(AliasTable::new("emails").alias("dave@example.org", "Alice"));
+    let mut is = Introspector::default();
+    let events = ExpectEvents::subscribe(&mut is);
+    let counted = apply(&votes, &aliases, &mut is);
+    assert_eq!(counted.iter().map(|v|v.vote_for.as_deref()).collect::<Vec<_>>(),
+        vec![Some("Bob"), None, Some("Bob"), Some("Alice"), Some("Alice"), Some("9999")]);
+    let applied = |voter: &'static str, table: &'static str| Expect::matching(voter, move |r|
+        matches!(r, EventRecord::AliasApplied { voter_id, table: t, .. } if voter_id == voter && t == table));
+    events.check(&[applied("Carol", "member numbers"), applied("Dave", "emails")]).unwrap();
+    // Alice has a ballot, so her name is not an alias
+    assert_eq!(events.events().len(), 2);
+    let mut is = Introspector::default();
+    let events = ExpectEvents::subscribe(&mut is);
+    let result = VoteCounter::new(&counted, is).tally().unwrap();
+    assert_eq!(result.winner.as_deref(), Some("Alice"));
+    events.check(&[Expect::invalid_vote(InvalidVoteCause::UnrecognizedVote)]).unwrap();
+}
+
+#[test]
+fn salted_tie_break() {
+    use crate::options::OptionsError;
+    let votes = crate::scenario::parse("Alice 4 Bob\nBob 4 Alice\n").unwrap();
+    let winner = |salt: Option<Vec<u8>>| {
+        let options = ElectionOptions { tie_break_salt: salt, ..Default::default() };
+        VoteCounter::with_options(&votes, Default::default(), options).tally().map(|r|r.winner)
+    };
+    let unsalted = winner(None).unwrap();
+    assert!(unsalted.is_some());
+    // Each salt decides the tie independently, so some salt must decide it the other way
+    let flipped = (0_u8..32).find(|&b|winner(Some(vec![b; 32])).unwrap() != unsalted);
+    assert!(flipped.is_some());
+    let salt = vec![flipped.unwrap_or(0); 32];
+    assert_eq!(winner(Some(salt.clone())), winner(Some(salt.clone())));
+
+    let mut is = crate::Introspector::default();
+    is.subscribe(Vec::new(), |b: &mut Vec<Vec<u8>>, e: &crate::introspector::DeterministicTieBreakerHash| {
+        b.push(e.bytes.clone());
+    });
+    let options = ElectionOptions { tie_break_salt: Some(salt.clone()), ..Default::default() };
+    let mut vc = VoteCounter::with_options(&votes, is, options);
+    vc.find_winner();
+    let hashed = vc.is.into_contexts::<Vec<Vec<u8>>>().remove(0);
+    assert!(!hashed.is_empty() && hashed.iter().all(|b|b.ends_with(&salt)));
+
+    assert_eq!(winner(Some(Vec::new())), Err(crate::TallyError::InvalidOptions(OptionsError::EmptySalt)));
+}
+
+#[test]
+fn quarantine_sink() {
+    use std::cell::RefCell;
+    use std::ops::ControlFlow;
+    use std::rc::Rc;
+    use crate::bulk::{ingest, Format};
+    use crate::introspector::{Introspector, InvalidVoteCause};
+    use crate::live::LiveTally;
+    use crate::quarantine::{write_line, Original, Quarantine, QuarantineCause};
+    let file = Rc::new(RefCell::new(Vec::<u8>::new()));
+    let out = file.clone();
+    let quarantine = Quarantine::with_sink(move |q|write_line(&mut *out.borrow_mut(), q).unwrap());
+
+    let csv = "Alice,,2,true\nBob,Alice,1,true\nCarol,\"Smith, Bob\",1,false\nDave,Dave,1,false\nErin,Alice,lots,false\n";
+    let mut live = LiveTally::new(Default::default());
+    let report = ingest(csv.as_bytes(), Format::Csv, &mut live, 10, |_|ControlFlow::Continue(())).unwrap();
+    quarantine.rejected(&report.rejected);
+    let mut is = Introspector::default();
+    quarantine.subscribe(&mut is);
+    let mut vc = VoteCounter::new(live.ballots(), is);
+    assert_eq!(vc.find_winner().map(|w|w.voter_id.to_string()), Some("Alice".into()));
+    drop(vc);
+
+    let held = quarantine.take();
+    assert_eq!(held.iter().map(|q|q.cause.clone()).collect::<Vec<_>>(), vec![
+        QuarantineCause::Rejected(crate::bulk::Rejection::Malformed("bad number of votes \"lots\"".into())),
+        QuarantineCause::Invalid(InvalidVoteCause::UnrecognizedVote),
+        QuarantineCause::Invalid(InvalidVoteCause::SelfVote),
+    ]);
+    assert!(matches!(&held[0].original, Original::Line { line: 5, text } if text.starts_with("Erin,")));
+    assert!(matches!(&held[1].original, Original::Ballot(v) if v.voter_id == "Carol"));
+    assert!(quarantine.is_empty());
+    // Each line is the cause then the ballot as it can be uploaded again
+    assert_eq!(String::from_utf8(file.take()).unwrap(), "Malformed: bad number of votes \"lots\"\tErin,Alice,lots,false\n\
+        UnrecognizedVote\tCarol,\"Smith, Bob\",1,false\n\
+        SelfVote\tDave,Dave,1,false\n");
+}
+
+#[test]
+fn tied_winners_shown() {
+    let tie = crate::scenario::parse("Alice 4 Bob\nBob 4 Alice\nCarol 1\n").unwrap();
+    let ids = |w: Vec<&Vote>|w.iter().map(|v|v.voter_id.to_string()).collect::<Vec<_>>();
+    let mut vc = VoteCounter::new(&tie, Default::default());
+    assert_eq!(ids(vc.find_winner_all()), vec!["Alice", "Bob"]);
+    // find_winner still breaks the tie
+    let winner = vc.find_winner().unwrap();
+    assert!(winner.voter_id == "Alice" || winner.voter_id == "Bob");
+
+    let mut v = Votes::new("no_tie");
+    v.candidate("Alice", "");
+    v.candidate("Bob", "");
+    v.votes("Alice", 2);
+    let mut vc = VoteCounter::new(&v.v, Default::default());
+    assert_eq!(ids(vc.find_winner_all()), vec!["no_tie/Alice"]);
+    let none: Vec<Vote> = Vec::new();
+    assert!(VoteCounter::new(&none, Default::default()).find_winner_all().is_empty());
+}
+
+#[test]
+fn handlers_see_state() {
+    use crate::introspector::{Introspector, PatronSelection, VoteDelegation, InvalidVote};
+    let votes = crate::scenario::parse("
+        Alice 1 Bob
+        Bob 2 Alice
+        Charlie 5 Alice
+        Dave 1 Dave voter
+    ").unwrap();
+    let mut is = Introspector::default();
+    // The patron's score and the score of who they voted for, looked up in the count
+    is.subscribe_with_state(Vec::new(), |seen: &mut Vec<(String, Option<Weight>, Option<Weight>)>, e: &PatronSelection, state| {
+        let state = state.unwrap();
+        let to = state.vote_for(&e.potential_patron.voter_id).unwrap();
+        seen.push((e.potential_patron.voter_id.to_string(), state.total_votes(&e.potential_patron.voter_id),
+            state.total_votes(&to.voter_id)));
+    });
+    // Alice's total grows as the votes are delegated to her
+    is.subscribe_with_state(Vec::new(), |totals: &mut Vec<Weight>, e: &VoteDelegation, state| {
+        if e.to.voter_id == "Alice" {
+            totals.extend(state.and_then(|s|s.total_votes("Alice")));
+        }
+    });
+    is.subscribe_with_state(0, |n: &mut usize, _: &InvalidVote, state| {
+        assert!(state.is_none());
+        *n += 1;
+    });
+    let mut vc = VoteCounter::new(&votes, is);
+    assert_eq!(vc.find_winner().map(|w|w.voter_id.to_string()), Some("Charlie".into()));
+    let seen = vc.is.contexts_mut::<Vec<(String, Option<Weight>, Option<Weight>)>>().remove(0).clone();
+    assert_eq!(seen, vec![("Charlie".to_owned(), Some(5), Some(8))]);
+    let totals = vc.is.contexts_mut::<Vec<Weight>>().remove(0).clone();
+    assert!(totals.len() >= 2 && totals.windows(2).all(|w|w[0] <= w[1]), "{totals:?}");
+    assert_eq!(vc.is.contexts_mut::<usize>().remove(0), &mut 1);
+}
+
+#[test]
+fn add_vote_incrementally() {
+    use crate::introspector::VoteDelegation;
+    let votes = crate::scenario::parse("
+        Alice 1 Bob
+        Bob 2 Alice
+        Carol 3 Alice
+        Dave 1 Carol voter
+        Erin 2 Dave
+        Frank 1 -
+        Gina 4 Gina
+        Hank 2 Ivy
+        Ivy 3 Alice
+        Jack 5 Erin voter
+    ").unwrap();
+    let summary = |vc: &mut VoteCounter|{
+        let mut invalid = vc.invalid_votes().iter()
+            .map(|(c, v)|format!("{c:?} {}", v.voter_id))
+            .collect::<Vec<_>>();
+        invalid.sort();
+        let rings = vc.rings().iter()
+            .map(|r|r.iter().map(|v|v.voter_id.to_string()).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let ranking = vc.iter().map(|(n, v)|(n, v.voter_id.to_string())).collect::<Vec<_>>();
+        let info = format!("{:?}", vc.find(|_|true));
+        (invalid, rings, ranking, info, vc.find_winner().map(|w|w.voter_id.to_string()))
+    };
+    let mut vc = VoteCounter::new(&votes[..2], Default::default());
+    vc.introspector_mut().subscribe(0usize, |n, _: &VoteDelegation| *n += 1);
+    for i in 2..votes.len() {
+        let before = *vc.is.contexts_mut::<usize>()[0];
+        vc.add_vote(&votes[i]);
+        if votes[i].voter_id == "Jack" {
+            // Only Jack's votes are delegated, down to the Alice and Bob ring
+            assert_eq!(*vc.is.contexts_mut::<usize>()[0] - before, 5);
+        }
+        let mut fresh = VoteCounter::new(&votes[..=i], Default::default());
+        assert_eq!(summary(&mut vc), summary(&mut fresh), "after {}", votes[i].voter_id);
+    }
+
+    // Revoked votes stay revoked when Hank's vote for Ivy requires reading every ballot
+    let mut vc = VoteCounter::new(&votes[..8], Default::default());
+    vc.revoke_vote_by_id("Alice");
+    vc.add_vote(&votes[8]);
+    assert_eq!(vc.rings().len(), 0);
+    assert_eq!(vc.iter().next().map(|(n, v)|(n, v.voter_id.to_string())), Some((14, "Alice".to_owned())));
+}
+
+#[test]
+fn update_and_remove_votes() {
+    use crate::{TallyError, UnrecognizedVotePolicy};
+    let votes = crate::scenario::parse("
+        Alice 1 Bob
+        Bob 2 Alice
+        Carol 3 Alice
+        Dave 1 Carol voter
+        Erin 2 Dave
+        Frank 1 Bob
+    ").unwrap();
+    let late = crate::scenario::parse("Carol 6 Erin").unwrap();
+    // The ballots as they should be counted, in order
+    let mut model = crate::scenario::to_text(&votes);
+    let summary = |vc: &mut VoteCounter|{
+        let mut invalid = vc.invalid_votes().iter()
+            .map(|(c, v)|format!("{c:?} {}", v.voter_id))
+            .collect::<Vec<_>>();
+        invalid.sort();
+        let info = vc.find(|_|true).iter()
+            .map(|c|(c.vote.voter_id.to_string(), c.total_votes, c.rank,
+                c.vote_for.map(|v|v.voter_id.to_string()), c.in_ring))
+            .collect::<Vec<_>>();
+        let winners = vc.find_winners(2).iter().map(|w|w.voter_id.to_string()).collect::<Vec<_>>();
+        (invalid, info, winners, vc.view().ranking().to_vec())
+    };
+    let mut vc = VoteCounter::new(&votes, Default::default());
+    let check = |vc: &mut VoteCounter, model: &str|{
+        let ballots = crate::scenario::parse(model).unwrap();
+        assert_eq!(summary(vc), summary(&mut VoteCounter::new(&ballots, Default::default())), "{model}");
+    };
+    let edit = |model: &mut String, name: &str, line: Option<&str>|{
+        let lines = model.lines()
+            .filter_map(|l|if l.split(' ').next() == Some(name) { line } else { Some(l) })
+            .collect::<Vec<_>>();
+        *model = lines.join("\n");
+    };
+
+    vc.update_vote("Dave", Some("Bob"), 4).unwrap();
+    edit(&mut model, "Dave", Some("Dave 4 Bob voter"));
+    check(&mut vc, &model);
+    vc.update_vote("Alice", None, 1).unwrap();
+    edit(&mut model, "Alice", Some("Alice 1 -"));
+    check(&mut vc, &model);
+    // A new ring of Alice and Carol
+    vc.update_vote("Alice", Some("Carol"), 2).unwrap();
+    edit(&mut model, "Alice", Some("Alice 2 Carol"));
+    check(&mut vc, &model);
+
+    assert_eq!(vc.update_vote("Nobody", None, 1), Err(TallyError::NoBallot("Nobody".into())));
+    let strict = ElectionOptions { unrecognized_vote: UnrecognizedVotePolicy::Error, ..Default::default() };
+    let mut other = VoteCounter::with_options(&votes, Default::default(), strict.clone());
+    assert_eq!(other.update_vote("Alice", Some("Nobody"), 1),
+        Err(TallyError::UnrecognizedVote("Alice".into(), "Nobody".into())));
+
+    // Nobody votes for Frank, then Carol's voters are left voting for nobody
+    assert!(vc.remove_vote("Frank"));
+    edit(&mut model, "Frank", None);
+    check(&mut vc, &model);
+    assert!(vc.remove_vote("Carol"));
+    edit(&mut model, "Carol", None);
+    check(&mut vc, &model);
+    assert!(!vc.remove_vote("Carol"));
+
+    // Alice's updated vote counts for Carol again when she returns
+    vc.add_vote(&late[0]);
+    model.push_str("\nCarol 6 Erin");
+    check(&mut vc, &model);
+
+    // A vote revoked after it was updated stays revoked when the ballots are read again
+    vc.update_vote("Erin", Some("Alice"), 3).unwrap();
+    vc.revoke_vote_by_id("Erin");
+    assert!(vc.remove_vote("Bob"));
+    let ballots = crate::scenario::parse("Alice 2 Carol\nDave 4 Bob voter\nErin 3 -\nCarol 6 Erin").unwrap();
+    let fresh = VoteCounter::new(&ballots, Default::default());
+    let ranking = |vc: &VoteCounter|vc.iter().map(|(n, v)|(n, v.voter_id.to_string())).collect::<Vec<_>>();
+    assert_eq!(ranking(&vc), ranking(&fresh));
+
+    // The limits apply to the ballots as updated, so a vote added after an update can
+    // take the total over MAX_TOTAL_VOTES
+    let near_max = crate::limits::MAX_TOTAL_VOTES - 10;
+    let votes = crate::scenario::parse("Alice 1 -\nBob 1 Alice\nCarol 1 Alice").unwrap();
+    let late = crate::scenario::parse(&format!("Dave {near_max} Carol")).unwrap();
+    let mut vc = VoteCounter::new(&votes, Default::default());
+    vc.update_vote("Carol", None, near_max).unwrap();
+    vc.add_vote(&late[0]);
+    let ballots = crate::scenario::parse(&format!(
+        "Alice 1 -\nBob 1 Alice\nCarol {near_max} -\nDave {near_max} Carol")).unwrap();
+    let expected = VoteCounter::new(&ballots, Default::default()).tally();
+    assert!(matches!(expected, Err(TallyError::WeightOverflow { .. })));
+    assert_eq!(vc.tally(), expected);
+
+    // Under OverflowPolicy::Rescale a heavier ballot shifts everyone's votes, and the
+    // shift is undone when it is lighter again, as if the ballots were counted anew
+    let rescale = ElectionOptions { overflow: crate::OverflowPolicy::Rescale, ..Default::default() };
+    let heavy = crate::limits::MAX_TOTAL_VOTES;
+    let votes = crate::scenario::parse("Alice 5 -\nBob 3 -\nCarol 2 Bob").unwrap();
+    let mut vc = VoteCounter::with_options(&votes, Default::default(), rescale.clone());
+    for weight in [heavy, 2] {
+        vc.update_vote("Carol", Some("Bob"), weight).unwrap();
+        let ballots = crate::scenario::parse(&format!("Alice 5 -\nBob 3 -\nCarol {weight} Bob")).unwrap();
+        let mut fresh = VoteCounter::with_options(&ballots, Default::default(), rescale.clone());
+        assert_eq!(ranking(&vc), ranking(&fresh), "{weight}");
+        assert_eq!(vc.tally(), fresh.tally());
+    }
+    // Under OverflowPolicy::Error the total before shifting is checked and nothing changes
+    let mut vc = VoteCounter::new(&votes, Default::default());
+    let before = ranking(&vc);
+    assert_eq!(vc.update_vote("Carol", Some("Bob"), heavy),
+        Err(TallyError::WeightOverflow { total: crate::types::widen(heavy) + 8, limit: heavy }));
+    assert_eq!(ranking(&vc), before);
+
+    // A vote for someone who has no ballot is counted under the UnrecognizedVotePolicy, also
+    // when their ballot is removed after the update and the ballots are read again
+    let counted = |vc: &mut VoteCounter|{
+        let mut invalid = vc.invalid_votes().iter()
+            .map(|(c, v)|format!("{c:?} {}", v.voter_id))
+            .collect::<Vec<_>>();
+        invalid.sort();
+        let implicit = vc.implicit_voters().into_iter().map(|(id, n)|(id.to_owned(), n)).collect::<Vec<_>>();
+        (ranking(vc), implicit, invalid, vc.tally())
+    };
+    let steps: [(&str, Option<&str>, Weight, &str); 3] = [
+        ("Carol", Some("Zed"), 2, "Alice 5 -\nBob 3 -\nCarol 2 Zed\nDave 1 Carol voter"),
+        ("Alice", Some("Bob"), 5, "Alice 5 Bob\nBob 3 -\nCarol 2 Zed\nDave 1 Carol voter"),
+        ("Bob", None, 0, "Alice 5 Bob\nCarol 2 Zed\nDave 1 Carol voter"),
+    ];
+    let votes = crate::scenario::parse("Alice 5 -\nBob 3 -\nCarol 2 Bob\nDave 1 Carol voter").unwrap();
+    for unrecognized_vote in [UnrecognizedVotePolicy::Discard, UnrecognizedVotePolicy::ImplicitVoter] {
+        let options = ElectionOptions { unrecognized_vote, ..Default::default() };
+        let mut vc = VoteCounter::with_options(&votes, Default::default(), options.clone());
+        for (voter_id, target, weight, model) in steps {
+            if target.is_some() {
+                vc.update_vote(voter_id, target, weight).unwrap();
+            } else {
+                assert!(vc.remove_vote(voter_id));
+            }
+            let ballots = crate::scenario::parse(model).unwrap();
+            let mut fresh = VoteCounter::with_options(&ballots, Default::default(), options.clone());
+            assert_eq!(counted(&mut vc), counted(&mut fresh), "{unrecognized_vote:?} {model}");
+        }
+    }
+    // Under UnrecognizedVotePolicy::Error the count fails once the ballot is removed
+    let mut vc = VoteCounter::with_options(&votes, Default::default(), strict.clone());
+    vc.update_vote("Carol", Some("Alice"), 2).unwrap();
+    vc.update_vote("Alice", Some("Bob"), 5).unwrap();
+    assert!(vc.remove_vote("Bob"));
+    let ballots = crate::scenario::parse("Alice 5 Bob\nCarol 2 Alice\nDave 1 Carol voter").unwrap();
+    let expected = VoteCounter::with_options(&ballots, Default::default(), strict).tally();
+    assert_eq!(expected, Err(TallyError::UnrecognizedVote("Alice".into(), "Bob".into())));
+    assert_eq!(vc.tally(), expected);
+}
+
+#[test]
+fn api_snapshot() {
+    // The API as rustdoc sees it, with every feature, listed by the public-api crate as
+    // `cargo public-api --omit blanket-impls` would.
+    // rustdoc's JSON output needs a nightly toolchain.
+    let root = env!("CARGO_MANIFEST_DIR");
+    let json = rustdoc_json::Builder::default()
+        .toolchain("nightly")
+        .manifest_path(format!("{root}/Cargo.toml"))
+        .all_features(true)
+        .silent(true)
+        .build()
+        .expect("rustdoc JSON, which needs `rustup toolchain install nightly`");
+    let api = public_api::Builder::from_rustdoc_json(json)
+        .omit_blanket_impls(true)
+        .build()
+        .unwrap()
+        .to_string();
+    let snapshot_path = format!("{root}/public-api.txt");
+    if std::env::var_os("ELECTORIUM_UPDATE_API").is_some() {
+        std::fs::write(&snapshot_path, &api).unwrap();
+    }
+    let snapshot = std::fs::read_to_string(&snapshot_path).unwrap_or_default();
+    let added = api.lines().filter(|l|!snapshot.lines().any(|s|s == *l)).collect::<Vec<_>>();
+    let removed = snapshot.lines().filter(|l|!api.lines().any(|s|s == *l)).collect::<Vec<_>>();
+    assert!(api == snapshot,
+        "The public API changed, see the API stability policy in prelude.rs, then run the \
+        tests with ELECTORIUM_UPDATE_API=1 to update public-api.txt.\nAdded:\n{}\nRemoved:\n{}",
+        added.join("\n"), removed.join("\n"));
+}
@@ -1,18 +1,257 @@
 // SPDX-License-Identifier: MIT OR ISC
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+/// Information about a voter which is not used in the count but is carried with their
+/// ballot, such as a display name, party affiliation or profile URL.
+pub type Metadata = BTreeMap<String, String>;
+
+/// The number of votes of a ballot and every total of them. This is a u64 unless the
+/// wide-weights feature is enabled, then it is a u128 so that weights such as 18 decimal
+/// token balances can be counted exactly. A count whose totals fit in a u64 has the same
+/// result, events, archive and signature with either.
+#[cfg(not(feature = "wide-weights"))]
+pub type Weight = u64;
+/// The number of votes of a ballot and every total of them, a u128 because the
+/// wide-weights feature is enabled.
+#[cfg(feature = "wide-weights")]
+pub type Weight = u128;
+
+/// A weight as a u128, which every total of them is computed in when it may not fit.
+#[allow(clippy::useless_conversion)]
+pub(crate) fn widen(w: Weight) -> u128 {
+    u128::from(w)
+}
+
+/// A u64 as a weight, which it always fits in.
+#[allow(clippy::useless_conversion)]
+pub(crate) fn weight(n: u64) -> Weight {
+    Weight::from(n)
+}
+
+/// A u128 total as a weight, Weight::MAX if it does not fit.
+#[allow(clippy::unnecessary_fallible_conversions)]
+pub(crate) fn narrow(n: u128) -> Weight {
+    Weight::try_from(n).unwrap_or(Weight::MAX)
+}
+
+/// A ballot. The IDs are Cow so that ballots which are already in memory, for instance
+/// parsed from a mapped file, can be counted without copying every string, see
+/// Vote::borrowed(). Vote<'static> with owned IDs is the usual case.
 #[derive(Debug)]
-pub struct Vote {
+pub struct Vote<'s> {
     /// The unique ID of the voter/candidate
-    pub voter_id: String,
-    /// The unique ID of the candidate who they are voting for
-    pub vote_for: String,
+    pub voter_id: Cow<'s, str>,
+    /// The unique ID of the candidate who they are voting for, None if they did not vote
+    /// for anyone
+    pub vote_for: Option<Cow<'s, str>>,
     /// How many votes they have - in a typical national election this would be 1
     /// In the case of stock companies, for instance, this would be number of shares.
-    pub number_of_votes: u64,
+    pub number_of_votes: Weight,
     /// If this voter willing to also be a candidate for election?
     pub willing_candidate: bool,
+    /// Anything the application wants to show alongside the voter, empty if there is
+    /// nothing. It is never looked at by the count, and the winner's is copied into
+    /// ElectionResult::winner_metadata.
+    pub metadata: Cow<'s, Metadata>,
+    /// Later preferences, in order, which the vote falls through to if vote_for is not
+    /// a willing candidate. Empty for a ballot with one choice, see InvalidVoteCause and
+    /// introspector::Fallback.
+    pub fallbacks: Vec<Cow<'s, str>>,
+    /// An explicit abstention: the ballot counts toward turnout and quorum but its votes
+    /// go to no one, vote_for and willing_candidate are ignored. A ballot which does not
+    /// vote for anyone is not an abstention, its votes stay with the voter if they are a
+    /// candidate.
+    pub abstain: bool,
 }
-impl PartialEq for Vote {
+/// Two votes are equal only if they are the same ballot in memory, use same_as() to
+/// compare a copy, e.g. one which was cloned or deserialized, with the original.
+impl PartialEq for Vote<'_> {
     fn eq(&self, other: &Self) -> bool {
         std::ptr::eq(self, other)
     }
-}
\ No newline at end of file
+}
+impl<'s> Vote<'s> {
+    /// A ballot whose IDs borrow from the caller's memory
+    pub fn borrowed(voter_id: &'s str, vote_for: Option<&'s str>, number_of_votes: Weight, willing_candidate: bool) -> Self {
+        Vote {
+            voter_id: Cow::Borrowed(voter_id),
+            vote_for: vote_for.map(Cow::Borrowed),
+            number_of_votes,
+            willing_candidate,
+            metadata: Cow::Owned(Metadata::new()),
+            fallbacks: Vec::new(),
+            abstain: false,
+        }
+    }
+    /// This ballot with metadata attached
+    pub fn with_metadata(mut self, metadata: Cow<'s, Metadata>) -> Self {
+        self.metadata = metadata;
+        self
+    }
+    /// This ballot as an abstention, see Vote::abstain
+    pub fn as_abstention(mut self) -> Self {
+        self.abstain = true;
+        self
+    }
+    /// This ballot with later preferences, see Vote::fallbacks
+    pub fn with_fallbacks(mut self, fallbacks: Vec<Cow<'s, str>>) -> Self {
+        self.fallbacks = fallbacks;
+        self
+    }
+    /// Whether every field of other is the same as this one
+    pub fn same_as(&self, other: &Vote<'_>) -> bool {
+        self.voter_id == other.voter_id
+            && self.vote_for == other.vote_for
+            && self.number_of_votes == other.number_of_votes
+            && self.willing_candidate == other.willing_candidate
+            && self.metadata == other.metadata
+            && self.fallbacks == other.fallbacks
+            && self.abstain == other.abstain
+    }
+    /// A copy of this ballot which owns its IDs
+    pub fn to_owned_vote(&self) -> Vote<'static> {
+        Vote {
+            voter_id: Cow::Owned(self.voter_id.to_string()),
+            vote_for: self.vote_for.as_deref().map(|v|Cow::Owned(v.to_owned())),
+            number_of_votes: self.number_of_votes,
+            willing_candidate: self.willing_candidate,
+            metadata: Cow::Owned((*self.metadata).clone()),
+            fallbacks: self.fallbacks.iter().map(|f|Cow::Owned(f.to_string())).collect(),
+            abstain: self.abstain,
+        }
+    }
+    /// This ballot with its IDs owned, copying them only if they are borrowed
+    pub fn into_owned(self) -> Vote<'static> {
+        Vote {
+            voter_id: Cow::Owned(self.voter_id.into_owned()),
+            vote_for: self.vote_for.map(|v|Cow::Owned(v.into_owned())),
+            number_of_votes: self.number_of_votes,
+            willing_candidate: self.willing_candidate,
+            metadata: Cow::Owned(self.metadata.into_owned()),
+            fallbacks: self.fallbacks.into_iter().map(|f|Cow::Owned(f.into_owned())).collect(),
+            abstain: self.abstain,
+        }
+    }
+}
+/// An error which prevented the tally. Most of these are internal inconsistencies which
+/// indicate a bug in electorium rather than a problem with the ballots, but they are
+/// reported as errors so that a long-running process which embeds the tally is never
+/// aborted. Others are ballots which the options in force do not allow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TallyError {
+    /// A candidate who is not willing was found among the willing candidates
+    UnwillingInRanking(String),
+    /// The potential patron was compared against themselves as the runner-up
+    PatronIsRunnerUp(String),
+    /// A ballot voted for someone who has no ballot, under UnrecognizedVotePolicy::Error.
+    /// The arguments are the voter_id and vote_for of the ballot.
+    UnrecognizedVote(String, String),
+    /// More than one ballot has this voter_id, under DuplicatePolicy::Error
+    DuplicateVoter(String),
+    /// The estimated memory for the ballots is more than ElectionOptions::memory_limit,
+    /// see limits::estimate_memory()
+    MemoryLimit { estimate: usize, limit: usize },
+    /// There are more ballots than ElectionOptions::max_ballots
+    TooManyBallots { count: usize, limit: usize },
+    /// The ballot at this position has an ID longer than ElectionOptions::max_id_len,
+    /// the ID itself is not included because it may be enormous
+    IdTooLong { ballot: usize, len: usize, limit: usize },
+    /// The ElectionOptions are not coherent, see ElectionOptions::validate()
+    InvalidOptions(crate::options::OptionsError),
+    /// The ballots carry more than limits::MAX_TOTAL_VOTES votes in total, under
+    /// OverflowPolicy::Error
+    WeightOverflow { total: u128, limit: Weight },
+    /// Under Parallelism::Validate, this step gave a different answer on several threads
+    /// than on one, the machine or the build should not be trusted
+    ParallelMismatch(&'static str),
+    /// Fewer ballots or votes were counted than ElectionOptions::min_ballots or min_votes
+    /// require, so there is no winner
+    NoQuorum { ballots: u64, votes: Weight },
+    /// No ballot from this voter is being counted, see VoteCounter::update_vote()
+    NoBallot(String),
+    /// This candidate was found at a position past the end of the ballots
+    BallotOutOfRange(usize),
+}
+impl std::fmt::Display for TallyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TallyError::UnwillingInRanking(id) =>
+                write!(f, "Candidate {id} is ranked but is not a willing candidate"),
+            TallyError::PatronIsRunnerUp(id) =>
+                write!(f, "Potential patron {id} is also the runner-up"),
+            TallyError::UnrecognizedVote(id, vote_for) =>
+                write!(f, "Voter {id} voted for {vote_for} who is not a voter or candidate"),
+            TallyError::DuplicateVoter(id) =>
+                write!(f, "There is more than one ballot from voter {id}"),
+            TallyError::MemoryLimit { estimate, limit } =>
+                write!(f, "Counting needs about {estimate} bytes, the limit is {limit}"),
+            TallyError::TooManyBallots { count, limit } =>
+                write!(f, "There are {count} ballots, the limit is {limit}"),
+            TallyError::IdTooLong { ballot, len, limit } =>
+                write!(f, "Ballot number {ballot} has an ID of {len} bytes, the limit is {limit}"),
+            TallyError::InvalidOptions(e) => write!(f, "Invalid options: {e}"),
+            TallyError::WeightOverflow { total, limit } =>
+                write!(f, "The ballots carry {total} votes, the limit is {limit}"),
+            TallyError::ParallelMismatch(step) =>
+                write!(f, "The {step} differ between the serial and parallel count"),
+            TallyError::NoQuorum { ballots, votes } =>
+                write!(f, "Quorum not reached with {ballots} ballots carrying {votes} votes"),
+            TallyError::NoBallot(id) => write!(f, "There is no ballot from voter {id}"),
+            TallyError::BallotOutOfRange(ballot) =>
+                write!(f, "Candidate at ballot number {ballot} is past the end of the ballots"),
+        }
+    }
+}
+impl std::error::Error for TallyError {}
+
+/// A ballot which would not be counted as a vote for anyone, see VoteCounter::validate().
+/// index is the position of the ballot in the slice which was validated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BallotError {
+    /// The ballot does not vote for anyone
+    NoVote { index: usize, voter_id: String },
+    /// The ballot votes for its own voter
+    SelfVote { index: usize, voter_id: String },
+    /// The ballot votes for someone who has no ballot
+    UnrecognizedVote { index: usize, voter_id: String, vote_for: String },
+    /// An earlier ballot has the same voter_id
+    Duplicate { index: usize, voter_id: String },
+}
+impl BallotError {
+    pub(crate) fn new(cause: &crate::introspector::InvalidVoteCause, index: usize, v: &Vote) -> Self {
+        use crate::introspector::InvalidVoteCause as C;
+        let voter_id = v.voter_id.to_string();
+        match cause {
+            C::NoVote => BallotError::NoVote { index, voter_id },
+            C::SelfVote => BallotError::SelfVote { index, voter_id },
+            C::UnrecognizedVote =>
+                BallotError::UnrecognizedVote { index, voter_id, vote_for: v.vote_for.as_deref().unwrap_or_default().to_owned() },
+            C::Duplicate => BallotError::Duplicate { index, voter_id },
+        }
+    }
+    /// The position of the ballot
+    pub fn index(&self) -> usize {
+        match self {
+            BallotError::NoVote { index, .. } |
+            BallotError::SelfVote { index, .. } |
+            BallotError::UnrecognizedVote { index, .. } |
+            BallotError::Duplicate { index, .. } => *index,
+        }
+    }
+}
+impl std::fmt::Display for BallotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BallotError::NoVote { index, voter_id } =>
+                write!(f, "Ballot {index} of {voter_id} does not vote for anyone"),
+            BallotError::SelfVote { index, voter_id } =>
+                write!(f, "Ballot {index} of {voter_id} votes for themselves"),
+            BallotError::UnrecognizedVote { index, voter_id, vote_for } =>
+                write!(f, "Ballot {index} of {voter_id} votes for {vote_for} who has no ballot"),
+            BallotError::Duplicate { index, voter_id } =>
+                write!(f, "Ballot {index} of {voter_id} is a duplicate"),
+        }
+    }
+}
+impl std::error::Error for BallotError {}
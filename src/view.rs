@@ -0,0 +1,96 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! A read-only copy of what a count found, detached from the ballots and the introspector.
+//!
+//! VoteCounter borrows the ballots and refers to them in everything it returns, so it
+//! cannot outlive the buffer they were read from. A TallyView owns copies of the IDs it
+//! needs and shares them behind an Arc, so it is cheap to clone and can be handed to
+//! other threads, for instance kept by a web server to answer queries after the ballots
+//! have been dropped. See VoteCounter::view().
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::explain::{self, Explanation};
+use crate::record::EventRecord;
+use crate::result::ElectionResult;
+use crate::types::{Weight, TallyError};
+use crate::CandidateInfo;
+
+/// An owned copy of a CandidateInfo, with the ballots referred to by voter_id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CandidateView {
+    pub voter_id: String,
+    /// See CandidateInfo::total_votes
+    pub total_votes: Weight,
+    /// See CandidateInfo::rank
+    pub rank: Option<usize>,
+    /// The voter_id of the person they voted for, if that vote was counted
+    pub vote_for: Option<String>,
+    pub in_ring: bool,
+}
+impl From<&CandidateInfo<'_>> for CandidateView {
+    fn from(c: &CandidateInfo<'_>) -> Self {
+        CandidateView {
+            voter_id: c.vote.voter_id.to_string(),
+            total_votes: c.total_votes,
+            rank: c.rank,
+            vote_for: c.vote_for.map(|v|v.voter_id.to_string()),
+            in_ring: c.in_ring,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    result: Result<ElectionResult, TallyError>,
+    ranking: Vec<(String, Weight)>,
+    candidates: Vec<CandidateView>,
+    by_id: HashMap<String, usize>,
+    trail: Vec<EventRecord>,
+}
+
+/// What a count found, see the module documentation. Clones share the same data.
+#[derive(Debug, Clone)]
+pub struct TallyView {
+    inner: Arc<Inner>,
+}
+impl TallyView {
+    pub(crate) fn new(
+        result: Result<ElectionResult, TallyError>,
+        ranking: Vec<(String, Weight)>,
+        candidates: Vec<CandidateView>,
+        trail: Vec<EventRecord>,
+    ) -> Self {
+        let by_id = candidates.iter().enumerate().map(|(i, c)|(c.voter_id.clone(), i)).collect();
+        TallyView { inner: Arc::new(Inner { result, ranking, candidates, by_id, trail }) }
+    }
+    /// The result of the count, as VoteCounter::tally()
+    pub fn result(&self) -> Result<&ElectionResult, &TallyError> {
+        self.inner.result.as_ref()
+    }
+    /// The voter_id of the winner, None if there is none or the count failed
+    pub fn winner(&self) -> Option<&str> {
+        self.inner.result.as_ref().ok().and_then(|r|r.winner.as_deref())
+    }
+    /// Each willing candidate with the votes they would receive with all possible
+    /// delegations, in the order of VoteCounter::iter()
+    pub fn ranking(&self) -> &[(String, Weight)] {
+        &self.inner.ranking
+    }
+    /// Every ballot, in the order of VoteCounter::find()
+    pub fn candidates(&self) -> &[CandidateView] {
+        &self.inner.candidates
+    }
+    /// The ballot with this voter_id, if there is one
+    pub fn candidate(&self, voter_id: &str) -> Option<&CandidateView> {
+        self.inner.by_id.get(voter_id).and_then(|&i|self.inner.candidates.get(i))
+    }
+    /// Every event of the count, as recorded by record::subscribe_all(), which explains
+    /// how the result was reached
+    pub fn trail(&self) -> &[EventRecord] {
+        &self.inner.trail
+    }
+    /// How the result was reached, in sentences for an announcement, see explain::explain()
+    pub fn explain(&self) -> Explanation {
+        explain::explain(&self.inner.trail)
+    }
+}
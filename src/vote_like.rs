@@ -0,0 +1,78 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! Counting an application's own ballot structs without copying them into Votes.
+//!
+//! VoteCounter refers to the ballots by &Vote in every event and in the ranking, so it is
+//! not generic over the ballot type. Instead borrow_all() makes a Vote for each ballot
+//! which borrows its IDs, which costs one small struct per ballot and no string copies.
+use std::borrow::Cow;
+
+use crate::options::ElectionOptions;
+use crate::result::ElectionResult;
+use crate::types::{Vote, Metadata, Weight, TallyError};
+use crate::VoteCounter;
+
+/// Anything which can be counted as a ballot, see Vote for the meaning of each field.
+pub trait VoteLike {
+    fn voter_id(&self) -> &str;
+    /// None if they did not vote for anyone
+    fn vote_for(&self) -> Option<&str>;
+    fn weight(&self) -> Weight;
+    fn willing(&self) -> bool;
+    /// See Vote::metadata, none by default
+    fn metadata(&self) -> Option<&Metadata> {
+        None
+    }
+    /// See Vote::fallbacks, none by default
+    fn fallbacks(&self) -> Vec<&str> {
+        Vec::new()
+    }
+    /// See Vote::abstain, false by default
+    fn abstain(&self) -> bool {
+        false
+    }
+    /// A Vote which borrows from this ballot
+    fn as_vote(&self) -> Vote<'_> {
+        let vote = Vote::borrowed(self.voter_id(), self.vote_for(), self.weight(), self.willing())
+            .with_fallbacks(self.fallbacks().into_iter().map(Cow::Borrowed).collect());
+        let vote = if self.abstain() { vote.as_abstention() } else { vote };
+        match self.metadata() {
+            Some(m) => vote.with_metadata(Cow::Borrowed(m)),
+            None => vote,
+        }
+    }
+}
+impl VoteLike for Vote<'_> {
+    fn voter_id(&self) -> &str {
+        &self.voter_id
+    }
+    fn vote_for(&self) -> Option<&str> {
+        self.vote_for.as_deref()
+    }
+    fn weight(&self) -> Weight {
+        self.number_of_votes
+    }
+    fn willing(&self) -> bool {
+        self.willing_candidate
+    }
+    fn metadata(&self) -> Option<&Metadata> {
+        Some(&self.metadata)
+    }
+    fn fallbacks(&self) -> Vec<&str> {
+        self.fallbacks.iter().map(|f|&**f).collect()
+    }
+    fn abstain(&self) -> bool {
+        self.abstain
+    }
+}
+
+/// A Vote for each ballot, borrowing from them, to pass to VoteCounter
+pub fn borrow_all<T: VoteLike>(ballots: &[T]) -> Vec<Vote<'_>> {
+    ballots.iter().map(VoteLike::as_vote).collect()
+}
+
+/// Count ballots of any VoteLike type under options
+pub fn tally<T: VoteLike>(ballots: &[T], options: ElectionOptions) -> Result<ElectionResult, TallyError> {
+    let votes = borrow_all(ballots);
+    let mut vc = VoteCounter::with_options(&votes, Default::default(), options);
+    vc.tally()
+}
@@ -0,0 +1,45 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! Deterministic pathological ballot sets for benchmarks and stress tests.
+//! Each generator produces the same ballots for the same size, so performance work
+//! can target a reproducible adversarial shape. Part of the strategies feature.
+use crate::strategies::candidate_id;
+use crate::types::Vote;
+
+fn candidate(id: usize, vote_for: Option<String>) -> Vote<'static> {
+    Vote {
+        voter_id: candidate_id(id).into(),
+        vote_for: vote_for.map(Into::into),
+        number_of_votes: 1,
+        willing_candidate: true,
+        metadata: Default::default(),
+        fallbacks: Vec::new(),
+        abstain: false,
+    }
+}
+
+/// A single delegation chain c0 -> c1 -> ... -> c(n-1), every vote travels the longest
+/// possible path.
+pub fn chain(n: usize) -> Vec<Vote<'static>> {
+    (0..n)
+        .map(|i| candidate(i, (i + 1 < n).then(||candidate_id(i + 1))))
+        .collect()
+}
+
+/// One ring of n candidates, each voting for the next, so every candidate has the same
+/// total and the within-ring tie-break must consider all of them.
+pub fn giant_ring(n: usize) -> Vec<Vote<'static>> {
+    (0..n).map(|i| candidate(i, Some(candidate_id((i + 1) % n)))).collect()
+}
+
+/// n candidates all voting for c0, the largest possible fan-in to one candidate.
+pub fn star(n: usize) -> Vec<Vote<'static>> {
+    (0..n)
+        .map(|i| candidate(i, (i != 0).then(||candidate_id(0))))
+        .collect()
+}
+
+/// n candidates with the same weight who do not vote, every one of them is tied and
+/// the winner is decided by the deterministic tie-breaker.
+pub fn all_tied(n: usize) -> Vec<Vote<'static>> {
+    (0..n).map(|i| candidate(i, None)).collect()
+}
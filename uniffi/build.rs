@@ -0,0 +1,4 @@
+// SPDX-License-Identifier: MIT OR ISC
+fn main() {
+    uniffi::generate_scaffolding("src/electorium.udl").unwrap();
+}
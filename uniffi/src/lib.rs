@@ -0,0 +1,204 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! UniFFI bindings so that mobile apps can verify a result against the published ballots.
+//! The types mirror electorium's, because UniFFI needs them to be defined in this crate.
+
+// The generated code does not pass clippy, so the lints are only allowed inside it
+#[allow(clippy::empty_line_after_doc_comments, unpredictable_function_pointer_comparisons)]
+mod scaffolding {
+    use super::*;
+    uniffi::include_scaffolding!("electorium");
+}
+// The derived FFI traits name it from the crate root
+use scaffolding::UniFfiTag;
+
+pub struct Vote {
+    pub voter_id: String,
+    pub vote_for: Option<String>,
+    pub number_of_votes: u64,
+    pub willing_candidate: bool,
+    pub fallbacks: Vec<String>,
+    pub abstain: bool,
+}
+impl From<Vote> for electorium::Vote<'static> {
+    fn from(v: Vote) -> Self {
+        electorium::Vote {
+            voter_id: v.voter_id.into(),
+            vote_for: v.vote_for.map(Into::into),
+            number_of_votes: v.number_of_votes,
+            willing_candidate: v.willing_candidate,
+            metadata: Default::default(),
+            fallbacks: v.fallbacks.into_iter().map(Into::into).collect(),
+            abstain: v.abstain,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub enum PatronRule {
+    Enabled,
+    Disabled,
+    Strict,
+}
+
+#[derive(Clone)]
+pub enum UnrecognizedVotePolicy {
+    Discard,
+    ImplicitVoter,
+    Error,
+}
+
+#[derive(Clone)]
+pub enum DuplicatePolicy {
+    FirstWins,
+    LastWins,
+    MergeWeights,
+    Error,
+}
+
+#[derive(Clone)]
+pub enum OverflowPolicy {
+    Error,
+    Rescale,
+}
+
+#[derive(Clone)]
+pub enum AlgorithmVersion {
+    V2,
+    V3,
+}
+
+#[derive(Clone)]
+pub struct ElectionOptions {
+    pub algorithm: AlgorithmVersion,
+    pub patron_rule: PatronRule,
+    pub unrecognized_vote: UnrecognizedVotePolicy,
+    pub duplicate_voter: DuplicatePolicy,
+    pub overflow: OverflowPolicy,
+    pub memory_limit: Option<u64>,
+    pub max_id_len: Option<u64>,
+    pub max_ballots: Option<u64>,
+    pub registry_weight: Option<u64>,
+    pub min_ballots: Option<u64>,
+    pub min_votes: Option<u64>,
+    pub tie_break_salt: Option<Vec<u8>>,
+}
+impl From<ElectionOptions> for electorium::ElectionOptions {
+    fn from(o: ElectionOptions) -> Self {
+        electorium::ElectionOptions {
+            algorithm: match o.algorithm {
+                AlgorithmVersion::V2 => electorium::AlgorithmVersion::V2,
+                AlgorithmVersion::V3 => electorium::AlgorithmVersion::V3,
+            },
+            patron_rule: match o.patron_rule {
+                PatronRule::Enabled => electorium::PatronRule::Enabled,
+                PatronRule::Disabled => electorium::PatronRule::Disabled,
+                PatronRule::Strict => electorium::PatronRule::Strict,
+            },
+            unrecognized_vote: match o.unrecognized_vote {
+                UnrecognizedVotePolicy::Discard => electorium::UnrecognizedVotePolicy::Discard,
+                UnrecognizedVotePolicy::ImplicitVoter => electorium::UnrecognizedVotePolicy::ImplicitVoter,
+                UnrecognizedVotePolicy::Error => electorium::UnrecognizedVotePolicy::Error,
+            },
+            duplicate_voter: match o.duplicate_voter {
+                DuplicatePolicy::FirstWins => electorium::DuplicatePolicy::FirstWins,
+                DuplicatePolicy::LastWins => electorium::DuplicatePolicy::LastWins,
+                DuplicatePolicy::MergeWeights => electorium::DuplicatePolicy::MergeWeights,
+                DuplicatePolicy::Error => electorium::DuplicatePolicy::Error,
+            },
+            overflow: match o.overflow {
+                OverflowPolicy::Error => electorium::OverflowPolicy::Error,
+                OverflowPolicy::Rescale => electorium::OverflowPolicy::Rescale,
+            },
+            memory_limit: o.memory_limit.map(|l|usize::try_from(l).unwrap_or(usize::MAX)),
+            max_id_len: o.max_id_len.map(|l|usize::try_from(l).unwrap_or(usize::MAX)),
+            max_ballots: o.max_ballots.map(|l|usize::try_from(l).unwrap_or(usize::MAX)),
+            registry_weight: o.registry_weight,
+            min_ballots: o.min_ballots,
+            min_votes: o.min_votes,
+            tie_break_salt: o.tie_break_salt,
+        }
+    }
+}
+
+pub struct RuleSet {
+    pub crate_version: String,
+    pub options_version: u32,
+    pub options: ElectionOptions,
+}
+
+pub struct VotesFrom {
+    pub voter_id: String,
+    pub votes: u64,
+}
+
+pub struct Contributions {
+    pub own: u64,
+    pub supporters: Vec<VotesFrom>,
+    pub through_ring: Vec<VotesFrom>,
+}
+impl From<electorium::result::Contributions> for Contributions {
+    fn from(c: electorium::result::Contributions) -> Self {
+        let from = |v: Vec<(String, u64)>| {
+            v.into_iter().map(|(voter_id, votes)|VotesFrom { voter_id, votes }).collect()
+        };
+        Contributions { own: c.own, supporters: from(c.supporters), through_ring: from(c.through_ring) }
+    }
+}
+
+pub struct ElectionResult {
+    pub winner: Option<String>,
+    pub votes: u64,
+    pub contributions: Contributions,
+    pub rules: RuleSet,
+}
+
+pub enum BallotError {
+    NoVote { index: u64, voter_id: String },
+    SelfVote { index: u64, voter_id: String },
+    UnrecognizedVote { index: u64, voter_id: String, vote_for: String },
+    Duplicate { index: u64, voter_id: String },
+}
+impl From<electorium::BallotError> for BallotError {
+    fn from(e: electorium::BallotError) -> Self {
+        use electorium::BallotError as E;
+        let index = |i: usize|u64::try_from(i).unwrap_or(u64::MAX);
+        match e {
+            E::NoVote { index: i, voter_id } => BallotError::NoVote { index: index(i), voter_id },
+            E::SelfVote { index: i, voter_id } => BallotError::SelfVote { index: index(i), voter_id },
+            E::UnrecognizedVote { index: i, voter_id, vote_for } =>
+                BallotError::UnrecognizedVote { index: index(i), voter_id, vote_for },
+            E::Duplicate { index: i, voter_id } => BallotError::Duplicate { index: index(i), voter_id },
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TallyError {
+    #[error("{0}")]
+    Internal(String),
+}
+
+pub fn tally(votes: Vec<Vote>, options: ElectionOptions) -> Result<ElectionResult, TallyError> {
+    let votes = votes.into_iter().map(electorium::Vote::from).collect::<Vec<_>>();
+    let is = electorium::introspector::Introspector::default();
+    let mut vc = electorium::VoteCounter::with_options(&votes, is, options.clone().into());
+    let res = vc.tally().map_err(|e|TallyError::Internal(e.to_string()))?;
+    Ok(ElectionResult {
+        winner: res.winner,
+        votes: res.votes,
+        contributions: res.contributions.into(),
+        rules: RuleSet {
+            crate_version: res.rules.crate_version,
+            options_version: res.rules.options_version,
+            // Same as res.rules.options, which may contain versions unknown to the bindings
+            options,
+        },
+    })
+}
+
+pub fn validate(votes: Vec<Vote>, options: ElectionOptions) -> Result<Vec<BallotError>, TallyError> {
+    let votes = votes.into_iter().map(electorium::Vote::from).collect::<Vec<_>>();
+    let errors = electorium::VoteCounter::validate(&votes, &options.into())
+        .map_err(|e|TallyError::Internal(e.to_string()))?;
+    Ok(errors.into_iter().map(BallotError::from).collect())
+}
@@ -0,0 +1,78 @@
+// SPDX-License-Identifier: MIT OR ISC
+//! wasm-bindgen bindings so that a browser can count an election and receive each event
+//! of the count as it happens, to animate the delegation, ring and patron steps.
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use wasm_bindgen::prelude::*;
+
+use electorium::record::json_introspector;
+use electorium::{ElectionOptions, VoteCounter};
+
+/// A ballot as JSON, with the fields of an NDJSON line of electorium::bulk.
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Ballot {
+    voter_id: String,
+    #[serde(default)]
+    vote_for: Option<String>,
+    number_of_votes: electorium::Weight,
+    willing_candidate: bool,
+    #[serde(default)]
+    metadata: electorium::Metadata,
+    #[serde(default)]
+    fallbacks: Vec<String>,
+    #[serde(default)]
+    abstain: bool,
+}
+impl From<Ballot> for electorium::Vote<'static> {
+    fn from(b: Ballot) -> Self {
+        electorium::Vote {
+            voter_id: b.voter_id.into(),
+            vote_for: b.vote_for.filter(|v|!v.is_empty()).map(Into::into),
+            number_of_votes: b.number_of_votes,
+            willing_candidate: b.willing_candidate,
+            metadata: std::borrow::Cow::Owned(b.metadata),
+            fallbacks: b.fallbacks.into_iter().map(Into::into).collect(),
+            abstain: b.abstain,
+        }
+    }
+}
+
+/// Count the ballots, a JSON array of Ballot, with options as JSON or an empty string
+/// for the defaults, and return the ElectionResult as JSON.
+///
+/// on_event is called with each event of the count as a JSON EventRecord, while the
+/// count runs, and with Progress events too if progress is true. If on_event throws,
+/// the count still finishes and then the first exception is thrown by tally().
+#[wasm_bindgen]
+pub fn tally(
+    ballots: &str,
+    options: &str,
+    on_event: js_sys::Function,
+    progress: bool,
+) -> Result<String, JsValue> {
+    let ballots: Vec<Ballot> = serde_json::from_str(ballots)
+        .map_err(|e|JsError::new(&format!("Invalid ballots: {}", e)))?;
+    let votes = ballots.into_iter().map(Into::into).collect::<Vec<electorium::Vote>>();
+    let options = if options.is_empty() {
+        ElectionOptions::default()
+    } else {
+        serde_json::from_str(options).map_err(|e|JsError::new(&format!("Invalid options: {}", e)))?
+    };
+    let thrown = Rc::new(RefCell::new(None));
+    let is = {
+        let thrown = thrown.clone();
+        json_introspector(move |json| {
+            if let Err(e) = on_event.call1(&JsValue::NULL, &JsValue::from_str(json)) {
+                thrown.borrow_mut().get_or_insert(e);
+            }
+        }, progress)
+    };
+    let result = VoteCounter::with_options(&votes, is, options).tally()
+        .map_err(|e|JsError::new(&e.to_string()))?;
+    if let Some(e) = thrown.borrow_mut().take() {
+        return Err(e);
+    }
+    serde_json::to_string(&result).map_err(|e|JsError::new(&e.to_string()).into())
+}